@@ -22,5 +22,11 @@ namespace Applications {
 
     [Verb("focused-application", HelpText = "Get focused application.")]
     internal class FocusedApplicationOptions {
+        [Option("window", HelpText = "Handle of the window to inspect instead of the foreground window.", Required = false)]
+        public long? Window { get; set; }
+    }
+
+    [Verb("serve", HelpText = "Serve JSON-RPC requests over stdin/stdout until stdin closes.")]
+    internal class ServeOptions {
     }
 }
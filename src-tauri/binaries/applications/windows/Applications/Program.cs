@@ -1,5 +1,6 @@
 ﻿using Windows.Management.Deployment;
 using Newtonsoft.Json;
+using Newtonsoft.Json.Linq;
 using System.Text;
 using CommandLine;
 using System.Diagnostics;
@@ -15,10 +16,19 @@ namespace Applications {
         public string name { get; set; }
         public string path { get; set; }
         public bool uwp { get; set; }
+        public string? version { get; set; }
+        public string? executable { get; set; }
+        public List<string> display_names { get; set; } = new List<string>();
+        public string? category { get; set; }
     }
 
     internal class FocusedApplication : Application {
         public string? selected_text { get; set; }
+        public string? focused_text { get; set; }
+        // Offsets in UTF-16 code units of `focused_text`.
+        public int? selection_start { get; set; }
+        public int? selection_length { get; set; }
+        public string? url { get; set; }
     }
 
     internal class Program {
@@ -30,7 +40,8 @@ namespace Applications {
                 ListApplicationsOptions,
                 OpenApplicationOptions,
                 ExtractApplicationIconOptions,
-                FocusedApplicationOptions
+                FocusedApplicationOptions,
+                ServeOptions
             >(args)
                 .WithParsed<ListApplicationsOptions>(o => {
                     var applications = ListApplications();
@@ -43,11 +54,88 @@ namespace Applications {
                     ExtractIcon(o.Input, o.Output);
                 })
                 .WithParsed<FocusedApplicationOptions>(o => {
-                    var focusedApplication = GetFocusedApplication();
+                    var focusedApplication = GetFocusedApplication(o.Window);
                     Console.WriteLine(JsonConvert.SerializeObject(focusedApplication));
+                })
+                .WithParsed<ServeOptions>(o => {
+                    Serve();
                 });
         }
 
+        const int ProtocolVersion = 1;
+
+        static object? HandleRequest(string method, JObject parameters) {
+            switch (method) {
+                case "initialize":
+                    return new { protocol_version = ProtocolVersion };
+                case "list":
+                    return ListApplications();
+                case "extract_icon":
+                    ExtractIcon((string)parameters["input"]!, (string)parameters["output"]!);
+                    return null;
+                case "open":
+                    OpenApplication((string)parameters["target"]!);
+                    return null;
+                case "launch":
+                    LaunchApplication(
+                        (string)parameters["target"]!,
+                        parameters["arguments"]?.ToObject<List<string>>() ?? new List<string>(),
+                        parameters["files"]?.ToObject<List<string>>() ?? new List<string>(),
+                        (bool?)parameters["hidden"] ?? false,
+                        (bool?)parameters["background"] ?? false
+                    ).GetAwaiter().GetResult();
+                    return null;
+                case "focused_application":
+                    return GetFocusedApplication((long?)parameters["window"]);
+                default:
+                    throw new MissingMethodException($"unknown method `{method}`");
+            }
+        }
+
+        static readonly object OutputLock = new object();
+
+        static void Respond(JToken? id, string method, JObject parameters) {
+            object response;
+
+            try {
+                response = new { jsonrpc = "2.0", id, result = HandleRequest(method, parameters) };
+            } catch (MissingMethodException e) {
+                response = new { jsonrpc = "2.0", id, error = new { code = -32601, message = e.Message } };
+            } catch (Exception e) {
+                response = new { jsonrpc = "2.0", id, error = new { code = -32000, message = e.Message } };
+            }
+
+            var line = JsonConvert.SerializeObject(response, Formatting.None);
+
+            lock (OutputLock) {
+                Console.WriteLine(line);
+                Console.Out.Flush();
+            }
+        }
+
+        static void Serve() {
+            string? line;
+
+            while ((line = Console.ReadLine()) != null) {
+                JObject request;
+
+                try {
+                    request = JObject.Parse(line);
+                } catch (JsonException) {
+                    continue;
+                }
+
+                var id = request["id"];
+                var method = (string?)request["method"] ?? "";
+                var parameters = request["params"] as JObject ?? new JObject();
+
+                // Requests run concurrently and answer in any order, so the focused application,
+                // which the panel waits for with a short timeout, is not stuck behind a slow list
+                // or a batch of icon extractions.
+                Task.Run(() => Respond(id, method, parameters));
+            }
+        }
+
         static List<Application> ListApplications() {
             List<Application> applications = new List<Application>();
 
@@ -60,7 +148,9 @@ namespace Applications {
                     id = data.entry.AppUserModelId,
                     name = data.entry.DisplayInfo.DisplayName,
                     path = data.package.InstalledPath,
-                    uwp = true
+                    uwp = true,
+                    version = GetPackageVersion(data.package),
+                    display_names = new List<string> { data.package.DisplayName }
                 })
                 .ToList();
 
@@ -74,11 +164,25 @@ namespace Applications {
             var startMenuApplications = startMenuFolders
                 .Select((startMenuFolderPath) => new DirectoryInfo(startMenuFolderPath))
                 .SelectMany((startMenuFolder) => startMenuFolder.GetFiles("*.lnk", SearchOption.AllDirectories))
-                .Select((shortcut) => new Application {
-                    id = shortcut.FullName,
-                    name = shortcut.Name.Replace(".lnk", ""),
-                    path = shortcut.FullName,
-                    uwp = false
+                .Select((shortcut) => {
+                    var executablePath = GetShortcutTargetPath(shortcut.FullName);
+                    var versionInfo = GetVersionInfo(executablePath);
+
+                    var application = new Application {
+                        id = shortcut.FullName,
+                        name = shortcut.Name.Replace(".lnk", ""),
+                        path = shortcut.FullName,
+                        uwp = false,
+                        version = versionInfo?.ProductVersion?.Trim(),
+                        executable = executablePath,
+                        category = GetShortcutCategory(shortcut)
+                    };
+
+                    if (!string.IsNullOrWhiteSpace(versionInfo?.FileDescription)) {
+                        application.display_names.Add(versionInfo.FileDescription.Trim());
+                    }
+
+                    return application;
                 });
 
             applications.AddRange(startMenuApplications);
@@ -86,6 +190,165 @@ namespace Applications {
             return applications.OrderBy(application => application.name).ToList();
         }
 
+        static string? GetShortcutTargetPath(string shortcutPath) {
+            Shortcut shortcut;
+
+            try {
+                shortcut = Shortcut.ReadFromFile(shortcutPath);
+            } catch (Exception) {
+                return null;
+            }
+
+            string? shortcutTargetPath = null;
+
+            if (shortcut.LinkInfo?.LocalBasePath != null) {
+                shortcutTargetPath = shortcut.LinkInfo.LocalBasePath;
+            } else if (shortcut.LinkTargetIDList?.Path != null) {
+                shortcutTargetPath = shortcut.LinkTargetIDList.Path;
+            } else if (shortcut.StringData?.IconLocation != null) {
+                shortcutTargetPath = shortcut.StringData.IconLocation;
+            }
+
+            if (shortcutTargetPath == null) {
+                return null;
+            }
+
+            var resolvedShortcutTargetPath = Environment.ExpandEnvironmentVariables(shortcutTargetPath);
+
+            return File.Exists(resolvedShortcutTargetPath) ? resolvedShortcutTargetPath : null;
+        }
+
+        static FileVersionInfo? GetVersionInfo(string? executablePath) {
+            if (executablePath == null) {
+                return null;
+            }
+
+            try {
+                return FileVersionInfo.GetVersionInfo(executablePath);
+            } catch (Exception) {
+                return null;
+            }
+        }
+
+        static string? GetPackageVersion(Windows.ApplicationModel.Package package) {
+            var version = package.Id.Version;
+            return $"{version.Major}.{version.Minor}.{version.Build}.{version.Revision}";
+        }
+
+        // Start menu folders such as "Accessories" or "Windows Tools" act as categories; shortcuts
+        // directly in "Programs" have none.
+        static string? GetShortcutCategory(FileInfo shortcut) {
+            var folderName = shortcut.Directory?.Name;
+
+            if (folderName == null || folderName == "Programs" || folderName == "Start Menu") {
+                return null;
+            }
+
+            return folderName;
+        }
+
+        // Quotes an argument the way CommandLineToArgvW parses it back.
+        static string QuoteArgument(string argument) {
+            if (argument.Length > 0 && !argument.Any(c => char.IsWhiteSpace(c) || c == '"')) {
+                return argument;
+            }
+
+            var quoted = new StringBuilder("\"");
+            var backslashes = 0;
+
+            foreach (var c in argument) {
+                if (c == '\\') {
+                    backslashes++;
+                    continue;
+                }
+
+                quoted.Append('\\', c == '"' ? backslashes * 2 + 1 : backslashes);
+                quoted.Append(c);
+                backslashes = 0;
+            }
+
+            quoted.Append('\\', backslashes * 2);
+            quoted.Append('"');
+
+            return quoted.ToString();
+        }
+
+        static async Task LaunchApplication(string target, List<string> arguments, List<string> files, bool hidden, bool background) {
+            if (target.EndsWith(".lnk")) {
+                var hasParameters = arguments.Count > 0 || files.Count > 0;
+
+                // Shortcuts carry their own arguments and working directory, so only bypass them
+                // for the executable when there is something to pass along.
+                var executablePath = hasParameters ? GetShortcutTargetPath(target) : null;
+
+                var startInfo = new ProcessStartInfo(executablePath ?? target) {
+                    UseShellExecute = true,
+                    Arguments = string.Join(" ", files.Concat(arguments).Select(QuoteArgument)),
+                    WindowStyle = hidden
+                        ? ProcessWindowStyle.Hidden
+                        : background ? ProcessWindowStyle.Minimized : ProcessWindowStyle.Normal
+                };
+
+                if (executablePath != null) {
+                    startInfo.WorkingDirectory = Path.GetDirectoryName(executablePath);
+                }
+
+                Process.Start(startInfo);
+
+                return;
+            }
+
+            PackageManager packageManager = new PackageManager();
+            var packages = packageManager.FindPackagesForUser(string.Empty);
+
+            var entry = packages
+                .SelectMany((package) => package.GetAppListEntries(), (package, entry) => new { package, entry })
+                .FirstOrDefault(data => data.entry.AppUserModelId == target);
+
+            if (entry == null) {
+                throw new Exception("Specified target does not exist.");
+            }
+
+            if (files.Count > 0) {
+                var launcherOptions = new Windows.System.LauncherOptions {
+                    TargetApplicationPackageFamilyName = entry.package.Id.FamilyName
+                };
+
+                foreach (var file in files) {
+                    bool isLaunched;
+
+                    if (Uri.TryCreate(file, UriKind.Absolute, out var uri) && !uri.IsFile) {
+                        isLaunched = await Windows.System.Launcher.LaunchUriAsync(uri, launcherOptions);
+                    } else {
+                        var storageFile = await Windows.Storage.StorageFile.GetFileFromPathAsync(file);
+                        isLaunched = await Windows.System.Launcher.LaunchFileAsync(storageFile, launcherOptions);
+                    }
+
+                    if (!isLaunched) {
+                        throw new Exception($"`{file}` could not be opened with the application.");
+                    }
+                }
+
+                return;
+            }
+
+            if (arguments.Count > 0) {
+                var activationManager = (IApplicationActivationManager)new ApplicationActivationManager();
+                activationManager.ActivateApplication(
+                    target,
+                    string.Join(" ", arguments.Select(QuoteArgument)),
+                    ActivateOptions.None,
+                    out _
+                );
+
+                return;
+            }
+
+            if (!await entry.entry.LaunchAsync()) {
+                throw new Exception("The application could not be launched.");
+            }
+        }
+
         static async void OpenApplication(string target) {
             if (target.EndsWith(".lnk")) {
                 Process.Start(new ProcessStartInfo(target) { UseShellExecute = true });
@@ -113,10 +376,44 @@ namespace Applications {
             }
         }
 
-        static FocusedApplication? GetFocusedApplication() {
+        static readonly string[] BrowserExecutables = {
+            "chrome.exe", "msedge.exe", "brave.exe", "vivaldi.exe", "opera.exe", "firefox.exe"
+        };
+
+        // Reads the address bar of a browser window: `urlbar-input` in Firefox and the first edit
+        // control in Chromium-based browsers.
+        static string? GetBrowserUrl(IUIAutomation automation, IUIAutomationElement windowElement, string path) {
+            var executable = Path.GetFileName(path).ToLowerInvariant();
+
+            if (!BrowserExecutables.Contains(executable)) {
+                return null;
+            }
+
+            var condition = executable == "firefox.exe"
+                ? automation.CreatePropertyCondition(UIA_PropertyIds.UIA_AutomationIdPropertyId, "urlbar-input")
+                : automation.CreatePropertyCondition(UIA_PropertyIds.UIA_ControlTypePropertyId, UIA_ControlTypeIds.UIA_EditControlTypeId);
+
+            var addressBar = windowElement.FindFirst(TreeScope.TreeScope_Descendants, condition);
+
+            if (addressBar == null) {
+                return null;
+            }
+
+            var valuePattern = (IUIAutomationValuePattern)addressBar.GetCurrentPattern(UIA_PatternIds.UIA_ValuePatternId);
+            var value = valuePattern?.CurrentValue?.Trim();
+
+            if (string.IsNullOrEmpty(value)) {
+                return null;
+            }
+
+            // Chromium hides the scheme of https addresses.
+            return value.Contains("://") ? value : "https://" + value;
+        }
+
+        static FocusedApplication? GetFocusedApplication(long? window) {
             FocusedApplication? focusedApplication = null;
 
-            var foregroundWindow = GetForegroundWindow();
+            var foregroundWindow = window.HasValue ? new IntPtr(window.Value) : GetForegroundWindow();
 
             if (foregroundWindow.Equals(IntPtr.Zero)) {
                 return null;
@@ -184,7 +481,8 @@ namespace Applications {
                     id = entry.AppUserModelId,
                     name = entry.DisplayInfo.DisplayName,
                     path = entry.AppInfo.PackageFamilyName,
-                    uwp = true
+                    uwp = true,
+                    executable = path
                 };
             } else {
                 var startMenuFolders = new List<string> {
@@ -196,27 +494,7 @@ namespace Applications {
                     .Select((startMenuFolderPath) => new DirectoryInfo(startMenuFolderPath))
                     .SelectMany((startMenuFolder) => startMenuFolder.GetFiles("*.lnk", SearchOption.AllDirectories))
                     .Select((shortcutPath) => {
-                        var shortcut = Shortcut.ReadFromFile(shortcutPath.FullName);
-
-                        string? shortcutTargetPath = null;
-
-                        if (shortcut.LinkInfo?.LocalBasePath != null) {
-                            shortcutTargetPath = shortcut.LinkInfo.LocalBasePath;
-                        } else if (shortcut.LinkTargetIDList?.Path != null) {
-                            shortcutTargetPath = shortcut.LinkTargetIDList.Path;
-                        } else if (shortcut.StringData?.IconLocation != null) {
-                            shortcutTargetPath = shortcut.StringData.IconLocation;
-                        }
-
-                        string? executablePath = null;
-
-                        if (shortcutTargetPath != null) {
-                            var resolvedShortcutTargetPath = Environment.ExpandEnvironmentVariables(shortcutTargetPath);
-
-                            if (File.Exists(resolvedShortcutTargetPath)) {
-                                executablePath = resolvedShortcutTargetPath;
-                            }
-                        }
+                        var executablePath = GetShortcutTargetPath(shortcutPath.FullName);
 
                         return new { executablePath, shortcutPath };
                     })
@@ -230,18 +508,29 @@ namespace Applications {
                     return null;
                 }
 
+                var versionInfo = GetVersionInfo(path);
+
                 focusedApplication = new FocusedApplication {
                     id = shortcut.FullName ?? "",
                     name = displayName,
                     path = shortcut.FullName ?? "",
-                    uwp = false
+                    uwp = false,
+                    version = versionInfo?.ProductVersion?.Trim(),
+                    executable = path,
+                    category = GetShortcutCategory(shortcut)
                 };
+
+                if (!string.IsNullOrWhiteSpace(versionInfo?.FileDescription)) {
+                    focusedApplication.display_names.Add(versionInfo.FileDescription.Trim());
+                }
             }
 
             if (focusedApplication == null) {
                 return null;
             }
 
+            focusedApplication.url = GetBrowserUrl(automation, windowElement, path);
+
             var focusedElement = automation.GetFocusedElement();
 
             if (focusedElement == null) {
@@ -267,9 +556,44 @@ namespace Applications {
 
             focusedApplication.selected_text = selectedText;
 
+            var documentRange = textPattern.DocumentRange;
+            focusedApplication.focused_text = documentRange.GetText(-1);
+
+            if (selection != null && selection.Length > 0) {
+                // The caret offset is the length of the text from the start of the document up to
+                // the selection.
+                var selectedRange = selection.GetElement(0);
+                var textBefore = documentRange.Clone();
+
+                textBefore.MoveEndpointByRange(
+                    TextPatternRangeEndpoint.TextPatternRangeEndpoint_End,
+                    selectedRange,
+                    TextPatternRangeEndpoint.TextPatternRangeEndpoint_Start
+                );
+
+                focusedApplication.selection_start = textBefore.GetText(-1).Length;
+                focusedApplication.selection_length = selectedRange.GetText(-1).Length;
+            }
+
             return focusedApplication;
         }
 
+        enum ActivateOptions {
+            None = 0x00000000,
+            DesignMode = 0x00000001,
+            NoErrorUI = 0x00000002,
+            NoSplashScreen = 0x00000004
+        }
+
+        [ComImport, Guid("2e941141-7f97-4756-ba1d-9decde894a3d"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+        interface IApplicationActivationManager {
+            IntPtr ActivateApplication([In] string appUserModelId, [In] string arguments, [In] ActivateOptions options, [Out] out uint processId);
+        }
+
+        [ComImport, Guid("45BA127D-10A8-46EA-8AB7-56EA9078943C")]
+        class ApplicationActivationManager {
+        }
+
         [DllImport("user32.dll", CharSet = CharSet.Auto, ExactSpelling = true)]
         internal static extern IntPtr GetForegroundWindow();
 
@@ -0,0 +1,113 @@
+//! Stand-in for the Windows `applications` sidecar that speaks the same line-delimited JSON-RPC
+//! protocol with canned data, so the sidecar client can be exercised on any platform by pointing
+//! `LANDER_APPLICATIONS_SIDECAR` at this binary.
+
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    thread, time,
+};
+
+const PROTOCOL_VERSION: u64 = 1;
+
+fn handle_request(method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => Ok(json!({ "protocol_version": PROTOCOL_VERSION })),
+        "list" => Ok(json!([
+            {
+                "id": "Microsoft.WindowsCalculator_8wekyb3d8bbwe!App",
+                "name": "Calculator",
+                "path": "C:\\Program Files\\WindowsApps\\Microsoft.WindowsCalculator",
+                "uwp": true,
+                "version": "11.2307.4.0",
+                "executable": null,
+                "display_names": ["Rechner"],
+                "category": null
+            },
+            {
+                "id": "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Notepad.lnk",
+                "name": "Notepad",
+                "path": "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Notepad.lnk",
+                "uwp": false,
+                "version": "10.0.22621.1",
+                "executable": "C:\\Windows\\System32\\notepad.exe",
+                "display_names": ["Notepad"],
+                "category": "Accessories"
+            }
+        ])),
+        "extract_icon" => {
+            let output = params["output"]
+                .as_str()
+                .ok_or((-32602, "missing `output`".to_string()))?;
+
+            fs::write(output, []).map_err(|error| (-32000, error.to_string()))?;
+
+            Ok(Value::Null)
+        }
+        "open" => match params["target"].as_str() {
+            Some(_) => Ok(Value::Null),
+            None => Err((-32602, "missing `target`".to_string())),
+        },
+        "launch" => match params["target"].as_str() {
+            Some(_) => Ok(Value::Null),
+            None => Err((-32602, "missing `target`".to_string())),
+        },
+        "focused_application" => Ok(json!({
+            "id": "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Notepad.lnk",
+            "name": "Notepad",
+            "path": "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Notepad.lnk",
+            "selected_text": "selected text",
+            "uwp": false
+        })),
+        "sleep" => {
+            let milliseconds = params["milliseconds"].as_u64().unwrap_or(0);
+            thread::sleep(time::Duration::from_millis(milliseconds));
+
+            Ok(Value::Null)
+        }
+        "crash" => std::process::exit(1),
+        _ => Err((-32601, format!("unknown method `{}`", method))),
+    }
+}
+
+fn main() {
+    if std::env::args().nth(1).as_deref() != Some("serve") {
+        eprintln!("usage: applications-mock serve");
+        std::process::exit(2);
+    }
+
+    let stdin = io::stdin();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(_) => continue,
+        };
+
+        // Like the real sidecar, requests run concurrently and answer in any order.
+        thread::spawn(move || {
+            let id = request["id"].clone();
+            let method = request["method"].as_str().unwrap_or_default();
+
+            let response = match handle_request(method, &request["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, message)) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": message }
+                }),
+            };
+
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            writeln!(stdout, "{}", response).unwrap();
+            stdout.flush().unwrap();
+        });
+    }
+}
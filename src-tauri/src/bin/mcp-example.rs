@@ -0,0 +1,168 @@
+//! Minimal Model Context Protocol server over stdio with a few tools, a resource and a prompt, for
+//! trying out the MCP client. Add it in the settings as a `stdio` server whose command is the path
+//! of this binary.
+
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const README: &str = "This is the example MCP server bundled with Lander. It exposes the `echo`, \
+`add` and `current_time` tools, this resource and the `review_code` prompt.";
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "echo",
+            "description": "Returns the given text unchanged.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"],
+            },
+        },
+        {
+            "name": "add",
+            "description": "Adds two numbers.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "a": { "type": "number" },
+                    "b": { "type": "number" },
+                },
+                "required": ["a", "b"],
+            },
+        },
+        {
+            "name": "current_time",
+            "description": "Returns the current time as seconds since the Unix epoch.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+    ])
+}
+
+fn text_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn call_tool(name: &str, arguments: &Value) -> Result<Value, (i64, String)> {
+    match name {
+        "echo" => Ok(text_result(
+            arguments["text"].as_str().unwrap_or_default().to_string(),
+            false,
+        )),
+        "add" => match (arguments["a"].as_f64(), arguments["b"].as_f64()) {
+            (Some(a), Some(b)) => Ok(text_result((a + b).to_string(), false)),
+            _ => Ok(text_result("`a` and `b` must be numbers".to_string(), true)),
+        },
+        "current_time" => {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+
+            Ok(text_result(seconds.to_string(), false))
+        }
+        _ => Err((-32602, format!("unknown tool `{}`", name))),
+    }
+}
+
+fn handle_request(method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+            "serverInfo": { "name": "lander-example", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => call_tool(
+            params["name"].as_str().unwrap_or_default(),
+            &params["arguments"],
+        ),
+        "resources/list" => Ok(json!({
+            "resources": [{
+                "uri": "example://readme",
+                "name": "README",
+                "description": "What this server offers",
+                "mimeType": "text/plain",
+            }],
+        })),
+        "resources/read" => match params["uri"].as_str() {
+            Some("example://readme") => Ok(json!({
+                "contents": [{
+                    "uri": "example://readme",
+                    "mimeType": "text/plain",
+                    "text": README,
+                }],
+            })),
+            _ => Err((-32002, "resource not found".to_string())),
+        },
+        "prompts/list" => Ok(json!({
+            "prompts": [{
+                "name": "review_code",
+                "description": "Asks for a review of a code snippet",
+                "arguments": [{ "name": "code", "required": true }],
+            }],
+        })),
+        "prompts/get" => match params["name"].as_str() {
+            Some("review_code") => Ok(json!({
+                "messages": [{
+                    "role": "user",
+                    "content": {
+                        "type": "text",
+                        "text": format!(
+                            "Please review this code:\n\n{}",
+                            params["arguments"]["code"].as_str().unwrap_or_default()
+                        ),
+                    },
+                }],
+            })),
+            _ => Err((-32602, "unknown prompt".to_string())),
+        },
+        _ => Err((-32601, format!("unknown method `{}`", method))),
+    }
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(_) => continue,
+        };
+
+        // Notifications such as `notifications/initialized` get no response.
+        if request["id"].is_null() {
+            continue;
+        }
+
+        let id = request["id"].clone();
+        let method = request["method"].as_str().unwrap_or_default();
+
+        let response = match handle_request(method, &request["params"]) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        };
+
+        writeln!(stdout, "{}", response).unwrap();
+        stdout.flush().unwrap();
+    }
+}
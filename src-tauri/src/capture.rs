@@ -0,0 +1,325 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder},
+    imageops::{self, FilterType},
+    ColorType, DynamicImage, ImageEncoder, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{path::PathBuf, time::Duration};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::panel::{self, geometry::Rect};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+use macos as capture;
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+use self::windows as capture;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+use linux as capture;
+
+/// Time for the panel to disappear before the screen is captured.
+const PANEL_HIDE_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Png,
+    Jpeg,
+}
+
+impl Format {
+    fn media_type(&self) -> &'static str {
+        match self {
+            Format::Png => "image/png",
+            Format::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Longest side in pixels; larger captures are downscaled, since models resize them anyway.
+    pub max_dimension: u32,
+    pub format: Format,
+    /// From 1 to 100.
+    pub jpeg_quality: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_dimension: 1568,
+            format: Format::Png,
+            jpeg_quality: 80,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    /// The main display.
+    Screen,
+    /// The focused window of the frontmost application.
+    Window,
+    /// The given rectangle, or one the user selects when there is none.
+    Region,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Capture {
+    media_type: String,
+    /// Base64 of the encoded image.
+    data: String,
+    width: u32,
+    height: u32,
+}
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("capture")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+/// Converts rows of 32-bit BGRA or BGRX pixels, as returned by every platform's capture API, to
+/// an opaque RGBA image.
+fn from_bgra(width: usize, height: usize, bytes_per_row: usize, data: &[u8]) -> Option<RgbaImage> {
+    if bytes_per_row < width * 4 || data.len() < bytes_per_row * height {
+        return None;
+    }
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+
+    for row in data.chunks(bytes_per_row).take(height) {
+        for pixel in row[..width * 4].chunks_exact(4) {
+            pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+        }
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, pixels)
+}
+
+fn encode(image: RgbaImage, settings: &Settings) -> Result<Capture, String> {
+    let max_dimension = settings.max_dimension.max(1);
+    let (width, height) = image.dimensions();
+
+    let image = if width.max(height) > max_dimension {
+        let scale = max_dimension as f64 / width.max(height) as f64;
+        let width = ((width as f64 * scale).round() as u32).max(1);
+        let height = ((height as f64 * scale).round() as u32).max(1);
+
+        imageops::resize(&image, width, height, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    let (width, height) = image.dimensions();
+    let mut data = Vec::new();
+
+    match settings.format {
+        Format::Png => {
+            PngEncoder::new(&mut data).write_image(image.as_raw(), width, height, ColorType::Rgba8)
+        }
+        Format::Jpeg => {
+            let image = DynamicImage::ImageRgba8(image).to_rgb8();
+            JpegEncoder::new_with_quality(&mut data, settings.jpeg_quality.clamp(1, 100))
+                .encode_image(&image)
+        }
+    }
+    .map_err(|error| error.to_string())?;
+
+    Ok(Capture {
+        media_type: settings.format.media_type().to_string(),
+        data: STANDARD.encode(data),
+        width,
+        height,
+    })
+}
+
+fn set_panel_visible(app_handle: &AppHandle<Wry>, is_visible: bool) {
+    let handle = app_handle.clone();
+
+    app_handle
+        .run_on_main_thread(move || {
+            if is_visible {
+                panel::show_panel(handle);
+            } else {
+                panel::hide_panel(handle);
+            }
+        })
+        .unwrap_or_default();
+}
+
+/// Captures without hiding the panel. Returns `None` when the user cancels the region selection.
+pub async fn capture_image(
+    target: Target,
+    rect: Option<Rect>,
+) -> Result<Option<RgbaImage>, String> {
+    if let (Target::Region, None) = (target, rect) {
+        return capture::select_region().await;
+    }
+
+    tauri::async_runtime::spawn_blocking(move || match (target, rect) {
+        (Target::Window, _) => capture::capture_focused_window(),
+        (Target::Region, Some(rect)) => capture::capture_rect(&rect),
+        _ => capture::capture_screen(),
+    })
+    .await
+    .map_err(|error| error.to_string())?
+    .map(Some)
+}
+
+/// Captures with the panel hidden meanwhile, so it does not cover what the user wants to capture.
+///
+/// `rect` is in the units of window frames: points on macOS and physical pixels elsewhere.
+pub async fn capture_image_without_panel(
+    app_handle: &AppHandle<Wry>,
+    target: Target,
+    rect: Option<Rect>,
+) -> Result<Option<RgbaImage>, String> {
+    set_panel_visible(app_handle, false);
+    tokio::time::sleep(PANEL_HIDE_DELAY).await;
+
+    let image = capture_image(target, rect).await;
+
+    set_panel_visible(app_handle, true);
+
+    image
+}
+
+/// Captures the screen, the focused window or a region without the panel and returns it
+/// downscaled and encoded for a multimodal prompt. Resolves to `null` when the user cancels the
+/// region selection.
+#[tauri::command]
+pub async fn capture_screenshot(
+    app_handle: AppHandle<Wry>,
+    target: Target,
+    rect: Option<Rect>,
+) -> Result<Option<String>, String> {
+    let settings = get_settings(&app_handle);
+
+    let image = match capture_image_without_panel(&app_handle, target, rect).await? {
+        Some(image) => image,
+        None => return Ok(None),
+    };
+
+    let capture = tauri::async_runtime::spawn_blocking(move || encode(image, &settings))
+        .await
+        .map_err(|error| error.to_string())??;
+
+    Ok(Some(
+        serde_json::to_string(&capture).expect("error serializing json"),
+    ))
+}
+
+#[tauri::command]
+pub fn get_capture_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_capture_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("capture".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(capture: &Capture) -> DynamicImage {
+        image::load_from_memory(&STANDARD.decode(&capture.data).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn bgra_rows_skip_their_padding() {
+        // Two pixels per row, padded to 12 bytes with a marker that must not show up.
+        let data = [
+            1, 2, 3, 0, 4, 5, 6, 0, 99, 99, 99, 99, //
+            7, 8, 9, 0, 10, 11, 12, 0, 99, 99, 99, 99,
+        ];
+
+        let image = from_bgra(2, 2, 12, &data).unwrap();
+
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(
+            image.into_raw(),
+            vec![3, 2, 1, 255, 6, 5, 4, 255, 9, 8, 7, 255, 12, 11, 10, 255]
+        );
+    }
+
+    #[test]
+    fn short_bgra_buffers_are_rejected() {
+        // A row narrower than its pixels.
+        assert!(from_bgra(2, 1, 4, &[0; 8]).is_none());
+        // Fewer rows than the height.
+        assert!(from_bgra(2, 2, 8, &[0; 15]).is_none());
+        assert!(from_bgra(2, 2, 8, &[0; 16]).is_some());
+    }
+
+    #[test]
+    fn the_longest_side_is_capped() {
+        let settings = Settings {
+            max_dimension: 100,
+            ..Settings::default()
+        };
+
+        let capture = encode(RgbaImage::new(400, 100), &settings).unwrap();
+
+        assert_eq!((capture.width, capture.height), (100, 25));
+        assert_eq!(decode(&capture).to_rgba8().dimensions(), (100, 25));
+
+        let capture = encode(RgbaImage::new(30, 1000), &settings).unwrap();
+
+        assert_eq!((capture.width, capture.height), (3, 100));
+
+        // Thin captures keep at least a pixel.
+        let capture = encode(RgbaImage::new(1000, 1), &settings).unwrap();
+
+        assert_eq!((capture.width, capture.height), (100, 1));
+    }
+
+    #[test]
+    fn small_captures_keep_their_size() {
+        let settings = Settings {
+            max_dimension: 100,
+            format: Format::Jpeg,
+            ..Settings::default()
+        };
+
+        let capture = encode(RgbaImage::new(100, 60), &settings).unwrap();
+
+        assert_eq!(capture.media_type, "image/jpeg");
+        assert_eq!((capture.width, capture.height), (100, 60));
+        assert_eq!(decode(&capture).to_rgb8().dimensions(), (100, 60));
+    }
+}
@@ -0,0 +1,195 @@
+use ashpd::{
+    desktop::{screenshot::Screenshot, ResponseError},
+    Error as PortalError,
+};
+use image::{imageops, RgbaImage};
+use std::env;
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{AtomEnum, ConnectionExt, ImageFormat, Window},
+    rust_connection::RustConnection,
+};
+
+use crate::panel::geometry::Rect;
+
+/// Wayland compositors only let applications capture the screen through the desktop portal.
+fn is_wayland() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Asks the desktop portal for a screenshot. When `is_interactive`, the portal lets the user pick
+/// the screen, a window or a region first. Returns `None` when the user cancels.
+async fn capture_with_portal(is_interactive: bool) -> Result<Option<RgbaImage>, String> {
+    let response = Screenshot::request()
+        .interactive(is_interactive)
+        .modal(true)
+        .send()
+        .await
+        .and_then(|request| request.response());
+
+    let screenshot = match response {
+        Ok(screenshot) => screenshot,
+        Err(PortalError::Response(ResponseError::Cancelled)) => return Ok(None),
+        Err(error) => return Err(format!("the screenshot portal failed: {}", error)),
+    };
+
+    let path = screenshot
+        .uri()
+        .to_file_path()
+        .map_err(|_| "the screenshot portal returned no file".to_string())?;
+
+    let image = image::open(&path).map_err(|error| error.to_string())?;
+
+    // The portal saves to the pictures directory, where the user did not ask for a file.
+    std::fs::remove_file(&path).unwrap_or_default();
+
+    Ok(Some(image.to_rgba8()))
+}
+
+fn capture_with_portal_blocking(is_interactive: bool) -> Result<RgbaImage, String> {
+    tauri::async_runtime::block_on(capture_with_portal(is_interactive))?
+        .ok_or_else(|| "the capture was cancelled".to_string())
+}
+
+fn connect() -> Result<(RustConnection, Window, u16, u16), String> {
+    let (connection, screen) = RustConnection::connect(None).map_err(|error| error.to_string())?;
+    let screen = &connection.setup().roots[screen];
+    let (root, width, height) = (screen.root, screen.width_in_pixels, screen.height_in_pixels);
+
+    Ok((connection, root, width, height))
+}
+
+/// Reads a rectangle of the root window, clipped to the screen.
+fn get_root_image(
+    connection: &RustConnection,
+    root: Window,
+    screen_size: (u16, u16),
+    rect: &Rect,
+) -> Result<RgbaImage, String> {
+    let x = rect.x.max(0.0).min(screen_size.0 as f64);
+    let y = rect.y.max(0.0).min(screen_size.1 as f64);
+    let width = (rect.x + rect.width).min(screen_size.0 as f64) - x;
+    let height = (rect.y + rect.height).min(screen_size.1 as f64) - y;
+
+    if width < 1.0 || height < 1.0 {
+        return Err("the region to capture is off screen".to_string());
+    }
+
+    let (width, height) = (width as u16, height as u16);
+
+    let reply = connection
+        .get_image(
+            ImageFormat::Z_PIXMAP,
+            root,
+            x as i16,
+            y as i16,
+            width,
+            height,
+            !0,
+        )
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+
+    if reply.depth != 24 && reply.depth != 32 {
+        return Err(format!(
+            "the screen depth of {} bits is not supported",
+            reply.depth
+        ));
+    }
+
+    super::from_bgra(
+        width as usize,
+        height as usize,
+        width as usize * 4,
+        &reply.data,
+    )
+    .ok_or_else(|| "the captured image is incomplete".to_string())
+}
+
+fn get_active_window_frame(connection: &RustConnection, root: Window) -> Option<Rect> {
+    let atom = connection
+        .intern_atom(true, b"_NET_ACTIVE_WINDOW")
+        .ok()?
+        .reply()
+        .ok()?
+        .atom;
+
+    let window = connection
+        .get_property(false, root, atom, AtomEnum::WINDOW, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()
+        .filter(|window| *window != 0)?;
+
+    let geometry = connection.get_geometry(window).ok()?.reply().ok()?;
+    let position = connection
+        .translate_coordinates(window, root, 0, 0)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    Some(Rect {
+        x: position.dst_x as f64,
+        y: position.dst_y as f64,
+        width: geometry.width as f64,
+        height: geometry.height as f64,
+    })
+}
+
+pub fn capture_screen() -> Result<RgbaImage, String> {
+    if is_wayland() {
+        return capture_with_portal_blocking(false);
+    }
+
+    let (connection, root, width, height) = connect()?;
+
+    let rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: width as f64,
+        height: height as f64,
+    };
+
+    get_root_image(&connection, root, (width, height), &rect)
+}
+
+/// Wayland does not expose the focused window, so the portal asks the user to pick one.
+pub fn capture_focused_window() -> Result<RgbaImage, String> {
+    if is_wayland() {
+        return capture_with_portal_blocking(true);
+    }
+
+    let (connection, root, width, height) = connect()?;
+    let frame = get_active_window_frame(&connection, root).ok_or("no window is focused")?;
+
+    get_root_image(&connection, root, (width, height), &frame)
+}
+
+pub fn capture_rect(rect: &Rect) -> Result<RgbaImage, String> {
+    if !is_wayland() {
+        let (connection, root, width, height) = connect()?;
+        return get_root_image(&connection, root, (width, height), rect);
+    }
+
+    let mut image = capture_with_portal_blocking(false)?;
+    let (width, height) = image.dimensions();
+
+    let x = (rect.x.max(0.0) as u32).min(width);
+    let y = (rect.y.max(0.0) as u32).min(height);
+    let region_width = (rect.width.max(0.0) as u32).min(width - x);
+    let region_height = (rect.height.max(0.0) as u32).min(height - y);
+
+    if region_width == 0 || region_height == 0 {
+        return Err("the region to capture is off screen".to_string());
+    }
+
+    Ok(imageops::crop(&mut image, x, y, region_width, region_height).to_image())
+}
+
+/// Uses the desktop portal, which most X11 desktops provide as well, for its selection UI.
+pub async fn select_region() -> Result<Option<RgbaImage>, String> {
+    capture_with_portal(true).await
+}
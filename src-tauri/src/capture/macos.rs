@@ -0,0 +1,103 @@
+use core_graphics::{
+    display::{
+        kCGNullWindowID, kCGWindowImageBoundsIgnoreFraming, kCGWindowImageDefault,
+        kCGWindowListOptionIncludingWindow, kCGWindowListOptionOnScreenOnly, CGDisplay, CGRectNull,
+    },
+    geometry::{CGPoint, CGRect, CGSize},
+    image::CGImage,
+};
+use image::RgbaImage;
+use std::{env, fs, process::Command};
+
+use crate::command::window::macos::get_window_id;
+use crate::panel::geometry::Rect;
+use crate::util::macos::{get_focused_window, get_frontmost_application_pid};
+
+/// `CGImage`s of the window server are 32-bit BGRA with premultiplied alpha, at the backing
+/// scale of the display.
+fn to_rgba_image(image: CGImage) -> Result<RgbaImage, String> {
+    if image.bits_per_component() != 8 || image.bits_per_pixel() != 32 {
+        return Err("the screen uses an unsupported pixel format".to_string());
+    }
+
+    let data = image.data();
+
+    super::from_bgra(
+        image.width(),
+        image.height(),
+        image.bytes_per_row(),
+        data.bytes(),
+    )
+    .ok_or_else(|| "the captured image is incomplete".to_string())
+}
+
+/// Fails with a hint when the image is missing, which is how a denied screen recording
+/// permission shows up.
+fn or_permission_error(image: Option<CGImage>) -> Result<CGImage, String> {
+    image.ok_or_else(|| {
+        "the screen could not be captured; allow Lander to record the screen in System \
+         Settings > Privacy & Security > Screen Recording"
+            .to_string()
+    })
+}
+
+pub fn capture_screen() -> Result<RgbaImage, String> {
+    to_rgba_image(or_permission_error(CGDisplay::main().image())?)
+}
+
+pub fn capture_focused_window() -> Result<RgbaImage, String> {
+    let window_id = get_frontmost_application_pid()
+        .and_then(get_focused_window)
+        .and_then(|window| get_window_id(&window))
+        .ok_or("no window is focused")?;
+
+    let image = CGDisplay::screenshot(
+        unsafe { CGRectNull },
+        kCGWindowListOptionIncludingWindow,
+        window_id,
+        kCGWindowImageBoundsIgnoreFraming,
+    );
+
+    to_rgba_image(or_permission_error(image)?)
+}
+
+pub fn capture_rect(rect: &Rect) -> Result<RgbaImage, String> {
+    let bounds = CGRect::new(
+        &CGPoint::new(rect.x, rect.y),
+        &CGSize::new(rect.width, rect.height),
+    );
+
+    let image = CGDisplay::screenshot(
+        bounds,
+        kCGWindowListOptionOnScreenOnly,
+        kCGNullWindowID,
+        kCGWindowImageDefault,
+    );
+
+    to_rgba_image(or_permission_error(image)?)
+}
+
+/// Lets the user drag out a region, or press space to pick a window, with the system's
+/// `screencapture` tool. Returns `None` when the user presses escape.
+pub async fn select_region() -> Result<Option<RgbaImage>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let path = env::temp_dir().join(format!("lander-capture-{}.png", std::process::id()));
+
+        let status = Command::new("/usr/sbin/screencapture")
+            .args(["-i", "-x", "-t", "png"])
+            .arg(&path)
+            .status()
+            .map_err(|error| error.to_string())?;
+
+        if !status.success() || !path.exists() {
+            return Ok(None);
+        }
+
+        let image = image::open(&path).map_err(|error| error.to_string());
+        fs::remove_file(&path).unwrap_or_default();
+
+        Ok(Some(image?.to_rgba8()))
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
@@ -0,0 +1,295 @@
+use image::{imageops, ImageFormat, RgbaImage};
+use std::{
+    ffi::c_void,
+    mem,
+    process::Command,
+    slice,
+    time::{Duration, Instant},
+};
+use windows_sys::Win32::{
+    Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+        GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT,
+        DIB_RGB_COLORS, HDC, SRCCOPY,
+    },
+    Storage::Xps::PrintWindow,
+    System::{
+        DataExchange::{
+            CloseClipboard, GetClipboardData, GetClipboardSequenceNumber, OpenClipboard,
+        },
+        Memory::{GlobalLock, GlobalSize, GlobalUnlock},
+    },
+    UI::WindowsAndMessaging::{GetSystemMetrics, PW_RENDERFULLCONTENT, SM_CXSCREEN, SM_CYSCREEN},
+};
+
+use crate::command::application::windows::foreground;
+use crate::command::window::windows::{get_visible_window_rect, get_window_rect};
+use crate::panel::geometry::Rect;
+
+/// Draws into a bitmap of the given size with `draw`, which gets the memory device context and
+/// the screen's, and reads the bitmap back.
+fn capture_bitmap(
+    width: i32,
+    height: i32,
+    draw: impl FnOnce(HDC, HDC) -> bool,
+) -> Result<RgbaImage, String> {
+    if width <= 0 || height <= 0 {
+        return Err("the region to capture is empty".to_string());
+    }
+
+    unsafe {
+        let screen = GetDC(0);
+        let memory = CreateCompatibleDC(screen);
+        let bitmap = CreateCompatibleBitmap(screen, width, height);
+        let previous = SelectObject(memory, bitmap);
+
+        let is_drawn = draw(memory, screen);
+
+        let mut info: BITMAPINFO = mem::zeroed();
+        info.bmiHeader = BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative for rows from top to bottom.
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB as u32,
+            ..mem::zeroed()
+        };
+
+        let mut data = vec![0u8; width as usize * height as usize * 4];
+
+        let lines = if is_drawn {
+            GetDIBits(
+                memory,
+                bitmap,
+                0,
+                height as u32,
+                data.as_mut_ptr() as *mut c_void,
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        } else {
+            0
+        };
+
+        SelectObject(memory, previous);
+        DeleteObject(bitmap);
+        DeleteDC(memory);
+        ReleaseDC(0, screen);
+
+        if lines != height {
+            return Err("the screen could not be captured".to_string());
+        }
+
+        super::from_bgra(width as usize, height as usize, width as usize * 4, &data)
+            .ok_or_else(|| "the captured image is incomplete".to_string())
+    }
+}
+
+/// Copies a rectangle of the screen in physical pixels. Windows on top of it are included, as the
+/// user sees them.
+fn capture_screen_rect(x: i32, y: i32, width: i32, height: i32) -> Result<RgbaImage, String> {
+    capture_bitmap(width, height, |memory, screen| unsafe {
+        BitBlt(
+            memory,
+            0,
+            0,
+            width,
+            height,
+            screen,
+            x,
+            y,
+            SRCCOPY | CAPTUREBLT,
+        ) != 0
+    })
+}
+
+pub fn capture_screen() -> Result<RgbaImage, String> {
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    capture_screen_rect(0, 0, width, height)
+}
+
+/// Has the window render itself, so windows in front of it, like the panel, are left out.
+pub fn capture_focused_window() -> Result<RgbaImage, String> {
+    let hwnd = foreground::get_foreground_window().ok_or("no window is focused")?;
+    let frame = get_window_rect(hwnd).ok_or("no window is focused")?;
+    let visible_frame = get_visible_window_rect(hwnd).unwrap_or(frame);
+
+    let mut image = capture_bitmap(
+        frame.width.round() as i32,
+        frame.height.round() as i32,
+        |memory, _| unsafe { PrintWindow(hwnd, memory, PW_RENDERFULLCONTENT) != 0 },
+    )?;
+
+    // `PrintWindow` draws the invisible resize borders too.
+    let x = (visible_frame.x - frame.x).max(0.0) as u32;
+    let y = (visible_frame.y - frame.y).max(0.0) as u32;
+    let width = (visible_frame.width as u32).min(image.width().saturating_sub(x));
+    let height = (visible_frame.height as u32).min(image.height().saturating_sub(y));
+
+    Ok(imageops::crop(&mut image, x, y, width, height).to_image())
+}
+
+pub fn capture_rect(rect: &Rect) -> Result<RgbaImage, String> {
+    capture_screen_rect(
+        rect.x.round() as i32,
+        rect.y.round() as i32,
+        rect.width.round() as i32,
+        rect.height.round() as i32,
+    )
+}
+
+/// How long the Snipping Tool's overlay is given. Pressing escape in it leaves no trace, so a
+/// cancelled selection is only noticed then.
+const SELECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SELECTION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clipboard format of device-independent bitmaps, from `Win32_System_Ole`.
+const CF_DIB: u32 = 8;
+
+/// Turns a device-independent bitmap, as found on the clipboard, into a BMP file by prepending
+/// the file header, which holds where the pixels start.
+fn dib_to_bmp(dib: &[u8]) -> Option<Vec<u8>> {
+    const BI_BITFIELDS: u32 = 3;
+
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            dib.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    let header_size = read_u32(0)?;
+    let bit_count = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let compression = read_u32(16)?;
+    let colors_used = read_u32(32)?;
+
+    // Only the original header is followed by the color masks; later versions contain them.
+    let masks_size = if header_size == 40 && compression == BI_BITFIELDS {
+        12
+    } else {
+        0
+    };
+
+    let colors = match colors_used {
+        0 if bit_count <= 8 => 1 << bit_count,
+        colors_used => colors_used,
+    };
+
+    let pixels_offset = 14 + header_size + masks_size + colors * 4;
+    let file_size = u32::try_from(14 + dib.len()).ok()?;
+
+    let mut bmp = Vec::with_capacity(14 + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&file_size.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&pixels_offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+
+    Some(bmp)
+}
+
+fn read_clipboard_image() -> Option<RgbaImage> {
+    let dib = unsafe {
+        if OpenClipboard(0) == 0 {
+            return None;
+        }
+
+        let data = GetClipboardData(CF_DIB);
+        let pointer = if data == 0 {
+            std::ptr::null_mut()
+        } else {
+            GlobalLock(data)
+        };
+
+        let dib = if pointer.is_null() {
+            None
+        } else {
+            let dib = slice::from_raw_parts(pointer as *const u8, GlobalSize(data)).to_vec();
+            GlobalUnlock(data);
+            Some(dib)
+        };
+
+        CloseClipboard();
+        dib
+    }?;
+
+    image::load_from_memory_with_format(&dib_to_bmp(&dib)?, ImageFormat::Bmp)
+        .ok()
+        .map(|image| image.to_rgba8())
+}
+
+/// Lets the user drag out a region with the Snipping Tool's overlay, which copies it to the
+/// clipboard, replacing what was there. Returns `None` when nothing was copied in time.
+pub async fn select_region() -> Result<Option<RgbaImage>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let sequence_number = unsafe { GetClipboardSequenceNumber() };
+
+        Command::new("explorer.exe")
+            .arg("ms-screenclip:")
+            .spawn()
+            .map_err(|error| format!("the Snipping Tool could not be opened: {}", error))?;
+
+        let started_at = Instant::now();
+
+        while started_at.elapsed() < SELECTION_TIMEOUT {
+            std::thread::sleep(SELECTION_POLL_INTERVAL);
+
+            if unsafe { GetClipboardSequenceNumber() } != sequence_number {
+                // The tool may put other formats on the clipboard before the bitmap.
+                if let Some(image) = read_clipboard_image() {
+                    return Ok(Some(image));
+                }
+            }
+        }
+
+        Ok(None)
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2×1 pixel 32-bit DIB, bottom-up, with a red and a blue pixel.
+    fn dib() -> Vec<u8> {
+        let mut dib = vec![];
+        dib.extend_from_slice(&40u32.to_le_bytes());
+        dib.extend_from_slice(&2i32.to_le_bytes());
+        dib.extend_from_slice(&1i32.to_le_bytes());
+        dib.extend_from_slice(&1u16.to_le_bytes());
+        dib.extend_from_slice(&32u16.to_le_bytes());
+        dib.extend_from_slice(&[0; 24]);
+        dib.extend_from_slice(&[0, 0, 255, 0, 255, 0, 0, 0]);
+        dib
+    }
+
+    #[test]
+    fn dib_to_bmp_points_past_the_header() {
+        let bmp = dib_to_bmp(&dib()).unwrap();
+
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32::from_le_bytes(bmp[2..6].try_into().unwrap()), 62);
+        assert_eq!(u32::from_le_bytes(bmp[10..14].try_into().unwrap()), 54);
+    }
+
+    #[test]
+    fn dib_to_bmp_decodes() {
+        let image =
+            image::load_from_memory_with_format(&dib_to_bmp(&dib()).unwrap(), ImageFormat::Bmp)
+                .unwrap()
+                .to_rgba8();
+
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0[..3], [255, 0, 0]);
+        assert_eq!(image.get_pixel(1, 0).0[..3], [0, 0, 255]);
+    }
+
+    #[test]
+    fn dib_to_bmp_rejects_truncated_headers() {
+        assert_eq!(dib_to_bmp(&[40, 0, 0]), None);
+    }
+}
@@ -1,7 +1,13 @@
 pub mod application;
+pub mod file;
+pub mod window;
+
+use crate::usage::{self, Kind};
 
 pub fn setup(app_handle: tauri::AppHandle) {
-    application::setup(app_handle);
+    application::setup(app_handle.clone());
+    file::setup(app_handle.clone());
+    window::setup(app_handle);
 }
 
 #[tauri::command]
@@ -13,18 +19,61 @@ pub async fn get_installed_applications(app_handle: tauri::AppHandle) -> String
 }
 
 #[tauri::command]
-pub fn get_focused_application(app_handle: tauri::AppHandle) -> String {
-    let application = application::get_focused_application(app_handle);
+pub async fn get_focused_application(app_handle: tauri::AppHandle) -> String {
+    let mut application = application::get_focused_application(app_handle.clone());
+
+    if let Some(application) = application.as_mut() {
+        application::recognize_missing_text(&app_handle, application).await;
+    }
+
     let serialized_result = serde_json::to_string(&application).expect("error deserializing json");
     serialized_result
 }
 
+pub fn get_focused_application_id(app_handle: &tauri::AppHandle) -> Option<String> {
+    application::get_focused_application_id(app_handle.clone())
+}
+
 #[tauri::command]
 pub fn launch_application(app_handle: tauri::AppHandle, id: &str) {
+    let source_application = get_focused_application_id(&app_handle);
     crate::panel::hide_panel(app_handle.clone());
+    usage::record(&app_handle, Kind::Launch, id, source_application.as_deref());
     application::launch_application(id, app_handle);
 }
 
+#[tauri::command]
+pub async fn launch_application_with_options(
+    app_handle: tauri::AppHandle,
+    id: String,
+    options: application::LaunchOptions,
+) -> Result<(), String> {
+    let source_application = get_focused_application_id(&app_handle);
+
+    if !options.background {
+        crate::panel::hide_panel(app_handle.clone());
+    }
+
+    application::launch_application_with_options(&id, app_handle.clone(), options).await?;
+    usage::record(&app_handle, Kind::Launch, &id, source_application.as_deref());
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_application_metadata(
+    app_handle: tauri::AppHandle,
+    id: &str,
+    aliases: Vec<String>,
+    keywords: Vec<String>,
+) {
+    application::set_application_metadata(
+        app_handle,
+        id,
+        application::metadata::Metadata { aliases, keywords },
+    );
+}
+
 #[tauri::command]
 pub fn copy_text_to_clipboard(text: &str) {
     application::copy_text_to_clipboard(text);
@@ -37,10 +86,118 @@ pub fn get_text_from_clipboard() -> String {
 
 #[tauri::command]
 pub fn insert_text(app_handle: tauri::AppHandle, text: &str) {
+    let source_application = get_focused_application_id(&app_handle);
+    usage::record(
+        &app_handle,
+        Kind::Insertion,
+        "insert",
+        source_application.as_deref(),
+    );
     application::insert_text(app_handle, text);
 }
 
 #[tauri::command]
 pub fn replace_text(app_handle: tauri::AppHandle, text: &str) {
+    let source_application = get_focused_application_id(&app_handle);
+    usage::record(
+        &app_handle,
+        Kind::Insertion,
+        "replace",
+        source_application.as_deref(),
+    );
     application::replace_text(app_handle, text);
 }
+
+#[tauri::command]
+pub fn get_running_applications() -> String {
+    let applications = window::get_running_applications();
+    serde_json::to_string(&applications).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn activate_window(
+    app_handle: tauri::AppHandle,
+    pid: i32,
+    window_id: Option<String>,
+) -> Result<(), String> {
+    crate::panel::hide_panel(app_handle);
+    window::activate_window(pid, window_id.as_deref())
+}
+
+#[tauri::command]
+pub fn hide_application(pid: i32) -> Result<(), String> {
+    window::hide_application(pid)
+}
+
+#[tauri::command]
+pub fn quit_application(pid: i32) -> Result<(), String> {
+    window::quit_application(pid)
+}
+
+#[tauri::command]
+pub fn force_quit_application(pid: i32) -> Result<(), String> {
+    window::force_quit_application(pid)
+}
+
+#[tauri::command]
+pub fn manage_window(app_handle: tauri::AppHandle, action: window::Action) -> Result<(), String> {
+    window::manage_window(&app_handle, action)
+}
+
+#[tauri::command]
+pub fn get_window_hotkeys(app_handle: tauri::AppHandle) -> String {
+    let hotkeys = window::get_hotkeys(&app_handle);
+    serde_json::to_string(&hotkeys).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_window_hotkeys(
+    app_handle: tauri::AppHandle,
+    hotkeys: std::collections::HashMap<window::Action, String>,
+) -> Result<(), String> {
+    window::set_hotkeys(&app_handle, hotkeys)
+}
+
+#[tauri::command]
+pub async fn search_files(
+    app_handle: tauri::AppHandle,
+    query: String,
+    limit: Option<usize>,
+    content: Option<bool>,
+) -> String {
+    let matches = tauri::async_runtime::spawn_blocking(move || {
+        file::search_files(&app_handle, &query, limit, content.unwrap_or(false))
+    })
+    .await
+    .unwrap_or_default();
+
+    serde_json::to_string(&matches).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn get_file_search_settings(app_handle: tauri::AppHandle) -> String {
+    let settings = file::get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_file_search_settings(app_handle: tauri::AppHandle, settings: file::Settings) {
+    file::set_settings(&app_handle, settings);
+}
+
+#[tauri::command]
+pub fn open_file(app_handle: tauri::AppHandle, path: &str) -> Result<(), String> {
+    crate::panel::hide_panel(app_handle);
+    file::open_file(path)
+}
+
+#[tauri::command]
+pub fn reveal_file(app_handle: tauri::AppHandle, path: &str) -> Result<(), String> {
+    crate::panel::hide_panel(app_handle);
+    file::reveal_file(path)
+}
+
+#[tauri::command]
+pub fn copy_file_path(path: &str) {
+    application::copy_text_to_clipboard(path);
+}
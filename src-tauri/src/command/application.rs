@@ -3,31 +3,157 @@ extern crate clipboard;
 use clipboard::{ClipboardContext, ClipboardProvider};
 use rdev::{simulate, EventType, Key};
 use serde::{Deserialize, Serialize};
-use std::{thread, time};
+use std::{path::PathBuf, thread, time};
+
+pub mod index;
+pub mod metadata;
 
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "macos")]
 use macos as application;
 
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub mod sidecar;
+
 #[cfg(target_os = "windows")]
 pub mod windows;
 #[cfg(target_os = "windows")]
 use self::windows as application;
 
 pub use application::State;
+pub use index::State as IndexState;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Application {
     id: String,
+    /// Display name in the user's language.
     name: String,
     icon: String,
     path: String,
+    #[serde(default)]
+    version: Option<String>,
+    /// Path of the binary the application bundle or shortcut runs.
+    #[serde(default)]
+    executable: Option<String>,
+    /// Names the application is known by in other languages, excluding `name`.
+    #[serde(default)]
+    localized_names: Vec<String>,
+    #[serde(default)]
+    category: Option<String>,
+    /// User-defined names to launch the application by, from `settings.json`.
+    #[serde(default)]
+    aliases: Vec<String>,
+    /// User-defined search keywords, from `settings.json`.
+    #[serde(default)]
+    keywords: Vec<String>,
     selected_text: Option<String>,
     focused_text: Option<String>,
+    #[serde(default)]
+    context: Context,
+}
+
+/// Where the user is in the focused application, for prompts about "this" page, file or paragraph.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Context {
+    pub window_title: Option<String>,
+    /// Offset of the caret, or of the start of the selection, in characters of `focused_text`.
+    pub caret_position: Option<usize>,
+    /// Up to `CARET_CONTEXT_LENGTH` characters of `focused_text` before the caret or selection.
+    pub text_before_caret: Option<String>,
+    /// Up to `CARET_CONTEXT_LENGTH` characters of `focused_text` after the caret or selection.
+    pub text_after_caret: Option<String>,
+    /// Address of the page shown in the focused browser window.
+    pub url: Option<String>,
+    /// Path of the file open in the focused window.
+    pub document_path: Option<String>,
+}
+
+/// Characters of the focused text kept on each side of the caret.
+const CARET_CONTEXT_LENGTH: usize = 2000;
+
+/// Converts an offset in UTF-16 code units, as the accessibility APIs report them, to one in
+/// characters. Returns `None` past the end or inside a surrogate pair.
+fn to_char_offset(text: &str, utf16_offset: usize) -> Option<usize> {
+    let mut units = 0;
+
+    for (index, character) in text.chars().enumerate() {
+        if units >= utf16_offset {
+            return (units == utf16_offset).then(|| index);
+        }
+
+        units += character.len_utf16();
+    }
+
+    (units == utf16_offset).then(|| text.chars().count())
+}
+
+impl Context {
+    /// Sets the caret position and the text around it from the selected range of `text`, given in
+    /// UTF-16 code units. Ranges that do not fit the text are ignored.
+    pub fn set_caret(&mut self, text: &str, location: usize, length: usize) {
+        let (start, end) = match (
+            to_char_offset(text, location),
+            to_char_offset(text, location + length),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+
+        let characters = text.chars().collect::<Vec<_>>();
+
+        self.caret_position = Some(start);
+        self.text_before_caret = Some(
+            characters[start.saturating_sub(CARET_CONTEXT_LENGTH)..start]
+                .iter()
+                .collect(),
+        );
+        self.text_after_caret = Some(
+            characters[end..(end + CARET_CONTEXT_LENGTH).min(characters.len())]
+                .iter()
+                .collect(),
+        );
+    }
+}
+
+impl Application {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selected_text.as_deref()
+    }
+
+    /// Strings the launcher search matches queries against.
+    pub fn search_terms(&self) -> Vec<&str> {
+        let mut terms = vec![self.name.as_str()];
+
+        for term in self
+            .aliases
+            .iter()
+            .chain(self.localized_names.iter())
+            .chain(self.keywords.iter())
+        {
+            if !terms.contains(&term.as_str()) {
+                terms.push(term);
+            }
+        }
+
+        terms
+    }
 }
 
 pub async fn get_installed_applications(app_handle: tauri::AppHandle) -> Vec<Application> {
+    index::get_installed_applications(app_handle).await
+}
+
+async fn scan_installed_applications(app_handle: tauri::AppHandle) -> Vec<Application> {
     #[cfg(target_os = "macos")]
     return application::get_installed_applications(app_handle);
 
@@ -35,16 +161,128 @@ pub async fn get_installed_applications(app_handle: tauri::AppHandle) -> Vec<App
     application::get_installed_applications(app_handle).await
 }
 
+async fn update_installed_applications(
+    app_handle: tauri::AppHandle,
+    applications: Vec<Application>,
+    changed_paths: &[PathBuf],
+) -> Vec<Application> {
+    #[cfg(target_os = "macos")]
+    return application::update_installed_applications(app_handle, applications, changed_paths);
+
+    #[cfg(target_os = "windows")]
+    application::update_installed_applications(app_handle, applications, changed_paths).await
+}
+
+fn get_watch_paths(app_handle: tauri::AppHandle) -> Vec<PathBuf> {
+    application::get_watch_paths(app_handle)
+}
+
+fn store_icons(app_handle: tauri::AppHandle, applications: Vec<Application>) {
+    application::store_icons(app_handle, applications);
+}
+
+/// How to launch an application beyond simply bringing it up.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchOptions {
+    /// Command-line arguments passed to the application.
+    pub arguments: Vec<String>,
+    /// Files or URLs for the application to open.
+    pub files: Vec<String>,
+    /// Start another instance even if the application is already running (macOS only).
+    pub new_instance: bool,
+    /// Launch without showing any windows.
+    pub hidden: bool,
+    /// Launch without bringing the application to the foreground.
+    pub background: bool,
+}
+
+fn is_url(target: &str) -> bool {
+    match target.split_once(':') {
+        Some((scheme, rest)) => {
+            scheme.len() > 1
+                && scheme
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || "+-.".contains(char))
+                && (rest.starts_with("//") || scheme == "mailto" || scheme == "tel")
+        }
+        None => false,
+    }
+}
+
+/// Resolves file paths to absolute ones, since the application is started in a different
+/// working directory. URLs are passed through untouched.
+fn resolve_files(files: Vec<String>) -> Result<Vec<String>, String> {
+    files
+        .into_iter()
+        .map(|file| {
+            if is_url(&file) {
+                return Ok(file);
+            }
+
+            // `dunce` leaves out the `\\?\` prefix Windows adds, which many applications
+            // cannot open.
+            dunce::canonicalize(&file)
+                .map(|path| path.display().to_string())
+                .map_err(|_| format!("file `{}` does not exist", file))
+        })
+        .collect()
+}
+
 pub fn launch_application(id: &str, app_handle: tauri::AppHandle) {
     application::launch_application(id, app_handle);
 }
 
+pub async fn launch_application_with_options(
+    id: &str,
+    app_handle: tauri::AppHandle,
+    mut options: LaunchOptions,
+) -> Result<(), String> {
+    options.files = resolve_files(options.files)?;
+
+    application::launch_application_with_options(id, app_handle, options).await
+}
+
 pub fn setup(app_handle: tauri::AppHandle) {
-    application::setup(app_handle);
+    application::setup(app_handle.clone());
+    index::setup(app_handle);
 }
 
 pub fn get_focused_application(app_handle: tauri::AppHandle) -> Option<Application> {
-    application::get_focused_application(app_handle)
+    let mut application = application::get_focused_application(app_handle.clone())?;
+    metadata::apply(&app_handle, std::slice::from_mut(&mut application));
+
+    Some(application)
+}
+
+/// Id of the focused application. Unlike `get_focused_application` it reads neither the
+/// application's bundle nor its text, so it is cheap enough to call on every launch.
+pub fn get_focused_application_id(app_handle: tauri::AppHandle) -> Option<String> {
+    application::get_focused_application_id(app_handle)
+}
+
+/// Fills in `focused_text` from the focused window's pixels when the application exposes no
+/// text to the accessibility APIs, as with many Electron, Java and game-engine apps.
+pub async fn recognize_missing_text(app_handle: &tauri::AppHandle, application: &mut Application) {
+    let has_text = [&application.selected_text, &application.focused_text]
+        .iter()
+        .any(|text| {
+            text.as_deref()
+                .map_or(false, |text| !text.trim().is_empty())
+        });
+
+    if !has_text {
+        application.focused_text = crate::ocr::recognize_focused_window(app_handle).await;
+    }
+}
+
+pub fn set_application_metadata(
+    app_handle: tauri::AppHandle,
+    id: &str,
+    metadata: metadata::Metadata,
+) {
+    metadata::set(&app_handle, id, metadata);
+    index::refresh(&app_handle);
 }
 
 fn send_key(event_type: &EventType) {
@@ -57,14 +295,15 @@ pub fn copy_text_to_clipboard(text: &str) {
     ctx.set_contents(text.to_owned()).unwrap();
 }
 
-fn paste_text() {
+/// Presses `key` with command on macOS and control elsewhere.
+fn send_shortcut(key: Key) {
     #[cfg(target_os = "macos")]
     send_key(&EventType::KeyPress(Key::MetaLeft));
     #[cfg(not(target_os = "macos"))]
     send_key(&EventType::KeyPress(Key::ControlLeft));
 
-    send_key(&EventType::KeyPress(Key::KeyV));
-    send_key(&EventType::KeyRelease(Key::KeyV));
+    send_key(&EventType::KeyPress(key));
+    send_key(&EventType::KeyRelease(key));
 
     #[cfg(target_os = "macos")]
     send_key(&EventType::KeyRelease(Key::MetaLeft));
@@ -72,6 +311,42 @@ fn paste_text() {
     send_key(&EventType::KeyRelease(Key::ControlLeft));
 }
 
+fn paste_text() {
+    send_shortcut(Key::KeyV);
+}
+
+/// Copies the selection of the focused application with the copy shortcut and restores the
+/// clipboard after. Returns `None` when nothing is selected.
+pub fn copy_selected_text() -> Option<String> {
+    let mut ctx: ClipboardContext = ClipboardProvider::new().ok()?;
+    let prev_clipboard_contents = ctx.get_contents().unwrap_or_default();
+
+    // Emptied first, so that an empty selection does not return what was copied before.
+    ctx.set_contents(String::new()).ok()?;
+
+    // The hotkey asking for the selection may still be held, which would change the shortcut.
+    for key in [
+        Key::ShiftLeft,
+        Key::ShiftRight,
+        Key::Alt,
+        Key::AltGr,
+        Key::MetaLeft,
+        Key::MetaRight,
+    ] {
+        send_key(&EventType::KeyRelease(key));
+    }
+
+    send_shortcut(Key::KeyC);
+
+    thread::sleep(time::Duration::from_millis(200));
+
+    let copied = ctx.get_contents().unwrap_or_default();
+    ctx.set_contents(prev_clipboard_contents)
+        .unwrap_or_default();
+
+    Some(copied).filter(|text| !text.trim().is_empty())
+}
+
 pub fn replace_text(app_handle: tauri::AppHandle, text_ptr: &str) {
     crate::panel::hide_panel(app_handle);
 
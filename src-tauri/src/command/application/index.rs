@@ -0,0 +1,386 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{mpsc, Mutex},
+    thread, time,
+};
+use tauri::{AppHandle, Manager, Wry};
+
+use super::Application;
+
+/// Time to wait for a burst of filesystem events (e.g. an installer copying a bundle) to settle
+/// before the index is updated.
+const DEBOUNCE_DURATION: time::Duration = time::Duration::from_millis(750);
+
+/// Longest a burst of events can delay an update, so a long-running install still shows up.
+const MAX_DEBOUNCE_DURATION: time::Duration = time::Duration::from_secs(5);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Diff {
+    pub added: Vec<Application>,
+    pub updated: Vec<Application>,
+    pub removed: Vec<String>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Default)]
+pub struct Store {
+    applications: Vec<Application>,
+    is_built: bool,
+    watcher: Option<RecommendedWatcher>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+pub fn diff_applications(previous: &[Application], next: &[Application]) -> Diff {
+    let previous_by_id = previous
+        .iter()
+        .map(|application| (application.id.as_str(), application))
+        .collect::<HashMap<_, _>>();
+
+    let next_by_id = next
+        .iter()
+        .map(|application| (application.id.as_str(), application))
+        .collect::<HashMap<_, _>>();
+
+    let mut diff = Diff::default();
+
+    for application in next {
+        match previous_by_id.get(application.id.as_str()) {
+            None => diff.added.push(application.clone()),
+            Some(previous_application) if *previous_application != application => {
+                diff.updated.push(application.clone())
+            }
+            _ => {}
+        }
+    }
+
+    for application in previous {
+        if !next_by_id.contains_key(application.id.as_str()) {
+            diff.removed.push(application.id.clone());
+        }
+    }
+
+    diff
+}
+
+fn get_index_path(app_handle: &AppHandle<Wry>) -> Option<PathBuf> {
+    app_handle
+        .path_resolver()
+        .app_data_dir()
+        .map(|app_data_dir| app_data_dir.join("application-data").join("index.json"))
+}
+
+fn load(app_handle: &AppHandle<Wry>) -> Option<Vec<Application>> {
+    let data = fs::read_to_string(get_index_path(app_handle)?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn save(app_handle: &AppHandle<Wry>, applications: &[Application]) {
+    if let Some(index_path) = get_index_path(app_handle) {
+        if let Some(parent) = index_path.parent() {
+            fs::create_dir_all(parent).unwrap_or_default();
+        }
+
+        if let Ok(data) = serde_json::to_string(applications) {
+            fs::write(index_path, data).unwrap_or_default();
+        }
+    }
+}
+
+/// Replaces the indexed applications, persists them and pushes the resulting diff to the
+/// webview. Icons are only extracted for applications that are new, changed or missing one.
+fn apply(app_handle: &AppHandle<Wry>, mut applications: Vec<Application>) {
+    super::metadata::apply(app_handle, &mut applications);
+
+    let diff = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        let diff = diff_applications(&store.applications, &applications);
+
+        store.applications = applications;
+        store.is_built = true;
+
+        diff
+    };
+
+    let applications = get_applications(app_handle);
+
+    save(app_handle, &applications);
+
+    let applications_without_icon = applications
+        .iter()
+        .filter(|application| {
+            diff.added
+                .iter()
+                .chain(diff.updated.iter())
+                .any(|changed| changed.id == application.id)
+                || !PathBuf::from(&application.icon).exists()
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if !applications_without_icon.is_empty() {
+        super::store_icons(app_handle.clone(), applications_without_icon);
+    }
+
+    if !diff.is_empty() {
+        app_handle
+            .emit_all("installed_applications_changed", diff)
+            .unwrap_or_default();
+    }
+}
+
+fn get_applications(app_handle: &AppHandle<Wry>) -> Vec<Application> {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .applications
+        .clone()
+}
+
+pub async fn rebuild(app_handle: AppHandle<Wry>) {
+    let applications = super::scan_installed_applications(app_handle.clone()).await;
+    apply(&app_handle, applications);
+}
+
+async fn update(app_handle: AppHandle<Wry>, changed_paths: Vec<PathBuf>) {
+    let applications = super::update_installed_applications(
+        app_handle.clone(),
+        get_applications(&app_handle),
+        &changed_paths,
+    )
+    .await;
+
+    apply(&app_handle, applications);
+}
+
+/// Re-applies user metadata to the indexed applications without rescanning them.
+pub fn refresh(app_handle: &AppHandle<Wry>) {
+    if app_handle.state::<State>().0.lock().unwrap().is_built {
+        apply(app_handle, get_applications(app_handle));
+    }
+}
+
+pub async fn get_installed_applications(app_handle: AppHandle<Wry>) -> Vec<Application> {
+    let is_built = app_handle.state::<State>().0.lock().unwrap().is_built;
+
+    if !is_built {
+        rebuild(app_handle.clone()).await;
+    }
+
+    get_applications(&app_handle)
+}
+
+/// Collects the paths changed in a burst of events, starting with `path`, until none arrive for
+/// `debounce` or `max_wait` has passed since the first one.
+fn collect_changed_paths(
+    rx: &mpsc::Receiver<PathBuf>,
+    path: PathBuf,
+    debounce: time::Duration,
+    max_wait: time::Duration,
+) -> Vec<PathBuf> {
+    let started_at = time::Instant::now();
+    let mut changed_paths = vec![path];
+
+    loop {
+        let remaining = max_wait.saturating_sub(started_at.elapsed());
+
+        if remaining.is_zero() {
+            break;
+        }
+
+        match rx.recv_timeout(debounce.min(remaining)) {
+            Ok(path) => {
+                if !changed_paths.contains(&path) {
+                    changed_paths.push(path);
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    changed_paths
+}
+
+fn watch(app_handle: AppHandle<Wry>) {
+    let (tx, rx) = mpsc::channel::<PathBuf>();
+
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
+                for path in event.paths {
+                    tx.send(path).unwrap_or_default();
+                }
+            }
+        }
+    });
+
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(_) => return,
+    };
+
+    for path in super::get_watch_paths(app_handle.clone()) {
+        watcher
+            .watch(&path, RecursiveMode::Recursive)
+            .unwrap_or_default();
+    }
+
+    app_handle.state::<State>().0.lock().unwrap().watcher = Some(watcher);
+
+    thread::spawn(move || {
+        while let Ok(path) = rx.recv() {
+            let changed_paths =
+                collect_changed_paths(&rx, path, DEBOUNCE_DURATION, MAX_DEBOUNCE_DURATION);
+
+            tauri::async_runtime::block_on(update(app_handle.clone(), changed_paths));
+        }
+    });
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    if let Some(applications) = load(&app_handle) {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.applications = applications;
+        store.is_built = true;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        rebuild(app_handle.clone()).await;
+        watch(app_handle);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn application(id: &str, version: &str) -> Application {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "icon": "",
+            "path": format!("/Applications/{}.app", id),
+            "version": version,
+            "selected_text": null,
+            "focused_text": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn diff_reports_added_updated_and_removed_applications() {
+        let previous = vec![
+            application("com.example.kept", "1.0"),
+            application("com.example.updated", "1.0"),
+            application("com.example.removed", "1.0"),
+        ];
+        let next = vec![
+            application("com.example.kept", "1.0"),
+            application("com.example.updated", "2.0"),
+            application("com.example.added", "1.0"),
+        ];
+
+        let diff = diff_applications(&previous, &next);
+
+        assert_eq!(diff.added, vec![application("com.example.added", "1.0")]);
+        assert_eq!(
+            diff.updated,
+            vec![application("com.example.updated", "2.0")]
+        );
+        assert_eq!(diff.removed, vec!["com.example.removed".to_string()]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_of_unchanged_applications_is_empty() {
+        let applications = vec![
+            application("com.example.first", "1.0"),
+            application("com.example.second", "1.0"),
+        ];
+
+        assert!(diff_applications(&applications, &applications).is_empty());
+        assert!(diff_applications(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn diff_from_empty_index_adds_everything() {
+        let next = vec![application("com.example.first", "1.0")];
+
+        let diff = diff_applications(&[], &next);
+
+        assert_eq!(diff.added, next);
+        assert!(diff.updated.is_empty());
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn collects_burst_without_duplicates() {
+        let (tx, rx) = mpsc::channel();
+
+        tx.send(PathBuf::from("/Applications/A.app")).unwrap();
+        tx.send(PathBuf::from("/Applications/B.app")).unwrap();
+        tx.send(PathBuf::from("/Applications/A.app")).unwrap();
+
+        let changed_paths = collect_changed_paths(
+            &rx,
+            PathBuf::from("/Applications/A.app"),
+            time::Duration::from_millis(10),
+            time::Duration::from_secs(5),
+        );
+
+        assert_eq!(
+            changed_paths,
+            vec![
+                PathBuf::from("/Applications/A.app"),
+                PathBuf::from("/Applications/B.app")
+            ]
+        );
+    }
+
+    #[test]
+    fn flushes_after_max_wait_while_events_keep_arriving() {
+        let (tx, rx) = mpsc::channel();
+
+        let sender = thread::spawn(move || {
+            for index in 0..100 {
+                if tx
+                    .send(PathBuf::from(format!("/Applications/{}.app", index)))
+                    .is_err()
+                {
+                    break;
+                }
+                thread::sleep(time::Duration::from_millis(10));
+            }
+        });
+
+        let started_at = time::Instant::now();
+        let changed_paths = collect_changed_paths(
+            &rx,
+            PathBuf::from("/Applications/first.app"),
+            time::Duration::from_millis(200),
+            time::Duration::from_millis(100),
+        );
+
+        assert!(started_at.elapsed() < time::Duration::from_millis(500));
+        assert!(changed_paths.len() < 101);
+
+        drop(rx);
+        sender.join().unwrap();
+    }
+}
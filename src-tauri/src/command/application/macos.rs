@@ -1,8 +1,9 @@
 use accessibility::{AXAttribute, AXUIElement};
 use accessibility_sys::{
-    kAXChildrenAttribute, kAXComboBoxRole, kAXFocusedAttribute, kAXFocusedUIElementAttribute,
-    kAXRoleAttribute, kAXSelectedTextAttribute, kAXTextAreaRole, kAXTextFieldRole,
-    kAXValueAttribute,
+    kAXChildrenAttribute, kAXComboBoxRole, kAXDocumentAttribute, kAXFocusedAttribute,
+    kAXFocusedUIElementAttribute, kAXParentAttribute, kAXRoleAttribute, kAXSelectedTextAttribute,
+    kAXSelectedTextRangeAttribute, kAXTextAreaRole, kAXTextFieldRole, kAXTitleAttribute,
+    kAXURLAttribute, kAXValueAttribute, kAXValueTypeCFRange, AXValueGetValue, AXValueRef,
 };
 use block::ConcreteBlock;
 use cocoa::appkit::NSApplicationActivationPolicy;
@@ -10,9 +11,10 @@ use cocoa::foundation::NSInteger;
 use cocoa::{base::nil, foundation::NSUInteger};
 use core_foundation::{
     array::CFArray,
-    base::{FromVoid, TCFType, ToVoid},
+    base::{kCFAllocatorDefault, CFRange, FromVoid, TCFType, ToVoid},
     boolean::CFBoolean,
     string::CFString,
+    url::{CFURLCreateWithString, CFURL},
 };
 use directories::ProjectDirs;
 use dirs;
@@ -21,16 +23,30 @@ use objc::{
     runtime::{Class, Object},
     sel, sel_impl,
 };
+use once_cell::sync::Lazy;
 use plist::{Dictionary, Value};
 use regex::Regex;
 use std::sync::Mutex;
 use std::{
     ffi::c_void,
     process::{Command, Stdio},
+    ptr,
 };
 use std::{fs, path::Path, path::PathBuf};
 
-use crate::command::application::Application;
+use crate::command::application::{Application, Context, LaunchOptions};
+use crate::util::macos::get_focused_window;
+
+/// Role of the element browsers render pages in; not among `accessibility_sys`'s constants.
+const WEB_AREA_ROLE: &str = "AXWebArea";
+
+/// Levels of a browser window searched for its web area when focus is outside of it.
+const WEB_AREA_SEARCH_DEPTH: usize = 6;
+
+/// A `"key" = "value";` entry of a `.strings` file, with the key optionally unquoted.
+static STRINGS_ENTRY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)"?([A-Za-z0-9_.\-]+)"?\s*=\s*"((?:[^"\\]|\\.)*)"\s*;"#).unwrap()
+});
 
 #[derive(Default)]
 pub struct State(pub Mutex<Option<()>>);
@@ -80,24 +96,265 @@ fn get_plist(path: &Path) -> Dictionary {
         .clone()
 }
 
+fn get_bool_from_plist(plist: &Dictionary, key: &str) -> bool {
+    match plist.get(key) {
+        Some(Value::Boolean(value)) => *value,
+        Some(Value::Integer(value)) => value.as_signed() == Some(1),
+        Some(Value::String(value)) => value == "1" || value.eq_ignore_ascii_case("yes"),
+        _ => false,
+    }
+}
+
+/// Languages from System Settings, most preferred first (e.g. `en-GB`).
+fn get_preferred_languages() -> Vec<String> {
+    unsafe {
+        let locale_class = Class::get("NSLocale").unwrap();
+        let languages: *mut Object = msg_send![locale_class, preferredLanguages];
+        let count: NSUInteger = msg_send![languages, count];
+
+        (0..count)
+            .filter_map(|index| {
+                let language: *mut Object = msg_send![languages, objectAtIndex: index];
+                let utf8_string_ptr: *const std::os::raw::c_char = msg_send![language, UTF8String];
+
+                if utf8_string_ptr.is_null() {
+                    None
+                } else {
+                    Some(
+                        std::ffi::CStr::from_ptr(utf8_string_ptr)
+                            .to_string_lossy()
+                            .into_owned(),
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+/// Candidate `.lproj` directory names for a language such as `pt-BR`, most specific first.
+fn get_lproj_names(language: &str) -> Vec<String> {
+    let base = language.split('-').next().unwrap_or(language);
+
+    let legacy_name = match base {
+        "en" => "English",
+        "fr" => "French",
+        "de" => "German",
+        "es" => "Spanish",
+        "it" => "Italian",
+        "ja" => "Japanese",
+        "nl" => "Dutch",
+        _ => "",
+    };
+
+    let mut names: Vec<String> = vec![];
+
+    for name in [language, &language.replace('-', "_"), base, legacy_name] {
+        if !name.is_empty() && !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    names
+}
+
+fn unescape_strings_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+
+    unescaped
+}
+
+/// Reads an `InfoPlist.strings` file, which is either a compiled (binary) property list or the
+/// old-style `"key" = "value";` text format in UTF-8 or UTF-16.
+fn get_strings(path: &Path) -> Option<Dictionary> {
+    if let Ok(value) = Value::from_file(path) {
+        return value.into_dictionary();
+    }
+
+    let bytes = fs::read(path).ok()?;
+
+    let text = match bytes.as_slice() {
+        [0xff, 0xfe, rest @ ..] => String::from_utf16_lossy(
+            &rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        [0xfe, 0xff, rest @ ..] => String::from_utf16_lossy(
+            &rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+
+    let mut strings = Dictionary::new();
+
+    for captures in STRINGS_ENTRY.captures_iter(&text) {
+        strings.insert(
+            captures[1].to_string(),
+            Value::String(unescape_strings_value(&captures[2])),
+        );
+    }
+
+    Some(strings)
+}
+
+fn get_localized_name(strings: &Dictionary) -> Option<String> {
+    get_key_from_plist(strings, "CFBundleDisplayName")
+        .or_else(|| get_key_from_plist(strings, "CFBundleName"))
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// `.lproj` directory names for the preferred languages, most preferred first.
+fn get_preferred_lproj_names() -> Vec<String> {
+    let mut lproj_names: Vec<String> = vec![];
+
+    for lproj_name in get_preferred_languages()
+        .iter()
+        .flat_map(|language| get_lproj_names(language))
+    {
+        if !lproj_names.contains(&lproj_name) {
+            lproj_names.push(lproj_name);
+        }
+    }
+
+    lproj_names
+}
+
+/// Display names for the given localizations, in the same order. Newer system applications keep
+/// all of them in a single `InfoPlist.loctable` keyed by localization, the others in an
+/// `InfoPlist.strings` per `.lproj` directory.
+fn get_localized_names(path: &Path, lproj_names: &[String]) -> Vec<(String, String)> {
+    let resources_path = path.join("Contents/Resources");
+
+    let loctable = Value::from_file(resources_path.join("InfoPlist.loctable"))
+        .ok()
+        .and_then(|value| value.into_dictionary());
+
+    lproj_names
+        .iter()
+        .filter_map(|lproj_name| {
+            let localized_name = loctable
+                .as_ref()
+                .and_then(|loctable| loctable.get(lproj_name))
+                .and_then(|strings| strings.as_dictionary())
+                .and_then(get_localized_name)
+                .or_else(|| {
+                    get_strings(
+                        &resources_path
+                            .join(format!("{}.lproj", lproj_name))
+                            .join("InfoPlist.strings"),
+                    )
+                    .as_ref()
+                    .and_then(get_localized_name)
+                })?;
+
+            Some((lproj_name.clone(), localized_name))
+        })
+        .collect()
+}
+
+/// Mirrors how Finder names bundles: the localized `CFBundleDisplayName` for the user's language
+/// when the bundle opts in with `LSHasLocalizedDisplayName`, otherwise the file name.
+fn get_display_name(
+    path: &Path,
+    plist: &Dictionary,
+    localized_names: &[(String, String)],
+) -> String {
+    let file_name = path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or_default()
+        .trim_end_matches(".app")
+        .to_string();
+
+    if get_key_from_plist(plist, "CFBundleIdentifier").as_deref() == Some("com.apple.findmy") {
+        return "Find My".to_string();
+    }
+
+    if !get_bool_from_plist(plist, "LSHasLocalizedDisplayName") {
+        return file_name;
+    }
+
+    localized_names
+        .first()
+        .map(|(_, localized_name)| localized_name.clone())
+        .or_else(|| get_localized_name(plist))
+        .unwrap_or(file_name)
+}
+
+/// Turns an `LSApplicationCategoryType` such as `public.app-category.developer-tools` into
+/// `Developer Tools`.
+fn get_category(plist: &Dictionary) -> Option<String> {
+    let category_type = get_key_from_plist(plist, "LSApplicationCategoryType")?;
+    let slug = category_type.rsplit('.').next()?;
+
+    let category = slug
+        .split('-')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if category.is_empty() {
+        None
+    } else {
+        Some(category)
+    }
+}
+
 fn get_application_from_path(path: &Path) -> Application {
     let plist = get_plist(path);
 
     let bundle_id = get_key_from_plist(&plist, "CFBundleIdentifier").unwrap_or("".to_string());
 
-    let mut name = path
-        .to_str()
-        .unwrap()
-        .split("/")
-        .last()
-        .unwrap()
-        .replace(".app", "")
-        .to_string();
+    let localized_names = get_localized_names(path, &get_preferred_lproj_names());
+
+    let name = get_display_name(path, &plist, &localized_names);
 
-    if bundle_id == "com.apple.findmy" {
-        name = "Find My".to_string()
+    let mut other_names: Vec<String> = vec![];
+
+    for (_, localized_name) in localized_names {
+        if localized_name != name && !other_names.contains(&localized_name) {
+            other_names.push(localized_name);
+        }
     }
 
+    let version = get_key_from_plist(&plist, "CFBundleShortVersionString")
+        .or_else(|| get_key_from_plist(&plist, "CFBundleVersion"));
+
+    let executable = get_key_from_plist(&plist, "CFBundleExecutable").map(|executable| {
+        path.join("Contents/MacOS")
+            .join(executable)
+            .display()
+            .to_string()
+    });
+
+    let category = get_category(&plist);
+
     let icon = get_icon_path(path).unwrap().display().to_string();
 
     let path = path.to_str().unwrap().to_string();
@@ -107,21 +364,26 @@ fn get_application_from_path(path: &Path) -> Application {
         name,
         icon,
         path,
+        version,
+        executable,
+        localized_names: other_names,
+        category,
+        aliases: vec![],
+        keywords: vec![],
         selected_text: None,
         focused_text: None,
+        context: Context::default(),
     }
 }
 
 pub fn get_application_paths() -> Vec<PathBuf> {
-    vec![
-        Path::new("/System/Library/CoreServices/Finder.app"),
-        Path::new("/Applications/"),
-        Path::new("/System/Applications/"),
-        &dirs::home_dir().unwrap().join("Applications"),
-    ]
-    .iter()
-    .flat_map(|path| search_for_applications(path))
-    .collect::<Vec<_>>()
+    let mut root_paths = vec![PathBuf::from("/System/Library/CoreServices/Finder.app")];
+    root_paths.extend(get_root_paths());
+
+    root_paths
+        .iter()
+        .flat_map(|path| search_for_applications(path))
+        .collect::<Vec<_>>()
 }
 
 fn get_icon_path(application_path: &Path) -> Option<PathBuf> {
@@ -169,22 +431,31 @@ fn save_icon_as_png(icns_path: &Path, output_path: &Path) {
         .expect("failed to execute process");
 }
 
-fn store_icons(app_handle: tauri::AppHandle) {
+pub fn store_icons(_app_handle: tauri::AppHandle, applications: Vec<Application>) {
     if let Some(project_dir) = ProjectDirs::from("com", "lander", "Lander") {
         let project_data_path = project_dir.data_local_dir();
         let project_icons_path = project_data_path.join("application-data/icons");
 
         if fs::create_dir_all(&project_icons_path).is_ok() {
-            let application_paths = get_application_paths();
+            for application in applications {
+                let application_path = Path::new(&application.path);
 
-            for application_path in application_paths {
-                if let Some(application_icon_path) = get_icon_icns_path(&application_path) {
-                    if let Some(project_icon_path) = get_icon_path(&application_path) {
+                if let Some(application_icon_path) = get_icon_icns_path(application_path) {
+                    if let Some(project_icon_path) = get_icon_path(application_path) {
                         save_icon_as_png(&application_icon_path, &project_icon_path);
                     }
                 }
             }
+        }
+    }
+}
 
+fn copy_bundled_icons(app_handle: tauri::AppHandle) {
+    if let Some(project_dir) = ProjectDirs::from("com", "lander", "Lander") {
+        let project_data_path = project_dir.data_local_dir();
+        let project_icons_path = project_data_path.join("application-data/icons");
+
+        if fs::create_dir_all(&project_icons_path).is_ok() {
             let bundled_icons_path = app_handle
                 .path_resolver()
                 .resolve_resource("assets/application-data/icons")
@@ -209,16 +480,89 @@ fn store_icons(app_handle: tauri::AppHandle) {
     }
 }
 
+fn get_root_paths() -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("/Applications/"),
+        PathBuf::from("/System/Applications/"),
+        dirs::home_dir().unwrap().join("Applications"),
+    ]
+}
+
+pub fn get_watch_paths(_app_handle: tauri::AppHandle) -> Vec<PathBuf> {
+    get_root_paths()
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// The application bundle `path` is in. Bundles nest helper applications (e.g.
+/// `Contents/Frameworks/Helper.app`), so this is the outermost one.
+fn get_bundle_path(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .filter(|ancestor| ancestor.display().to_string().ends_with(".app"))
+        .last()
+        .map(|ancestor| ancestor.to_path_buf())
+}
+
+fn is_application_bundle(path: &Path) -> bool {
+    path.join("Contents/Info.plist").is_file()
+}
+
+fn sort_applications(applications: &mut Vec<Application>) {
+    applications.sort_unstable_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+}
+
+/// Re-reads only the bundles touched by `changed_paths`. Changes outside of a bundle, such as
+/// a new folder of applications, fall back to a full scan.
+pub fn update_installed_applications(
+    app_handle: tauri::AppHandle,
+    mut applications: Vec<Application>,
+    changed_paths: &[PathBuf],
+) -> Vec<Application> {
+    let mut bundle_paths: Vec<PathBuf> = vec![];
+
+    for changed_path in changed_paths {
+        match get_bundle_path(changed_path) {
+            Some(bundle_path) => {
+                if !bundle_paths.contains(&bundle_path) {
+                    bundle_paths.push(bundle_path);
+                }
+            }
+            None => return get_installed_applications(app_handle),
+        }
+    }
+
+    for bundle_path in bundle_paths {
+        let bundle_path_string = bundle_path.to_str().unwrap().to_string();
+
+        applications.retain(|application| application.path != bundle_path_string);
+
+        if is_application_bundle(&bundle_path) {
+            let application = get_application_from_path(&bundle_path);
+
+            if application.id != "com.lander.Lander" {
+                applications.retain(|existing| existing.id != application.id);
+                applications.push(application);
+            }
+        }
+    }
+
+    sort_applications(&mut applications);
+
+    applications
+}
+
 pub fn get_installed_applications(_app_handle: tauri::AppHandle) -> Vec<Application> {
     let application_paths = get_application_paths();
 
     let mut applications: Vec<Application> = application_paths
         .iter()
+        .filter(|path| is_application_bundle(path))
         .map(|path| get_application_from_path(&path))
         .filter(|application| application.id != "com.lander.Lander")
         .collect::<Vec<_>>();
 
-    applications.sort_unstable_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    sort_applications(&mut applications);
 
     applications
 }
@@ -231,6 +575,40 @@ pub fn launch_application(id: &str, _app_handle: tauri::AppHandle) {
         .expect("failed to execute process");
 }
 
+pub async fn launch_application_with_options(
+    id: &str,
+    _app_handle: tauri::AppHandle,
+    options: LaunchOptions,
+) -> Result<(), String> {
+    let mut command = Command::new("open");
+    command.arg("-b").arg(id);
+
+    if options.new_instance {
+        command.arg("-n");
+    }
+
+    if options.background {
+        command.arg("-g");
+    }
+
+    if options.hidden {
+        command.arg("-j");
+    }
+
+    command.args(&options.files);
+
+    if !options.arguments.is_empty() {
+        command.arg("--args").args(&options.arguments);
+    }
+
+    command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
 pub fn get_focused_ns_application() -> Option<*mut Object> {
     unsafe {
         let workspace_class = Class::get("NSWorkspace").unwrap();
@@ -295,32 +673,30 @@ fn search_for_focused_element(element: &AXUIElement) -> Option<AXUIElement> {
     None
 }
 
+fn get_focused_element(focused_app: &AXUIElement) -> Option<AXUIElement> {
+    match focused_app.attribute(&AXAttribute::new(&CFString::new(
+        kAXFocusedUIElementAttribute,
+    ))) {
+        Ok(val) => val.downcast::<AXUIElement>(),
+        Err(_) => search_for_focused_element(focused_app),
+    }
+}
+
+fn get_string_attribute(element: &AXUIElement, attribute: &str) -> Option<String> {
+    element
+        .attribute(&AXAttribute::new(&CFString::new(attribute)))
+        .ok()?
+        .downcast::<CFString>()
+        .map(|value| value.to_string())
+}
+
 fn get_selected_text_from_ns_application(ns_application: *mut Object) -> Option<String> {
     let pid: i32 = unsafe { msg_send![ns_application, processIdentifier] };
 
     let focused_app = AXUIElement::application(pid);
+    let focused_element = get_focused_element(&focused_app)?;
 
-    let focused_element = match focused_app.attribute(&AXAttribute::new(&CFString::new(
-        kAXFocusedUIElementAttribute,
-    ))) {
-        Ok(val) => val.downcast::<AXUIElement>().unwrap(),
-        Err(_) => {
-            if let Some(focused_element) = search_for_focused_element(&focused_app) {
-                focused_element
-            } else {
-                return None;
-            }
-        }
-    };
-
-    let selected_text = match focused_element
-        .attribute(&AXAttribute::new(&CFString::new(kAXSelectedTextAttribute)))
-    {
-        Ok(val) => val.downcast::<CFString>().unwrap().to_string(),
-        Err(_) => {
-            return None;
-        }
-    };
+    let selected_text = get_string_attribute(&focused_element, kAXSelectedTextAttribute)?;
 
     if selected_text.is_empty() {
         return None;
@@ -329,50 +705,185 @@ fn get_selected_text_from_ns_application(ns_application: *mut Object) -> Option<
     return Some(selected_text);
 }
 
+fn is_text_element(element: &AXUIElement) -> bool {
+    match get_string_attribute(element, kAXRoleAttribute) {
+        Some(role) => {
+            role == kAXTextFieldRole || role == kAXTextAreaRole || role == kAXComboBoxRole
+        }
+        None => false,
+    }
+}
+
 fn get_focused_element_text_value_from_ns_application(
     ns_application: *mut Object,
 ) -> Option<String> {
     let pid: i32 = unsafe { msg_send![ns_application, processIdentifier] };
 
     let focused_app = AXUIElement::application(pid);
+    let focused_element = get_focused_element(&focused_app)?;
 
-    let focused_element = match focused_app.attribute(&AXAttribute::new(&CFString::new(
-        kAXFocusedUIElementAttribute,
-    ))) {
-        Ok(val) => val.downcast::<AXUIElement>().unwrap(),
-        Err(_) => {
-            if let Some(focused_element) = search_for_focused_element(&focused_app) {
-                focused_element
-            } else {
-                return None;
+    if !is_text_element(&focused_element) {
+        return None;
+    }
+
+    get_string_attribute(&focused_element, kAXValueAttribute)
+}
+
+/// Selected range of a text element in UTF-16 code units.
+fn get_selected_text_range(element: &AXUIElement) -> Option<CFRange> {
+    let value = element
+        .attribute(&AXAttribute::new(&CFString::new(
+            kAXSelectedTextRangeAttribute,
+        )))
+        .ok()?;
+
+    let mut range = CFRange::init(0, 0);
+
+    let has_range = unsafe {
+        AXValueGetValue(
+            value.as_CFTypeRef() as AXValueRef,
+            kAXValueTypeCFRange,
+            &mut range as *mut CFRange as *mut c_void,
+        )
+    };
+
+    if has_range && range.location >= 0 && range.length >= 0 {
+        Some(range)
+    } else {
+        None
+    }
+}
+
+fn get_url_attribute(element: &AXUIElement) -> Option<String> {
+    element
+        .attribute(&AXAttribute::new(&CFString::new(kAXURLAttribute)))
+        .ok()?
+        .downcast::<CFURL>()
+        .map(|url| url.get_string().to_string())
+}
+
+fn get_children(element: &AXUIElement) -> Vec<AXUIElement> {
+    match element.attribute(&AXAttribute::new(&CFString::new(kAXChildrenAttribute))) {
+        Ok(val) => match val.downcast::<CFArray>() {
+            Some(children) => children
+                .get_all_values()
+                .into_iter()
+                .map(|child| unsafe { AXUIElement::from_void(child).to_owned() })
+                .collect(),
+            None => vec![],
+        },
+        Err(_) => vec![],
+    }
+}
+
+/// Browsers expose the page's address on their `AXWebArea`. It is usually an ancestor of the
+/// focused element; when the address bar has focus, it is looked up in the window instead,
+/// breadth first and only a few levels deep, as web areas are near the top of browser windows.
+fn get_page_url(focused_element: Option<&AXUIElement>, window: &AXUIElement) -> Option<String> {
+    let mut element = focused_element.cloned();
+
+    while let Some(current) = element {
+        if get_string_attribute(&current, kAXRoleAttribute).as_deref() == Some(WEB_AREA_ROLE) {
+            return get_url_attribute(&current);
+        }
+
+        element = current
+            .attribute(&AXAttribute::new(&CFString::new(kAXParentAttribute)))
+            .ok()
+            .and_then(|parent| parent.downcast::<AXUIElement>());
+    }
+
+    let mut level = vec![window.clone()];
+
+    for _ in 0..WEB_AREA_SEARCH_DEPTH {
+        let mut next_level = vec![];
+
+        for element in level {
+            if get_string_attribute(&element, kAXRoleAttribute).as_deref() == Some(WEB_AREA_ROLE) {
+                if let Some(url) = get_url_attribute(&element) {
+                    return Some(url);
+                }
             }
+
+            next_level.extend(get_children(&element));
         }
+
+        level = next_level;
+    }
+
+    None
+}
+
+/// Windows of document-based applications report their file as a `file://` URL.
+fn get_document_path(window: &AXUIElement) -> Option<String> {
+    let url = CFString::new(&get_string_attribute(window, kAXDocumentAttribute)?);
+
+    let url = unsafe {
+        let url_ref =
+            CFURLCreateWithString(kCFAllocatorDefault, url.as_concrete_TypeRef(), ptr::null());
+
+        if url_ref.is_null() {
+            return None;
+        }
+
+        CFURL::wrap_under_create_rule(url_ref)
     };
 
-    let element_role =
-        match focused_element.attribute(&AXAttribute::new(&CFString::new(kAXRoleAttribute))) {
-            Ok(val) => val.downcast::<CFString>().unwrap().to_string(),
-            Err(_) => {
-                return None;
-            }
-        };
+    url.to_path().map(|path| path.display().to_string())
+}
 
-    if element_role != kAXTextFieldRole
-        && element_role != kAXTextAreaRole
-        && element_role != kAXComboBoxRole
-    {
-        return None;
+fn get_context_from_ns_application(
+    ns_application: *mut Object,
+    focused_text: Option<&str>,
+) -> Context {
+    let pid: i32 = unsafe { msg_send![ns_application, processIdentifier] };
+
+    let focused_element = get_focused_element(&AXUIElement::application(pid));
+    let mut context = Context::default();
+
+    if let (Some(element), Some(text)) = (&focused_element, focused_text) {
+        if let Some(range) = get_selected_text_range(element) {
+            context.set_caret(text, range.location as usize, range.length as usize);
+        }
     }
 
-    let text = match focused_element.attribute(&AXAttribute::new(&CFString::new(kAXValueAttribute)))
-    {
-        Ok(val) => val.downcast::<CFString>().unwrap().to_string(),
-        Err(_) => {
+    if let Some(window) = get_focused_window(pid) {
+        context.window_title =
+            get_string_attribute(&window, kAXTitleAttribute).filter(|title| !title.is_empty());
+        context.url = get_page_url(focused_element.as_ref(), &window);
+        context.document_path = get_document_path(&window);
+    }
+
+    context
+}
+
+/// Bundle identifier of the frontmost application, without reading its bundle or text.
+pub fn get_focused_application_id(_app_handle: tauri::AppHandle) -> Option<String> {
+    let ns_application = get_focused_ns_application()?;
+
+    let bundle_id = unsafe {
+        let bundle_identifier: *mut Object = msg_send![ns_application, bundleIdentifier];
+
+        if bundle_identifier.is_null() {
             return None;
         }
+
+        let utf8_string_ptr: *const std::os::raw::c_char = msg_send![bundle_identifier, UTF8String];
+
+        if utf8_string_ptr.is_null() {
+            return None;
+        }
+
+        std::ffi::CStr::from_ptr(utf8_string_ptr)
+            .to_string_lossy()
+            .into_owned()
     };
 
-    return Some(text);
+    if bundle_id == "com.lander.Lander" {
+        return None;
+    }
+
+    Some(bundle_id)
 }
 
 pub fn get_focused_application(_app_handle: tauri::AppHandle) -> Option<Application> {
@@ -387,6 +898,8 @@ pub fn get_focused_application(_app_handle: tauri::AppHandle) -> Option<Applicat
 
     application.selected_text = get_selected_text_from_ns_application(ns_application);
     application.focused_text = get_focused_element_text_value_from_ns_application(ns_application);
+    application.context =
+        get_context_from_ns_application(ns_application, application.focused_text.as_deref());
 
     Some(application)
 }
@@ -467,6 +980,55 @@ fn register_application_accessibility_listener() {
 }
 
 pub fn setup(app_handle: tauri::AppHandle) {
-    store_icons(app_handle);
+    copy_bundled_icons(app_handle);
     register_application_accessibility_listener();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/applications/Localized.app")
+    }
+
+    #[test]
+    fn lproj_names_go_from_specific_to_legacy() {
+        assert_eq!(get_lproj_names("pt-BR"), vec!["pt-BR", "pt_BR", "pt"]);
+        assert_eq!(get_lproj_names("en"), vec!["en", "English"]);
+    }
+
+    #[test]
+    fn localized_names_come_from_the_loctable_and_strings_files() {
+        let lproj_names = ["fr", "pt-BR", "pt_BR", "de", "ja"].map(String::from);
+
+        assert_eq!(
+            get_localized_names(&fixture_path(), &lproj_names),
+            vec![
+                ("fr".to_string(), "Localisé".to_string()),
+                ("pt_BR".to_string(), "Buscar".to_string()),
+                ("de".to_string(), "Wo ist?".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn localized_names_skip_other_languages() {
+        assert!(get_localized_names(&fixture_path(), &["it".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn bundle_paths_are_the_outermost_application() {
+        assert_eq!(
+            get_bundle_path(Path::new(
+                "/Applications/Slack.app/Contents/Frameworks/Slack Helper.app/Contents/Info.plist"
+            )),
+            Some(PathBuf::from("/Applications/Slack.app"))
+        );
+        assert_eq!(
+            get_bundle_path(Path::new("/Applications/Utilities/Terminal.app")),
+            Some(PathBuf::from("/Applications/Utilities/Terminal.app"))
+        );
+        assert_eq!(get_bundle_path(Path::new("/Applications/Utilities")), None);
+    }
+}
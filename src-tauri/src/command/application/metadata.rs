@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use super::Application;
+
+const SETTINGS_KEY: &str = "application_metadata";
+
+/// Search metadata the user attached to an application.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub aliases: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+impl Metadata {
+    fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && self.keywords.is_empty()
+    }
+}
+
+fn clean(values: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = vec![];
+
+    for value in values {
+        let value = value.trim().to_string();
+
+        if !value.is_empty() && !cleaned.contains(&value) {
+            cleaned.push(value);
+        }
+    }
+
+    cleaned
+}
+
+pub fn get_all(app_handle: &AppHandle<Wry>) -> HashMap<String, Metadata> {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get(SETTINGS_KEY)
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+pub fn set(app_handle: &AppHandle<Wry>, id: &str, metadata: Metadata) {
+    let metadata = Metadata {
+        aliases: clean(metadata.aliases),
+        keywords: clean(metadata.keywords),
+    };
+
+    let mut all_metadata = get_all(app_handle);
+
+    if metadata.is_empty() {
+        all_metadata.remove(id);
+    } else {
+        all_metadata.insert(id.to_string(), metadata);
+    }
+
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert(SETTINGS_KEY.to_string(), serde_json::json!(all_metadata))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+/// Copies the user's aliases and keywords onto `applications`, clearing stale ones.
+pub fn apply(app_handle: &AppHandle<Wry>, applications: &mut [Application]) {
+    let all_metadata = get_all(app_handle);
+
+    for application in applications {
+        let metadata = all_metadata
+            .get(&application.id)
+            .cloned()
+            .unwrap_or_default();
+
+        application.aliases = metadata.aliases;
+        application.keywords = metadata.keywords;
+    }
+}
@@ -0,0 +1,325 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tauri::api::process::{Command, CommandChild, CommandEvent};
+use tokio::sync::oneshot;
+
+/// Version of the line-delimited JSON-RPC protocol spoken by the `applications` sidecar. Bump it
+/// together with the sidecar whenever a method or its payload changes shape.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum Error {
+    Spawn(String),
+    Timeout,
+    Crashed,
+    Protocol(String),
+    Remote { code: i64, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Spawn(message) => write!(f, "failed to spawn sidecar: {}", message),
+            Error::Timeout => write!(f, "sidecar request timed out"),
+            Error::Crashed => write!(f, "sidecar exited before responding"),
+            Error::Protocol(message) => write!(f, "invalid sidecar response: {}", message),
+            Error::Remote { code, message } => write!(f, "sidecar error {}: {}", code, message),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    id: Option<u64>,
+    result: Option<Value>,
+    error: Option<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct InitializeResult {
+    protocol_version: u64,
+}
+
+pub enum Program {
+    /// Binary bundled through `tauri.conf.json` > `externalBin`.
+    Sidecar(String),
+    /// Arbitrary executable, used to run against the mock sidecar.
+    Path(PathBuf),
+}
+
+struct Connection {
+    child: CommandChild,
+    is_alive: Arc<AtomicBool>,
+    /// Task reading responses, which fails the pending requests once the process exits.
+    reader: tauri::async_runtime::JoinHandle<()>,
+}
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, Error>>>>>;
+
+/// Client for a long-lived sidecar process. The process is spawned on the first request and
+/// respawned transparently on the next request after it exits or crashes.
+pub struct Client {
+    program: Program,
+    connection: tokio::sync::Mutex<Option<Connection>>,
+    pending: Pending,
+    next_id: AtomicU64,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        match std::env::var("LANDER_APPLICATIONS_SIDECAR") {
+            Ok(path) => Self::new(Program::Path(PathBuf::from(path))),
+            Err(_) => Self::new(Program::Sidecar("applications".to_string())),
+        }
+    }
+}
+
+fn handle_line(pending: &Pending, line: &str) {
+    let response = match serde_json::from_str::<Response>(line.trim()) {
+        Ok(response) => response,
+        Err(_) => return,
+    };
+
+    let sender = match response.id {
+        Some(id) => pending.lock().unwrap().remove(&id),
+        None => None,
+    };
+
+    if let Some(sender) = sender {
+        let result = match (response.result, response.error) {
+            (_, Some(error)) => Err(Error::Remote {
+                code: error.code,
+                message: error.message,
+            }),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        };
+
+        sender.send(result).ok();
+    }
+}
+
+impl Client {
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            connection: tokio::sync::Mutex::new(None),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn command(&self) -> Result<Command, Error> {
+        let command = match &self.program {
+            Program::Sidecar(name) => {
+                Command::new_sidecar(name).map_err(|error| Error::Spawn(error.to_string()))?
+            }
+            Program::Path(path) => Command::new(path.display().to_string()),
+        };
+
+        Ok(command.args(["serve"]))
+    }
+
+    fn register(&self) -> (u64, oneshot::Receiver<Result<Value, Error>>) {
+        let (tx, rx) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+
+        self.pending.lock().unwrap().insert(id, tx);
+
+        (id, rx)
+    }
+
+    async fn wait(
+        &self,
+        id: u64,
+        rx: oneshot::Receiver<Result<Value, Error>>,
+        timeout: Duration,
+    ) -> Result<Value, Error> {
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(Error::Crashed),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    fn encode(id: u64, method: &str, params: Value) -> Vec<u8> {
+        let request = Request {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        };
+
+        let mut line = serde_json::to_vec(&request).unwrap();
+        line.push(b'\n');
+        line
+    }
+
+    async fn spawn(&self) -> Result<Connection, Error> {
+        let (mut rx, mut child) = self
+            .command()?
+            .spawn()
+            .map_err(|error| Error::Spawn(error.to_string()))?;
+
+        let is_alive = Arc::new(AtomicBool::new(true));
+
+        let reader_pending = Arc::clone(&self.pending);
+        let reader_is_alive = Arc::clone(&is_alive);
+
+        let reader = tauri::async_runtime::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(line) => handle_line(&reader_pending, &line),
+                    CommandEvent::Terminated(_) => break,
+                    _ => {}
+                }
+            }
+
+            reader_is_alive.store(false, Ordering::SeqCst);
+
+            for (_, sender) in reader_pending.lock().unwrap().drain() {
+                sender.send(Err(Error::Crashed)).ok();
+            }
+        });
+
+        if let Err(error) = self.initialize(&mut child).await {
+            child.kill().unwrap_or_default();
+            return Err(error);
+        }
+
+        Ok(Connection {
+            child,
+            is_alive,
+            reader,
+        })
+    }
+
+    async fn initialize(&self, child: &mut CommandChild) -> Result<(), Error> {
+        let (id, response) = self.register();
+
+        if let Err(error) = child.write(&Self::encode(
+            id,
+            "initialize",
+            json!({ "protocol_version": PROTOCOL_VERSION }),
+        )) {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(Error::Spawn(error.to_string()));
+        }
+
+        let result = self.wait(id, response, REQUEST_TIMEOUT).await?;
+        let initialize_result = serde_json::from_value::<InitializeResult>(result)
+            .map_err(|error| Error::Protocol(error.to_string()))?;
+
+        if initialize_result.protocol_version != PROTOCOL_VERSION {
+            return Err(Error::Protocol(format!(
+                "expected protocol version {}, sidecar speaks {}",
+                PROTOCOL_VERSION, initialize_result.protocol_version
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Writes `line` to the sidecar, spawning it first if needed, and returns the liveness flag
+    /// that identifies the process written to.
+    async fn write(&self, line: &[u8]) -> Result<Arc<AtomicBool>, Error> {
+        let mut connection = self.connection.lock().await;
+
+        let is_alive = connection
+            .as_ref()
+            .map(|connection| connection.is_alive.load(Ordering::SeqCst))
+            .unwrap_or(false);
+
+        if !is_alive {
+            *connection = Some(self.spawn().await?);
+        }
+
+        let connection = connection.as_mut().unwrap();
+
+        connection.child.write(line).map_err(|_| Error::Crashed)?;
+
+        Ok(Arc::clone(&connection.is_alive))
+    }
+
+    /// Kills the sidecar that a request timed out on, as it may be stuck, so that the next
+    /// request spawns a new one. Requests still waiting on it fail as crashed before that.
+    async fn kill(&self, is_alive: &Arc<AtomicBool>) {
+        let mut connection = self.connection.lock().await;
+
+        let is_same_process = connection.as_ref().map_or(false, |connection| {
+            Arc::ptr_eq(&connection.is_alive, is_alive)
+        });
+
+        if is_same_process {
+            if let Some(connection) = connection.take() {
+                connection.child.kill().unwrap_or_default();
+                connection.reader.await.unwrap_or_default();
+            }
+        }
+    }
+
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, Error> {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT)
+            .await
+    }
+
+    pub async fn request_with_timeout<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<T, Error> {
+        let (id, response) = self.register();
+
+        let is_alive = match self.write(&Self::encode(id, method, params)).await {
+            Ok(is_alive) => is_alive,
+            Err(error) => {
+                self.pending.lock().unwrap().remove(&id);
+                return Err(error);
+            }
+        };
+
+        let result = match self.wait(id, response, timeout).await {
+            Err(Error::Timeout) => {
+                self.kill(&is_alive).await;
+                return Err(Error::Timeout);
+            }
+            result => result?,
+        };
+
+        serde_json::from_value(result).map_err(|error| Error::Protocol(error.to_string()))
+    }
+}
@@ -1,12 +1,15 @@
 use serde::Deserialize;
+use serde_json::json;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::{fs, hash::Hasher, path::Path, path::PathBuf};
-use tauri::api::process::CommandEvent;
-use tauri::{api::process::Command, Manager};
+use tauri::Manager;
 use twox_hash::XxHash64;
 
-use crate::command::application::Application;
+use crate::command::application::{sidecar, Application, Context, LaunchOptions};
+use crate::command::window::windows::get_window_title;
+
+pub mod foreground;
 
 #[derive(Clone, Debug, Deserialize)]
 struct WindowsApplication {
@@ -15,12 +18,34 @@ struct WindowsApplication {
     path: String,
     selected_text: Option<String>,
     uwp: bool,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    executable: Option<String>,
+    /// The executable's `FileDescription`, or the package's display name in other languages.
+    #[serde(default)]
+    display_names: Vec<String>,
+    #[serde(default)]
+    category: Option<String>,
+    /// Text of the focused element, read through its UI Automation text pattern.
+    #[serde(default)]
+    focused_text: Option<String>,
+    /// Start and length of the selection, or the caret, in UTF-16 code units of `focused_text`.
+    #[serde(default)]
+    selection_start: Option<usize>,
+    #[serde(default)]
+    selection_length: Option<usize>,
+    /// Address bar contents of a focused browser window.
+    #[serde(default)]
+    url: Option<String>,
 }
 
 #[derive(Default)]
 pub struct Store {
     application_ids: HashMap<String, String>,
     pub focused_application: Option<Application>,
+    windows_applications: HashMap<String, WindowsApplication>,
+    sidecar: Arc<sidecar::Client>,
 }
 
 #[derive(Default)]
@@ -44,6 +69,10 @@ fn get_start_menu_app_id(application_path: &Path, app_handle: tauri::AppHandle)
     hash.to_string()
 }
 
+fn get_sidecar(app_handle: &tauri::AppHandle) -> Arc<sidecar::Client> {
+    Arc::clone(&app_handle.state::<State>().0.lock().unwrap().sidecar)
+}
+
 fn get_application_data_icon_path(app_handle: tauri::AppHandle) -> Option<PathBuf> {
     if let Some(app_data_path) = app_handle.path_resolver().app_data_dir() {
         let application_data_icon_path = app_data_path.join(r"application-data\icons");
@@ -76,13 +105,49 @@ fn get_application_from_windows_application(
 
     let selected_text = windows_application.selected_text;
 
+    let mut localized_names: Vec<String> = vec![];
+
+    for display_name in windows_application.display_names {
+        let display_name = display_name.trim().to_string();
+
+        if !display_name.is_empty()
+            && display_name != name
+            && !localized_names.contains(&display_name)
+        {
+            localized_names.push(display_name);
+        }
+    }
+
+    let mut context = Context {
+        url: windows_application.url,
+        ..Context::default()
+    };
+
+    if let (Some(text), Some(start)) = (
+        &windows_application.focused_text,
+        windows_application.selection_start,
+    ) {
+        context.set_caret(
+            text,
+            start,
+            windows_application.selection_length.unwrap_or(0),
+        );
+    }
+
     Application {
         id,
         name,
         icon,
         path,
+        version: windows_application.version,
+        executable: windows_application.executable,
+        localized_names,
+        category: windows_application.category,
+        aliases: vec![],
+        keywords: vec![],
         selected_text,
-        focused_text: None,
+        focused_text: windows_application.focused_text,
+        context,
     }
 }
 
@@ -96,73 +161,94 @@ fn get_application_icon_path(app_handle: tauri::AppHandle, app_id: String) -> Op
     }
 }
 
-fn save_icon_as_png(input_path: &str, output_path: &Path) {
-    Command::new_sidecar("applications")
-        .expect("failed to create `applications` binary command")
-        .args([
-            "extract-icon",
-            &format!("{}", input_path),
-            &format!("{}", output_path.to_str().unwrap()),
-        ])
-        .spawn()
-        .unwrap();
+async fn save_icon_as_png(app_handle: tauri::AppHandle, input_path: &str, output_path: &Path) {
+    get_sidecar(&app_handle)
+        .request::<()>(
+            "extract_icon",
+            json!({ "input": input_path, "output": output_path.to_str().unwrap() }),
+        )
+        .await
+        .unwrap_or_default();
 }
 
-async fn store_icons(app_handle: tauri::AppHandle) {
-    if let Some(application_data_icon_path) = get_application_data_icon_path(app_handle.clone()) {
-        if fs::create_dir_all(&application_data_icon_path).is_ok() {
-            let windows_applications = get_windows_applications().await;
-
-            for windows_application in windows_applications {
-                let target_icon_path = if windows_application.uwp {
-                    windows_application.id.clone()
-                } else {
-                    windows_application.path.clone()
-                };
-
-                let icon_id = if windows_application.uwp {
-                    windows_application.id
-                } else {
-                    get_start_menu_app_id(Path::new(&windows_application.path), app_handle.clone())
-                };
-
-                if let Some(project_icon_path) =
-                    get_application_icon_path(app_handle.clone(), icon_id.clone())
-                {
-                    save_icon_as_png(&target_icon_path, &project_icon_path);
+pub fn store_icons(app_handle: tauri::AppHandle, applications: Vec<Application>) {
+    tauri::async_runtime::spawn(async move {
+        if let Some(application_data_icon_path) = get_application_data_icon_path(app_handle.clone())
+        {
+            if fs::create_dir_all(&application_data_icon_path).is_ok() {
+                let windows_applications = applications
+                    .iter()
+                    .filter_map(|application| {
+                        app_handle
+                            .state::<State>()
+                            .0
+                            .lock()
+                            .unwrap()
+                            .windows_applications
+                            .get(&application.id)
+                            .cloned()
+                    })
+                    .collect::<Vec<_>>();
+
+                for windows_application in windows_applications {
+                    let target_icon_path = if windows_application.uwp {
+                        windows_application.id.clone()
+                    } else {
+                        windows_application.path.clone()
+                    };
+
+                    let icon_id = if windows_application.uwp {
+                        windows_application.id
+                    } else {
+                        get_start_menu_app_id(
+                            Path::new(&windows_application.path),
+                            app_handle.clone(),
+                        )
+                    };
+
+                    if let Some(project_icon_path) =
+                        get_application_icon_path(app_handle.clone(), icon_id.clone())
+                    {
+                        save_icon_as_png(app_handle.clone(), &target_icon_path, &project_icon_path)
+                            .await;
+                    }
                 }
             }
         }
-    }
+    });
 }
 
-async fn get_windows_applications() -> Vec<WindowsApplication> {
-    let (mut rx, _child) = Command::new_sidecar("applications")
-        .expect("failed to create `applications` binary command")
-        .args(vec!["list"])
-        .spawn()
-        .unwrap();
+async fn get_windows_applications(app_handle: tauri::AppHandle) -> Vec<WindowsApplication> {
+    let windows_applications = get_sidecar(&app_handle)
+        .request::<Vec<WindowsApplication>>("list", json!({}))
+        .await
+        .unwrap_or_default();
 
-    while let Some(event) = rx.recv().await {
-        if let CommandEvent::Stdout(data) = event {
-            let windows_applications: Vec<WindowsApplication> =
-                serde_json::from_str(&data).unwrap();
+    let windows_applications = windows_applications
+        .iter()
+        .filter(|application| {
+            application.name != "Lander" && application.name != "Lander (Preview)"
+        })
+        .cloned()
+        .collect::<Vec<_>>();
 
-            return windows_applications
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .windows_applications
+        .extend(
+            windows_applications
                 .iter()
-                .filter(|application| {
-                    application.name != "Lander" || application.name != "Lander (Preview)"
-                })
-                .cloned()
-                .collect::<Vec<_>>();
-        }
-    }
+                .map(|application| (application.id.clone(), application.clone())),
+        );
 
-    vec![]
+    windows_applications
 }
 
 pub async fn get_installed_applications(app_handle: tauri::AppHandle) -> Vec<Application> {
-    let windows_application = get_windows_applications().await;
+    let windows_application = get_windows_applications(app_handle.clone()).await;
 
     return windows_application
         .iter()
@@ -175,62 +261,142 @@ pub async fn get_installed_applications(app_handle: tauri::AppHandle) -> Vec<App
         .collect::<Vec<_>>();
 }
 
+pub fn get_watch_paths(_app_handle: tauri::AppHandle) -> Vec<PathBuf> {
+    vec![
+        std::env::var("ProgramData")
+            .map(|path| PathBuf::from(path).join(r"Microsoft\Windows\Start Menu\Programs")),
+        std::env::var("APPDATA")
+            .map(|path| PathBuf::from(path).join(r"Microsoft\Windows\Start Menu\Programs")),
+    ]
+    .into_iter()
+    .filter_map(|path| path.ok())
+    .filter(|path| path.is_dir())
+    .collect()
+}
+
+/// Start menu shortcuts and packaged apps can't be mapped back to individual entries from a
+/// changed path, so any change re-lists everything through the sidecar and the index diffs it.
+pub async fn update_installed_applications(
+    app_handle: tauri::AppHandle,
+    _applications: Vec<Application>,
+    _changed_paths: &[PathBuf],
+) -> Vec<Application> {
+    get_installed_applications(app_handle).await
+}
+
+/// Start menu shortcuts are listed under a hash of their path, which maps back to the path
+/// until the next listing; other ids are launched as they are.
+fn get_launch_target(id: &str, app_handle: &tauri::AppHandle) -> Result<String, String> {
+    match id.parse::<f64>() {
+        Ok(_) => app_handle
+            .state::<State>()
+            .0
+            .lock()
+            .unwrap()
+            .application_ids
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("`{}` is not an installed application", id)),
+        Err(_) => Ok(id.to_string()),
+    }
+}
+
 pub fn launch_application(id: &str, app_handle: tauri::AppHandle) {
-    let target = match id.parse::<f64>() {
-        Ok(_) => {
-            let state_binding = app_handle.state::<State>();
-            let state = state_binding.0.lock().unwrap();
-            state.application_ids.get(id).unwrap().to_string()
-        }
-        Err(_) => id.to_string(),
+    let target = match get_launch_target(id, &app_handle) {
+        Ok(target) => target,
+        Err(_) => return,
     };
 
-    Command::new_sidecar("applications")
-        .expect("failed to create `applications` binary command")
-        .args(vec!["open", &target])
-        .spawn()
-        .unwrap();
+    let sidecar = get_sidecar(&app_handle);
+
+    tauri::async_runtime::spawn(async move {
+        sidecar
+            .request::<()>("open", json!({ "target": target }))
+            .await
+            .unwrap_or_default();
+    });
 }
 
-pub fn setup(app_handle: tauri::AppHandle) {
-    tauri::async_runtime::spawn(async {
-        store_icons(app_handle).await;
+/// `new_instance` is ignored: Windows starts a new process for every launch unless the
+/// application itself forwards to a running instance.
+pub async fn launch_application_with_options(
+    id: &str,
+    app_handle: tauri::AppHandle,
+    options: LaunchOptions,
+) -> Result<(), String> {
+    let target = get_launch_target(id, &app_handle)?;
+
+    get_sidecar(&app_handle)
+        .request::<()>(
+            "launch",
+            json!({
+                "target": target,
+                "arguments": options.arguments,
+                "files": options.files,
+                "hidden": options.hidden,
+                "background": options.background,
+            }),
+        )
+        .await
+        .map_err(|error| error.to_string())
+}
+
+pub fn setup(_app_handle: tauri::AppHandle) {
+    foreground::register_foreground_listener();
+}
+
+/// Captures the focused application and its selection once, right before the panel takes
+/// focus. The target window comes from the foreground event hook rather than polling, and the
+/// sidecar answers concurrently, so the request does not wait behind a listing or icon extraction.
+pub async fn capture_focused_application(app_handle: tauri::AppHandle) {
+    let window = foreground::get_foreground_window();
+
+    let windows_application = get_sidecar(&app_handle)
+        .request_with_timeout::<Option<WindowsApplication>>(
+            "focused_application",
+            json!({ "window": window }),
+            std::time::Duration::from_millis(1000),
+        )
+        .await
+        .unwrap_or_default();
+
+    let focused_application = windows_application.map(|windows_application| {
+        get_application_from_windows_application(app_handle.clone(), windows_application)
     });
+
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .focused_application = focused_application;
 }
 
-pub async fn set_focused_application(app_handle: tauri::AppHandle) {
-    let (mut rx, _child) = Command::new_sidecar("applications")
-        .expect("failed to create `applications` binary command")
-        .args(vec!["focused-application"])
-        .spawn()
-        .unwrap();
-
-    while let Some(event) = rx.recv().await {
-        if let CommandEvent::Stdout(data) = event {
-            if let Ok(windows_application) = serde_json::from_str::<WindowsApplication>(&data) {
-                let focused_application = Some(get_application_from_windows_application(
-                    app_handle.clone(),
-                    windows_application,
-                ));
-
-                app_handle
-                    .state::<State>()
-                    .0
-                    .lock()
-                    .unwrap()
-                    .focused_application = focused_application;
-            }
-        }
-    }
+/// Id of the application captured before the panel took focus.
+pub fn get_focused_application_id(app_handle: tauri::AppHandle) -> Option<String> {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .focused_application
+        .as_ref()
+        .map(|application| application.id.clone())
 }
 
 pub fn get_focused_application(app_handle: tauri::AppHandle) -> Option<Application> {
-    app_handle
+    let mut application = app_handle
         .clone()
         .state::<State>()
         .0
         .lock()
         .unwrap()
         .focused_application
-        .clone()
+        .clone()?;
+
+    application.context.window_title = foreground::get_foreground_window()
+        .map(get_window_title)
+        .filter(|title| !title.is_empty());
+
+    Some(application)
 }
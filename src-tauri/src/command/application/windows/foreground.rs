@@ -0,0 +1,68 @@
+use std::{
+    sync::atomic::{AtomicIsize, Ordering},
+    thread,
+};
+use windows_sys::Win32::{
+    Foundation::HWND,
+    UI::{
+        Accessibility::{SetWinEventHook, HWINEVENTHOOK},
+        WindowsAndMessaging::{
+            DispatchMessageW, GetForegroundWindow, GetMessageW, TranslateMessage,
+            EVENT_SYSTEM_FOREGROUND, MSG, OBJID_WINDOW, WINEVENT_OUTOFCONTEXT,
+            WINEVENT_SKIPOWNPROCESS,
+        },
+    },
+};
+
+/// Last window outside of Lander that received focus, updated by the foreground event hook.
+static FOREGROUND_WINDOW: AtomicIsize = AtomicIsize::new(0);
+
+unsafe extern "system" fn handle_foreground_change(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    if hwnd != 0 && id_object == OBJID_WINDOW {
+        FOREGROUND_WINDOW.store(hwnd, Ordering::SeqCst);
+    }
+}
+
+pub fn get_foreground_window() -> Option<HWND> {
+    match FOREGROUND_WINDOW.load(Ordering::SeqCst) {
+        0 => None,
+        hwnd => Some(hwnd),
+    }
+}
+
+pub fn register_foreground_listener() {
+    FOREGROUND_WINDOW.store(unsafe { GetForegroundWindow() }, Ordering::SeqCst);
+
+    thread::spawn(|| unsafe {
+        // Out-of-context hooks are delivered through the message queue of the thread that
+        // registered them, so it has to keep pumping messages for as long as the app runs.
+        let hook = SetWinEventHook(
+            EVENT_SYSTEM_FOREGROUND,
+            EVENT_SYSTEM_FOREGROUND,
+            0,
+            Some(handle_foreground_change),
+            0,
+            0,
+            WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+        );
+
+        if hook == 0 {
+            return;
+        }
+
+        let mut message: MSG = std::mem::zeroed();
+
+        while GetMessageW(&mut message, 0, 0, 0) > 0 {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    });
+}
@@ -0,0 +1,617 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::{Ordering, Reverse},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::search::fuzzy;
+
+pub mod index;
+
+pub use index::State;
+
+const DEFAULT_LIMIT: usize = 50;
+
+/// Content matches shown per file.
+const MAX_CONTENT_MATCHES: usize = 3;
+
+/// Characters of a matching line kept around the match.
+const MAX_LINE_LENGTH: usize = 200;
+
+/// Bytes sniffed for NUL characters to tell binary files apart from text.
+const BINARY_SNIFF_LENGTH: usize = 8000;
+
+/// Bytes of file contents one search reads at most, so a broad query over a large index stays
+/// responsive.
+const MAX_CONTENT_SEARCH_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Score of files that only match by content, below any subsequence match of the name.
+const CONTENT_SCORE: f64 = 300.0;
+
+const RECENT_BOOST: f64 = 20.0;
+const WEEK: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Directories to index.
+    pub roots: Vec<PathBuf>,
+    /// Gitignore-style globs excluded on top of `.gitignore` and `.ignore` files.
+    pub exclude: Vec<String>,
+    pub include_hidden: bool,
+    pub respect_ignore_files: bool,
+    pub max_depth: usize,
+    /// Indexing stops once this many entries are collected.
+    pub max_entries: usize,
+    /// Files larger than this are skipped by content search.
+    pub max_content_size: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let roots = dirs::home_dir()
+            .map(|home_dir| {
+                ["Desktop", "Documents", "Downloads"]
+                    .iter()
+                    .map(|name| home_dir.join(name))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            roots,
+            exclude: vec![
+                "node_modules".to_string(),
+                "target".to_string(),
+                "__pycache__".to_string(),
+                "*.photoslibrary".to_string(),
+            ],
+            include_hidden: false,
+            respect_ignore_files: true,
+            max_depth: 12,
+            max_entries: 200_000,
+            max_content_size: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEntry {
+    path: String,
+    name: String,
+    is_dir: bool,
+    size: u64,
+    /// Seconds since the Unix epoch.
+    modified: u64,
+}
+
+impl FileEntry {
+    fn from_path(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        Some(Self {
+            path: path.display().to_string(),
+            name: path.file_name()?.to_string_lossy().into_owned(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentMatch {
+    /// 1-based line number.
+    line: usize,
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMatch {
+    #[serde(flatten)]
+    entry: FileEntry,
+    score: f64,
+    /// Character ranges into `name`, or into `path` when the query contains a separator.
+    highlights: Vec<(usize, usize)>,
+    content_matches: Vec<ContentMatch>,
+}
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("file_search")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+pub fn set_settings(app_handle: &AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("file_search".to_string(), serde_json::json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+
+    index::rebuild(app_handle.clone());
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_path_query(query: &str) -> bool {
+    query.contains('/') || query.contains(std::path::MAIN_SEPARATOR)
+}
+
+fn match_entry(entry: &FileEntry, query: &str, now: u64) -> Option<FileMatch> {
+    let fuzzy_match = if is_path_query(query) {
+        fuzzy::fuzzy_match(&entry.path, query)?
+    } else {
+        fuzzy::fuzzy_match(&entry.name, query)?
+    };
+
+    let boost = if now.saturating_sub(entry.modified) < WEEK {
+        RECENT_BOOST
+    } else {
+        0.0
+    };
+
+    Some(FileMatch {
+        entry: entry.clone(),
+        score: fuzzy_match.score + boost,
+        highlights: fuzzy_match.highlights,
+        content_matches: vec![],
+    })
+}
+
+fn read_text(path: &Path, max_size: u64) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+
+    if file.metadata().ok()?.len() > max_size {
+        return None;
+    }
+
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes).ok()?;
+
+    if bytes
+        .iter()
+        .take(BINARY_SNIFF_LENGTH)
+        .any(|byte| *byte == 0)
+    {
+        return None;
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+fn truncate_line(line: &str, match_index: usize) -> String {
+    let line = line.trim_end();
+
+    if line.chars().count() <= MAX_LINE_LENGTH {
+        return line.trim_start().to_string();
+    }
+
+    let start = match_index.saturating_sub(MAX_LINE_LENGTH / 2);
+
+    line.chars()
+        .skip(start)
+        .take(MAX_LINE_LENGTH)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Case-insensitive search for `query` in a text file, returning the first few matching lines.
+fn search_content(path: &Path, query: &str, max_size: u64) -> Vec<ContentMatch> {
+    let text = match read_text(path, max_size) {
+        Some(text) => text,
+        None => return vec![],
+    };
+
+    let query = query.to_lowercase();
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let lowercase_line = line.to_lowercase();
+            let byte_index = lowercase_line.find(&query)?;
+            let match_index = lowercase_line[..byte_index].chars().count();
+
+            Some(ContentMatch {
+                line: index + 1,
+                text: truncate_line(line, match_index),
+            })
+        })
+        .take(MAX_CONTENT_MATCHES)
+        .collect()
+}
+
+fn sort_matches(matches: &mut [FileMatch]) {
+    matches.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b.entry.modified.cmp(&a.entry.modified))
+    });
+}
+
+/// Adds the matching lines of each text file in `matches`, then appends the files of `entries`
+/// that only match by content. Stops once `limit` files match, `budget` bytes were read or
+/// `is_current` turns false.
+fn add_content_matches(
+    matches: &mut Vec<FileMatch>,
+    entries: &mut [FileEntry],
+    query: &str,
+    limit: usize,
+    max_content_size: u64,
+    mut budget: u64,
+    is_current: impl Fn() -> bool,
+) {
+    let mut read_content = |entry: &FileEntry| {
+        if entry.is_dir || entry.size > max_content_size || entry.size > budget {
+            return vec![];
+        }
+
+        budget -= entry.size;
+        search_content(Path::new(&entry.path), query, max_content_size)
+    };
+
+    for file_match in matches.iter_mut() {
+        file_match.content_matches = read_content(&file_match.entry);
+    }
+
+    // Files matching only by content share a score and are ordered by modification time, so
+    // searching the most recently modified first allows stopping at `limit`.
+    entries.sort_by_key(|entry| Reverse(entry.modified));
+
+    for entry in entries.iter() {
+        if matches.len() >= limit || !is_current() {
+            break;
+        }
+
+        if matches
+            .iter()
+            .any(|file_match| file_match.entry.path == entry.path)
+        {
+            continue;
+        }
+
+        let content_matches = read_content(entry);
+
+        if !content_matches.is_empty() {
+            matches.push(FileMatch {
+                entry: entry.clone(),
+                score: CONTENT_SCORE,
+                highlights: vec![],
+                content_matches,
+            });
+        }
+    }
+}
+
+/// Fuzzy-matches file and folder names, or full paths when the query contains a separator.
+/// With `content`, text files whose contents contain the query are returned as well. Content
+/// search stops once `limit` files match, its byte budget is spent or a newer search started.
+pub fn search_files(
+    app_handle: &AppHandle<Wry>,
+    query: &str,
+    limit: Option<usize>,
+    content: bool,
+) -> Vec<FileMatch> {
+    let query = query.trim();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let generation = index::start_search(app_handle);
+
+    if query.is_empty() {
+        return vec![];
+    }
+
+    let settings = get_settings(app_handle);
+    let mut entries = index::get_entries(app_handle);
+    let now = now();
+
+    let mut matches = entries
+        .iter()
+        .filter_map(|entry| match_entry(entry, query, now))
+        .collect::<Vec<_>>();
+
+    sort_matches(&mut matches);
+    matches.truncate(limit);
+
+    if !content {
+        return matches;
+    }
+
+    add_content_matches(
+        &mut matches,
+        &mut entries,
+        query,
+        limit,
+        settings.max_content_size,
+        MAX_CONTENT_SEARCH_SIZE,
+        || index::is_current_search(app_handle, generation),
+    );
+
+    matches
+}
+
+fn get_existing_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+
+    if path.exists() {
+        Ok(path)
+    } else {
+        Err(format!("`{}` does not exist", path.display()))
+    }
+}
+
+pub fn open_file(path: &str) -> Result<(), String> {
+    let path = get_existing_path(path)?;
+
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+
+    #[cfg(target_os = "windows")]
+    let mut command = Command::new("explorer");
+
+    command
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+pub fn reveal_file(path: &str) -> Result<(), String> {
+    let path = get_existing_path(path)?;
+
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-R").arg(path).spawn();
+
+    #[cfg(target_os = "windows")]
+    let result = Command::new("explorer")
+        .arg(format!("/select,{}", path.display()))
+        .spawn();
+
+    result.map(|_| ()).map_err(|error| error.to_string())
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    index::setup(app_handle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 100 * WEEK;
+
+    fn entry(path: &str, modified: u64) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            name: Path::new(path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned(),
+            is_dir: false,
+            size: 0,
+            modified,
+        }
+    }
+
+    fn ranked(entries: &[FileEntry], query: &str) -> Vec<String> {
+        let mut matches = entries
+            .iter()
+            .filter_map(|entry| match_entry(entry, query, NOW))
+            .collect::<Vec<_>>();
+
+        sort_matches(&mut matches);
+
+        matches
+            .into_iter()
+            .map(|file_match| file_match.entry.path)
+            .collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lander-file-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    /// Writes a file and returns its entry, modified at `modified`.
+    fn write(dir: &Path, name: &str, contents: &[u8], modified: u64) -> FileEntry {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+
+        FileEntry {
+            modified,
+            ..FileEntry::from_path(&path).unwrap()
+        }
+    }
+
+    #[test]
+    fn names_rank_by_match_quality_then_recency() {
+        let entries = [
+            entry("/docs/old report.pdf", 0),
+            entry("/docs/receipt export.md", 0),
+            entry("/docs/report.pdf", 0),
+            entry("/docs/unrelated.txt", 0),
+            entry("/archive/report.pdf", 10),
+        ];
+
+        assert_eq!(
+            ranked(&entries, "report"),
+            vec![
+                "/archive/report.pdf",
+                "/docs/report.pdf",
+                "/docs/old report.pdf",
+                "/docs/receipt export.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn recently_modified_files_are_boosted() {
+        let old = entry("/docs/report.pdf", 0);
+        let recent = entry("/docs/reports.pdf", NOW - WEEK + 1);
+
+        let old_score = match_entry(&old, "report", NOW).unwrap().score;
+        let recent_score = match_entry(&recent, "report", NOW).unwrap().score;
+
+        assert_eq!(
+            recent_score,
+            fuzzy::fuzzy_match("reports.pdf", "report").unwrap().score + RECENT_BOOST
+        );
+        assert!(recent_score > old_score);
+    }
+
+    #[test]
+    fn queries_with_a_separator_match_the_path() {
+        let entries = [
+            entry("/projects/lander/notes.md", 0),
+            entry("/archive/notes.md", 0),
+        ];
+
+        assert_eq!(
+            ranked(&entries, "lander/notes"),
+            vec!["/projects/lander/notes.md"]
+        );
+        assert!(ranked(&entries, "lander").is_empty());
+
+        let file_match = match_entry(&entries[0], "lander/notes", NOW).unwrap();
+        assert_eq!(file_match.highlights, vec![(10, 22)]);
+    }
+
+    #[test]
+    fn content_search_returns_the_first_matching_lines() {
+        let dir = temp_dir("lines");
+        let long_line = format!("{}Needle{}", "a".repeat(300), "b".repeat(300));
+        let file = write(
+            &dir,
+            "notes.txt",
+            format!(
+                "one NEEDLE\ntwo\n  three needle  \n{}\nfive needle\n",
+                long_line
+            )
+            .as_bytes(),
+            0,
+        );
+
+        let content_matches = search_content(Path::new(&file.path), "needle", 1024 * 1024);
+
+        assert_eq!(
+            content_matches
+                .iter()
+                .map(|content_match| content_match.line)
+                .collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+        assert_eq!(content_matches[1].text, "three needle");
+        assert_eq!(content_matches[2].text.chars().count(), MAX_LINE_LENGTH);
+        assert!(content_matches[2].text.contains("Needle"));
+
+        // Too large to read, or binary.
+        assert!(search_content(Path::new(&file.path), "needle", 10).is_empty());
+
+        let binary = write(&dir, "image.bin", b"needle\0needle", 0);
+        assert!(search_content(Path::new(&binary.path), "needle", 1024).is_empty());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn content_search_stops_at_its_limits() {
+        let dir = temp_dir("limits");
+        let mut entries = vec![
+            write(&dir, "a.txt", b"needle", 4),
+            write(&dir, "b.txt", b"needle and more", 3),
+            write(&dir, "c.txt", b"needle", 2),
+            write(&dir, "d.txt", b"nothing", 1),
+        ];
+
+        let search = |entries: &mut [FileEntry], limit, max_content_size, budget| {
+            let mut matches = vec![];
+
+            add_content_matches(
+                &mut matches,
+                entries,
+                "needle",
+                limit,
+                max_content_size,
+                budget,
+                || true,
+            );
+
+            matches
+                .into_iter()
+                .map(|file_match| file_match.entry.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            search(&mut entries, 10, 100, 100),
+            vec!["a.txt", "b.txt", "c.txt"]
+        );
+        assert_eq!(search(&mut entries, 2, 100, 100), vec!["a.txt", "b.txt"]);
+        assert_eq!(search(&mut entries, 10, 10, 100), vec!["a.txt", "c.txt"]);
+
+        // The budget covers `a.txt` and `c.txt`, but not `b.txt` after `a.txt`.
+        assert_eq!(search(&mut entries, 10, 100, 12), vec!["a.txt", "c.txt"]);
+
+        let mut matches = vec![];
+        add_content_matches(&mut matches, &mut entries, "needle", 10, 100, 100, || false);
+        assert!(matches.is_empty());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn name_matches_get_their_content_matches() {
+        let dir = temp_dir("names");
+        let mut entries = vec![
+            write(&dir, "needle.txt", b"first needle", 2),
+            write(&dir, "other.txt", b"second needle", 1),
+        ];
+        let mut matches = vec![match_entry(&entries[0], "needle", NOW).unwrap()];
+
+        add_content_matches(&mut matches, &mut entries, "needle", 10, 100, 100, || true);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].entry.name, "needle.txt");
+        assert_eq!(matches[0].content_matches[0].text, "first needle");
+        assert_eq!(matches[1].entry.name, "other.txt");
+        assert_eq!(matches[1].score, CONTENT_SCORE);
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,559 @@
+use ignore::{
+    gitignore::Gitignore,
+    overrides::{Override, OverrideBuilder},
+    WalkBuilder,
+};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+    sync::{mpsc, Mutex},
+    thread, time,
+};
+use tauri::{AppHandle, Manager, Wry};
+
+use super::{FileEntry, Settings};
+
+/// Time to wait for a burst of filesystem events (e.g. a checkout or an unzip) to settle before
+/// the index is updated.
+const DEBOUNCE_DURATION: time::Duration = time::Duration::from_millis(750);
+
+#[derive(Default)]
+pub struct Store {
+    entries: HashMap<String, FileEntry>,
+    is_built: bool,
+    watcher: Option<RecommendedWatcher>,
+    /// Incremented by every search, so content search for a superseded query stops early.
+    search_generation: u64,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+fn build_overrides(settings: &Settings, root: &Path) -> Result<Override, ignore::Error> {
+    let mut overrides = OverrideBuilder::new(root);
+
+    for pattern in &settings.exclude {
+        overrides.add(&format!("!{}", pattern)).ok();
+    }
+
+    overrides.build()
+}
+
+/// Walks `path` below `root`, honoring hidden files, ignore files and the excluded globs, and
+/// returns everything except `path` itself.
+fn walk(settings: &Settings, root: &Path, path: &Path, max_depth: usize) -> Vec<FileEntry> {
+    let mut builder = WalkBuilder::new(path);
+
+    builder
+        .hidden(!settings.include_hidden)
+        .ignore(settings.respect_ignore_files)
+        .git_ignore(settings.respect_ignore_files)
+        .git_global(settings.respect_ignore_files)
+        .git_exclude(settings.respect_ignore_files)
+        .parents(settings.respect_ignore_files)
+        .require_git(false)
+        .follow_links(false)
+        .max_depth(Some(max_depth));
+
+    if let Ok(overrides) = build_overrides(settings, root) {
+        builder.overrides(overrides);
+    }
+
+    builder
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.depth() > 0)
+        .filter_map(|entry| FileEntry::from_path(entry.path()))
+        .take(settings.max_entries)
+        .collect()
+}
+
+/// Whether the ignore files of the directories above `path` exclude it. As in a walk, the
+/// closest directory decides and its `.ignore` file takes precedence over its `.gitignore`.
+fn is_ignored_by_files(path: &Path, is_dir: bool) -> bool {
+    for directory in path.ancestors().skip(1) {
+        for name in [".ignore", ".gitignore"] {
+            let (gitignore, _) = Gitignore::new(directory.join(name));
+            let matched = gitignore.matched(path, is_dir);
+
+            if matched.is_ignore() {
+                return true;
+            }
+
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+    }
+
+    false
+}
+
+/// Dot files, and on Windows files with the hidden attribute, as a walk judges them.
+fn is_hidden(path: &Path) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+        if std::fs::metadata(path).map_or(false, |metadata| {
+            metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+        }) {
+            return true;
+        }
+    }
+
+    path.file_name()
+        .map_or(false, |name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Whether a walk of `root` would skip `path`, assuming it does not skip its parent.
+fn is_excluded(settings: &Settings, root: &Path, path: &Path, is_dir: bool) -> bool {
+    if !settings.include_hidden && is_hidden(path) {
+        return true;
+    }
+
+    let is_overridden = build_overrides(settings, root).map_or(false, |overrides| {
+        overrides.matched(path, is_dir).is_ignore()
+    });
+
+    is_overridden || (settings.respect_ignore_files && is_ignored_by_files(path, is_dir))
+}
+
+/// Entries for a changed path below `root`: the path itself, unless a walk would skip it, and
+/// everything below it when it is a directory.
+fn changed_entries(settings: &Settings, root: &Path, path: &Path) -> Vec<FileEntry> {
+    let depth = path
+        .strip_prefix(root)
+        .map(|relative_path| relative_path.components().count())
+        .unwrap_or(0);
+
+    if depth == 0 || depth > settings.max_depth {
+        return vec![];
+    }
+
+    let entry = match FileEntry::from_path(path) {
+        Some(entry) => entry,
+        None => return vec![],
+    };
+
+    if is_excluded(settings, root, path, entry.is_dir) {
+        return vec![];
+    }
+
+    let mut entries = if entry.is_dir {
+        walk(settings, root, path, settings.max_depth - depth)
+    } else {
+        vec![]
+    };
+
+    entries.insert(0, entry);
+    entries
+}
+
+fn get_root<'a>(settings: &'a Settings, path: &Path) -> Option<&'a PathBuf> {
+    settings.roots.iter().find(|root| path.starts_with(root))
+}
+
+fn build(app_handle: &AppHandle<Wry>) {
+    let settings = super::get_settings(app_handle);
+    let mut entries = HashMap::new();
+
+    for root in &settings.roots {
+        for entry in walk(&settings, root, root, settings.max_depth) {
+            if entries.len() >= settings.max_entries {
+                break;
+            }
+
+            entries.insert(entry.path.clone(), entry);
+        }
+    }
+
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    store.entries = entries;
+    store.is_built = true;
+}
+
+/// Re-indexes a changed path. Paths whose parent is not indexed are inside an excluded or
+/// ignored directory (or too deep) and are dropped.
+fn update(app_handle: &AppHandle<Wry>, settings: &Settings, changed_paths: Vec<PathBuf>) {
+    for path in changed_paths {
+        let root = match get_root(settings, &path) {
+            Some(root) => root,
+            None => continue,
+        };
+
+        let key = path.display().to_string();
+        let prefix = format!("{}{}", key, MAIN_SEPARATOR);
+
+        let is_parent_indexed = {
+            let state = app_handle.state::<State>();
+            let mut store = state.0.lock().unwrap();
+
+            store
+                .entries
+                .retain(|entry_key, _| entry_key != &key && !entry_key.starts_with(&prefix));
+
+            match path.parent() {
+                Some(parent) if parent == root.as_path() => true,
+                Some(parent) => store.entries.contains_key(&parent.display().to_string()),
+                None => false,
+            }
+        };
+
+        if !is_parent_indexed {
+            continue;
+        }
+
+        let entries = changed_entries(settings, root, &path);
+
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        for entry in entries {
+            if store.entries.len() >= settings.max_entries {
+                break;
+            }
+
+            store.entries.insert(entry.path.clone(), entry);
+        }
+    }
+}
+
+pub fn get_entries(app_handle: &AppHandle<Wry>) -> Vec<FileEntry> {
+    let is_built = app_handle.state::<State>().0.lock().unwrap().is_built;
+
+    if !is_built {
+        build(app_handle);
+    }
+
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .entries
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// Starts a search and returns its generation, which stops being current once the next one
+/// starts.
+pub fn start_search(app_handle: &AppHandle<Wry>) -> u64 {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    store.search_generation += 1;
+    store.search_generation
+}
+
+pub fn is_current_search(app_handle: &AppHandle<Wry>, generation: u64) -> bool {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .search_generation
+        == generation
+}
+
+/// Maps a path from a filesystem event back below the configured root it belongs to. Events
+/// carry resolved paths (e.g. `/private/var` for `/var` on macOS), while the index is keyed by
+/// the roots as configured.
+fn to_root_path(roots: &[(PathBuf, PathBuf)], path: PathBuf) -> PathBuf {
+    roots
+        .iter()
+        .find_map(|(canonical_root, root)| {
+            path.strip_prefix(canonical_root)
+                .ok()
+                .map(|relative_path| root.join(relative_path))
+        })
+        .unwrap_or(path)
+}
+
+fn watch(app_handle: AppHandle<Wry>) {
+    let (tx, rx) = mpsc::channel::<PathBuf>();
+
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
+                for path in event.paths {
+                    tx.send(path).unwrap_or_default();
+                }
+            }
+        }
+    });
+
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(_) => return,
+    };
+
+    let roots = super::get_settings(&app_handle)
+        .roots
+        .into_iter()
+        .map(|root| {
+            (
+                dunce::canonicalize(&root).unwrap_or_else(|_| root.clone()),
+                root,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (canonical_root, _) in &roots {
+        watcher
+            .watch(canonical_root, RecursiveMode::Recursive)
+            .unwrap_or_default();
+    }
+
+    // Replacing the watcher drops the previous one, which closes its channel and ends the
+    // thread below.
+    app_handle.state::<State>().0.lock().unwrap().watcher = Some(watcher);
+
+    thread::spawn(move || {
+        while let Ok(path) = rx.recv() {
+            let mut changed_paths = vec![to_root_path(&roots, path)];
+
+            while let Ok(path) = rx.recv_timeout(DEBOUNCE_DURATION) {
+                let path = to_root_path(&roots, path);
+
+                if !changed_paths.contains(&path) {
+                    changed_paths.push(path);
+                }
+            }
+
+            let settings = super::get_settings(&app_handle);
+            update(&app_handle, &settings, changed_paths);
+        }
+    });
+}
+
+/// Re-walks all roots in the background, e.g. after the settings changed.
+pub fn rebuild(app_handle: AppHandle<Wry>) {
+    thread::spawn(move || {
+        app_handle.state::<State>().0.lock().unwrap().watcher = None;
+
+        build(&app_handle);
+        watch(app_handle);
+    });
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    rebuild(app_handle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A tree with a hidden file, an excluded directory and ignore files:
+    ///
+    /// ```text
+    /// .gitignore       *.log, build/
+    /// .hidden.txt
+    /// notes.txt
+    /// debug.log
+    /// build/out.bin
+    /// node_modules/pkg/index.js
+    /// src/.ignore      !keep.log
+    /// src/main.rs
+    /// src/keep.log
+    /// docs/a/b/deep.md
+    /// ```
+    fn temp_tree(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("lander-index-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&root).ok();
+
+        let files = [
+            (".gitignore", "*.log\nbuild/\n"),
+            (".hidden.txt", ""),
+            ("notes.txt", ""),
+            ("debug.log", ""),
+            ("build/out.bin", ""),
+            ("node_modules/pkg/index.js", ""),
+            ("src/.ignore", "!keep.log\n"),
+            ("src/main.rs", ""),
+            ("src/keep.log", ""),
+            ("docs/a/b/deep.md", ""),
+        ];
+
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        root
+    }
+
+    fn settings(root: &Path) -> Settings {
+        Settings {
+            roots: vec![root.to_path_buf()],
+            exclude: vec!["node_modules".to_string()],
+            ..Settings::default()
+        }
+    }
+
+    fn relative_paths(root: &Path, entries: &[FileEntry]) -> Vec<String> {
+        let mut paths = entries
+            .iter()
+            .map(|entry| {
+                Path::new(&entry.path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace(MAIN_SEPARATOR, "/")
+            })
+            .collect::<Vec<_>>();
+
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn walk_honors_hidden_files_ignore_files_and_excluded_globs() {
+        let root = temp_tree("walk");
+
+        assert_eq!(
+            relative_paths(&root, &walk(&settings(&root), &root, &root, 12)),
+            vec![
+                "docs",
+                "docs/a",
+                "docs/a/b",
+                "docs/a/b/deep.md",
+                "notes.txt",
+                "src",
+                "src/keep.log",
+                "src/main.rs"
+            ]
+        );
+
+        let settings = Settings {
+            include_hidden: true,
+            respect_ignore_files: false,
+            exclude: vec!["*.md".to_string(), "docs/a/b".to_string()],
+            ..settings(&root)
+        };
+
+        assert_eq!(
+            relative_paths(&root, &walk(&settings, &root, &root, 1)),
+            vec![
+                ".gitignore",
+                ".hidden.txt",
+                "build",
+                "debug.log",
+                "docs",
+                "node_modules",
+                "notes.txt",
+                "src"
+            ]
+        );
+        assert!(!relative_paths(&root, &walk(&settings, &root, &root, 12))
+            .iter()
+            .any(|path| path.starts_with("docs/a/b")));
+
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn changed_paths_are_excluded_like_walked_ones() {
+        let root = temp_tree("changed");
+        let settings = settings(&root);
+        let walked = relative_paths(&root, &walk(&settings, &root, &root, 12));
+
+        for path in [
+            ".hidden.txt",
+            "notes.txt",
+            "debug.log",
+            "build",
+            "node_modules",
+            "src/main.rs",
+            "src/keep.log",
+            "docs/a/b/deep.md",
+        ] {
+            let entries = changed_entries(&settings, &root, &root.join(path));
+
+            assert_eq!(
+                !entries.is_empty(),
+                walked.contains(&path.to_string()),
+                "{}",
+                path
+            );
+        }
+
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn changed_directories_bring_their_contents() {
+        let root = temp_tree("directory");
+        let settings = Settings {
+            max_depth: 3,
+            ..settings(&root)
+        };
+
+        fs::create_dir_all(root.join("docs/new/deeper")).unwrap();
+        fs::write(root.join("docs/new/draft.md"), "").unwrap();
+        fs::write(root.join("docs/new/draft.log"), "").unwrap();
+        fs::write(root.join("docs/new/deeper/too-deep.md"), "").unwrap();
+
+        let entries = changed_entries(&settings, &root, &root.join("docs/new"));
+
+        assert_eq!(entries[0].name, "new");
+        assert_eq!(
+            relative_paths(&root, &entries),
+            vec!["docs/new", "docs/new/deeper", "docs/new/draft.md"]
+        );
+        assert!(changed_entries(&settings, &root, &root.join("docs/missing")).is_empty());
+        assert!(changed_entries(&settings, &root, &root).is_empty());
+
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn walk_stops_at_the_entry_limit() {
+        let root = temp_tree("limit");
+        let settings = Settings {
+            max_entries: 3,
+            ..settings(&root)
+        };
+
+        assert_eq!(walk(&settings, &root, &root, 12).len(), 3);
+
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn event_paths_map_back_to_the_configured_root() {
+        let roots = vec![
+            (
+                PathBuf::from("/private/var/folders"),
+                PathBuf::from("/var/folders"),
+            ),
+            (
+                PathBuf::from("/Users/lander/Documents"),
+                PathBuf::from("/Users/lander/Documents"),
+            ),
+        ];
+
+        assert_eq!(
+            to_root_path(&roots, PathBuf::from("/private/var/folders/notes/todo.md")),
+            PathBuf::from("/var/folders/notes/todo.md")
+        );
+        assert_eq!(
+            to_root_path(&roots, PathBuf::from("/Users/lander/Documents/report.pdf")),
+            PathBuf::from("/Users/lander/Documents/report.pdf")
+        );
+        assert_eq!(
+            to_root_path(&roots, PathBuf::from("/tmp/other.txt")),
+            PathBuf::from("/tmp/other.txt")
+        );
+    }
+}
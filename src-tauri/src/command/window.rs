@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, GlobalShortcutManager, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::panel::geometry::Rect;
+
+pub mod layout;
+
+pub use layout::Action;
+
+#[cfg(target_os = "macos")]
+pub mod macos;
+#[cfg(target_os = "macos")]
+use macos as window;
+
+#[cfg(target_os = "windows")]
+pub mod windows;
+#[cfg(target_os = "windows")]
+use self::windows as window;
+
+#[derive(Default)]
+pub struct Store {
+    /// Frames windows had before the first action applied to them, by window id.
+    restore_frames: HashMap<String, Rect>,
+    hotkeys: HashMap<Action, String>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Window {
+    /// `CGWindowID` on macOS, `HWND` on Windows.
+    id: String,
+    pid: i32,
+    title: String,
+    is_minimized: bool,
+    is_focused: bool,
+    /// Key of the monitor the window is on, as used for remembered panel positions.
+    monitor: Option<String>,
+    /// Frame with a top-left origin, in points on macOS and physical pixels on Windows.
+    frame: Option<Rect>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RunningApplication {
+    /// Bundle identifier on macOS, executable path on Windows.
+    id: String,
+    name: String,
+    pid: i32,
+    path: Option<String>,
+    is_active: bool,
+    /// Hidden on macOS; every window minimized on Windows.
+    is_hidden: bool,
+    /// Windows front to back.
+    windows: Vec<Window>,
+}
+
+pub fn get_running_applications() -> Vec<RunningApplication> {
+    window::get_running_applications()
+}
+
+pub fn activate_window(pid: i32, window_id: Option<&str>) -> Result<(), String> {
+    window::activate_window(pid, window_id)
+}
+
+pub fn hide_application(pid: i32) -> Result<(), String> {
+    window::hide_application(pid)
+}
+
+pub fn quit_application(pid: i32) -> Result<(), String> {
+    window::quit_application(pid)
+}
+
+pub fn force_quit_application(pid: i32) -> Result<(), String> {
+    window::force_quit_application(pid)
+}
+
+pub fn manage_window(app_handle: &AppHandle<Wry>, action: Action) -> Result<(), String> {
+    window::manage_focused_window(|window_id, frame, screens| {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        if action == Action::Restore {
+            return store.restore_frames.remove(window_id);
+        }
+
+        let next_frame = match action {
+            Action::NextDisplay => layout::frame_on_next_screen(frame, screens),
+            _ => layout::screen_index(frame, screens)
+                .and_then(|index| layout::frame_for_action(action, frame, &screens[index])),
+        }?;
+
+        store
+            .restore_frames
+            .entry(window_id.to_string())
+            .or_insert(*frame);
+
+        Some(next_frame)
+    })
+}
+
+fn save_hotkeys(app_handle: &AppHandle<Wry>, hotkeys: &HashMap<Action, String>) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("window_hotkeys".to_string(), serde_json::json!(hotkeys))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+fn load_hotkeys(app_handle: &AppHandle<Wry>) -> HashMap<Action, String> {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("window_hotkeys")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+/// Binds each hotkey to its action, replacing the previous bindings. Hotkeys that cannot be
+/// registered are left out and reported together.
+fn register_hotkeys(
+    app_handle: &AppHandle<Wry>,
+    hotkeys: HashMap<Action, String>,
+) -> Result<(), String> {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+    let mut shortcut_manager = app_handle.global_shortcut_manager();
+
+    for hotkey in store.hotkeys.values() {
+        shortcut_manager.unregister(hotkey).unwrap_or_default();
+    }
+
+    let mut registered_hotkeys = HashMap::new();
+    let mut errors = vec![];
+
+    for (action, hotkey) in hotkeys {
+        let handler_app_handle = app_handle.clone();
+
+        match shortcut_manager.register(&hotkey, move || {
+            manage_window(&handler_app_handle, action).unwrap_or_default();
+        }) {
+            Ok(_) => {
+                registered_hotkeys.insert(action, hotkey);
+            }
+            Err(error) => errors.push(format!("`{}` could not be registered: {}", hotkey, error)),
+        }
+    }
+
+    store.hotkeys = registered_hotkeys;
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Replaces the global hotkeys bound to window actions and persists the ones that could be
+/// registered.
+pub fn set_hotkeys(
+    app_handle: &AppHandle<Wry>,
+    hotkeys: HashMap<Action, String>,
+) -> Result<(), String> {
+    let hotkeys = hotkeys
+        .into_iter()
+        .filter(|(_, hotkey)| !hotkey.trim().is_empty())
+        .collect::<HashMap<_, _>>();
+
+    let result = register_hotkeys(app_handle, hotkeys);
+
+    save_hotkeys(app_handle, &get_hotkeys(app_handle));
+
+    result
+}
+
+pub fn get_hotkeys(app_handle: &AppHandle<Wry>) -> HashMap<Action, String> {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .hotkeys
+        .clone()
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    let hotkeys = load_hotkeys(&app_handle);
+    register_hotkeys(&app_handle, hotkeys).unwrap_or_default();
+}
@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+
+use crate::panel::geometry::Rect;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Maximize,
+    LeftHalf,
+    RightHalf,
+    TopHalf,
+    BottomHalf,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    LeftThird,
+    CenterThird,
+    RightThird,
+    Center,
+    NextDisplay,
+    /// Back to the frame the window had before the first action applied to it.
+    Restore,
+}
+
+fn intersection_area(a: &Rect, b: &Rect) -> f64 {
+    let width = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+    let height = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+
+    if width > 0.0 && height > 0.0 {
+        width * height
+    } else {
+        0.0
+    }
+}
+
+/// Index of the screen that holds most of `window`, or the one nearest to its center when the
+/// window is entirely off-screen.
+pub fn screen_index(window: &Rect, screens: &[Rect]) -> Option<usize> {
+    let center_x = window.x + window.width / 2.0;
+    let center_y = window.y + window.height / 2.0;
+
+    let distance = |screen: &Rect| {
+        let dx = (screen.x + screen.width / 2.0) - center_x;
+        let dy = (screen.y + screen.height / 2.0) - center_y;
+
+        dx * dx + dy * dy
+    };
+
+    screens
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| {
+            intersection_area(window, a)
+                .partial_cmp(&intersection_area(window, b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| {
+                    distance(b)
+                        .partial_cmp(&distance(a))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+        })
+        .map(|(index, _)| index)
+}
+
+/// Splits `length` into two whole parts, giving the remainder to the second one.
+fn split(length: f64) -> (f64, f64) {
+    let first = (length / 2.0).floor();
+    (first, length - first)
+}
+
+/// Splits `length` into three whole parts, giving the remainder to the last one.
+fn split_in_thirds(length: f64) -> (f64, f64, f64) {
+    let first = (length / 3.0).floor();
+    (first, first, length - 2.0 * first)
+}
+
+/// Frame for a tiling action within `screen`, the usable area of the window's display (without
+/// menu bar, dock or taskbar). `NextDisplay` and `Restore` need more context and return `None`.
+pub fn frame_for_action(action: Action, window: &Rect, screen: &Rect) -> Option<Rect> {
+    let (left_width, right_width) = split(screen.width);
+    let (top_height, bottom_height) = split(screen.height);
+    let (left_third_width, center_third_width, right_third_width) = split_in_thirds(screen.width);
+
+    let left = Rect {
+        width: left_width,
+        ..*screen
+    };
+    let right = Rect {
+        x: screen.x + left_width,
+        width: right_width,
+        ..*screen
+    };
+
+    let frame = match action {
+        Action::Maximize => *screen,
+        Action::LeftHalf => left,
+        Action::RightHalf => right,
+        Action::TopHalf => Rect {
+            height: top_height,
+            ..*screen
+        },
+        Action::BottomHalf => Rect {
+            y: screen.y + top_height,
+            height: bottom_height,
+            ..*screen
+        },
+        Action::TopLeft => Rect {
+            height: top_height,
+            ..left
+        },
+        Action::TopRight => Rect {
+            height: top_height,
+            ..right
+        },
+        Action::BottomLeft => Rect {
+            y: screen.y + top_height,
+            height: bottom_height,
+            ..left
+        },
+        Action::BottomRight => Rect {
+            y: screen.y + top_height,
+            height: bottom_height,
+            ..right
+        },
+        Action::LeftThird => Rect {
+            width: left_third_width,
+            ..*screen
+        },
+        Action::CenterThird => Rect {
+            x: screen.x + left_third_width,
+            width: center_third_width,
+            ..*screen
+        },
+        Action::RightThird => Rect {
+            x: screen.x + left_third_width + center_third_width,
+            width: right_third_width,
+            ..*screen
+        },
+        Action::Center => {
+            let width = window.width.min(screen.width);
+            let height = window.height.min(screen.height);
+
+            Rect {
+                x: (screen.x + (screen.width - width) / 2.0).round(),
+                y: (screen.y + (screen.height - height) / 2.0).round(),
+                width,
+                height,
+            }
+        }
+        Action::NextDisplay | Action::Restore => return None,
+    };
+
+    Some(frame)
+}
+
+/// Moves `window` to the display after the one it is on, ordered left to right and then top to
+/// bottom, keeping its relative position and size.
+pub fn frame_on_next_screen(window: &Rect, screens: &[Rect]) -> Option<Rect> {
+    if screens.len() < 2 {
+        return None;
+    }
+
+    let mut ordered_screens = screens.to_vec();
+    ordered_screens.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    let index = screen_index(window, &ordered_screens)?;
+    let source = ordered_screens[index];
+    let target = ordered_screens[(index + 1) % ordered_screens.len()];
+
+    let width = (window.width * target.width / source.width)
+        .round()
+        .min(target.width);
+    let height = (window.height * target.height / source.height)
+        .round()
+        .min(target.height);
+
+    let x = (target.x + (window.x - source.x) * target.width / source.width)
+        .round()
+        .max(target.x)
+        .min(target.x + target.width - width);
+    let y = (target.y + (window.y - source.y) * target.height / source.height)
+        .round()
+        .max(target.y)
+        .min(target.y + target.height - height);
+
+    Some(Rect {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// A 1440p primary display at 125% with a taskbar, a 4K display above it with a negative
+    /// origin, and a portrait display on its left.
+    fn screens() -> Vec<Rect> {
+        vec![
+            rect(0.0, 0.0, 2560.0, 1392.0),
+            rect(-640.0, -2160.0, 3840.0, 2160.0),
+            rect(-1080.0, -300.0, 1080.0, 1920.0),
+        ]
+    }
+
+    #[test]
+    fn halves_cover_the_screen() {
+        let window = rect(100.0, 100.0, 800.0, 600.0);
+
+        for screen in screens() {
+            let left = frame_for_action(Action::LeftHalf, &window, &screen).unwrap();
+            let right = frame_for_action(Action::RightHalf, &window, &screen).unwrap();
+            let top = frame_for_action(Action::TopHalf, &window, &screen).unwrap();
+            let bottom = frame_for_action(Action::BottomHalf, &window, &screen).unwrap();
+
+            assert_eq!(left.x, screen.x);
+            assert_eq!(left.x + left.width, right.x);
+            assert_eq!(right.x + right.width, screen.x + screen.width);
+            assert_eq!((left.y, left.height), (screen.y, screen.height));
+
+            assert_eq!(top.y, screen.y);
+            assert_eq!(top.y + top.height, bottom.y);
+            assert_eq!(bottom.y + bottom.height, screen.y + screen.height);
+            assert_eq!((top.x, top.width), (screen.x, screen.width));
+        }
+    }
+
+    #[test]
+    fn halves_give_the_odd_pixel_to_the_second_half() {
+        let screen = rect(-1001.0, 20.0, 1001.0, 801.0);
+        let window = rect(0.0, 0.0, 100.0, 100.0);
+
+        assert_eq!(
+            frame_for_action(Action::LeftHalf, &window, &screen),
+            Some(rect(-1001.0, 20.0, 500.0, 801.0))
+        );
+        assert_eq!(
+            frame_for_action(Action::RightHalf, &window, &screen),
+            Some(rect(-501.0, 20.0, 501.0, 801.0))
+        );
+        assert_eq!(
+            frame_for_action(Action::BottomRight, &window, &screen),
+            Some(rect(-501.0, 420.0, 501.0, 401.0))
+        );
+    }
+
+    #[test]
+    fn thirds_cover_the_screen() {
+        let window = rect(0.0, 0.0, 100.0, 100.0);
+        let screen = rect(-1080.0, -300.0, 1081.0, 1920.0);
+
+        assert_eq!(
+            frame_for_action(Action::LeftThird, &window, &screen),
+            Some(rect(-1080.0, -300.0, 360.0, 1920.0))
+        );
+        assert_eq!(
+            frame_for_action(Action::CenterThird, &window, &screen),
+            Some(rect(-720.0, -300.0, 360.0, 1920.0))
+        );
+        assert_eq!(
+            frame_for_action(Action::RightThird, &window, &screen),
+            Some(rect(-360.0, -300.0, 361.0, 1920.0))
+        );
+    }
+
+    #[test]
+    fn center_keeps_the_size_and_fits_the_screen() {
+        let screen = rect(-640.0, -2160.0, 3840.0, 2160.0);
+
+        assert_eq!(
+            frame_for_action(Action::Center, &rect(0.0, 0.0, 801.0, 600.0), &screen),
+            Some(rect(880.0, -1380.0, 801.0, 600.0))
+        );
+        assert_eq!(
+            frame_for_action(Action::Center, &rect(0.0, 0.0, 5000.0, 600.0), &screen),
+            Some(rect(-640.0, -1380.0, 3840.0, 600.0))
+        );
+    }
+
+    #[test]
+    fn maximize_fills_the_usable_area() {
+        let window = rect(10.0, 10.0, 100.0, 100.0);
+
+        for screen in screens() {
+            assert_eq!(
+                frame_for_action(Action::Maximize, &window, &screen),
+                Some(screen)
+            );
+        }
+    }
+
+    #[test]
+    fn next_display_and_restore_need_more_context() {
+        let window = rect(10.0, 10.0, 100.0, 100.0);
+        let screen = screens()[0];
+
+        assert_eq!(
+            frame_for_action(Action::NextDisplay, &window, &screen),
+            None
+        );
+        assert_eq!(frame_for_action(Action::Restore, &window, &screen), None);
+    }
+
+    #[test]
+    fn screen_index_prefers_the_largest_overlap() {
+        let screens = screens();
+
+        assert_eq!(
+            screen_index(&rect(-200.0, -100.0, 800.0, 600.0), &screens),
+            Some(0)
+        );
+        assert_eq!(
+            screen_index(&rect(-900.0, -200.0, 800.0, 600.0), &screens),
+            Some(2)
+        );
+        assert_eq!(
+            screen_index(&rect(1000.0, -1500.0, 800.0, 600.0), &screens),
+            Some(1)
+        );
+        assert_eq!(
+            screen_index(&rect(9000.0, 9000.0, 800.0, 600.0), &screens),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn next_display_scales_the_window_to_the_target() {
+        let screens = vec![
+            rect(0.0, 0.0, 1920.0, 1080.0),
+            rect(1920.0, -360.0, 3840.0, 2160.0),
+        ];
+
+        assert_eq!(
+            frame_on_next_screen(&rect(480.0, 270.0, 960.0, 540.0), &screens),
+            Some(rect(2880.0, 180.0, 1920.0, 1080.0))
+        );
+        assert_eq!(
+            frame_on_next_screen(&rect(2880.0, 180.0, 1920.0, 1080.0), &screens),
+            Some(rect(480.0, 270.0, 960.0, 540.0))
+        );
+        assert_eq!(
+            frame_on_next_screen(&rect(0.0, 0.0, 100.0, 100.0), &screens[..1]),
+            None
+        );
+    }
+}
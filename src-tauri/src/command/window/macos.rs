@@ -0,0 +1,401 @@
+use accessibility::{AXAttribute, AXUIElement};
+use accessibility_sys::{
+    kAXErrorSuccess, kAXMinimizedAttribute, kAXPositionAttribute, kAXRaiseAction, kAXSizeAttribute,
+    kAXTitleAttribute, kAXValueTypeCGPoint, kAXValueTypeCGSize, kAXWindowsAttribute, AXError,
+    AXUIElementPerformAction, AXUIElementRef, AXValueCreate,
+};
+use cocoa::{
+    appkit::{CGFloat, NSApplicationActivationPolicy},
+    base::{id, nil, BOOL, YES},
+    foundation::{NSInteger, NSRect, NSUInteger},
+};
+use core_foundation::{
+    array::CFArray,
+    base::{CFType, CFTypeRef, FromVoid, TCFType, ToVoid},
+    boolean::CFBoolean,
+    string::CFString,
+};
+use core_graphics::geometry::{CGPoint, CGSize};
+use objc::{class, msg_send, sel, sel_impl};
+use std::ffi::c_void;
+
+use crate::command::window::{RunningApplication, Window};
+use crate::nsstring_to_string;
+use crate::panel::geometry::Rect;
+
+const NS_APPLICATION_ACTIVATE_IGNORING_OTHER_APPS: NSUInteger = 1 << 1;
+
+extern "C" {
+    // Private, but the only way to map an accessibility window to its `CGWindowID`.
+    fn _AXUIElementGetWindow(element: AXUIElementRef, identifier: *mut u32) -> AXError;
+}
+
+/// A display with frames in accessibility coordinates: points with a top-left origin at the
+/// primary screen.
+pub struct Screen {
+    pub key: String,
+    pub frame: Rect,
+    pub visible_frame: Rect,
+}
+
+fn to_top_left_rect(rect: NSRect, primary_screen_height: f64) -> Rect {
+    Rect {
+        x: rect.origin.x,
+        y: primary_screen_height - rect.origin.y - rect.size.height,
+        width: rect.size.width,
+        height: rect.size.height,
+    }
+}
+
+pub fn get_screens() -> Vec<Screen> {
+    objc::rc::autoreleasepool(|| {
+        let screens: id = unsafe { msg_send![class!(NSScreen), screens] };
+        let count: NSUInteger = unsafe { msg_send![screens, count] };
+
+        if count == 0 {
+            return vec![];
+        }
+
+        let primary_screen: id = unsafe { msg_send![screens, objectAtIndex: 0] };
+        let primary_frame: NSRect = unsafe { msg_send![primary_screen, frame] };
+
+        (0..count)
+            .map(|index| {
+                let screen: id = unsafe { msg_send![screens, objectAtIndex: index] };
+                let frame: NSRect = unsafe { msg_send![screen, frame] };
+                let visible_frame: NSRect = unsafe { msg_send![screen, visibleFrame] };
+                let name: id = unsafe { msg_send![screen, localizedName] };
+                let scale_factor: CGFloat = unsafe { msg_send![screen, backingScaleFactor] };
+
+                // Same key the panel remembers positions under.
+                let key = nsstring_to_string!(name).unwrap_or_else(|| {
+                    format!(
+                        "{},{},{}x{}",
+                        (frame.origin.x * scale_factor) as i32,
+                        (frame.origin.y * scale_factor) as i32,
+                        (frame.size.width * scale_factor) as u32,
+                        (frame.size.height * scale_factor) as u32
+                    )
+                });
+
+                Screen {
+                    key,
+                    frame: to_top_left_rect(frame, primary_frame.size.height),
+                    visible_frame: to_top_left_rect(visible_frame, primary_frame.size.height),
+                }
+            })
+            .collect()
+    })
+}
+
+fn get_screen_key(screens: &[Screen], frame: &Rect) -> Option<String> {
+    let center_x = frame.x + frame.width / 2.0;
+    let center_y = frame.y + frame.height / 2.0;
+
+    screens
+        .iter()
+        .find(|screen| screen.frame.contains(center_x, center_y))
+        .map(|screen| screen.key.clone())
+}
+
+pub fn get_window_id(window: &AXUIElement) -> Option<u32> {
+    let mut identifier: u32 = 0;
+    let error = unsafe { _AXUIElementGetWindow(window.as_concrete_TypeRef(), &mut identifier) };
+
+    if error == kAXErrorSuccess {
+        Some(identifier)
+    } else {
+        None
+    }
+}
+
+pub fn get_windows(pid: i32) -> Vec<AXUIElement> {
+    let windows: CFArray = match AXUIElement::application(pid)
+        .attribute(&AXAttribute::new(&CFString::new(kAXWindowsAttribute)))
+    {
+        Ok(value) => match value.downcast::<CFArray>() {
+            Some(windows) => windows,
+            None => return vec![],
+        },
+        Err(_) => return vec![],
+    };
+
+    windows
+        .get_all_values()
+        .into_iter()
+        .map(|window| {
+            unsafe { AXUIElement::from_void(ToVoid::<*const c_void>::to_void(&window)) }.to_owned()
+        })
+        .collect()
+}
+
+fn get_string_attribute(element: &AXUIElement, attribute: &str) -> Option<String> {
+    element
+        .attribute(&AXAttribute::new(&CFString::new(attribute)))
+        .ok()?
+        .downcast::<CFString>()
+        .map(|value| value.to_string())
+}
+
+fn get_bool_attribute(element: &AXUIElement, attribute: &str) -> bool {
+    element
+        .attribute(&AXAttribute::new(&CFString::new(attribute)))
+        .ok()
+        .and_then(|value| value.downcast::<CFBoolean>())
+        == Some(CFBoolean::true_value())
+}
+
+fn get_running_ns_application(pid: i32) -> Option<id> {
+    let application: id = unsafe {
+        msg_send![
+            class!(NSRunningApplication),
+            runningApplicationWithProcessIdentifier: pid
+        ]
+    };
+
+    if application == nil {
+        None
+    } else {
+        Some(application)
+    }
+}
+
+fn get_application_from_ns_application(
+    ns_application: id,
+    screens: &[Screen],
+    focused_window_id: Option<u32>,
+) -> RunningApplication {
+    let pid: i32 = unsafe { msg_send![ns_application, processIdentifier] };
+    let bundle_identifier: id = unsafe { msg_send![ns_application, bundleIdentifier] };
+    let localized_name: id = unsafe { msg_send![ns_application, localizedName] };
+    let bundle_url: id = unsafe { msg_send![ns_application, bundleURL] };
+    let is_active: BOOL = unsafe { msg_send![ns_application, isActive] };
+    let is_hidden: BOOL = unsafe { msg_send![ns_application, isHidden] };
+    let is_active = is_active == YES;
+
+    let path = if bundle_url == nil {
+        None
+    } else {
+        let path: id = unsafe { msg_send![bundle_url, path] };
+        nsstring_to_string!(path)
+    };
+
+    let windows = get_windows(pid)
+        .iter()
+        .filter_map(|window| {
+            let window_id = get_window_id(window)?;
+            let frame = crate::util::macos::get_window_frame(window).map(|frame| Rect {
+                x: frame.origin.x,
+                y: frame.origin.y,
+                width: frame.size.width,
+                height: frame.size.height,
+            });
+
+            Some(Window {
+                id: window_id.to_string(),
+                pid,
+                title: get_string_attribute(window, kAXTitleAttribute).unwrap_or_default(),
+                is_minimized: get_bool_attribute(window, kAXMinimizedAttribute),
+                is_focused: is_active && focused_window_id == Some(window_id),
+                monitor: frame.and_then(|frame| get_screen_key(screens, &frame)),
+                frame,
+            })
+        })
+        .collect();
+
+    let name = nsstring_to_string!(localized_name).unwrap_or_default();
+
+    RunningApplication {
+        id: nsstring_to_string!(bundle_identifier).unwrap_or_else(|| name.clone()),
+        name,
+        pid,
+        path,
+        is_active,
+        is_hidden: is_hidden == YES,
+        windows,
+    }
+}
+
+pub fn get_running_applications() -> Vec<RunningApplication> {
+    let screens = get_screens();
+
+    let focused_window_id = crate::util::macos::get_frontmost_application_pid()
+        .and_then(crate::util::macos::get_focused_window)
+        .and_then(|window| get_window_id(&window));
+
+    objc::rc::autoreleasepool(|| {
+        let workspace: id = unsafe { msg_send![class!(NSWorkspace), sharedWorkspace] };
+        let running_applications: id = unsafe { msg_send![workspace, runningApplications] };
+        let count: NSUInteger = unsafe { msg_send![running_applications, count] };
+        let own_pid = std::process::id() as i32;
+
+        (0..count)
+            .filter_map(|index| {
+                let ns_application: id =
+                    unsafe { msg_send![running_applications, objectAtIndex: index] };
+                let pid: i32 = unsafe { msg_send![ns_application, processIdentifier] };
+                let activation_policy: NSInteger =
+                    unsafe { msg_send![ns_application, activationPolicy] };
+
+                if pid == own_pid
+                    || activation_policy
+                        != NSApplicationActivationPolicy::NSApplicationActivationPolicyRegular
+                            as NSInteger
+                {
+                    return None;
+                }
+
+                Some(get_application_from_ns_application(
+                    ns_application,
+                    &screens,
+                    focused_window_id,
+                ))
+            })
+            .collect()
+    })
+}
+
+pub fn raise_window(window: &AXUIElement) {
+    window
+        .set_attribute(
+            &AXAttribute::new(&CFString::new(kAXMinimizedAttribute)),
+            CFBoolean::false_value().into_CFType(),
+        )
+        .unwrap_or_default();
+
+    unsafe {
+        AXUIElementPerformAction(
+            window.as_concrete_TypeRef(),
+            CFString::new(kAXRaiseAction).as_concrete_TypeRef(),
+        );
+    }
+}
+
+pub fn activate_window(pid: i32, window_id: Option<&str>) -> Result<(), String> {
+    let ns_application = get_running_ns_application(pid)
+        .ok_or_else(|| format!("application {} is not running", pid))?;
+
+    if let Some(window_id) = window_id {
+        let window = get_windows(pid)
+            .into_iter()
+            .find(|window| {
+                get_window_id(window).map(|id| id.to_string()).as_deref() == Some(window_id)
+            })
+            .ok_or_else(|| format!("window {} does not exist", window_id))?;
+
+        raise_window(&window);
+    }
+
+    unsafe {
+        let _: BOOL = msg_send![ns_application, unhide];
+        let _: BOOL = msg_send![
+            ns_application,
+            activateWithOptions: NS_APPLICATION_ACTIVATE_IGNORING_OTHER_APPS
+        ];
+    }
+
+    Ok(())
+}
+
+pub fn hide_application(pid: i32) -> Result<(), String> {
+    let ns_application = get_running_ns_application(pid)
+        .ok_or_else(|| format!("application {} is not running", pid))?;
+
+    unsafe {
+        let _: BOOL = msg_send![ns_application, hide];
+    }
+
+    Ok(())
+}
+
+pub fn quit_application(pid: i32) -> Result<(), String> {
+    let ns_application = get_running_ns_application(pid)
+        .ok_or_else(|| format!("application {} is not running", pid))?;
+
+    let terminated: BOOL = unsafe { msg_send![ns_application, terminate] };
+
+    if terminated == YES {
+        Ok(())
+    } else {
+        Err(format!("application {} refused to quit", pid))
+    }
+}
+
+pub fn force_quit_application(pid: i32) -> Result<(), String> {
+    let ns_application = get_running_ns_application(pid)
+        .ok_or_else(|| format!("application {} is not running", pid))?;
+
+    let terminated: BOOL = unsafe { msg_send![ns_application, forceTerminate] };
+
+    if terminated == YES {
+        Ok(())
+    } else {
+        Err(format!("application {} could not be force quit", pid))
+    }
+}
+
+fn set_window_frame(window: &AXUIElement, frame: &Rect) {
+    let origin = CGPoint::new(frame.x, frame.y);
+    let size = CGSize::new(frame.width, frame.height);
+
+    let position = unsafe {
+        CFType::wrap_under_create_rule(AXValueCreate(
+            kAXValueTypeCGPoint,
+            &origin as *const CGPoint as *const c_void,
+        ) as CFTypeRef)
+    };
+    let size = unsafe {
+        CFType::wrap_under_create_rule(AXValueCreate(
+            kAXValueTypeCGSize,
+            &size as *const CGSize as *const c_void,
+        ) as CFTypeRef)
+    };
+
+    let position_attribute = AXAttribute::new(&CFString::new(kAXPositionAttribute));
+    let size_attribute = AXAttribute::new(&CFString::new(kAXSizeAttribute));
+
+    // Resizing on both sides of the move keeps the system from clamping a window that does not
+    // fit on the display it is leaving or entering.
+    window
+        .set_attribute(&size_attribute, size.clone())
+        .unwrap_or_default();
+    window
+        .set_attribute(&position_attribute, position)
+        .unwrap_or_default();
+    window
+        .set_attribute(&size_attribute, size)
+        .unwrap_or_default();
+}
+
+/// Hands the focused window of the frontmost application, its frame and the usable area of
+/// every display to `f`, and applies the frame it returns.
+pub fn manage_focused_window(
+    f: impl FnOnce(&str, &Rect, &[Rect]) -> Option<Rect>,
+) -> Result<(), String> {
+    let pid = crate::util::macos::get_frontmost_application_pid()
+        .filter(|pid| *pid != std::process::id() as i32)
+        .ok_or("no application is focused")?;
+
+    let window = crate::util::macos::get_focused_window(pid).ok_or("no window is focused")?;
+    let window_id = get_window_id(&window).ok_or("the focused window has no identifier")?;
+
+    let frame = crate::util::macos::get_window_frame(&window)
+        .map(|frame| Rect {
+            x: frame.origin.x,
+            y: frame.origin.y,
+            width: frame.size.width,
+            height: frame.size.height,
+        })
+        .ok_or("the focused window cannot be moved")?;
+
+    let screens = get_screens()
+        .into_iter()
+        .map(|screen| screen.visible_frame)
+        .collect::<Vec<_>>();
+
+    if let Some(frame) = f(&window_id.to_string(), &frame, &screens) {
+        set_window_frame(&window, &frame);
+    }
+
+    Ok(())
+}
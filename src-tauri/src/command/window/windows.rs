@@ -0,0 +1,412 @@
+use std::{collections::HashMap, ffi::c_void, mem, path::Path};
+use windows_sys::Win32::{
+    Foundation::{CloseHandle, BOOL, HWND, LPARAM, RECT},
+    Graphics::{
+        Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS},
+        Gdi::{
+            EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, HDC, HMONITOR, MONITORINFO,
+            MONITORINFOEXW, MONITOR_DEFAULTTONEAREST,
+        },
+    },
+    System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, TerminateProcess, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
+    },
+    UI::WindowsAndMessaging::{
+        EnumWindows, GetForegroundWindow, GetWindow, GetWindowLongPtrW, GetWindowRect,
+        GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow,
+        IsWindowVisible, IsZoomed, PostMessageW, SetForegroundWindow, SetWindowPos, ShowWindow,
+        GWL_EXSTYLE, GW_OWNER, SWP_NOACTIVATE, SWP_NOZORDER, SW_MINIMIZE, SW_RESTORE, WM_CLOSE,
+        WS_EX_TOOLWINDOW,
+    },
+};
+
+use crate::command::application::windows::foreground;
+use crate::command::window::{RunningApplication, Window};
+use crate::panel::geometry::Rect;
+
+unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = &mut *(lparam as *mut Vec<HWND>);
+    windows.push(hwnd);
+
+    1
+}
+
+fn is_cloaked(hwnd: HWND) -> bool {
+    let mut cloaked: u32 = 0;
+
+    let result = unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut u32 as *mut c_void,
+            mem::size_of::<u32>() as u32,
+        )
+    };
+
+    result == 0 && cloaked != 0
+}
+
+/// Whether the window would show up in the Alt+Tab list: visible, unowned, not a tool window
+/// and not cloaked (suspended UWP apps and windows on other virtual desktops).
+fn is_switchable_window(hwnd: HWND) -> bool {
+    unsafe {
+        IsWindowVisible(hwnd) != 0
+            && GetWindow(hwnd, GW_OWNER) == 0
+            && GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW == 0
+            && GetWindowTextLengthW(hwnd) > 0
+            && !is_cloaked(hwnd)
+    }
+}
+
+/// Top-level windows in z-order, front to back.
+pub fn get_switchable_windows() -> Vec<HWND> {
+    let mut windows: Vec<HWND> = vec![];
+
+    unsafe {
+        EnumWindows(
+            Some(enum_windows_callback),
+            &mut windows as *mut Vec<HWND> as LPARAM,
+        );
+    }
+
+    windows.retain(|hwnd| is_switchable_window(*hwnd));
+    windows
+}
+
+pub fn get_window_title(hwnd: HWND) -> String {
+    let length = unsafe { GetWindowTextLengthW(hwnd) };
+
+    if length <= 0 {
+        return String::new();
+    }
+
+    let mut buffer = vec![0u16; length as usize + 1];
+    let copied = unsafe { GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32) };
+
+    String::from_utf16_lossy(&buffer[..copied.max(0) as usize])
+}
+
+pub fn get_window_pid(hwnd: HWND) -> u32 {
+    let mut pid: u32 = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+
+    pid
+}
+
+pub fn get_window_rect(hwnd: HWND) -> Option<Rect> {
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+
+    if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+        return None;
+    }
+
+    Some(to_rect(&rect))
+}
+
+/// Name of the monitor the window is mostly on, e.g. `\\.\DISPLAY1`, the same key tauri
+/// reports and the panel remembers positions under.
+fn get_window_monitor(hwnd: HWND) -> Option<String> {
+    let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+
+    if monitor == 0 {
+        return None;
+    }
+
+    let mut info: MONITORINFOEXW = unsafe { mem::zeroed() };
+    info.monitorInfo.cbSize = mem::size_of::<MONITORINFOEXW>() as u32;
+
+    if unsafe { GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut _) } == 0 {
+        return None;
+    }
+
+    let length = info
+        .szDevice
+        .iter()
+        .position(|char| *char == 0)
+        .unwrap_or(info.szDevice.len());
+
+    Some(String::from_utf16_lossy(&info.szDevice[..length]))
+}
+
+fn get_process_path(pid: u32) -> Option<String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+
+        if process == 0 {
+            return None;
+        }
+
+        let mut buffer = vec![0u16; 1024];
+        let mut length = buffer.len() as u32;
+
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            buffer.as_mut_ptr(),
+            &mut length,
+        );
+
+        CloseHandle(process);
+
+        if result == 0 {
+            None
+        } else {
+            Some(String::from_utf16_lossy(&buffer[..length as usize]))
+        }
+    }
+}
+
+pub fn get_running_applications() -> Vec<RunningApplication> {
+    let foreground_window = unsafe { GetForegroundWindow() };
+    let own_pid = std::process::id();
+
+    let mut applications: Vec<RunningApplication> = vec![];
+    let mut indices: HashMap<u32, usize> = HashMap::new();
+
+    for hwnd in get_switchable_windows() {
+        let pid = get_window_pid(hwnd);
+
+        if pid == own_pid {
+            continue;
+        }
+
+        let window = Window {
+            id: hwnd.to_string(),
+            pid: pid as i32,
+            title: get_window_title(hwnd),
+            is_minimized: unsafe { IsIconic(hwnd) } != 0,
+            is_focused: hwnd == foreground_window,
+            monitor: get_window_monitor(hwnd),
+            frame: get_window_rect(hwnd),
+        };
+
+        let index = *indices.entry(pid).or_insert_with(|| {
+            let path = get_process_path(pid);
+
+            let name = path
+                .as_ref()
+                .and_then(|path| Path::new(path).file_stem())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| window.title.clone());
+
+            applications.push(RunningApplication {
+                id: path.clone().unwrap_or_else(|| pid.to_string()),
+                name,
+                pid: pid as i32,
+                path,
+                is_active: false,
+                is_hidden: false,
+                windows: vec![],
+            });
+
+            applications.len() - 1
+        });
+
+        let application = &mut applications[index];
+        application.is_active |= window.is_focused;
+        application.windows.push(window);
+    }
+
+    for application in applications.iter_mut() {
+        application.is_hidden = application.windows.iter().all(|window| window.is_minimized);
+    }
+
+    applications
+}
+
+fn get_application_windows(pid: i32) -> Result<Vec<HWND>, String> {
+    let windows = get_switchable_windows()
+        .into_iter()
+        .filter(|hwnd| get_window_pid(*hwnd) as i32 == pid)
+        .collect::<Vec<_>>();
+
+    if windows.is_empty() {
+        Err(format!("application {} has no windows", pid))
+    } else {
+        Ok(windows)
+    }
+}
+
+pub fn raise_window(hwnd: HWND) {
+    unsafe {
+        if IsIconic(hwnd) != 0 {
+            ShowWindow(hwnd, SW_RESTORE);
+        }
+
+        SetForegroundWindow(hwnd);
+    }
+}
+
+pub fn activate_window(pid: i32, window_id: Option<&str>) -> Result<(), String> {
+    let windows = get_application_windows(pid)?;
+
+    let hwnd = match window_id {
+        Some(window_id) => windows
+            .into_iter()
+            .find(|hwnd| hwnd.to_string() == window_id)
+            .ok_or_else(|| format!("window {} does not exist", window_id))?,
+        None => windows[0],
+    };
+
+    raise_window(hwnd);
+
+    Ok(())
+}
+
+/// Windows has no notion of hiding an application, so all of its windows are minimized.
+pub fn hide_application(pid: i32) -> Result<(), String> {
+    for hwnd in get_application_windows(pid)? {
+        unsafe { ShowWindow(hwnd, SW_MINIMIZE) };
+    }
+
+    Ok(())
+}
+
+/// Asks every window of the application to close, which lets it prompt for unsaved changes.
+pub fn quit_application(pid: i32) -> Result<(), String> {
+    for hwnd in get_application_windows(pid)? {
+        unsafe { PostMessageW(hwnd, WM_CLOSE, 0, 0) };
+    }
+
+    Ok(())
+}
+
+pub fn force_quit_application(pid: i32) -> Result<(), String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_TERMINATE, 0, pid as u32);
+
+        if process == 0 {
+            return Err(format!("application {} is not running", pid));
+        }
+
+        let result = TerminateProcess(process, 1);
+        CloseHandle(process);
+
+        if result == 0 {
+            Err(format!("application {} could not be force quit", pid))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn to_rect(rect: &RECT) -> Rect {
+    Rect {
+        x: rect.left as f64,
+        y: rect.top as f64,
+        width: (rect.right - rect.left) as f64,
+        height: (rect.bottom - rect.top) as f64,
+    }
+}
+
+/// The visible frame, without the invisible resize borders `GetWindowRect` includes on
+/// Windows 10 and later.
+pub fn get_visible_window_rect(hwnd: HWND) -> Option<Rect> {
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+
+    let result = unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut rect as *mut RECT as *mut c_void,
+            mem::size_of::<RECT>() as u32,
+        )
+    };
+
+    if result == 0 {
+        Some(to_rect(&rect))
+    } else {
+        get_window_rect(hwnd)
+    }
+}
+
+unsafe extern "system" fn enum_monitors_callback(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let work_areas = &mut *(lparam as *mut Vec<Rect>);
+
+    let mut info: MONITORINFO = mem::zeroed();
+    info.cbSize = mem::size_of::<MONITORINFO>() as u32;
+
+    if GetMonitorInfoW(monitor, &mut info) != 0 {
+        work_areas.push(to_rect(&info.rcWork));
+    }
+
+    1
+}
+
+/// Work areas of every monitor, excluding the taskbar.
+fn get_work_areas() -> Vec<Rect> {
+    let mut work_areas: Vec<Rect> = vec![];
+
+    unsafe {
+        EnumDisplayMonitors(
+            0,
+            std::ptr::null(),
+            Some(enum_monitors_callback),
+            &mut work_areas as *mut Vec<Rect> as LPARAM,
+        );
+    }
+
+    work_areas
+}
+
+/// Hands the last focused window outside of Lander, its visible frame and the work area of
+/// every monitor to `f`, and applies the frame it returns.
+pub fn manage_focused_window(
+    f: impl FnOnce(&str, &Rect, &[Rect]) -> Option<Rect>,
+) -> Result<(), String> {
+    let hwnd = foreground::get_foreground_window()
+        .filter(|hwnd| unsafe { IsWindow(*hwnd) } != 0)
+        .ok_or("no window is focused")?;
+
+    let frame = get_visible_window_rect(hwnd).ok_or("the focused window cannot be moved")?;
+
+    let frame = match f(&hwnd.to_string(), &frame, &get_work_areas()) {
+        Some(frame) => frame,
+        None => return Ok(()),
+    };
+
+    unsafe {
+        if IsZoomed(hwnd) != 0 || IsIconic(hwnd) != 0 {
+            ShowWindow(hwnd, SW_RESTORE);
+        }
+    }
+
+    let window_rect = get_window_rect(hwnd).ok_or("the focused window cannot be moved")?;
+    let visible_rect = get_visible_window_rect(hwnd).unwrap_or(window_rect);
+
+    let left_border = visible_rect.x - window_rect.x;
+    let top_border = visible_rect.y - window_rect.y;
+    let right_border = (window_rect.x + window_rect.width) - (visible_rect.x + visible_rect.width);
+    let bottom_border =
+        (window_rect.y + window_rect.height) - (visible_rect.y + visible_rect.height);
+
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            0,
+            (frame.x - left_border).round() as i32,
+            (frame.y - top_border).round() as i32,
+            (frame.width + left_border + right_border).round() as i32,
+            (frame.height + top_border + bottom_border).round() as i32,
+            SWP_NOZORDER | SWP_NOACTIVATE,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use rusqlite::Connection;
+use std::{fs, sync::Mutex};
+use tauri::{AppHandle, Manager, Wry};
+
+/// Schema migrations, applied in order and tracked through `PRAGMA user_version`. Never edit an
+/// entry once released; append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: usage events
+    "CREATE TABLE usage_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        target TEXT NOT NULL,
+        source_application TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX usage_events_kind_target ON usage_events (kind, target);
+    CREATE INDEX usage_events_created_at ON usage_events (created_at);",
+    // 2: retrieval chunks
+    "CREATE TABLE retrieval_chunks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        source_kind TEXT NOT NULL,
+        source_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        chunk_index INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        model TEXT NOT NULL,
+        vector BLOB NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX retrieval_chunks_source ON retrieval_chunks (source_kind, source_id);
+    CREATE INDEX retrieval_chunks_model ON retrieval_chunks (model);",
+    // 3: chat threads with full-text search over their messages
+    "CREATE TABLE threads (
+        id TEXT PRIMARY KEY,
+        type TEXT NOT NULL,
+        command TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX threads_updated_at ON threads (updated_at);
+    CREATE TABLE thread_messages (
+        id TEXT PRIMARY KEY,
+        thread_id TEXT NOT NULL REFERENCES threads (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        content TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX thread_messages_thread_id ON thread_messages (thread_id, position);
+    CREATE VIRTUAL TABLE thread_messages_fts USING fts5 (
+        content,
+        content = 'thread_messages',
+        content_rowid = 'rowid'
+    );
+    CREATE TRIGGER thread_messages_ai AFTER INSERT ON thread_messages BEGIN
+        INSERT INTO thread_messages_fts (rowid, content) VALUES (new.rowid, new.content);
+    END;
+    CREATE TRIGGER thread_messages_ad AFTER DELETE ON thread_messages BEGIN
+        INSERT INTO thread_messages_fts (thread_messages_fts, rowid, content)
+        VALUES ('delete', old.rowid, old.content);
+    END;
+    CREATE TRIGGER thread_messages_au AFTER UPDATE ON thread_messages BEGIN
+        INSERT INTO thread_messages_fts (thread_messages_fts, rowid, content)
+        VALUES ('delete', old.rowid, old.content);
+        INSERT INTO thread_messages_fts (rowid, content) VALUES (new.rowid, new.content);
+    END;",
+    // 4: audit log of shell commands proposed by the model
+    "CREATE TABLE command_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        command TEXT NOT NULL,
+        cwd TEXT NOT NULL,
+        status TEXT NOT NULL,
+        exit_code INTEGER,
+        output TEXT NOT NULL DEFAULT '',
+        started_at INTEGER NOT NULL,
+        duration_ms INTEGER
+    );
+    CREATE INDEX command_log_started_at ON command_log (started_at);",
+];
+
+#[derive(Default)]
+pub struct State(pub Mutex<Option<Connection>>);
+
+fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+fn open(app_handle: &AppHandle<Wry>) -> rusqlite::Result<Connection> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or(rusqlite::Error::InvalidPath("app data directory".into()))?;
+
+    fs::create_dir_all(&app_data_dir).unwrap_or_default();
+
+    let mut connection = Connection::open(app_data_dir.join("lander.db"))?;
+    connection.pragma_update(None, "journal_mode", "WAL")?;
+    connection.pragma_update(None, "foreign_keys", "ON")?;
+
+    migrate(&mut connection)?;
+
+    Ok(connection)
+}
+
+/// Migrated in-memory database for tests.
+#[cfg(test)]
+pub fn open_in_memory() -> Connection {
+    let mut connection = Connection::open_in_memory().unwrap();
+    connection
+        .pragma_update(None, "foreign_keys", "ON")
+        .unwrap();
+    migrate(&mut connection).unwrap();
+    connection
+}
+
+/// Runs `f` against the shared database connection, opening and migrating it on first use.
+pub fn with_connection<T>(
+    app_handle: &AppHandle<Wry>,
+    f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+) -> rusqlite::Result<T> {
+    let state = app_handle.state::<State>();
+    let mut connection = state.0.lock().unwrap();
+
+    if connection.is_none() {
+        *connection = Some(open(app_handle)?);
+    }
+
+    f(connection.as_mut().unwrap())
+}
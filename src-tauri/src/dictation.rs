@@ -0,0 +1,698 @@
+use rdev::{EventType, Key};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{path::PathBuf, sync::Mutex, thread, time::Duration};
+use tauri::{AppHandle, GlobalShortcutManager, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+pub mod audio;
+mod transcription;
+#[cfg(feature = "local-transcription")]
+mod whisper;
+
+const DEFAULT_PARTIAL_INTERVAL_MS: u64 = 1500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// An OpenAI-compatible `/audio/transcriptions` endpoint.
+    Remote,
+    /// whisper.cpp on the CPU; requires the `local-transcription` feature.
+    Local,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    /// The transcript is returned to the panel, which puts it in the prompt.
+    Panel,
+    /// The transcript is inserted into the focused application with `insert_text`.
+    Application,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Push-to-talk hotkey that dictates into the focused application while it is held. Without
+    /// the keyboard hook that reports its release, pressing it again stops.
+    pub hotkey: String,
+    pub backend: Backend,
+    pub remote_url: String,
+    pub remote_model: String,
+    /// Name of the stored secret sent as a bearer token, if any.
+    pub remote_api_key_secret: String,
+    /// `ggml` model file for whisper.cpp.
+    pub local_model_path: Option<PathBuf>,
+    /// ISO 639-1 code of the spoken language; detected from the audio when unset.
+    pub language: Option<String>,
+    /// Milliseconds between partial transcripts while recording; 0 turns them off. When unset,
+    /// only the local backend makes them, as each one uploads everything recorded so far.
+    pub partial_interval_ms: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            hotkey: String::new(),
+            backend: Backend::Remote,
+            remote_url: "https://api.openai.com/v1/audio/transcriptions".to_string(),
+            remote_model: "whisper-1".to_string(),
+            remote_api_key_secret: "openai_api_key".to_string(),
+            local_model_path: None,
+            language: None,
+            partial_interval_ms: None,
+        }
+    }
+}
+
+impl Settings {
+    fn partial_interval(&self) -> Option<Duration> {
+        let interval_ms = self.partial_interval_ms.unwrap_or(match self.backend {
+            Backend::Local => DEFAULT_PARTIAL_INTERVAL_MS,
+            Backend::Remote => 0,
+        });
+
+        (interval_ms > 0).then(|| Duration::from_millis(interval_ms))
+    }
+}
+
+struct Recording {
+    id: u64,
+    target: Target,
+    recorder: audio::Recorder,
+    /// Key whose release stops a push-to-talk recording.
+    release_key: Option<Key>,
+}
+
+/// Thread running the keyboard hook. rdev cannot remove its hook, so the message loop (Windows)
+/// or run loop (macOS) of the thread is ended instead, which releases the hook with the thread.
+/// The X11 loop on Linux cannot be interrupted, so there the hook keeps running unheard.
+struct KeyListener {
+    #[cfg(target_os = "windows")]
+    thread_id: u32,
+    #[cfg(target_os = "macos")]
+    run_loop: core_foundation::runloop::CFRunLoop,
+}
+
+// `CFRunLoopStop` may be called from any thread.
+#[cfg(target_os = "macos")]
+unsafe impl Send for KeyListener {}
+
+impl KeyListener {
+    fn current() -> Self {
+        Self {
+            #[cfg(target_os = "windows")]
+            thread_id: unsafe { windows_sys::Win32::System::Threading::GetCurrentThreadId() },
+            #[cfg(target_os = "macos")]
+            run_loop: core_foundation::runloop::CFRunLoop::get_current(),
+        }
+    }
+
+    /// Ends the hook's loop, returning whether the hook is released.
+    fn stop(&self) -> bool {
+        #[cfg(target_os = "windows")]
+        {
+            use windows_sys::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
+
+            unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) != 0 }
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            self.run_loop.stop();
+            true
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            false
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Store {
+    recording: Option<Recording>,
+    next_id: u64,
+    hotkey: Option<String>,
+    /// Keys held down, while the keyboard hook runs.
+    pressed_keys: Option<Vec<Key>>,
+    /// Thread of the keyboard hook, kept to stop it.
+    key_listener: Option<KeyListener>,
+    #[cfg(feature = "local-transcription")]
+    local_model: Option<(PathBuf, std::sync::Arc<whisper::Model>)>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("dictation")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn is_recording(app_handle: &AppHandle<Wry>, id: u64) -> bool {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .recording
+        .as_ref()
+        .map_or(false, |recording| recording.id == id)
+}
+
+/// Transcribes what was recorded so far at the configured interval and emits it as
+/// `dictation_partial` events, until the recording stops.
+async fn emit_partial_transcripts(app_handle: AppHandle<Wry>, id: u64, settings: Settings) {
+    let interval = match settings.partial_interval() {
+        Some(interval) => interval,
+        None => return,
+    };
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let samples = {
+            let state = app_handle.state::<State>();
+            let store = state.0.lock().unwrap();
+
+            match &store.recording {
+                Some(recording) if recording.id == id => recording.recorder.snapshot(),
+                _ => return,
+            }
+        };
+
+        let text = match transcription::transcribe(&app_handle, &settings, &samples).await {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+
+        // The recording may have stopped while the transcription ran.
+        if !is_recording(&app_handle, id) {
+            return;
+        }
+
+        app_handle
+            .emit_all("dictation_partial", json!({ "id": id, "text": text }))
+            .unwrap_or_default();
+    }
+}
+
+/// Opens the microphone and starts recording, until `release_key` is released if set. Fails when a
+/// recording is already running.
+pub fn start(
+    app_handle: &AppHandle<Wry>,
+    target: Target,
+    release_key: Option<Key>,
+) -> Result<u64, String> {
+    let settings = get_settings(app_handle);
+
+    let id = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        if store.recording.is_some() {
+            return Err("dictation is already running".to_string());
+        }
+
+        let recorder = audio::Recorder::start()?;
+
+        store.next_id += 1;
+        let id = store.next_id;
+
+        store.recording = Some(Recording {
+            id,
+            target,
+            recorder,
+            release_key,
+        });
+
+        id
+    };
+
+    app_handle
+        .emit_all("dictation_started", json!({ "id": id, "target": target }))
+        .unwrap_or_default();
+
+    tauri::async_runtime::spawn(emit_partial_transcripts(app_handle.clone(), id, settings));
+
+    Ok(id)
+}
+
+/// Stops recording and transcribes it, emitting `dictation_finished`. Transcripts for the
+/// focused application are pasted into it. Returns `None` when nothing was being recorded.
+pub async fn stop(app_handle: &AppHandle<Wry>) -> Result<Option<String>, String> {
+    let recording = app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .recording
+        .take();
+
+    let Recording {
+        id,
+        target,
+        recorder,
+        ..
+    } = match recording {
+        Some(recording) => recording,
+        None => return Ok(None),
+    };
+
+    let samples = tauri::async_runtime::spawn_blocking(move || recorder.stop())
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let settings = get_settings(app_handle);
+    let result = transcription::transcribe(app_handle, &settings, &samples).await;
+
+    app_handle
+        .emit_all(
+            "dictation_finished",
+            match &result {
+                Ok(text) => json!({ "id": id, "text": text }),
+                Err(error) => json!({ "id": id, "error": error }),
+            },
+        )
+        .unwrap_or_default();
+
+    let text = result?;
+
+    if target == Target::Application && !text.is_empty() {
+        crate::command::insert_text(app_handle.clone(), &text);
+    }
+
+    Ok(Some(text))
+}
+
+fn stop_in_background(app_handle: &AppHandle<Wry>) {
+    let app_handle = app_handle.clone();
+
+    tauri::async_runtime::spawn(async move {
+        stop(&app_handle).await.unwrap_or_default();
+    });
+}
+
+/// Key whose release ends push-to-talk for `hotkey`, the one it adds to its modifiers.
+fn release_key(hotkey: &str) -> Option<Key> {
+    const LETTERS: [Key; 26] = [
+        Key::KeyA,
+        Key::KeyB,
+        Key::KeyC,
+        Key::KeyD,
+        Key::KeyE,
+        Key::KeyF,
+        Key::KeyG,
+        Key::KeyH,
+        Key::KeyI,
+        Key::KeyJ,
+        Key::KeyK,
+        Key::KeyL,
+        Key::KeyM,
+        Key::KeyN,
+        Key::KeyO,
+        Key::KeyP,
+        Key::KeyQ,
+        Key::KeyR,
+        Key::KeyS,
+        Key::KeyT,
+        Key::KeyU,
+        Key::KeyV,
+        Key::KeyW,
+        Key::KeyX,
+        Key::KeyY,
+        Key::KeyZ,
+    ];
+    const DIGITS: [Key; 10] = [
+        Key::Num0,
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+    ];
+    const FUNCTION_KEYS: [Key; 12] = [
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+        Key::F9,
+        Key::F10,
+        Key::F11,
+        Key::F12,
+    ];
+
+    let name = hotkey.rsplit('+').next()?.trim().to_uppercase();
+    let name = name
+        .strip_prefix("KEY")
+        .or_else(|| name.strip_prefix("DIGIT"))
+        .filter(|name| name.len() == 1)
+        .unwrap_or(&name);
+
+    if let [character] = name.as_bytes() {
+        match character {
+            b'A'..=b'Z' => return Some(LETTERS[(character - b'A') as usize]),
+            b'0'..=b'9' => return Some(DIGITS[(character - b'0') as usize]),
+            _ => {}
+        }
+    }
+
+    if let Some(index) = name
+        .strip_prefix('F')
+        .and_then(|number| number.parse::<usize>().ok())
+        .filter(|number| (1..=FUNCTION_KEYS.len()).contains(number))
+    {
+        return Some(FUNCTION_KEYS[index - 1]);
+    }
+
+    Some(match name {
+        "SPACE" => Key::Space,
+        "ENTER" | "RETURN" => Key::Return,
+        "TAB" => Key::Tab,
+        "ESCAPE" | "ESC" => Key::Escape,
+        "BACKSPACE" => Key::Backspace,
+        "DELETE" | "DEL" => Key::Delete,
+        "INSERT" => Key::Insert,
+        "HOME" => Key::Home,
+        "END" => Key::End,
+        "PAGEUP" => Key::PageUp,
+        "PAGEDOWN" => Key::PageDown,
+        "UP" | "ARROWUP" => Key::UpArrow,
+        "DOWN" | "ARROWDOWN" => Key::DownArrow,
+        "LEFT" | "ARROWLEFT" => Key::LeftArrow,
+        "RIGHT" | "ARROWRIGHT" => Key::RightArrow,
+        "," | "COMMA" => Key::Comma,
+        "." | "PERIOD" => Key::Dot,
+        "/" | "SLASH" => Key::Slash,
+        ";" | "SEMICOLON" => Key::SemiColon,
+        "'" | "QUOTE" => Key::Quote,
+        "[" | "BRACKETLEFT" => Key::LeftBracket,
+        "]" | "BRACKETRIGHT" => Key::RightBracket,
+        "\\" | "BACKSLASH" => Key::BackSlash,
+        "`" | "BACKQUOTE" => Key::BackQuote,
+        "-" | "MINUS" => Key::Minus,
+        "=" | "EQUAL" => Key::Equal,
+        _ => return None,
+    })
+}
+
+fn is_key_pressed(app_handle: &AppHandle<Wry>, key: Key) -> bool {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .pressed_keys
+        .as_ref()
+        .map_or(false, |pressed_keys| pressed_keys.contains(&key))
+}
+
+/// Starts push-to-talk, or stops a running recording that has no key to release.
+fn on_hotkey(app_handle: &AppHandle<Wry>) {
+    let (recording_release_key, release_key) = {
+        let state = app_handle.state::<State>();
+        let store = state.0.lock().unwrap();
+
+        let release_key = store
+            .pressed_keys
+            .as_ref()
+            .and(store.hotkey.as_deref())
+            .and_then(release_key);
+
+        (
+            store
+                .recording
+                .as_ref()
+                .map(|recording| recording.release_key),
+            release_key,
+        )
+    };
+
+    match recording_release_key {
+        Some(None) => stop_in_background(app_handle),
+        // Holding the hotkey repeats its press.
+        Some(Some(_)) => {}
+        None => match start(app_handle, Target::Application, release_key) {
+            // The key may have been released while the microphone opened.
+            Ok(_) => {
+                if let Some(key) = release_key {
+                    if !is_key_pressed(app_handle, key) {
+                        stop_in_background(app_handle);
+                    }
+                }
+            }
+            Err(error) => app_handle
+                .emit_all("dictation_finished", json!({ "error": error }))
+                .unwrap_or_default(),
+        },
+    }
+}
+
+fn on_key_event(app_handle: &AppHandle<Wry>, event_type: EventType) {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    let pressed_keys = match store.pressed_keys.as_mut() {
+        Some(pressed_keys) => pressed_keys,
+        None => return,
+    };
+
+    match event_type {
+        EventType::KeyPress(key) if !pressed_keys.contains(&key) => pressed_keys.push(key),
+        EventType::KeyRelease(key) => {
+            pressed_keys.retain(|pressed_key| *pressed_key != key);
+
+            let is_release_key = store
+                .recording
+                .as_ref()
+                .map_or(false, |recording| recording.release_key == Some(key));
+
+            drop(store);
+
+            if is_release_key {
+                stop_in_background(app_handle);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Global hotkeys only report presses, so releases come from a keyboard hook. It needs the
+/// accessibility permission on macOS, as typing into other applications does; without it, the
+/// hotkey toggles dictation instead. The hook only runs while a hotkey is registered.
+fn listen_for_keys(app_handle: AppHandle<Wry>) {
+    {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        if store.pressed_keys.is_some() {
+            return;
+        }
+
+        store.pressed_keys = Some(vec![]);
+
+        // A hook that could not be stopped is still running.
+        if store.key_listener.is_some() {
+            return;
+        }
+    }
+
+    thread::spawn(move || {
+        app_handle.state::<State>().0.lock().unwrap().key_listener = Some(KeyListener::current());
+
+        let listener_app_handle = app_handle.clone();
+
+        let result = rdev::listen(move |event| match event.event_type {
+            EventType::KeyPress(_) | EventType::KeyRelease(_) => {
+                on_key_event(&listener_app_handle, event.event_type)
+            }
+            _ => {}
+        });
+
+        if result.is_err() {
+            let state = app_handle.state::<State>();
+            let mut store = state.0.lock().unwrap();
+
+            store.pressed_keys = None;
+            store.key_listener = None;
+        }
+    });
+}
+
+fn stop_listening_for_keys(app_handle: &AppHandle<Wry>) {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    store.pressed_keys = None;
+
+    if store
+        .key_listener
+        .as_ref()
+        .map_or(false, |key_listener| key_listener.stop())
+    {
+        store.key_listener = None;
+    }
+}
+
+fn register_hotkey(app_handle: &AppHandle<Wry>, hotkey: &str) {
+    let is_registered = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+        let mut shortcut_manager = app_handle.global_shortcut_manager();
+
+        if let Some(hotkey) = store.hotkey.take() {
+            shortcut_manager.unregister(&hotkey).unwrap_or_default();
+        }
+
+        let handler_app_handle = app_handle.clone();
+
+        if !hotkey.trim().is_empty()
+            && shortcut_manager
+                .register(hotkey, move || on_hotkey(&handler_app_handle))
+                .is_ok()
+        {
+            store.hotkey = Some(hotkey.to_string());
+        }
+
+        store.hotkey.is_some()
+    };
+
+    if is_registered {
+        listen_for_keys(app_handle.clone());
+    } else {
+        stop_listening_for_keys(app_handle);
+    }
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    let settings = get_settings(&app_handle);
+    register_hotkey(&app_handle, &settings.hotkey);
+}
+
+/// Starts recording from the microphone. Partial transcripts arrive as `dictation_partial` events
+/// until `stop_dictation` is called.
+#[tauri::command]
+pub fn start_dictation(app_handle: AppHandle<Wry>, target: Target) -> Result<u64, String> {
+    start(&app_handle, target, None)
+}
+
+/// Stops recording and resolves to the final transcript, or `null` when nothing was recorded.
+#[tauri::command]
+pub async fn stop_dictation(app_handle: AppHandle<Wry>) -> Result<Option<String>, String> {
+    stop(&app_handle).await
+}
+
+/// Stops recording without transcribing.
+#[tauri::command]
+pub fn cancel_dictation(app_handle: AppHandle<Wry>) {
+    let recording = app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .recording
+        .take();
+
+    if let Some(recording) = recording {
+        recording.recorder.stop();
+    }
+}
+
+/// Transcribes a WAV file with the configured backend, as dictation would.
+#[tauri::command]
+pub async fn transcribe_audio_file(
+    app_handle: AppHandle<Wry>,
+    path: String,
+) -> Result<String, String> {
+    let path = PathBuf::from(path);
+    let samples = tauri::async_runtime::spawn_blocking(move || audio::read_wav(&path))
+        .await
+        .map_err(|error| error.to_string())??;
+
+    let settings = get_settings(&app_handle);
+    transcription::transcribe(&app_handle, &settings, &samples).await
+}
+
+#[tauri::command]
+pub fn get_dictation_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_dictation_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("dictation".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+
+    register_hotkey(&app_handle, &settings.hotkey);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_key_is_the_key_after_the_modifiers() {
+        assert_eq!(release_key("CommandOrControl+Shift+D"), Some(Key::KeyD));
+        assert_eq!(release_key("Alt+KeyM"), Some(Key::KeyM));
+        assert_eq!(release_key("Control+Digit5"), Some(Key::Num5));
+        assert_eq!(release_key("Alt+Space"), Some(Key::Space));
+        assert_eq!(release_key("F9"), Some(Key::F9));
+        assert_eq!(release_key("Shift+ArrowUp"), Some(Key::UpArrow));
+        assert_eq!(release_key("Alt+,"), Some(Key::Comma));
+    }
+
+    #[test]
+    fn release_key_is_none_for_unknown_keys() {
+        assert_eq!(release_key(""), None);
+        assert_eq!(release_key("Alt+F24"), None);
+        assert_eq!(release_key("Alt+MediaPlayPause"), None);
+    }
+
+    #[test]
+    fn partial_transcripts_are_off_for_the_remote_backend_by_default() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.partial_interval(), None);
+
+        settings.partial_interval_ms = Some(2000);
+        assert_eq!(settings.partial_interval(), Some(Duration::from_secs(2)));
+
+        settings.backend = Backend::Local;
+        settings.partial_interval_ms = None;
+        assert_eq!(
+            settings.partial_interval(),
+            Some(Duration::from_millis(DEFAULT_PARTIAL_INTERVAL_MS))
+        );
+
+        settings.partial_interval_ms = Some(0);
+        assert_eq!(settings.partial_interval(), None);
+    }
+}
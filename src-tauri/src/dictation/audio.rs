@@ -0,0 +1,310 @@
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+use std::{
+    io::Cursor,
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+/// Whisper models, local and remote, are trained on 16 kHz mono audio.
+pub const SAMPLE_RATE: u32 = 16000;
+
+/// Longest recording kept, in seconds; the microphone keeps running, but later audio is dropped.
+const MAX_RECORDING_SECONDS: usize = 300;
+
+/// Audio from the default microphone, mixed down to mono at the device's sample rate.
+///
+/// `cpal` streams cannot move between threads on every platform, so each recording owns a thread
+/// that holds the stream until the recording stops.
+pub struct Recorder {
+    samples: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    samples: Arc<Mutex<Vec<f32>>>,
+) -> Result<Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    let max_samples = config.sample_rate.0 as usize * MAX_RECORDING_SECONDS;
+
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut samples = samples.lock().unwrap();
+
+                for frame in data.chunks(channels) {
+                    if samples.len() >= max_samples {
+                        break;
+                    }
+
+                    let sum = frame
+                        .iter()
+                        .map(|sample| sample.to_sample::<f32>())
+                        .sum::<f32>();
+
+                    samples.push(sum / frame.len() as f32);
+                }
+            },
+            // Errors mid-stream, like an unplugged microphone, end up as a shorter recording.
+            |_| {},
+            None,
+        )
+        .map_err(|error| error.to_string())
+}
+
+fn open_stream(samples: Arc<Mutex<Vec<f32>>>) -> Result<(Stream, u32), String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("no microphone is available")?;
+
+    let supported_config = device
+        .default_input_config()
+        .map_err(|error| error.to_string())?;
+
+    let sample_rate = supported_config.sample_rate().0;
+    let sample_format = supported_config.sample_format();
+    let config = supported_config.config();
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, samples),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, samples),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, samples),
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, samples),
+        format => Err(format!(
+            "the microphone's sample format {:?} is not supported",
+            format
+        )),
+    }?;
+
+    stream.play().map_err(|error| error.to_string())?;
+
+    Ok((stream, sample_rate))
+}
+
+impl Recorder {
+    /// Starts recording, failing when there is no microphone or access to it was denied.
+    pub fn start() -> Result<Self, String> {
+        let samples = Arc::new(Mutex::new(vec![]));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let (stop, stop_receiver) = mpsc::channel::<()>();
+
+        let stream_samples = samples.clone();
+
+        let thread = thread::spawn(move || {
+            let stream = match open_stream(stream_samples) {
+                Ok((stream, sample_rate)) => {
+                    ready_sender.send(Ok(sample_rate)).unwrap_or_default();
+                    stream
+                }
+                Err(error) => {
+                    ready_sender.send(Err(error)).unwrap_or_default();
+                    return;
+                }
+            };
+
+            // Returns once `stop` is called or the recorder is dropped.
+            stop_receiver.recv().unwrap_or_default();
+            drop(stream);
+        });
+
+        let sample_rate = ready_receiver
+            .recv()
+            .map_err(|_| "the microphone could not be opened".to_string())??;
+
+        Ok(Self {
+            samples,
+            sample_rate,
+            stop,
+            thread,
+        })
+    }
+
+    /// Audio recorded so far, at `SAMPLE_RATE`.
+    pub fn snapshot(&self) -> Vec<f32> {
+        let samples = self.samples.lock().unwrap().clone();
+        resample(&samples, self.sample_rate, SAMPLE_RATE)
+    }
+
+    pub fn stop(self) -> Vec<f32> {
+        let Recorder {
+            samples,
+            sample_rate,
+            stop,
+            thread,
+        } = self;
+
+        stop.send(()).unwrap_or_default();
+        thread.join().unwrap_or_default();
+
+        let samples = samples.lock().unwrap();
+        resample(&samples, sample_rate, SAMPLE_RATE)
+    }
+}
+
+/// Resamples mono audio, averaging the samples each output sample covers so downsampling does
+/// not alias.
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || from_rate == 0 || to_rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let length = (samples.len() as f64 / ratio) as usize;
+
+    (0..length)
+        .map(|index| {
+            let start = ((index as f64 * ratio) as usize).min(samples.len() - 1);
+            let end = (((index + 1) as f64 * ratio) as usize).clamp(start + 1, samples.len());
+
+            samples[start..end].iter().sum::<f32>() / (end - start) as f32
+        })
+        .collect()
+}
+
+/// Reads a WAV file of any channel count, sample rate and PCM or float format as audio at
+/// `SAMPLE_RATE`.
+pub fn read_wav(path: &Path) -> Result<Vec<f32>, String> {
+    let reader = hound::WavReader::open(path).map_err(|error| error.to_string())?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+        }
+    }
+    .map_err(|error| error.to_string())?;
+
+    let channels = spec.channels.max(1) as usize;
+    let samples = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect::<Vec<_>>();
+
+    Ok(resample(&samples, spec.sample_rate, SAMPLE_RATE))
+}
+
+/// Encodes audio at `SAMPLE_RATE` as a 16-bit PCM WAV file.
+pub fn encode_wav(samples: &[f32]) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut data = Cursor::new(vec![]);
+    let mut writer = hound::WavWriter::new(&mut data, spec).map_err(|error| error.to_string())?;
+
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer
+            .write_sample(sample)
+            .map_err(|error| error.to_string())?;
+    }
+
+    writer.finalize().map_err(|error| error.to_string())?;
+
+    Ok(data.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Half a second of a 440 Hz tone at half volume in the left channel of a 44.1 kHz 16-bit
+    /// stereo file, with a silent right channel.
+    fn tone_path() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dictation/tone.wav")
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples
+            .iter()
+            .fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn read_wav_mixes_down_and_resamples() {
+        let samples = read_wav(&tone_path()).unwrap();
+
+        assert_eq!(samples.len(), SAMPLE_RATE as usize / 2);
+        assert!((peak(&samples) - 0.25).abs() < 0.01, "{}", peak(&samples));
+    }
+
+    #[test]
+    fn read_wav_fails_on_other_files() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/documents/notes.txt");
+
+        assert!(read_wav(&path).is_err());
+    }
+
+    #[test]
+    fn encode_wav_round_trips() {
+        let samples = read_wav(&tone_path()).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("lander-dictation-{}.wav", std::process::id()));
+
+        std::fs::write(&path, encode_wav(&samples).unwrap()).unwrap();
+        let decoded = read_wav(&path);
+        std::fs::remove_file(&path).unwrap();
+        let decoded = decoded.unwrap();
+
+        assert_eq!(decoded.len(), samples.len());
+        assert!(decoded
+            .iter()
+            .zip(&samples)
+            .all(|(decoded, sample)| (decoded - sample).abs() < 1.0 / i16::MAX as f32 * 2.0));
+    }
+
+    #[test]
+    fn encode_wav_clamps() {
+        let data = encode_wav(&[2.0, -2.0]).unwrap();
+        let samples = hound::WavReader::new(Cursor::new(data))
+            .unwrap()
+            .into_samples::<i16>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(samples, [i16::MAX, -i16::MAX]);
+    }
+
+    #[test]
+    fn resample_averages_when_downsampling() {
+        assert_eq!(
+            resample(&[0.0, 1.0, 0.5, 0.5, 1.0, 1.0], 48000, 16000),
+            [0.5, 2.5 / 3.0]
+        );
+    }
+
+    #[test]
+    fn resample_upsamples_by_repeating() {
+        assert_eq!(
+            resample(&[0.25, 0.75], 8000, 16000),
+            [0.25, 0.25, 0.75, 0.75]
+        );
+    }
+
+    #[test]
+    fn resample_keeps_matching_rates_and_empty_audio() {
+        assert_eq!(resample(&[0.1, 0.2], 16000, 16000), [0.1, 0.2]);
+        assert!(resample(&[], 44100, 16000).is_empty());
+    }
+}
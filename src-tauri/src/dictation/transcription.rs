@@ -0,0 +1,145 @@
+use reqwest::{
+    multipart::{Form, Part},
+    Client,
+};
+use serde::Deserialize;
+use tauri::{AppHandle, Wry};
+
+use super::{audio, Backend, Settings};
+
+/// Shorter audio is silence or a mistaken key press, and Whisper rejects it anyway.
+const MIN_SAMPLES: usize = audio::SAMPLE_RATE as usize / 4;
+
+#[derive(Deserialize)]
+struct RemoteResponse {
+    text: String,
+}
+
+/// Calls an OpenAI-compatible `/audio/transcriptions` endpoint, authenticating with the stored
+/// secret.
+async fn transcribe_remote(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    samples: &[f32],
+) -> Result<String, String> {
+    let file = Part::bytes(audio::encode_wav(samples)?)
+        .file_name("dictation.wav")
+        .mime_str("audio/wav")
+        .map_err(|error| error.to_string())?;
+
+    let mut form = Form::new()
+        .part("file", file)
+        .text("model", settings.remote_model.clone())
+        .text("response_format", "json");
+
+    if let Some(language) = &settings.language {
+        form = form.text("language", language.clone());
+    }
+
+    let mut request = Client::new().post(&settings.remote_url).multipart(form);
+
+    if !settings.remote_api_key_secret.is_empty() {
+        let api_key = crate::secrets::get_secret(
+            app_handle,
+            &settings.remote_api_key_secret,
+            &settings.remote_url,
+        )
+        .map_err(|error| error.to_string())?;
+
+        request = request.bearer_auth(api_key);
+    }
+
+    let response = request.send().await.map_err(|error| error.to_string())?;
+    let status = response.status();
+    let text = response.text().await.map_err(|error| error.to_string())?;
+
+    if !status.is_success() {
+        return Err(format!(
+            "transcription request failed with {}: {}",
+            status, text
+        ));
+    }
+
+    let response: RemoteResponse =
+        serde_json::from_str(&text).map_err(|error| error.to_string())?;
+
+    Ok(response.text.trim().to_string())
+}
+
+#[cfg(feature = "local-transcription")]
+async fn transcribe_local(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    samples: &[f32],
+) -> Result<String, String> {
+    use tauri::Manager;
+
+    let model_path = settings
+        .local_model_path
+        .clone()
+        .ok_or_else(|| "no local Whisper model is configured".to_string())?;
+
+    let loaded_model = app_handle
+        .state::<super::State>()
+        .0
+        .lock()
+        .unwrap()
+        .local_model
+        .as_ref()
+        .filter(|(loaded_path, _)| *loaded_path == model_path)
+        .map(|(_, model)| model.clone());
+
+    // Loading takes a while for larger models, so it happens outside the lock.
+    let model = match loaded_model {
+        Some(model) => model,
+        None => {
+            let path = model_path.clone();
+            let model =
+                tauri::async_runtime::spawn_blocking(move || super::whisper::Model::load(&path))
+                    .await
+                    .map_err(|error| error.to_string())??;
+
+            let model = std::sync::Arc::new(model);
+            app_handle
+                .state::<super::State>()
+                .0
+                .lock()
+                .unwrap()
+                .local_model = Some((model_path, model.clone()));
+
+            model
+        }
+    };
+
+    let samples = samples.to_vec();
+    let language = settings.language.clone();
+
+    tauri::async_runtime::spawn_blocking(move || model.transcribe(&samples, language.as_deref()))
+        .await
+        .map_err(|error| error.to_string())?
+}
+
+#[cfg(not(feature = "local-transcription"))]
+async fn transcribe_local(
+    _app_handle: &AppHandle<Wry>,
+    _settings: &Settings,
+    _samples: &[f32],
+) -> Result<String, String> {
+    Err("Lander was built without the `local-transcription` feature".to_string())
+}
+
+/// Transcribes audio at `audio::SAMPLE_RATE` with the configured backend.
+pub async fn transcribe(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    samples: &[f32],
+) -> Result<String, String> {
+    if samples.len() < MIN_SAMPLES {
+        return Ok(String::new());
+    }
+
+    match settings.backend {
+        Backend::Remote => transcribe_remote(app_handle, settings, samples).await,
+        Backend::Local => transcribe_local(app_handle, settings, samples).await,
+    }
+}
@@ -0,0 +1,50 @@
+use std::{path::Path, thread};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+
+/// `ggml` model for whisper.cpp (e.g. `ggml-base.en.bin`), run on the CPU.
+pub struct Model(WhisperContext);
+
+impl Model {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        WhisperContext::new(&path.to_string_lossy())
+            .map(Model)
+            .map_err(|error| format!("the Whisper model could not be loaded: {}", error))
+    }
+
+    /// Transcribes audio at `audio::SAMPLE_RATE`. Without a language, it is detected from the
+    /// audio.
+    pub fn transcribe(&self, samples: &[f32], language: Option<&str>) -> Result<String, String> {
+        let mut state = self.0.create_state().map_err(|error| error.to_string())?;
+
+        let threads = thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+            .min(8);
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(language.unwrap_or("auto")));
+        params.set_n_threads(threads as i32);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        state
+            .full(params, samples)
+            .map_err(|error| error.to_string())?;
+
+        let segments = state.full_n_segments().map_err(|error| error.to_string())?;
+
+        let mut text = String::new();
+
+        for segment in 0..segments {
+            let segment = state
+                .full_get_segment_text(segment)
+                .map_err(|error| error.to_string())?;
+
+            text.push_str(&segment);
+        }
+
+        Ok(text.trim().to_string())
+    }
+}
@@ -0,0 +1,477 @@
+use quick_xml::{events::Event, Reader};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+/// Rough conversion used to budget text against the model context without a tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
+
+const DEFAULT_MAX_TOKENS: usize = 12_000;
+const DEFAULT_CHUNK_TOKENS: usize = 1_000;
+
+/// Files larger than this are rejected before any extraction happens.
+const MAX_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Bytes sniffed for NUL characters to tell binary files apart from text.
+const BINARY_SNIFF_LENGTH: usize = 8000;
+
+const CODE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cs", "css", "go", "h", "hpp", "html", "java", "js", "json", "jsx", "kt",
+    "lua", "php", "py", "rb", "rs", "scss", "sh", "sql", "swift", "toml", "ts", "tsx", "vue",
+    "xml", "yaml", "yml", "zig",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    PlainText,
+    Markdown,
+    Code,
+    Pdf,
+    Docx,
+}
+
+impl Format {
+    /// Picks a format by extension, falling back to plain text for files that look like text.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "txt" | "text" | "log" | "csv" => Some(Self::PlainText),
+            "md" | "markdown" | "mdx" => Some(Self::Markdown),
+            "pdf" => Some(Self::Pdf),
+            "docx" => Some(Self::Docx),
+            extension if CODE_EXTENSIONS.contains(&extension) => Some(Self::Code),
+            _ if is_text_file(path) => Some(Self::PlainText),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chunk {
+    pub index: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Document {
+    pub path: String,
+    pub name: String,
+    pub format: Option<Format>,
+    pub chunks: Vec<Chunk>,
+    /// Characters of extracted text before truncation.
+    pub total_length: usize,
+    /// Whether chunks were dropped to stay within the token budget.
+    pub is_truncated: bool,
+    pub error: Option<String>,
+}
+
+fn is_text_file(path: &Path) -> bool {
+    let mut buffer = vec![0; BINARY_SNIFF_LENGTH];
+
+    let length = match fs::File::open(path).and_then(|mut file| file.read(&mut buffer)) {
+        Ok(length) => length,
+        Err(_) => return false,
+    };
+
+    !buffer[..length].contains(&0) && std::str::from_utf8(&buffer[..length]).is_ok()
+}
+
+fn extract_plain_text(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    Ok(text.trim_start_matches('\u{feff}').replace("\r\n", "\n"))
+}
+
+fn extract_pdf_text(path: &Path) -> Result<String, String> {
+    let path = path.to_path_buf();
+
+    // The PDF parser panics on some malformed files instead of returning an error.
+    std::panic::catch_unwind(move || pdf_extract::extract_text(&path))
+        .map_err(|_| "failed to parse PDF".to_string())?
+        .map_err(|error| error.to_string())
+}
+
+/// Pulls the text runs out of a WordprocessingML body, keeping paragraphs, tabs and breaks.
+/// Tabs and breaks only count inside runs, since `w:tab` also defines tab stops in `w:pPr`.
+pub fn parse_docx_xml(xml: &str) -> Result<String, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut is_in_run = false;
+    let mut is_in_text = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => match element.name().as_ref() {
+                b"w:r" => is_in_run = true,
+                b"w:t" => is_in_text = true,
+                _ => {}
+            },
+            Ok(Event::End(element)) => match element.name().as_ref() {
+                b"w:r" => is_in_run = false,
+                b"w:t" => is_in_text = false,
+                b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Ok(Event::Empty(element)) if is_in_run => match element.name().as_ref() {
+                b"w:tab" => text.push('\t'),
+                b"w:br" | b"w:cr" => text.push('\n'),
+                _ => {}
+            },
+            Ok(Event::Text(content)) if is_in_text => {
+                text.push_str(&content.unescape().map_err(|error| error.to_string())?)
+            }
+            Ok(Event::Eof) => break,
+            Err(error) => return Err(error.to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(text)
+}
+
+fn extract_docx_text(path: &Path) -> Result<String, String> {
+    let file = fs::File::open(path).map_err(|error| error.to_string())?;
+    let mut archive =
+        zip::ZipArchive::new(BufReader::new(file)).map_err(|error| error.to_string())?;
+
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|error| error.to_string())?
+        .read_to_string(&mut xml)
+        .map_err(|error| error.to_string())?;
+
+    parse_docx_xml(&xml)
+}
+
+pub fn extract_text(path: &Path, format: Format) -> Result<String, String> {
+    let size = fs::metadata(path).map_err(|error| error.to_string())?.len();
+
+    if size > MAX_FILE_SIZE {
+        return Err(format!(
+            "file is larger than {} MB",
+            MAX_FILE_SIZE / 1024 / 1024
+        ));
+    }
+
+    let text = match format {
+        Format::PlainText | Format::Markdown | Format::Code => extract_plain_text(path)?,
+        Format::Pdf => extract_pdf_text(path)?,
+        Format::Docx => extract_docx_text(path)?,
+    };
+
+    Ok(text.trim().to_string())
+}
+
+/// Splits `text` into chunks of at most `chunk_length` characters, breaking at line ends where
+/// possible and only cutting inside a line when it is longer than a whole chunk.
+pub fn chunk_text(text: &str, chunk_length: usize) -> Vec<Chunk> {
+    let chunk_length = chunk_length.max(1);
+    let mut chunks = vec![];
+    let mut current = String::new();
+    let mut current_length = 0;
+
+    let mut push = |current: &mut String, current_length: &mut usize| {
+        let text = current.trim_end().trim_start_matches('\n');
+
+        if !text.trim().is_empty() {
+            chunks.push(Chunk {
+                index: 0,
+                text: text.to_string(),
+            });
+        }
+
+        current.clear();
+        *current_length = 0;
+    };
+
+    for line in text.split_inclusive('\n') {
+        let line_length = line.chars().count();
+
+        if current_length + line_length > chunk_length {
+            push(&mut current, &mut current_length);
+        }
+
+        if line_length <= chunk_length {
+            current.push_str(line);
+            current_length += line_length;
+            continue;
+        }
+
+        let characters = line.chars().collect::<Vec<_>>();
+
+        for part in characters.chunks(chunk_length) {
+            if current_length > 0 {
+                push(&mut current, &mut current_length);
+            }
+
+            current.extend(part);
+            current_length = part.len();
+        }
+    }
+
+    push(&mut current, &mut current_length);
+
+    for (index, chunk) in chunks.iter_mut().enumerate() {
+        chunk.index = index;
+    }
+
+    chunks
+}
+
+fn read_document(path: &Path, max_length: usize, chunk_length: usize) -> Document {
+    let mut document = Document {
+        path: path.display().to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        format: Format::from_path(path),
+        chunks: vec![],
+        total_length: 0,
+        is_truncated: false,
+        error: None,
+    };
+
+    let text = match document.format {
+        Some(format) => extract_text(path, format),
+        None => Err("unsupported file type".to_string()),
+    };
+
+    let text = match text {
+        Ok(text) => text,
+        Err(error) => {
+            document.error = Some(error);
+            return document;
+        }
+    };
+
+    document.total_length = text.chars().count();
+
+    let mut length = 0;
+
+    for chunk in chunk_text(&text, chunk_length) {
+        length += chunk.text.chars().count();
+
+        if length > max_length {
+            document.is_truncated = true;
+            break;
+        }
+
+        document.chunks.push(chunk);
+    }
+
+    document
+}
+
+/// Extracts and chunks the given files for use as prompt context. The token budget is shared
+/// evenly between the files, and files that cannot be read carry an error instead of chunks.
+pub fn read_documents(
+    paths: &[String],
+    max_tokens: Option<usize>,
+    chunk_tokens: Option<usize>,
+) -> Vec<Document> {
+    if paths.is_empty() {
+        return vec![];
+    }
+
+    let max_length = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS) * CHARS_PER_TOKEN / paths.len();
+    let chunk_length = chunk_tokens.unwrap_or(DEFAULT_CHUNK_TOKENS) * CHARS_PER_TOKEN;
+
+    paths
+        .iter()
+        .map(|path| read_document(Path::new(path), max_length, chunk_length.min(max_length)))
+        .collect()
+}
+
+#[tauri::command]
+pub async fn read_documents_for_context(
+    paths: Vec<String>,
+    max_tokens: Option<usize>,
+    chunk_tokens: Option<usize>,
+) -> String {
+    let documents = tauri::async_runtime::spawn_blocking(move || {
+        read_documents(&paths, max_tokens, chunk_tokens)
+    })
+    .await
+    .unwrap_or_default();
+
+    serde_json::to_string(&documents).expect("error serializing json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/documents")
+            .join(name)
+    }
+
+    fn extract_fixture(name: &str) -> (Option<Format>, Result<String, String>) {
+        let path = fixture(name);
+        let format = Format::from_path(&path);
+
+        (format, extract_text(&path, format.unwrap()))
+    }
+
+    #[test]
+    fn plain_text_drops_the_byte_order_mark_and_carriage_returns() {
+        let (format, text) = extract_fixture("notes.txt");
+
+        assert_eq!(format, Some(Format::PlainText));
+        assert_eq!(
+            text.unwrap(),
+            "Meeting notes\n\nAttendees: Ada, Grace\nNext review on Friday."
+        );
+    }
+
+    #[test]
+    fn markdown_is_kept_verbatim() {
+        let (format, text) = extract_fixture("readme.md");
+
+        assert_eq!(format, Some(Format::Markdown));
+        assert_eq!(
+            text.unwrap(),
+            "# Lander\n\nLander is a launcher with a chat panel.\n\n## Install\n\nDownload the \
+             latest release and open it."
+        );
+    }
+
+    #[test]
+    fn html_is_read_as_source_code() {
+        let (format, text) = extract_fixture("page.html");
+        let text = text.unwrap();
+
+        assert_eq!(format, Some(Format::Code));
+        assert!(text.starts_with("<!DOCTYPE html>"));
+        assert!(text.contains("<p>Fixed &amp; improved the panel.</p>"));
+    }
+
+    #[test]
+    fn pdf_text_is_extracted_line_by_line() {
+        let (format, text) = extract_fixture("report.pdf");
+
+        assert_eq!(format, Some(Format::Pdf));
+        assert_eq!(
+            text.unwrap()
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>(),
+            vec!["Quarterly report", "Revenue grew by 12 percent."]
+        );
+    }
+
+    #[test]
+    fn docx_keeps_paragraphs_tabs_and_breaks() {
+        let (format, text) = extract_fixture("letter.docx");
+
+        assert_eq!(format, Some(Format::Docx));
+        assert_eq!(
+            text.unwrap(),
+            "Dear Ada,\nItem\tPrice\nFirst line\nSecond line & more"
+        );
+    }
+
+    #[test]
+    fn docx_ignores_tab_stop_definitions() {
+        let (format, text) = extract_fixture("tab-stops.docx");
+
+        assert_eq!(format, Some(Format::Docx));
+        assert_eq!(
+            text.unwrap(),
+            "Chapter\tPage\nIntroduction\t1\nNo tabs here"
+        );
+    }
+
+    #[test]
+    fn binary_files_have_no_format() {
+        assert_eq!(Format::from_path(&fixture("image.bin")), None);
+    }
+
+    #[test]
+    fn chunks_break_at_line_ends() {
+        let chunks = chunk_text("first line\nsecond line\nthird line\n", 24);
+
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk {
+                    index: 0,
+                    text: "first line\nsecond line".to_string(),
+                },
+                Chunk {
+                    index: 1,
+                    text: "third line".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chunks_cut_lines_longer_than_a_chunk() {
+        let chunks = chunk_text("short\nabcdefghij\nend", 4);
+
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| chunk.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["shor", "t", "abcd", "efgh", "ij", "end"]
+        );
+        assert!(chunks
+            .iter()
+            .enumerate()
+            .all(|(index, chunk)| chunk.index == index));
+    }
+
+    #[test]
+    fn documents_share_the_token_budget() {
+        let paths = [fixture("notes.txt"), fixture("readme.md")]
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+
+        // 20 tokens are 80 characters, 40 for each file, in chunks of 20.
+        let documents = read_documents(&paths, Some(20), Some(5));
+
+        assert_eq!(documents.len(), 2);
+        assert!(documents.iter().all(|document| document.error.is_none()));
+        assert!(documents.iter().all(|document| {
+            document
+                .chunks
+                .iter()
+                .map(|chunk| chunk.text.chars().count())
+                .sum::<usize>()
+                <= 40
+        }));
+        assert!(documents[1].is_truncated);
+        assert_eq!(documents[1].chunks[0].text, "# Lander");
+    }
+
+    #[test]
+    fn unreadable_documents_carry_an_error() {
+        let paths = vec![
+            fixture("image.bin").display().to_string(),
+            fixture("missing.txt").display().to_string(),
+        ];
+
+        let documents = read_documents(&paths, None, None);
+
+        assert_eq!(documents[0].error.as_deref(), Some("unsupported file type"));
+        assert!(documents[1].error.is_some());
+        assert!(documents.iter().all(|document| document.chunks.is_empty()));
+    }
+}
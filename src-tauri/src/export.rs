@@ -0,0 +1,333 @@
+use chrono::{TimeZone, Utc};
+use pulldown_cmark::{html, Event, Options, Parser};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tauri::{AppHandle, Wry};
+
+use crate::{thread::Thread, usage};
+
+mod pdf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Markdown,
+    Html,
+    Json,
+    Pdf,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Json => "json",
+            Format::Pdf => "pdf",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Format::Markdown => "Markdown",
+            Format::Html => "HTML",
+            Format::Json => "JSON",
+            Format::Pdf => "PDF",
+        }
+    }
+}
+
+const HTML_STYLE: &str = "
+body { font: 15px/1.6 -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; color: #1f2328; max-width: 760px; margin: 40px auto; padding: 0 20px; }
+h1 { font-size: 24px; margin-bottom: 4px; }
+.exported { color: #656d76; font-size: 13px; margin-top: 0; }
+.message { border-top: 1px solid #d0d7de; padding: 16px 0; }
+.message header { display: flex; justify-content: space-between; font-size: 13px; color: #656d76; }
+.message header .author { font-weight: 600; color: #1f2328; }
+.message.system { color: #656d76; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 12px; overflow-x: auto; }
+code { font: 13px/1.45 ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; }
+:not(pre) > code { background: #eff1f3; border-radius: 4px; padding: 2px 4px; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 4px 8px; }
+";
+
+/// Display name for a message author as stored by the webview.
+fn author_label(author: &str) -> &str {
+    match author {
+        "User" => "You",
+        "AI" => "Lander",
+        "System" => "Context",
+        author => author,
+    }
+}
+
+/// Class of a message section, from a fixed set so imported authors cannot inject markup.
+fn author_class(author: &str) -> &'static str {
+    match author {
+        "User" => "user",
+        "AI" => "ai",
+        "System" => "system",
+        _ => "other",
+    }
+}
+
+fn format_timestamp(seconds: u64) -> String {
+    Utc.timestamp_opt(seconds as i64, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn format_rfc3339(seconds: u64) -> String {
+    Utc.timestamp_opt(seconds as i64, 0)
+        .single()
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    html::push_html(&mut escaped, std::iter::once(Event::Text(text.into())));
+    escaped
+}
+
+/// Renders message Markdown to HTML. Raw HTML in messages is escaped rather than passed through,
+/// so an exported thread can never run scripts.
+fn markdown_to_html(markdown: &str) -> String {
+    let parser = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    )
+    .map(|event| match event {
+        Event::Html(html) => Event::Text(html),
+        event => event,
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, parser);
+    output
+}
+
+pub fn render_markdown(thread: &Thread, exported_at: u64) -> String {
+    let mut output = format!(
+        "# {}\n\n*Exported {}*\n",
+        thread.title(),
+        format_timestamp(exported_at)
+    );
+
+    for message in &thread.messages {
+        output.push_str(&format!(
+            "\n---\n\n### {} · {}\n\n{}\n",
+            author_label(&message.author),
+            format_timestamp(message.created_at),
+            message.content.trim()
+        ));
+    }
+
+    output
+}
+
+pub fn render_html(thread: &Thread, exported_at: u64) -> String {
+    let title = escape_html(&thread.title());
+
+    let messages = thread
+        .messages
+        .iter()
+        .map(|message| {
+            format!(
+                "<section class=\"message {}\">\n<header><span class=\"author\">{}</span><time datetime=\"{}\">{}</time></header>\n{}</section>\n",
+                author_class(&message.author),
+                escape_html(author_label(&message.author)),
+                format_rfc3339(message.created_at),
+                format_timestamp(message.created_at),
+                markdown_to_html(message.content.trim())
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"exported\">Exported {exported_at}</p>\n{messages}</body>\n</html>\n",
+        title = title,
+        style = HTML_STYLE,
+        exported_at = format_timestamp(exported_at),
+        messages = messages
+    )
+}
+
+pub fn render_json(thread: &Thread, exported_at: u64) -> String {
+    let messages = thread
+        .messages
+        .iter()
+        .map(|message| {
+            serde_json::json!({
+                "id": message.id,
+                "author": message.author,
+                "content": message.content,
+                "created_at": format_rfc3339(message.created_at),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let export = serde_json::json!({
+        "id": thread.id,
+        "title": thread.title(),
+        "type": thread.thread_type,
+        "command": thread.command,
+        "created_at": format_rfc3339(thread.created_at),
+        "updated_at": format_rfc3339(thread.updated_at),
+        "exported_at": format_rfc3339(exported_at),
+        "messages": messages,
+    });
+
+    serde_json::to_string_pretty(&export).expect("error serializing json") + "\n"
+}
+
+pub fn render(thread: &Thread, format: Format, exported_at: u64) -> Vec<u8> {
+    match format {
+        Format::Markdown => render_markdown(thread, exported_at).into_bytes(),
+        Format::Html => render_html(thread, exported_at).into_bytes(),
+        Format::Json => render_json(thread, exported_at).into_bytes(),
+        Format::Pdf => pdf::render(thread, exported_at),
+    }
+}
+
+/// File name suggested in the save dialog, derived from the thread title.
+fn default_file_name(thread: &Thread, format: Format) -> String {
+    let title = thread
+        .title()
+        .chars()
+        .map(|character| match character {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '\n' | '\r' | '\t' => ' ',
+            character => character,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let title = title.chars().take(60).collect::<String>();
+
+    if title.is_empty() {
+        format!("Lander thread.{}", format.extension())
+    } else {
+        format!("{}.{}", title, format.extension())
+    }
+}
+
+/// Exports a thread to `path`, or to a location picked in a save dialog when no path is given.
+/// Resolves to the written path, or `None` when the dialog was canceled.
+#[tauri::command]
+pub async fn export_thread(
+    app_handle: AppHandle<Wry>,
+    id: String,
+    format: Format,
+    path: Option<String>,
+) -> Result<Option<String>, String> {
+    let thread = crate::thread::get(&app_handle, &id)?
+        .ok_or_else(|| format!("thread `{}` does not exist", id))?;
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let dialog = tauri::api::dialog::blocking::FileDialogBuilder::new()
+                .set_file_name(&default_file_name(&thread, format))
+                .add_filter(format.name(), &[format.extension()]);
+
+            match dialog.save_file() {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let data = render(&thread, format, usage::now());
+    fs::write(&path, data).map_err(|error| error.to_string())?;
+
+    Ok(Some(path.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const EXPORTED_AT: u64 = 1_700_000_000;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/export")
+            .join(name)
+    }
+
+    fn thread() -> Thread {
+        serde_json::from_str(&fs::read_to_string(fixture("thread.json")).unwrap()).unwrap()
+    }
+
+    /// Compares an export with the checked-in file. Run with `UPDATE_FIXTURES=1` to rewrite the
+    /// file after an intended change to the output.
+    fn assert_matches_fixture(format: Format, name: &str) {
+        let data = render(&thread(), format, EXPORTED_AT);
+        let path = fixture(name);
+
+        if std::env::var_os("UPDATE_FIXTURES").is_some() {
+            fs::write(&path, &data).unwrap();
+            return;
+        }
+
+        assert!(
+            fs::read(&path).unwrap() == data,
+            "the {} export differs from {}",
+            format.name(),
+            path.display()
+        );
+    }
+
+    #[test]
+    fn markdown_export_matches_fixture() {
+        assert_matches_fixture(Format::Markdown, "expected.md");
+    }
+
+    #[test]
+    fn html_export_matches_fixture() {
+        assert_matches_fixture(Format::Html, "expected.html");
+    }
+
+    #[test]
+    fn json_export_matches_fixture() {
+        assert_matches_fixture(Format::Json, "expected.json");
+    }
+
+    #[test]
+    fn pdf_export_matches_fixture() {
+        assert_matches_fixture(Format::Pdf, "expected.pdf");
+    }
+
+    #[test]
+    fn html_export_escapes_raw_html() {
+        let html = render_html(&thread(), EXPORTED_AT);
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    }
+
+    #[test]
+    fn html_export_keeps_authors_out_of_attributes() {
+        let mut thread = thread();
+        thread.messages[0].author = "x\" onmouseover=\"alert(1)".to_string();
+
+        let html = render_html(&thread, EXPORTED_AT);
+
+        assert!(!html.contains("onmouseover=\""));
+        assert!(html.contains("<section class=\"message other\">"));
+        assert!(html.contains("<span class=\"author\">x&quot; onmouseover=&quot;alert(1)</span>"));
+    }
+
+    #[test]
+    fn default_file_name_drops_reserved_characters() {
+        let mut thread = thread();
+        thread.command = Some(serde_json::json!({ "title": "a/b: c?  \"d\"" }));
+
+        assert_eq!(default_file_name(&thread, Format::Pdf), "a b c d.pdf");
+    }
+}
@@ -0,0 +1,468 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag};
+
+use super::{author_label, format_timestamp};
+use crate::thread::Thread;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const LINE_SPACING: f32 = 1.4;
+
+/// Advance widths of Helvetica for ASCII 32..=126, in thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+    Monospace,
+}
+
+impl Font {
+    fn name(&self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+            Font::Monospace => Name(b"F3"),
+        }
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units = text
+            .chars()
+            .map(|character| match self {
+                Font::Monospace => 600.0,
+                _ => {
+                    let width = match character as u32 {
+                        code @ 32..=126 => HELVETICA_WIDTHS[(code - 32) as usize] as f32,
+                        _ => 556.0,
+                    };
+
+                    // Bold glyphs are slightly wider; overestimating keeps lines inside the margin.
+                    if *self == Font::Bold {
+                        width * 1.08
+                    } else {
+                        width
+                    }
+                }
+            })
+            .sum::<f32>();
+
+        units * size / 1000.0
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    font: Font,
+    size: f32,
+    indent: f32,
+    gray: bool,
+    is_code: bool,
+    /// Extra space above the line, e.g. between paragraphs.
+    space_before: f32,
+}
+
+/// Maps text to WinAnsiEncoding, the encoding of the standard Type 1 fonts. Those fonts are not
+/// embedded and only cover Western European scripts, so everything else, such as Cyrillic, CJK
+/// or emoji, is replaced with `?`. Markdown, HTML and JSON exports keep all characters.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|character| match character {
+            ' '..='~' => character as u8,
+            '\u{a0}'..='\u{ff}' => character as u32 as u8,
+            '€' => 0x80,
+            '‚' => 0x82,
+            '„' => 0x84,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '™' => 0x99,
+            '\t' => b' ',
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Splits `text` into lines no wider than `width`, breaking between words and inside words that
+/// do not fit on a line of their own.
+fn wrap(text: &str, font: Font, size: f32, width: f32) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+
+        if font.width(&candidate, size) <= width {
+            line = candidate;
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        for character in word.chars() {
+            line.push(character);
+
+            if font.width(&line, size) > width {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, character.to_string()));
+            }
+        }
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+struct Layout {
+    lines: Vec<Line>,
+    width: f32,
+}
+
+impl Layout {
+    fn push_text(&mut self, text: &str, font: Font, size: f32, indent: f32, space_before: f32) {
+        for (index, text) in wrap(text, font, size, self.width - indent)
+            .into_iter()
+            .enumerate()
+        {
+            self.lines.push(Line {
+                text,
+                font,
+                size,
+                indent,
+                gray: false,
+                is_code: false,
+                space_before: if index == 0 { space_before } else { 0.0 },
+            });
+        }
+    }
+
+    fn push_code(&mut self, code: &str, indent: f32) {
+        let size = 9.0;
+        let columns = ((self.width - indent - 8.0) / Font::Monospace.width(" ", size)) as usize;
+
+        for (index, line) in code.trim_end_matches('\n').lines().enumerate() {
+            let characters = line.replace('\t', "    ").chars().collect::<Vec<_>>();
+            let parts = if characters.is_empty() {
+                vec![String::new()]
+            } else {
+                characters
+                    .chunks(columns.max(1))
+                    .map(|part| part.iter().collect())
+                    .collect()
+            };
+
+            for (part_index, text) in parts.into_iter().enumerate() {
+                self.lines.push(Line {
+                    text,
+                    font: Font::Monospace,
+                    size,
+                    indent,
+                    gray: false,
+                    is_code: true,
+                    space_before: if index == 0 && part_index == 0 {
+                        6.0
+                    } else {
+                        0.0
+                    },
+                });
+            }
+        }
+    }
+
+    /// Lays out message Markdown as paragraphs, headings, list items and code blocks. Inline
+    /// formatting is dropped.
+    fn push_markdown(&mut self, markdown: &str) {
+        let mut text = String::new();
+        let mut code = String::new();
+        let mut is_in_code = false;
+        let mut font = Font::Regular;
+        let mut size = 10.5;
+        let mut lists: Vec<Option<u64>> = vec![];
+        let mut quote_depth = 0;
+
+        for event in Parser::new(markdown) {
+            let indent = (lists.len() + quote_depth) as f32 * 14.0;
+
+            match event {
+                Event::Start(Tag::Heading(level, ..)) => {
+                    font = Font::Bold;
+                    size = match level {
+                        HeadingLevel::H1 => 15.0,
+                        HeadingLevel::H2 => 13.0,
+                        _ => 11.5,
+                    };
+                }
+                Event::Start(Tag::CodeBlock(_)) => is_in_code = true,
+                Event::Start(Tag::List(start)) => {
+                    // A nested list ends the text of the item that contains it.
+                    if !text.trim().is_empty() {
+                        self.push_text(&text, font, size, (indent - 14.0).max(0.0), 6.0);
+                        text.clear();
+                    }
+
+                    lists.push(start);
+                }
+                Event::Start(Tag::BlockQuote) => quote_depth += 1,
+                Event::Start(Tag::Item) => {
+                    text = match lists.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        }
+                        _ => "• ".to_string(),
+                    };
+                }
+                Event::End(Tag::Heading(..))
+                | Event::End(Tag::Paragraph)
+                | Event::End(Tag::Item) => {
+                    if !text.trim().is_empty() {
+                        let indent = if lists.is_empty() {
+                            indent
+                        } else {
+                            indent - 14.0
+                        };
+                        self.push_text(&text, font, size, indent.max(0.0), 6.0);
+                    }
+
+                    text.clear();
+                    font = Font::Regular;
+                    size = 10.5;
+                }
+                Event::End(Tag::CodeBlock(_)) => {
+                    self.push_code(&code, indent);
+                    code.clear();
+                    is_in_code = false;
+                }
+                Event::End(Tag::List(_)) => {
+                    lists.pop();
+                }
+                Event::End(Tag::BlockQuote) => quote_depth -= 1,
+                Event::Text(content) | Event::Code(content) | Event::Html(content) => {
+                    if is_in_code {
+                        code.push_str(&content);
+                    } else {
+                        text.push_str(&content);
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => text.push(' '),
+                Event::Rule => self.push_text("———", Font::Regular, size, indent, 6.0),
+                _ => {}
+            }
+        }
+
+        if !text.trim().is_empty() {
+            self.push_text(&text, font, size, 0.0, 6.0);
+        }
+    }
+}
+
+fn layout(thread: &Thread, exported_at: u64) -> Vec<Line> {
+    let mut layout = Layout {
+        lines: vec![],
+        width: PAGE_WIDTH - 2.0 * MARGIN,
+    };
+
+    layout.push_text(&thread.title(), Font::Bold, 18.0, 0.0, 0.0);
+    layout.lines.push(Line {
+        text: format!("Exported {}", format_timestamp(exported_at)),
+        font: Font::Regular,
+        size: 9.0,
+        indent: 0.0,
+        gray: true,
+        is_code: false,
+        space_before: 2.0,
+    });
+
+    for message in &thread.messages {
+        layout.lines.push(Line {
+            text: format!(
+                "{} · {}",
+                author_label(&message.author),
+                format_timestamp(message.created_at)
+            ),
+            font: Font::Bold,
+            size: 10.0,
+            indent: 0.0,
+            gray: true,
+            is_code: false,
+            space_before: 18.0,
+        });
+
+        layout.push_markdown(message.content.trim());
+    }
+
+    layout.lines
+}
+
+pub fn render(thread: &Thread, exported_at: u64) -> Vec<u8> {
+    let lines = layout(thread, exported_at);
+
+    let mut pages = vec![];
+    let mut content = Content::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    for line in lines {
+        let height = line.size * LINE_SPACING;
+
+        if y - line.space_before - height < MARGIN {
+            pages.push(content.finish());
+            content = Content::new();
+            y = PAGE_HEIGHT - MARGIN;
+        } else {
+            y -= line.space_before;
+        }
+
+        y -= height;
+
+        if line.is_code {
+            content.set_fill_gray(0.95);
+            content.rect(
+                MARGIN + line.indent,
+                y - line.size * 0.3,
+                PAGE_WIDTH - 2.0 * MARGIN - line.indent,
+                height,
+            );
+            content.fill_nonzero();
+        }
+
+        let x = MARGIN + line.indent + if line.is_code { 4.0 } else { 0.0 };
+
+        content.set_fill_gray(if line.gray { 0.4 } else { 0.1 });
+        content.begin_text();
+        content.set_font(line.font.name(), line.size);
+        content.next_line(x, y);
+        content.show(Str(&encode(&line.text)));
+        content.end_text();
+    }
+
+    pages.push(content.finish());
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_ids = [Ref::new(3), Ref::new(4), Ref::new(5)];
+    let info_id = Ref::new(6);
+    let first_page_id = 7;
+
+    let page_ids = (0..pages.len())
+        .map(|index| Ref::new(first_page_id + 2 * index as i32))
+        .collect::<Vec<_>>();
+
+    let mut pdf = Pdf::new();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+
+    for (id, base_font) in font_ids
+        .iter()
+        .zip(["Helvetica", "Helvetica-Bold", "Courier"])
+    {
+        pdf.type1_font(*id)
+            .base_font(Name(base_font.as_bytes()))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+
+    pdf.document_info(info_id)
+        .title(pdf_writer::TextStr(&thread.title()))
+        .producer(pdf_writer::TextStr("Lander"));
+
+    for (page_id, data) in page_ids.iter().zip(&pages) {
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+
+        for (font, id) in [Font::Regular, Font::Bold, Font::Monospace]
+            .iter()
+            .zip(font_ids)
+        {
+            fonts.pair(font.name(), id);
+        }
+
+        fonts.finish();
+        resources.finish();
+        page.finish();
+
+        pdf.stream(content_id, data);
+    }
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_maps_western_european_text_to_win_ansi() {
+        assert_eq!(
+            encode("Zoë – “quoted” …"),
+            b"Zo\xeb \x96 \x93quoted\x94 \x85"
+        );
+        assert_eq!(encode("a\tb"), b"a b");
+    }
+
+    #[test]
+    fn encode_replaces_characters_outside_of_win_ansi() {
+        assert_eq!(encode("Łukasz"), b"?ukasz");
+        assert_eq!(encode("王芳 🙂"), b"?? ?");
+    }
+
+    #[test]
+    fn wrap_keeps_lines_inside_the_width() {
+        let lines = wrap(
+            "The quick brown fox jumps over the lazy dog",
+            Font::Regular,
+            10.0,
+            100.0,
+        );
+
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|line| Font::Regular.width(line, 10.0) <= 100.0));
+        assert_eq!(
+            lines.join(" "),
+            "The quick brown fox jumps over the lazy dog"
+        );
+    }
+
+    #[test]
+    fn wrap_breaks_words_longer_than_a_line() {
+        let lines = wrap(&"m".repeat(40), Font::Monospace, 10.0, 60.0);
+
+        assert_eq!(
+            lines,
+            vec!["mmmmmmmmmm", "mmmmmmmmmm", "mmmmmmmmmm", "mmmmmmmmmm"]
+        );
+    }
+}
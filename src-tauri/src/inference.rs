@@ -0,0 +1,558 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+#[cfg(feature = "local-inference")]
+mod llama;
+// Only the prompt format is used by the settings without the `local-inference` feature.
+#[cfg_attr(not(feature = "local-inference"), allow(dead_code))]
+mod prompt;
+
+use prompt::{Message, PromptFormat};
+
+/// Bytes downloaded between `local_model_download` progress events.
+const DOWNLOAD_PROGRESS_INTERVAL: u64 = 8 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Answer chats with the selected local model instead of the server; requires the
+    /// `local-inference` feature.
+    pub enabled: bool,
+    /// Where models are listed from and downloaded to; `models` in the app data directory when
+    /// unset.
+    pub models_directory: Option<PathBuf>,
+    /// File name of the selected GGUF model in the models directory.
+    pub model: Option<String>,
+    pub prompt_format: PromptFormat,
+    /// Tokens of conversation and reply the model sees; older messages are left out beyond it.
+    pub context_length: u32,
+    /// Longest reply, in tokens.
+    pub max_tokens: u32,
+    pub temperature: f32,
+    /// Seconds the model stays in memory after its last reply; 0 keeps it loaded.
+    pub unload_after_seconds: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            models_directory: None,
+            model: None,
+            prompt_format: PromptFormat::ChatMl,
+            context_length: 4096,
+            max_tokens: 1024,
+            temperature: 0.7,
+            unload_after_seconds: 600,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LocalModel {
+    name: String,
+    size: u64,
+    is_selected: bool,
+    is_loaded: bool,
+}
+
+#[derive(Deserialize)]
+struct ChatRequest {
+    messages: Vec<Message>,
+}
+
+#[derive(Default)]
+pub struct Store {
+    #[cfg(feature = "local-inference")]
+    model: Option<(PathBuf, std::sync::Arc<llama::Model>)>,
+    /// Incremented on every reply, so the model is only unloaded once it sat idle.
+    #[cfg(feature = "local-inference")]
+    last_use: u64,
+    /// Names of the models being downloaded; removing one cancels its download.
+    downloads: HashSet<String>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("inference")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn models_directory(app_handle: &AppHandle<Wry>, settings: &Settings) -> Result<PathBuf, String> {
+    settings
+        .models_directory
+        .clone()
+        .or_else(|| {
+            app_handle
+                .path_resolver()
+                .app_data_dir()
+                .map(|app_data_dir| app_data_dir.join("models"))
+        })
+        .ok_or_else(|| "the models directory could not be found".to_string())
+}
+
+/// Path of a model in the models directory. Names are plain file names, so requests from the
+/// panel cannot reach outside it.
+fn model_path(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    name: &str,
+) -> Result<PathBuf, String> {
+    if Path::new(name)
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        != Some(name)
+    {
+        return Err(format!("`{}` is not a model name", name));
+    }
+
+    Ok(models_directory(app_handle, settings)?.join(name))
+}
+
+fn is_model_file(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("gguf"))
+}
+
+#[cfg(feature = "local-inference")]
+fn loaded_model_path(app_handle: &AppHandle<Wry>) -> Option<PathBuf> {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .model
+        .as_ref()
+        .map(|(path, _)| path.clone())
+}
+
+#[cfg(not(feature = "local-inference"))]
+fn loaded_model_path(_app_handle: &AppHandle<Wry>) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(feature = "local-inference")]
+fn unload(app_handle: &AppHandle<Wry>) {
+    app_handle.state::<State>().0.lock().unwrap().model = None;
+}
+
+#[cfg(not(feature = "local-inference"))]
+fn unload(_app_handle: &AppHandle<Wry>) {}
+
+/// Loads the selected model, or returns it when it is loaded already. Loading takes a few seconds
+/// for larger models, so it happens outside the lock.
+#[cfg(feature = "local-inference")]
+async fn load(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+) -> Result<std::sync::Arc<llama::Model>, String> {
+    let name = settings
+        .model
+        .as_deref()
+        .ok_or_else(|| "no local model is selected".to_string())?;
+
+    let path = model_path(app_handle, settings, name)?;
+
+    if let Some((loaded_path, model)) = &app_handle.state::<State>().0.lock().unwrap().model {
+        if *loaded_path == path {
+            return Ok(model.clone());
+        }
+    }
+
+    // The previous model is dropped first, as both may not fit in memory.
+    unload(app_handle);
+
+    let model_path = path.clone();
+    let model = tauri::async_runtime::spawn_blocking(move || llama::Model::load(&model_path))
+        .await
+        .map_err(|error| error.to_string())??;
+
+    let model = std::sync::Arc::new(model);
+    app_handle.state::<State>().0.lock().unwrap().model = Some((path, model.clone()));
+
+    Ok(model)
+}
+
+#[cfg(not(feature = "local-inference"))]
+async fn load(_app_handle: &AppHandle<Wry>, _settings: &Settings) -> Result<(), String> {
+    Err("Lander was built without the `local-inference` feature".to_string())
+}
+
+/// Unloads the model once it was not used for `unload_after_seconds`.
+#[cfg(feature = "local-inference")]
+fn schedule_unload(app_handle: &AppHandle<Wry>, settings: &Settings) {
+    let last_use = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.last_use += 1;
+        store.last_use
+    };
+
+    if settings.unload_after_seconds == 0 {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let delay = std::time::Duration::from_secs(settings.unload_after_seconds);
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        if store.last_use == last_use {
+            store.model = None;
+        }
+    });
+}
+
+/// Generates the reply on a blocking thread, emitting it on the `stream` event of the main window
+/// as the server's replies are, until it ends or `cancel_stream` is called.
+#[cfg(feature = "local-inference")]
+async fn generate(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    messages: Vec<Message>,
+) -> Result<(), String> {
+    let model = load(app_handle, settings).await?;
+
+    // Keeps an unload scheduled after the previous reply from dropping the model mid-reply.
+    app_handle.state::<State>().0.lock().unwrap().last_use += 1;
+
+    let generation_app_handle = app_handle.clone();
+    let generation_settings = settings.clone();
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let window = generation_app_handle.get_window("main").unwrap();
+
+        model.complete(&messages, &generation_settings, |text| {
+            if generation_app_handle
+                .state::<crate::stream::State>()
+                .0
+                .lock()
+                .unwrap()
+                .is_canceled
+            {
+                return false;
+            }
+
+            window.emit("stream", text).unwrap_or_default();
+            true
+        })
+    })
+    .await
+    .map_err(|error| error.to_string())?;
+
+    schedule_unload(app_handle, settings);
+
+    result
+}
+
+#[cfg(not(feature = "local-inference"))]
+async fn generate(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    _messages: Vec<Message>,
+) -> Result<(), String> {
+    load(app_handle, settings).await
+}
+
+async fn download(
+    app_handle: &AppHandle<Wry>,
+    url: &str,
+    name: &str,
+    path: &Path,
+) -> Result<Option<String>, String> {
+    let is_downloading = || {
+        app_handle
+            .state::<State>()
+            .0
+            .lock()
+            .unwrap()
+            .downloads
+            .contains(name)
+    };
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    }
+
+    let response = reqwest::get(url).await.map_err(|error| error.to_string())?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        return Err(format!("model download failed with {}", status));
+    }
+
+    let total = response.content_length();
+
+    // Partial downloads keep another extension, so they are never listed as models.
+    let partial_path = path.with_extension("gguf.part");
+    let mut file = fs::File::create(&partial_path).map_err(|error| error.to_string())?;
+
+    let mut stream = response.bytes_stream();
+    let mut downloaded = 0u64;
+    let mut reported = 0u64;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) if is_downloading() => chunk,
+            Ok(_) => {
+                fs::remove_file(&partial_path).unwrap_or_default();
+                return Ok(None);
+            }
+            Err(error) => {
+                fs::remove_file(&partial_path).unwrap_or_default();
+                return Err(error.to_string());
+            }
+        };
+
+        if let Err(error) = file.write_all(&chunk) {
+            fs::remove_file(&partial_path).unwrap_or_default();
+            return Err(error.to_string());
+        }
+
+        downloaded += chunk.len() as u64;
+
+        if downloaded - reported >= DOWNLOAD_PROGRESS_INTERVAL {
+            reported = downloaded;
+
+            app_handle
+                .emit_all(
+                    "local_model_download",
+                    json!({ "name": name, "downloaded": downloaded, "total": total }),
+                )
+                .unwrap_or_default();
+        }
+    }
+
+    file.flush().map_err(|error| error.to_string())?;
+    drop(file);
+
+    fs::rename(&partial_path, path).map_err(|error| error.to_string())?;
+
+    app_handle
+        .emit_all(
+            "local_model_download",
+            json!({ "name": name, "downloaded": downloaded, "total": Some(downloaded) }),
+        )
+        .unwrap_or_default();
+
+    Ok(Some(name.to_string()))
+}
+
+/// Answers a chat with the selected local model. Takes the body `stream` would send to the chat
+/// endpoint, and emits the reply on the same `stream` event, ending with `[END]`.
+#[tauri::command]
+pub async fn stream_local(app_handle: AppHandle<Wry>, body: String) -> Option<String> {
+    app_handle
+        .state::<crate::stream::State>()
+        .0
+        .lock()
+        .unwrap()
+        .is_canceled = false;
+
+    let window = app_handle.get_window("main").unwrap();
+
+    let request: ChatRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            window.emit("stream", "[LANDER_STREAM_ERROR]").unwrap();
+            return None;
+        }
+    };
+
+    let settings = get_settings(&app_handle);
+
+    tauri::async_runtime::spawn(async move {
+        if generate(&app_handle, &settings, request.messages)
+            .await
+            .is_err()
+        {
+            window.emit("stream", "[LANDER_STREAM_ERROR]").unwrap();
+        }
+
+        window.emit("stream", "[END]").unwrap();
+    });
+
+    // The server's replies carry usage headers, which local replies have none of.
+    Some(json!({ "headers": {} }).to_string())
+}
+
+/// GGUF models in the models directory, by name.
+#[tauri::command]
+pub fn list_local_models(app_handle: AppHandle<Wry>) -> Result<String, String> {
+    let settings = get_settings(&app_handle);
+    let directory = models_directory(&app_handle, &settings)?;
+    let loaded_path = loaded_model_path(&app_handle);
+
+    let mut models = match fs::read_dir(&directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && is_model_file(path))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?.to_string();
+
+                Some(LocalModel {
+                    is_selected: settings.model.as_deref() == Some(name.as_str()),
+                    is_loaded: loaded_path.as_deref() == Some(path.as_path()),
+                    size: path.metadata().map_or(0, |metadata| metadata.len()),
+                    name,
+                })
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+
+    models.sort_by_key(|model| model.name.to_lowercase());
+
+    Ok(serde_json::to_string(&models).expect("error serializing json"))
+}
+
+/// Downloads a GGUF model into the models directory, emitting `local_model_download` progress
+/// events. Resolves to the model's name, or `null` when the download was canceled.
+#[tauri::command]
+pub async fn download_local_model(
+    app_handle: AppHandle<Wry>,
+    url: String,
+    name: Option<String>,
+) -> Result<Option<String>, String> {
+    let name = match name.filter(|name| !name.trim().is_empty()) {
+        Some(name) => name,
+        None => url
+            .split(['?', '#'])
+            .next()
+            .and_then(|url| url.rsplit('/').next())
+            .unwrap_or_default()
+            .to_string(),
+    };
+
+    if !is_model_file(Path::new(&name)) {
+        return Err(format!("`{}` is not a GGUF model", name));
+    }
+
+    let settings = get_settings(&app_handle);
+    let path = model_path(&app_handle, &settings, &name)?;
+
+    if !app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .downloads
+        .insert(name.clone())
+    {
+        return Err(format!("`{}` is already being downloaded", name));
+    }
+
+    let result = download(&app_handle, &url, &name, &path).await;
+
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .downloads
+        .remove(&name);
+
+    result
+}
+
+#[tauri::command]
+pub fn cancel_local_model_download(app_handle: AppHandle<Wry>, name: String) {
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .downloads
+        .remove(&name);
+}
+
+#[tauri::command]
+pub fn delete_local_model(app_handle: AppHandle<Wry>, name: String) -> Result<(), String> {
+    let settings = get_settings(&app_handle);
+    let path = model_path(&app_handle, &settings, &name)?;
+
+    if loaded_model_path(&app_handle).as_deref() == Some(path.as_path()) {
+        unload(&app_handle);
+    }
+
+    fs::remove_file(path).map_err(|error| error.to_string())
+}
+
+/// Loads the selected model ahead of the first chat, which would otherwise wait for it.
+#[tauri::command]
+pub async fn load_local_model(app_handle: AppHandle<Wry>) -> Result<(), String> {
+    let settings = get_settings(&app_handle);
+
+    load(&app_handle, &settings).await?;
+
+    #[cfg(feature = "local-inference")]
+    schedule_unload(&app_handle, &settings);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unload_local_model(app_handle: AppHandle<Wry>) {
+    unload(&app_handle);
+}
+
+#[tauri::command]
+pub fn get_inference_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_inference_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("inference".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+
+    // A model that is no longer selected, or no longer used, only takes up memory.
+    let selected_path = settings
+        .model
+        .as_deref()
+        .and_then(|name| model_path(&app_handle, &settings, name).ok());
+
+    let loaded_path = loaded_model_path(&app_handle);
+
+    if loaded_path.is_some() && (!settings.enabled || loaded_path != selected_path) {
+        unload(&app_handle);
+    }
+}
@@ -0,0 +1,122 @@
+use llama_cpp::{
+    standard_sampler::{SamplerStage, StandardSampler},
+    LlamaModel, LlamaParams, SessionParams,
+};
+use std::{path::Path, thread};
+
+use super::{
+    prompt::{Message, StopFilter},
+    Settings,
+};
+
+/// GGUF model for llama.cpp, run on the CPU.
+pub struct Model(LlamaModel);
+
+impl Model {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let params = LlamaParams {
+            n_gpu_layers: 0,
+            ..Default::default()
+        };
+
+        LlamaModel::load_from_file(path, params)
+            .map(Model)
+            .map_err(|error| format!("the model could not be loaded: {}", error))
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.0
+            .tokenize_bytes(text, false, true)
+            .map_or(usize::MAX, |tokens| tokens.len())
+    }
+
+    /// Generates the reply to the conversation, passing the text to `on_text` as it is generated
+    /// until it returns `false`. Older messages are left out when the conversation does not fit
+    /// in the context next to `max_tokens` of reply.
+    pub fn complete(
+        &self,
+        messages: &[Message],
+        settings: &Settings,
+        mut on_text: impl FnMut(&str) -> bool,
+    ) -> Result<(), String> {
+        let context_length = settings.context_length.max(512) as usize;
+        let max_tokens = (settings.max_tokens as usize).min(context_length / 2);
+
+        let prompt = settings.prompt_format.format_to_fit(messages, |prompt| {
+            // One token for the beginning of sequence.
+            self.count_tokens(prompt) + 1 + max_tokens <= context_length
+        });
+
+        let threads = thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+            .min(8) as u32;
+
+        let mut session = self
+            .0
+            .create_session(SessionParams {
+                n_ctx: context_length as u32,
+                n_threads: threads,
+                n_threads_batch: threads,
+                ..Default::default()
+            })
+            .map_err(|error| error.to_string())?;
+
+        session
+            .advance_context_with_tokens([self.0.bos()])
+            .map_err(|error| error.to_string())?;
+
+        session
+            .advance_context(&prompt)
+            .map_err(|error| error.to_string())?;
+
+        let sampler = if settings.temperature <= 0.0 {
+            StandardSampler::new_greedy()
+        } else {
+            StandardSampler::new_softmax(
+                vec![
+                    SamplerStage::RepetitionPenalty {
+                        repetition_penalty: 1.1,
+                        frequency_penalty: 0.0,
+                        presence_penalty: 0.0,
+                        last_n: 64,
+                    },
+                    SamplerStage::TopK(40),
+                    SamplerStage::TopP(0.95),
+                    SamplerStage::MinP(0.05),
+                    SamplerStage::Temperature(settings.temperature),
+                ],
+                1,
+            )
+        };
+
+        let max_tokens = max_tokens.min(context_length.saturating_sub(session.context_size()));
+
+        // Generation stops when the handle is dropped, which breaking out of the loop does.
+        let completion = session
+            .start_completing_with(sampler, max_tokens)
+            .map_err(|error| error.to_string())?;
+
+        let mut stop_filter = StopFilter::new(settings.prompt_format);
+
+        for piece in completion.into_strings() {
+            let text = stop_filter.push(&piece);
+
+            if !text.is_empty() && !on_text(&text) {
+                return Ok(());
+            }
+
+            if stop_filter.is_stopped() {
+                return Ok(());
+            }
+        }
+
+        let text = stop_filter.finish();
+
+        if !text.is_empty() {
+            on_text(&text);
+        }
+
+        Ok(())
+    }
+}
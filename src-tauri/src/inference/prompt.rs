@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+
+/// Chat template the model was fine-tuned with. GGUF files carry it as a Jinja template, which is
+/// not evaluated here, so it is picked in the settings instead.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptFormat {
+    /// `<|im_start|>` and `<|im_end|>`, used by Qwen, OpenHermes, Dolphin and others.
+    ChatMl,
+    /// `[INST]` and `<<SYS>>`, used by Llama 2 Chat and Mistral Instruct.
+    Llama2,
+    /// `<|system|>`, `<|user|>` and `<|assistant|>`, used by Zephyr and TinyLlama Chat.
+    Zephyr,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Author {
+    User,
+    AI,
+    System,
+}
+
+/// A message as the panel sends it to the chat endpoint.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Message {
+    pub author: Author,
+    pub content: String,
+}
+
+impl PromptFormat {
+    /// Text that ends the reply; models keep writing the next turn when it is not caught.
+    pub fn stop_sequences(self) -> &'static [&'static str] {
+        match self {
+            PromptFormat::ChatMl => &["<|im_end|>", "<|im_start|>"],
+            PromptFormat::Llama2 => &["</s>", "[INST]"],
+            PromptFormat::Zephyr => &["</s>", "<|user|>"],
+        }
+    }
+
+    /// Formats the conversation up to the start of the assistant's reply. The beginning of
+    /// sequence token is left to the model.
+    pub fn format(self, messages: &[Message]) -> String {
+        match self {
+            PromptFormat::ChatMl => {
+                let mut prompt = String::new();
+
+                for message in messages {
+                    let role = match message.author {
+                        Author::User => "user",
+                        Author::AI => "assistant",
+                        Author::System => "system",
+                    };
+
+                    prompt.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        role,
+                        message.content.trim()
+                    ));
+                }
+
+                prompt.push_str("<|im_start|>assistant\n");
+                prompt
+            }
+            PromptFormat::Llama2 => {
+                // Llama 2 has a single system prompt, which goes inside the first instruction.
+                let system = messages
+                    .iter()
+                    .filter(|message| message.author == Author::System)
+                    .map(|message| message.content.trim())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                let mut prompt = String::new();
+                let mut is_first = true;
+
+                for message in messages {
+                    match message.author {
+                        Author::System => {}
+                        Author::User => {
+                            if !is_first {
+                                prompt.push_str("<s>");
+                            }
+
+                            prompt.push_str("[INST] ");
+
+                            if is_first && !system.is_empty() {
+                                prompt.push_str(&format!("<<SYS>>\n{}\n<</SYS>>\n\n", system));
+                            }
+
+                            prompt.push_str(&format!("{} [/INST]", message.content.trim()));
+                            is_first = false;
+                        }
+                        Author::AI => {
+                            prompt.push_str(&format!(" {} </s>", message.content.trim()));
+                        }
+                    }
+                }
+
+                prompt
+            }
+            PromptFormat::Zephyr => {
+                let mut prompt = String::new();
+
+                for message in messages {
+                    let role = match message.author {
+                        Author::User => "user",
+                        Author::AI => "assistant",
+                        Author::System => "system",
+                    };
+
+                    prompt.push_str(&format!("<|{}|>\n{}</s>\n", role, message.content.trim()));
+                }
+
+                prompt.push_str("<|assistant|>\n");
+                prompt
+            }
+        }
+    }
+
+    /// Formats the conversation, leaving out its oldest messages until `fits` accepts the prompt.
+    /// System messages and the last message are always kept.
+    pub fn format_to_fit(self, messages: &[Message], fits: impl Fn(&str) -> bool) -> String {
+        let mut messages = messages.to_vec();
+
+        loop {
+            let prompt = self.format(&messages);
+
+            if fits(&prompt) {
+                return prompt;
+            }
+
+            let oldest = messages[..messages.len().saturating_sub(1)]
+                .iter()
+                .position(|message| message.author != Author::System);
+
+            match oldest {
+                Some(index) => {
+                    messages.remove(index);
+                }
+                None => return prompt,
+            }
+        }
+    }
+}
+
+/// Holds back generated text that may be the start of a stop sequence, so none of it reaches the
+/// panel.
+pub struct StopFilter {
+    stop_sequences: &'static [&'static str],
+    pending: String,
+    is_stopped: bool,
+}
+
+impl StopFilter {
+    pub fn new(format: PromptFormat) -> Self {
+        Self {
+            stop_sequences: format.stop_sequences(),
+            pending: String::new(),
+            is_stopped: false,
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.is_stopped
+    }
+
+    /// Adds generated text and returns what can be shown.
+    pub fn push(&mut self, text: &str) -> String {
+        if self.is_stopped {
+            return String::new();
+        }
+
+        self.pending.push_str(text);
+
+        let stop = self
+            .stop_sequences
+            .iter()
+            .filter_map(|stop_sequence| self.pending.find(stop_sequence))
+            .min();
+
+        if let Some(index) = stop {
+            self.is_stopped = true;
+
+            let text = self.pending[..index].to_string();
+            self.pending.clear();
+            return text;
+        }
+
+        // The longest end of the pending text that a stop sequence starts with.
+        let held = self
+            .stop_sequences
+            .iter()
+            .flat_map(|stop_sequence| {
+                (1..stop_sequence.len())
+                    .filter(|length| stop_sequence.is_char_boundary(*length))
+                    .filter(|length| self.pending.ends_with(&stop_sequence[..*length]))
+            })
+            .max()
+            .unwrap_or(0);
+
+        let end = self.pending.len() - held;
+        let text = self.pending[..end].to_string();
+        self.pending.drain(..end);
+        text
+    }
+
+    /// Returns the text held back when generation ended without a stop sequence.
+    pub fn finish(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(author: Author, content: &str) -> Message {
+        Message {
+            author,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn stop_sequences_split_across_pushes_are_caught() {
+        let mut filter = StopFilter::new(PromptFormat::ChatMl);
+
+        assert_eq!(filter.push("Hello"), "Hello");
+        assert_eq!(filter.push(" there<|im"), " there");
+        assert_eq!(filter.push("_e"), "");
+        assert!(!filter.is_stopped());
+        assert_eq!(filter.push("nd|>\n<|im_start|>user"), "");
+        assert!(filter.is_stopped());
+        assert_eq!(filter.push("more"), "");
+        assert_eq!(filter.finish(), "");
+    }
+
+    #[test]
+    fn text_that_only_looks_like_a_stop_sequence_is_released() {
+        let mut filter = StopFilter::new(PromptFormat::Llama2);
+
+        assert_eq!(filter.push("a < b and [IN"), "a < b and ");
+        assert_eq!(filter.push("FO] done"), "[INFO] done");
+        assert!(!filter.is_stopped());
+    }
+
+    #[test]
+    fn finish_flushes_held_text() {
+        let mut filter = StopFilter::new(PromptFormat::Zephyr);
+
+        assert_eq!(filter.push("The end </"), "The end ");
+        assert_eq!(filter.finish(), "</");
+        assert_eq!(filter.finish(), "");
+    }
+
+    #[test]
+    fn llama2_puts_the_system_prompt_in_the_first_instruction() {
+        let prompt = PromptFormat::Llama2.format(&[
+            message(Author::System, " Be brief. "),
+            message(Author::User, "Hi"),
+            message(Author::AI, "Hello!"),
+            message(Author::System, "Answer in English."),
+            message(Author::User, "How are you?"),
+        ]);
+
+        assert_eq!(
+            prompt,
+            "[INST] <<SYS>>\nBe brief.\n\nAnswer in English.\n<</SYS>>\n\nHi [/INST] Hello! \
+             </s><s>[INST] How are you? [/INST]"
+        );
+    }
+
+    #[test]
+    fn llama2_without_a_system_prompt_has_no_sys_block() {
+        let prompt = PromptFormat::Llama2.format(&[message(Author::User, "Hi")]);
+
+        assert_eq!(prompt, "[INST] Hi [/INST]");
+    }
+
+    #[test]
+    fn chat_ml_ends_with_the_assistant_turn() {
+        let prompt = PromptFormat::ChatMl.format(&[
+            message(Author::System, "Be brief."),
+            message(Author::User, "Hi"),
+        ]);
+
+        assert_eq!(
+            prompt,
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+    }
+
+    #[test]
+    fn format_to_fit_drops_the_oldest_messages_first() {
+        let messages = [
+            message(Author::System, "Be brief."),
+            message(Author::User, "first question"),
+            message(Author::AI, "first answer"),
+            message(Author::User, "second question"),
+        ];
+
+        let prompt = PromptFormat::Zephyr
+            .format_to_fit(&messages, |prompt| !prompt.contains("first question"));
+
+        assert_eq!(
+            prompt,
+            "<|system|>\nBe brief.</s>\n<|assistant|>\nfirst answer</s>\n\
+             <|user|>\nsecond question</s>\n<|assistant|>\n"
+        );
+    }
+
+    #[test]
+    fn format_to_fit_keeps_system_messages_and_the_last_message() {
+        let messages = [
+            message(Author::User, "first question"),
+            message(Author::System, "Be brief."),
+            message(Author::AI, "first answer"),
+            message(Author::User, "second question"),
+        ];
+
+        // Nothing fits, so everything that may be dropped is.
+        let prompt = PromptFormat::Zephyr.format_to_fit(&messages, |_| false);
+
+        assert_eq!(
+            prompt,
+            "<|system|>\nBe brief.</s>\n<|user|>\nsecond question</s>\n<|assistant|>\n"
+        );
+    }
+}
@@ -3,19 +3,33 @@
     windows_subsystem = "windows"
 )]
 
+use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
-use std::{cmp, fs};
-use tauri::{Manager, PhysicalPosition, PhysicalSize, Position, Size, Theme, Wry};
+use tauri::{Manager, Theme, Wry};
 use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_store::{with_store, StoreCollection};
 use webdriver_install::Driver;
 
+mod capture;
 mod command;
 mod cortex;
+mod database;
+mod dictation;
+mod document;
+mod export;
+mod inference;
+mod mcp;
+mod ocr;
 mod panel;
+mod retrieval;
+mod search;
+mod secrets;
 mod settings;
+mod speech;
 mod stream;
+mod thread;
+mod tool;
+mod usage;
 mod util;
 
 fn main() {
@@ -26,26 +40,124 @@ fn main() {
         ))
         .plugin(tauri_plugin_store::Builder::default().build())
         .manage(panel::State::default())
+        .manage(panel::geometry::State::default())
         .manage(stream::State::default())
+        .manage(secrets::State::default())
+        .manage(database::State::default())
+        .manage(retrieval::State::default())
+        .manage(tool::State::default())
+        .manage(mcp::State::default())
+        .manage(ocr::State::default())
+        .manage(dictation::State::default())
+        .manage(speech::State::default())
+        .manage(inference::State::default())
         .manage(command::application::State::default())
+        .manage(command::application::IndexState::default())
+        .manage(command::window::State::default())
+        .manage(command::file::State::default())
         .invoke_handler(tauri::generate_handler![
             panel::init_panel,
             panel::show_panel,
             panel::hide_panel,
             panel::toggle_panel,
+            panel::get_panel_settings,
+            panel::set_panel_settings,
+            panel::reset_panel_positions,
             util::print,
             settings::open_settings_window,
             settings::register_main_window_hotkey,
             settings::fetch_user,
+            secrets::set_secret,
+            secrets::delete_secret,
+            secrets::has_secret,
             stream::stream,
             stream::cancel_stream,
             command::get_installed_applications,
             command::get_focused_application,
             command::launch_application,
+            command::launch_application_with_options,
+            command::set_application_metadata,
+            command::get_running_applications,
+            command::activate_window,
+            command::hide_application,
+            command::quit_application,
+            command::force_quit_application,
+            command::manage_window,
+            command::get_window_hotkeys,
+            command::set_window_hotkeys,
+            command::search_files,
+            command::get_file_search_settings,
+            command::set_file_search_settings,
+            command::open_file,
+            command::reveal_file,
+            command::copy_file_path,
+            search::query_applications,
             command::copy_text_to_clipboard,
             command::get_text_from_clipboard,
             command::insert_text,
             command::replace_text,
+            usage::record_usage,
+            usage::get_recent_usage,
+            usage::get_most_used,
+            usage::get_application_usage,
+            document::read_documents_for_context,
+            retrieval::get_retrieval_settings,
+            retrieval::set_retrieval_settings,
+            retrieval::index_document,
+            retrieval::index_thread,
+            retrieval::remove_from_index,
+            retrieval::search_index,
+            thread::upsert_thread,
+            thread::import_threads,
+            thread::get_threads,
+            thread::get_thread,
+            thread::delete_thread,
+            thread::delete_all_threads,
+            thread::search_threads,
+            export::export_thread,
+            capture::capture_screenshot,
+            capture::get_capture_settings,
+            capture::set_capture_settings,
+            ocr::recognize_text,
+            ocr::get_ocr_settings,
+            ocr::set_ocr_settings,
+            dictation::start_dictation,
+            dictation::stop_dictation,
+            dictation::cancel_dictation,
+            dictation::transcribe_audio_file,
+            dictation::get_dictation_settings,
+            dictation::set_dictation_settings,
+            speech::speak_text,
+            speech::stop_speaking,
+            speech::pause_speaking,
+            speech::resume_speaking,
+            speech::set_speech_rate,
+            speech::get_speech_settings,
+            speech::set_speech_settings,
+            inference::stream_local,
+            inference::list_local_models,
+            inference::download_local_model,
+            inference::cancel_local_model_download,
+            inference::delete_local_model,
+            inference::load_local_model,
+            inference::unload_local_model,
+            inference::get_inference_settings,
+            inference::set_inference_settings,
+            tool::get_tools,
+            tool::get_tool_settings,
+            tool::set_tool_settings,
+            tool::resolve_tool_approval,
+            tool::run_tools,
+            tool::shell::get_run_command_settings,
+            tool::shell::set_run_command_settings,
+            tool::shell::get_command_log,
+            mcp::get_mcp_settings,
+            mcp::set_mcp_settings,
+            mcp::get_mcp_servers,
+            mcp::reconnect_mcp_server,
+            mcp::call_mcp_tool,
+            mcp::read_mcp_resource,
+            mcp::get_mcp_prompt,
             cortex::google_search
         ])
         .setup(move |app| {
@@ -82,6 +194,10 @@ fn main() {
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
             command::setup(app.app_handle());
+            tool::setup(app.app_handle());
+            mcp::setup(app.app_handle());
+            dictation::setup(app.app_handle());
+            speech::setup(app.app_handle());
 
             with_store(
                 app.app_handle(),
@@ -120,31 +236,6 @@ fn main() {
                     .build()
                     .unwrap();
 
-                    if let Some(monitor) = main_window.current_monitor().unwrap() {
-                        let monitor_size = monitor.size();
-
-                        let window_width =
-                            cmp::min((monitor_size.width as f64 * 0.6).round() as u32, 1800);
-                        let window_height =
-                            cmp::min((monitor_size.height as f64 * 0.5).round() as u32, 1000);
-
-                        main_window
-                            .set_size(Size::Physical(PhysicalSize {
-                                width: window_width,
-                                height: window_height,
-                            }))
-                            .unwrap();
-
-                        main_window
-                            .set_position(Position::Physical(PhysicalPosition {
-                                x: ((monitor_size.width / 2) - (window_width / 2)) as i32,
-                                y: (((monitor_size.height as f32) * 0.4)
-                                    - ((window_height / 2) as f32))
-                                    as i32,
-                            }))
-                            .unwrap();
-                    }
-
                     #[cfg(target_os = "windows")]
                     {
                         let window_blur_app_handle = app.app_handle().clone();
@@ -155,36 +246,6 @@ fn main() {
                         });
                     }
 
-                    #[cfg(target_os = "windows")]
-                    {
-                        let focused_application_app_handle = app.app_handle().clone();
-                        let focused_application_main_window = main_window.clone();
-
-                        tauri::async_runtime::spawn(async move {
-                            let set_focused_window = async move {
-                                loop {
-                                    if let Ok(is_window_visible) =
-                                        focused_application_main_window.is_visible()
-                                    {
-                                        if !is_window_visible {
-                                            command::application::windows::set_focused_application(
-                                                focused_application_app_handle.clone(),
-                                            )
-                                            .await;
-                                        } else {
-                                            tokio::time::sleep(tokio::time::Duration::from_millis(
-                                                100,
-                                            ))
-                                            .await;
-                                        }
-                                    }
-                                }
-                            };
-
-                            tokio::spawn(set_focused_window);
-                        });
-                    }
-
                     let app_cache_dir = app.path_resolver().app_cache_dir().unwrap();
                     let webdriver_dir = app_cache_dir.join("webdrivers");
                     fs::create_dir_all(&webdriver_dir).unwrap();
@@ -202,20 +263,20 @@ fn main() {
             )
             .unwrap();
 
-            let get_installed_applications_request_app_handle = Arc::new(app.app_handle().clone());
-            app.listen_global("get_installed_applications_request", move |_event| {
-                let app_handle = Arc::clone(&get_installed_applications_request_app_handle);
-
-                tauri::async_runtime::spawn(async move {
-                    let payload =
-                        command::get_installed_applications(app_handle.as_ref().clone()).await;
+            panel::position_panel(app.app_handle());
 
-                    app_handle
-                        .as_ref()
-                        .emit_all("get_installed_applications_response", payload)
-                        .unwrap();
+            let window_geometry_app_handle = app.app_handle().clone();
+            app.get_window("main")
+                .unwrap()
+                .on_window_event(move |event| match event {
+                    tauri::WindowEvent::Moved(_) => {
+                        panel::remember_panel_position(window_geometry_app_handle.clone());
+                    }
+                    tauri::WindowEvent::ScaleFactorChanged { .. } => {
+                        panel::position_panel(window_geometry_app_handle.clone());
+                    }
+                    _ => {}
                 });
-            });
 
             Ok(())
         })
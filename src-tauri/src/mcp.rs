@@ -0,0 +1,677 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::tool;
+
+mod client;
+
+use client::{Client, Endpoint, REQUEST_TIMEOUT};
+
+/// Tool calls may run much longer than list requests, e.g. when a server queries a database.
+const TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Providers reject tool names longer than this.
+const MAX_TOOL_NAME_LENGTH: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transport {
+    /// A local process speaking JSON-RPC over stdin and stdout.
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        /// Values may reference stored secrets as `{{secret:<name>}}`.
+        #[serde(default)]
+        env: HashMap<String, String>,
+        #[serde(default)]
+        cwd: Option<PathBuf>,
+    },
+    /// The HTTP+SSE transport of earlier protocol revisions.
+    Sse {
+        url: String,
+        /// Values may reference stored secrets bound to the host of `url` as `{{secret:<name>}}`.
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// The streamable HTTP transport.
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Unique name, also used to prefix the server's tools.
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub transport: Transport,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub servers: Vec<ServerConfig>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Connecting,
+    Connected,
+    Failed,
+    Disabled,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Server {
+    name: String,
+    status: Status,
+    error: Option<String>,
+    /// Tools, resources and prompts as listed by the server.
+    tools: Vec<Value>,
+    resources: Vec<Value>,
+    prompts: Vec<Value>,
+    #[serde(skip)]
+    client: Option<Arc<Client>>,
+    /// Names the server's tools are registered under in the tool registry.
+    #[serde(skip)]
+    registered_tools: Vec<String>,
+}
+
+impl Server {
+    fn new(name: &str, status: Status) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            error: None,
+            tools: vec![],
+            resources: vec![],
+            prompts: vec![],
+            client: None,
+            registered_tools: vec![],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Store {
+    servers: BTreeMap<String, Server>,
+    /// Held while a server connects, so connects of the same server cannot overlap and an older
+    /// one cannot finish last.
+    connect_locks: HashMap<String, Arc<tokio::sync::Mutex<()>>>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("mcp")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn resolve_env(
+    app_handle: &AppHandle<Wry>,
+    env: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, String> {
+    env.iter()
+        .map(|(name, value)| {
+            crate::secrets::resolve_process_placeholders(app_handle, value)
+                .map(|value| (name.clone(), value))
+                .map_err(|error| error.to_string())
+        })
+        .collect()
+}
+
+fn resolve_headers(
+    app_handle: &AppHandle<Wry>,
+    url: &str,
+    headers: &HashMap<String, String>,
+) -> Result<HeaderMap, String> {
+    let mut header_map = HeaderMap::new();
+
+    for (name, value) in headers {
+        let value = crate::secrets::resolve_placeholders(app_handle, value, url)
+            .map_err(|error| error.to_string())?;
+
+        header_map.insert(
+            HeaderName::from_bytes(name.as_bytes()).map_err(|error| error.to_string())?,
+            HeaderValue::from_str(&value).map_err(|error| error.to_string())?,
+        );
+    }
+
+    Ok(header_map)
+}
+
+fn endpoint(app_handle: &AppHandle<Wry>, transport: &Transport) -> Result<Endpoint, String> {
+    Ok(match transport {
+        Transport::Stdio {
+            command,
+            args,
+            env,
+            cwd,
+        } => Endpoint::Stdio {
+            command: command.clone(),
+            args: args.clone(),
+            env: resolve_env(app_handle, env)?,
+            cwd: cwd.clone(),
+        },
+        Transport::Sse { url, headers } => Endpoint::Sse {
+            url: url.clone(),
+            headers: resolve_headers(app_handle, url, headers)?,
+        },
+        Transport::Http { url, headers } => Endpoint::Http {
+            url: url.clone(),
+            headers: resolve_headers(app_handle, url, headers)?,
+        },
+    })
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || character == '-' {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Name of a server tool in the tool registry, e.g. `github__create_issue`. Names that are taken,
+/// e.g. because they only differ after the length limit, get a numbered suffix.
+fn tool_name(server: &str, tool: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    let name = format!("{}__{}", sanitize(server), sanitize(tool));
+    let truncate = |length: usize| name.chars().take(length).collect::<String>();
+
+    let truncated_name = truncate(MAX_TOOL_NAME_LENGTH);
+
+    if !is_taken(&truncated_name) {
+        return truncated_name;
+    }
+
+    (2..)
+        .map(|number| {
+            let suffix = format!("_{}", number);
+            truncate(MAX_TOOL_NAME_LENGTH - suffix.len()) + &suffix
+        })
+        .find(|name| !is_taken(name))
+        .unwrap()
+}
+
+fn get_client(app_handle: &AppHandle<Wry>, server: &str) -> Result<Arc<Client>, String> {
+    let state = app_handle.state::<State>();
+    let store = state.0.lock().unwrap();
+
+    store
+        .servers
+        .get(server)
+        .and_then(|server| server.client.clone())
+        .filter(|client| client.is_alive())
+        .ok_or_else(|| format!("MCP server `{}` is not connected", server))
+}
+
+/// Flattens the content blocks of a tool result, or the contents of a resource, into text for the
+/// model.
+fn content_to_text(content: &Value) -> String {
+    content
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .map(|block| match block["type"].as_str() {
+                    Some("text") => block["text"].as_str().unwrap_or_default().to_string(),
+                    Some("resource") => match block["resource"]["text"].as_str() {
+                        Some(text) => text.to_string(),
+                        None => format!(
+                            "[resource {}]",
+                            block["resource"]["uri"].as_str().unwrap_or_default()
+                        ),
+                    },
+                    Some(kind) => format!(
+                        "[{} {}]",
+                        kind,
+                        block["mimeType"].as_str().unwrap_or_default()
+                    ),
+                    // Resource contents carry their text or blob without a type.
+                    None => match block["text"].as_str() {
+                        Some(text) => text.to_string(),
+                        None => format!("[resource {}]", block["uri"].as_str().unwrap_or_default()),
+                    },
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        })
+        .unwrap_or_default()
+}
+
+pub async fn call_tool(
+    app_handle: &AppHandle<Wry>,
+    server: &str,
+    name: &str,
+    arguments: Value,
+) -> Result<String, String> {
+    let client = get_client(app_handle, server)?;
+
+    let result = client
+        .request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+            TOOL_CALL_TIMEOUT,
+        )
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let text = content_to_text(&result["content"]);
+
+    if result["isError"].as_bool().unwrap_or(false) {
+        Err(text)
+    } else {
+        Ok(text)
+    }
+}
+
+pub async fn read_resource(
+    app_handle: &AppHandle<Wry>,
+    server: &str,
+    uri: &str,
+) -> Result<Value, String> {
+    let client = get_client(app_handle, server)?;
+
+    client
+        .request("resources/read", json!({ "uri": uri }), REQUEST_TIMEOUT)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+/// Registers every tool of a server, plus a tool reading its resources, in the tool registry so
+/// cortex can call them. Servers are not vetted, so their tools ask before running by default.
+fn register_tools(app_handle: &AppHandle<Wry>, server: &str, tools: &[Value], resources: &[Value]) {
+    let mut registered_tools = vec![];
+
+    for tool in tools {
+        let name = match tool["name"].as_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        let registered_name = tool_name(server, &name, |name| {
+            registered_tools
+                .iter()
+                .any(|registered_name| registered_name == name)
+                || tool::is_registered(app_handle, name)
+        });
+        let server = server.to_string();
+
+        tool::register(
+            app_handle,
+            tool::Tool {
+                name: registered_name.clone(),
+                description: tool["description"].as_str().unwrap_or_default().to_string(),
+                parameters: match &tool["inputSchema"] {
+                    Value::Null => json!({ "type": "object" }),
+                    schema => schema.clone(),
+                },
+                default_permission: tool::Permission::Ask,
+                asks_for_approval: false,
+                handler: tool::handler(move |app_handle, arguments| {
+                    let (server, name) = (server.clone(), name.clone());
+                    async move { call_tool(&app_handle, &server, &name, arguments).await }
+                }),
+            },
+        );
+
+        registered_tools.push(registered_name);
+    }
+
+    let uris = resources
+        .iter()
+        .filter_map(|resource| resource["uri"].as_str())
+        .collect::<Vec<_>>();
+
+    if !uris.is_empty() {
+        let registered_name = tool_name(server, "read_resource", |name| {
+            registered_tools
+                .iter()
+                .any(|registered_name| registered_name == name)
+                || tool::is_registered(app_handle, name)
+        });
+        let description = resources
+            .iter()
+            .map(|resource| {
+                format!(
+                    "- {}: {}",
+                    resource["uri"].as_str().unwrap_or_default(),
+                    resource["description"]
+                        .as_str()
+                        .or_else(|| resource["name"].as_str())
+                        .unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let server = server.to_string();
+
+        tool::register(
+            app_handle,
+            tool::Tool {
+                name: registered_name.clone(),
+                description: format!(
+                    "Reads a resource of the {} server. Available resources:\n{}",
+                    server, description
+                ),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "uri": { "type": "string", "enum": uris },
+                    },
+                    "required": ["uri"],
+                    "additionalProperties": false,
+                }),
+                default_permission: tool::Permission::Ask,
+                asks_for_approval: false,
+                handler: tool::handler(move |app_handle, arguments| {
+                    let server = server.clone();
+
+                    async move {
+                        let uri = arguments["uri"].as_str().unwrap_or_default();
+                        let result = read_resource(&app_handle, &server, uri).await?;
+
+                        Ok(content_to_text(&result["contents"]))
+                    }
+                }),
+            },
+        );
+
+        registered_tools.push(registered_name);
+    }
+
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    if let Some(server) = store.servers.get_mut(server) {
+        server.registered_tools = registered_tools;
+    }
+}
+
+/// Replaces the state of a server, removing the tools of the replaced state from the registry.
+fn update_server(app_handle: &AppHandle<Wry>, server: Server) {
+    let replaced_server = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+        store.servers.insert(server.name.clone(), server)
+    };
+
+    if let Some(replaced_server) = replaced_server {
+        for tool_name in &replaced_server.registered_tools {
+            tool::unregister(app_handle, tool_name);
+        }
+    }
+}
+
+fn connect_lock(app_handle: &AppHandle<Wry>, name: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    store
+        .connect_locks
+        .entry(name.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Stops a server and removes its tools from the registry.
+fn disconnect(app_handle: &AppHandle<Wry>, name: &str) {
+    let server = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+        store.servers.remove(name)
+    };
+
+    if let Some(server) = server {
+        for tool_name in &server.registered_tools {
+            tool::unregister(app_handle, tool_name);
+        }
+    }
+}
+
+async fn list_server(client: &Client, server: &mut Server) -> Result<(), client::Error> {
+    if client.has_capability("tools") {
+        server.tools = client.list("tools/list", "tools").await?;
+    }
+
+    if client.has_capability("resources") {
+        server.resources = client.list("resources/list", "resources").await?;
+    }
+
+    if client.has_capability("prompts") {
+        server.prompts = client.list("prompts/list", "prompts").await?;
+    }
+
+    Ok(())
+}
+
+pub async fn connect(app_handle: &AppHandle<Wry>, config: &ServerConfig) {
+    let lock = connect_lock(app_handle, &config.name);
+    let _guard = lock.lock().await;
+
+    disconnect(app_handle, &config.name);
+
+    if !config.enabled {
+        update_server(app_handle, Server::new(&config.name, Status::Disabled));
+        return;
+    }
+
+    update_server(app_handle, Server::new(&config.name, Status::Connecting));
+
+    let mut server = Server::new(&config.name, Status::Connected);
+
+    let result = async {
+        let endpoint = endpoint(app_handle, &config.transport)?;
+        let client = Client::connect(endpoint)
+            .await
+            .map_err(|error| error.to_string())?;
+
+        list_server(&client, &mut server)
+            .await
+            .map_err(|error| error.to_string())?;
+
+        Ok::<_, String>(client)
+    }
+    .await;
+
+    match result {
+        Ok(client) => {
+            server.client = Some(Arc::new(client));
+
+            let (tools, resources) = (server.tools.clone(), server.resources.clone());
+
+            update_server(app_handle, server);
+            register_tools(app_handle, &config.name, &tools, &resources);
+        }
+        Err(error) => {
+            server.status = Status::Failed;
+            server.error = Some(error);
+
+            update_server(app_handle, server);
+        }
+    }
+
+    app_handle
+        .emit_all("mcp_servers_changed", ())
+        .unwrap_or_default();
+}
+
+async fn connect_all(app_handle: AppHandle<Wry>) {
+    let settings = get_settings(&app_handle);
+
+    let names = {
+        let state = app_handle.state::<State>();
+        let store = state.0.lock().unwrap();
+        store.servers.keys().cloned().collect::<Vec<_>>()
+    };
+
+    for name in names {
+        if !settings.servers.iter().any(|server| server.name == name) {
+            let lock = connect_lock(&app_handle, &name);
+            let _guard = lock.lock().await;
+
+            disconnect(&app_handle, &name);
+        }
+    }
+
+    let connections = settings.servers.iter().map(|config| {
+        let app_handle = app_handle.clone();
+        async move { connect(&app_handle, config).await }
+    });
+
+    futures::future::join_all(connections).await;
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    tauri::async_runtime::spawn(connect_all(app_handle));
+}
+
+#[tauri::command]
+pub fn get_mcp_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+/// Saves the server list and reconnects every server.
+#[tauri::command]
+pub async fn set_mcp_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("mcp".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+
+    connect_all(app_handle).await;
+}
+
+#[tauri::command]
+pub fn get_mcp_servers(app_handle: AppHandle<Wry>) -> String {
+    let state = app_handle.state::<State>();
+    let store = state.0.lock().unwrap();
+
+    let servers = store.servers.values().collect::<Vec<_>>();
+
+    serde_json::to_string(&servers).expect("error serializing json")
+}
+
+#[tauri::command]
+pub async fn reconnect_mcp_server(app_handle: AppHandle<Wry>, name: String) -> Result<(), String> {
+    let config = get_settings(&app_handle)
+        .servers
+        .into_iter()
+        .find(|server| server.name == name)
+        .ok_or_else(|| format!("MCP server `{}` is not configured", name))?;
+
+    connect(&app_handle, &config).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn call_mcp_tool(
+    app_handle: AppHandle<Wry>,
+    server: String,
+    name: String,
+    arguments: Value,
+) -> Result<String, String> {
+    call_tool(&app_handle, &server, &name, arguments).await
+}
+
+#[tauri::command]
+pub async fn read_mcp_resource(
+    app_handle: AppHandle<Wry>,
+    server: String,
+    uri: String,
+) -> Result<String, String> {
+    let result = read_resource(&app_handle, &server, &uri).await?;
+    Ok(serde_json::to_string(&result).expect("error serializing json"))
+}
+
+/// Resolves a server prompt template into messages the webview can add to a thread.
+#[tauri::command]
+pub async fn get_mcp_prompt(
+    app_handle: AppHandle<Wry>,
+    server: String,
+    name: String,
+    arguments: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let client = get_client(&app_handle, &server)?;
+
+    let result = client
+        .request(
+            "prompts/get",
+            json!({ "name": name, "arguments": arguments.unwrap_or_default() }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&result).expect("error serializing json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_names_are_sanitized() {
+        assert_eq!(
+            tool_name("my server", "files.read", |_| false),
+            "my_server__files_read"
+        );
+    }
+
+    #[test]
+    fn colliding_tool_names_get_a_suffix() {
+        let prefix = "a".repeat(70);
+        let taken = [tool_name("server", &prefix, |_| false)];
+
+        let name = tool_name("server", &format!("{}_other", prefix), |name| {
+            taken.iter().any(|taken_name| taken_name == name)
+        });
+
+        assert_eq!(taken[0].len(), MAX_TOOL_NAME_LENGTH);
+        assert_eq!(name.len(), MAX_TOOL_NAME_LENGTH);
+        assert!(name.ends_with("_2"));
+        assert_ne!(name, taken[0]);
+    }
+}
@@ -0,0 +1,678 @@
+use futures::StreamExt;
+use reqwest::{
+    header::{HeaderMap, ACCEPT, CONTENT_TYPE},
+    Response, Url,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tauri::api::process::{Command, CommandChild, CommandEvent};
+use tokio::sync::oneshot;
+
+/// Revision of the Model Context Protocol sent in `initialize`.
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SESSION_HEADER: &str = "mcp-session-id";
+
+#[derive(Debug)]
+pub enum Error {
+    Spawn(String),
+    Connect(String),
+    Timeout,
+    Closed,
+    Protocol(String),
+    Remote { code: i64, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Spawn(message) => write!(f, "failed to spawn server: {}", message),
+            Error::Connect(message) => write!(f, "failed to connect to server: {}", message),
+            Error::Timeout => write!(f, "server request timed out"),
+            Error::Closed => write!(f, "server closed the connection"),
+            Error::Protocol(message) => write!(f, "invalid server message: {}", message),
+            Error::Remote { code, message } => write!(f, "server error {}: {}", code, message),
+        }
+    }
+}
+
+/// How a server is reached. Secret placeholders in `env` and `headers` are resolved before
+/// connecting.
+pub enum Endpoint {
+    Stdio {
+        command: String,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        cwd: Option<PathBuf>,
+    },
+    /// The HTTP+SSE transport: a GET opens an event stream that announces the URL to POST
+    /// messages to, and responses arrive on the stream.
+    Sse { url: String, headers: HeaderMap },
+    /// The streamable HTTP transport: every message is POSTed to `url` and responses come back as
+    /// JSON or as an event stream in the response body.
+    Http { url: String, headers: HeaderMap },
+}
+
+enum Transport {
+    Stdio(Mutex<Option<CommandChild>>),
+    Sse {
+        client: reqwest::Client,
+        endpoint: Url,
+        headers: HeaderMap,
+    },
+    Http {
+        client: reqwest::Client,
+        url: Url,
+        headers: HeaderMap,
+        session_id: Mutex<Option<String>>,
+    },
+}
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, Error>>>>>;
+
+struct Shared {
+    pending: Pending,
+    is_alive: AtomicBool,
+}
+
+impl Shared {
+    fn close(&self) {
+        self.is_alive.store(false, Ordering::SeqCst);
+
+        for (_, sender) in self.pending.lock().unwrap().drain() {
+            sender.send(Err(Error::Closed)).ok();
+        }
+    }
+
+    /// Dispatches a message from the server. Responses complete their pending request; requests
+    /// from the server get the reply to send back, since only `ping` is supported.
+    fn handle_message(&self, message: Value) -> Option<Value> {
+        if let Value::Array(messages) = message {
+            let replies = messages
+                .into_iter()
+                .filter_map(|message| self.handle_message(message))
+                .collect::<Vec<_>>();
+
+            return if replies.is_empty() {
+                None
+            } else {
+                Some(Value::Array(replies))
+            };
+        }
+
+        if let Some(method) = message["method"].as_str() {
+            if message["id"].is_null() {
+                // Notifications such as progress or log messages are not used.
+                return None;
+            }
+
+            return Some(match method {
+                "ping" => json!({ "jsonrpc": "2.0", "id": message["id"], "result": {} }),
+                method => json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("unsupported method `{}`", method) },
+                }),
+            });
+        }
+
+        let sender = message["id"]
+            .as_u64()
+            .and_then(|id| self.pending.lock().unwrap().remove(&id));
+
+        if let Some(sender) = sender {
+            let result = match message.get("error") {
+                Some(error) => Err(Error::Remote {
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                }),
+                None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+
+            sender.send(result).ok();
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Event {
+    name: String,
+    data: String,
+}
+
+/// Incremental parser for `text/event-stream` bodies.
+#[derive(Default)]
+struct EventParser {
+    buffer: Vec<u8>,
+}
+
+impl EventParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.buffer
+            .extend(bytes.iter().filter(|&&byte| byte != b'\r'));
+
+        let mut events = vec![];
+
+        while let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+            let block = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+            let block = String::from_utf8_lossy(&block);
+
+            let mut event = Event {
+                name: "message".to_string(),
+                data: String::new(),
+            };
+
+            for line in block.lines() {
+                if let Some(name) = line.strip_prefix("event:") {
+                    event.name = name.trim().to_string();
+                } else if let Some(data) = line.strip_prefix("data:") {
+                    if !event.data.is_empty() {
+                        event.data.push('\n');
+                    }
+
+                    event.data.push_str(data.strip_prefix(' ').unwrap_or(data));
+                }
+            }
+
+            if !event.data.is_empty() {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+}
+
+/// Client for one MCP server. Dropping it stops the server process or closes the event stream.
+pub struct Client {
+    transport: Arc<Transport>,
+    shared: Arc<Shared>,
+    next_id: AtomicU64,
+    reader: Option<tauri::async_runtime::JoinHandle<()>>,
+    /// The `initialize` result, with the server's capabilities and info.
+    pub server: Value,
+}
+
+impl Transport {
+    /// Sends a message. For the streamable HTTP transport the response is returned so its body can
+    /// be read for replies.
+    async fn send(&self, message: &Value) -> Result<Option<Response>, Error> {
+        match self {
+            Transport::Stdio(child) => {
+                let mut line = serde_json::to_vec(message).unwrap();
+                line.push(b'\n');
+
+                child
+                    .lock()
+                    .unwrap()
+                    .as_mut()
+                    .ok_or(Error::Closed)?
+                    .write(&line)
+                    .map_err(|_| Error::Closed)?;
+
+                Ok(None)
+            }
+            Transport::Sse {
+                client,
+                endpoint,
+                headers,
+            } => {
+                let response = client
+                    .post(endpoint.clone())
+                    .headers(headers.clone())
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(message.to_string())
+                    .send()
+                    .await
+                    .map_err(|error| Error::Connect(error.to_string()))?;
+
+                if !response.status().is_success() {
+                    return Err(Error::Connect(format!(
+                        "server responded {}",
+                        response.status()
+                    )));
+                }
+
+                Ok(None)
+            }
+            Transport::Http {
+                client,
+                url,
+                headers,
+                session_id,
+            } => {
+                let mut request = client
+                    .post(url.clone())
+                    .headers(headers.clone())
+                    .header(CONTENT_TYPE, "application/json")
+                    .header(ACCEPT, "application/json, text/event-stream")
+                    .body(message.to_string());
+
+                if let Some(session_id) = session_id.lock().unwrap().clone() {
+                    request = request.header(SESSION_HEADER, session_id);
+                }
+
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|error| Error::Connect(error.to_string()))?;
+
+                if !response.status().is_success() {
+                    return Err(Error::Connect(format!(
+                        "server responded {}",
+                        response.status()
+                    )));
+                }
+
+                if let Some(id) = response
+                    .headers()
+                    .get(SESSION_HEADER)
+                    .and_then(|id| id.to_str().ok())
+                {
+                    *session_id.lock().unwrap() = Some(id.to_string());
+                }
+
+                Ok(Some(response))
+            }
+        }
+    }
+}
+
+/// Reads messages from an event stream until it ends, sending replies through `transport`.
+/// Returns the data of the first `endpoint` event through `endpoint` when given.
+async fn read_events(
+    response: Response,
+    transport: Option<Arc<Transport>>,
+    shared: Arc<Shared>,
+    mut endpoint: Option<oneshot::Sender<String>>,
+) {
+    let mut parser = EventParser::default();
+    let mut stream = response.bytes_stream();
+
+    while let Some(Ok(bytes)) = stream.next().await {
+        for event in parser.push(&bytes) {
+            if event.name == "endpoint" {
+                if let Some(endpoint) = endpoint.take() {
+                    endpoint.send(event.data).ok();
+                }
+
+                continue;
+            }
+
+            let message = match serde_json::from_str(&event.data) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+
+            if let (Some(reply), Some(transport)) = (shared.handle_message(message), &transport) {
+                transport.send(&reply).await.ok();
+            }
+        }
+    }
+}
+
+/// Reads the body of a streamable HTTP response, which is either a JSON message or an event stream
+/// of messages.
+async fn read_response(response: Response, transport: Arc<Transport>, shared: Arc<Shared>) {
+    let is_event_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("text/event-stream"))
+        .unwrap_or(false);
+
+    if is_event_stream {
+        read_events(response, Some(transport), shared, None).await;
+        return;
+    }
+
+    let message = match response.json::<Value>().await {
+        Ok(message) => message,
+        // Notifications and replies are acknowledged with an empty `202 Accepted`.
+        Err(_) => return,
+    };
+
+    if let Some(reply) = shared.handle_message(message) {
+        transport.send(&reply).await.ok();
+    }
+}
+
+impl Client {
+    /// Connects to the server and performs the `initialize` handshake.
+    pub async fn connect(endpoint: Endpoint) -> Result<Self, Error> {
+        let shared = Arc::new(Shared {
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            is_alive: AtomicBool::new(true),
+        });
+
+        let (transport, reader) = match endpoint {
+            Endpoint::Stdio {
+                command,
+                args,
+                env,
+                cwd,
+            } => {
+                let mut command = Command::new(command).args(args).envs(env);
+
+                if let Some(cwd) = cwd {
+                    command = command.current_dir(cwd);
+                }
+
+                let (mut rx, child) = command
+                    .spawn()
+                    .map_err(|error| Error::Spawn(error.to_string()))?;
+
+                let transport = Arc::new(Transport::Stdio(Mutex::new(Some(child))));
+
+                let reader_transport = Arc::clone(&transport);
+                let reader_shared = Arc::clone(&shared);
+
+                let reader = tauri::async_runtime::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        match event {
+                            CommandEvent::Stdout(line) => {
+                                let message = match serde_json::from_str(line.trim()) {
+                                    Ok(message) => message,
+                                    // Servers may log to stdout before they start speaking JSON.
+                                    Err(_) => continue,
+                                };
+
+                                if let Some(reply) = reader_shared.handle_message(message) {
+                                    reader_transport.send(&reply).await.ok();
+                                }
+                            }
+                            CommandEvent::Terminated(_) => break,
+                            _ => {}
+                        }
+                    }
+
+                    reader_shared.close();
+                });
+
+                (transport, Some(reader))
+            }
+            Endpoint::Sse { url, headers } => {
+                let url = Url::parse(&url).map_err(|error| Error::Connect(error.to_string()))?;
+                let client = reqwest::Client::new();
+
+                let response = client
+                    .get(url.clone())
+                    .headers(headers.clone())
+                    .header(ACCEPT, "text/event-stream")
+                    .send()
+                    .await
+                    .map_err(|error| Error::Connect(error.to_string()))?;
+
+                if !response.status().is_success() {
+                    return Err(Error::Connect(format!(
+                        "server responded {}",
+                        response.status()
+                    )));
+                }
+
+                // Replies to server requests are POSTed to the endpoint, which is only known once
+                // the stream announces it, so the reader does not send any.
+                let (endpoint_tx, endpoint_rx) = oneshot::channel();
+                let reader_shared = Arc::clone(&shared);
+
+                let reader = tauri::async_runtime::spawn(async move {
+                    read_events(
+                        response,
+                        None,
+                        Arc::clone(&reader_shared),
+                        Some(endpoint_tx),
+                    )
+                    .await;
+                    reader_shared.close();
+                });
+
+                let endpoint = match tokio::time::timeout(REQUEST_TIMEOUT, endpoint_rx).await {
+                    Ok(Ok(endpoint)) => endpoint,
+                    Ok(Err(_)) => return Err(Error::Closed),
+                    Err(_) => {
+                        reader.abort();
+                        return Err(Error::Timeout);
+                    }
+                };
+
+                let endpoint = url
+                    .join(endpoint.trim())
+                    .map_err(|error| Error::Protocol(error.to_string()))?;
+
+                let transport = Arc::new(Transport::Sse {
+                    client,
+                    endpoint,
+                    headers,
+                });
+
+                (transport, Some(reader))
+            }
+            Endpoint::Http { url, headers } => {
+                let url = Url::parse(&url).map_err(|error| Error::Connect(error.to_string()))?;
+
+                let transport = Arc::new(Transport::Http {
+                    client: reqwest::Client::new(),
+                    url,
+                    headers,
+                    session_id: Mutex::new(None),
+                });
+
+                (transport, None)
+            }
+        };
+
+        let mut client = Self {
+            transport,
+            shared,
+            next_id: AtomicU64::new(1),
+            reader,
+            server: Value::Null,
+        };
+
+        client.server = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "Lander", "version": env!("CARGO_PKG_VERSION") },
+                }),
+                REQUEST_TIMEOUT,
+            )
+            .await?;
+
+        client
+            .notify("notifications/initialized", json!({}))
+            .await?;
+
+        Ok(client)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.shared.is_alive.load(Ordering::SeqCst)
+    }
+
+    pub fn has_capability(&self, name: &str) -> bool {
+        !self.server["capabilities"][name].is_null()
+    }
+
+    async fn send(&self, message: &Value) -> Result<(), Error> {
+        if let Some(response) = self.transport.send(message).await? {
+            tauri::async_runtime::spawn(read_response(
+                response,
+                Arc::clone(&self.transport),
+                Arc::clone(&self.shared),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), Error> {
+        if !self.is_alive() {
+            return Err(Error::Closed);
+        }
+
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, Error> {
+        if !self.is_alive() {
+            return Err(Error::Closed);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+
+        self.shared.pending.lock().unwrap().insert(id, tx);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        if let Err(error) = self.send(&message).await {
+            self.shared.pending.lock().unwrap().remove(&id);
+            return Err(error);
+        }
+
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(Error::Closed),
+            Err(_) => {
+                self.shared.pending.lock().unwrap().remove(&id);
+
+                self.notify(
+                    "notifications/cancelled",
+                    json!({ "requestId": id, "reason": "timed out" }),
+                )
+                .await
+                .ok();
+
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    /// Collects every page of a `*/list` request, e.g. `tools` from `tools/list`.
+    pub async fn list(&self, method: &str, key: &str) -> Result<Vec<Value>, Error> {
+        let mut items = vec![];
+        let mut cursor = Value::Null;
+
+        loop {
+            let params = if cursor.is_null() {
+                json!({})
+            } else {
+                json!({ "cursor": cursor })
+            };
+
+            let mut result = self.request(method, params, REQUEST_TIMEOUT).await?;
+
+            if let Some(page) = result[key].as_array_mut() {
+                items.append(page);
+            }
+
+            cursor = result["nextCursor"].take();
+
+            if cursor.is_null() {
+                return Ok(items);
+            }
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(reader) = &self.reader {
+            reader.abort();
+        }
+
+        match self.transport.as_ref() {
+            Transport::Stdio(child) => {
+                if let Some(child) = child.lock().unwrap().take() {
+                    child.kill().unwrap_or_default();
+                }
+            }
+            Transport::Http {
+                client,
+                url,
+                headers,
+                session_id,
+            } => {
+                // Ends the session on the server; failures only leave it to expire.
+                if let Some(session_id) = session_id.lock().unwrap().take() {
+                    let request = client
+                        .delete(url.clone())
+                        .headers(headers.clone())
+                        .header(SESSION_HEADER, session_id);
+
+                    tauri::async_runtime::spawn(async move {
+                        request.send().await.ok();
+                    });
+                }
+            }
+            Transport::Sse { .. } => {}
+        }
+
+        self.shared.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &str, data: &str) -> Event {
+        Event {
+            name: name.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = EventParser::default();
+
+        assert_eq!(parser.push(b"event: endpoint\ndata: /mess"), vec![]);
+        assert_eq!(
+            parser.push(b"ages?id=1\n\ndata: {}\n\n"),
+            vec![event("endpoint", "/messages?id=1"), event("message", "{}")]
+        );
+    }
+
+    #[test]
+    fn joins_data_lines_and_ignores_carriage_returns() {
+        let mut parser = EventParser::default();
+
+        assert_eq!(
+            parser.push(b"data: first\r\ndata:second\r\n\r\n"),
+            vec![event("message", "first\nsecond")]
+        );
+    }
+
+    #[test]
+    fn skips_events_without_data() {
+        let mut parser = EventParser::default();
+
+        assert_eq!(
+            parser.push(b": keep-alive\n\nevent: ping\n\ndata: 1\n\n"),
+            vec![event("message", "1")]
+        );
+    }
+}
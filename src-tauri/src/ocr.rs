@@ -0,0 +1,180 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::capture::{self, Target};
+use crate::panel::geometry::Rect;
+
+#[cfg(feature = "ocr")]
+mod engine;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Recognize the focused window when the focused application exposes no text through the
+    /// accessibility APIs.
+    pub focused_text_fallback: bool,
+    /// Tesseract language codes, joined with `+` for several (e.g. `eng+deu`).
+    pub language: String,
+    /// Directory of the `.traineddata` files; Tesseract's default location when unset.
+    pub data_path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            focused_text_fallback: true,
+            language: "eng".to_string(),
+            data_path: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Store {
+    #[cfg(feature = "ocr")]
+    engine: Option<((String, Option<PathBuf>), engine::Engine)>,
+}
+
+#[derive(Default)]
+#[cfg_attr(not(feature = "ocr"), allow(dead_code))]
+pub struct State(pub Mutex<Store>);
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("ocr")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+/// Drops the blank lines Tesseract leaves between blocks and the whitespace around lines.
+#[cfg_attr(not(feature = "ocr"), allow(dead_code))]
+fn clean_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(feature = "ocr")]
+async fn recognize_image(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    image: RgbaImage,
+) -> Result<String, String> {
+    let key = (settings.language.clone(), settings.data_path.clone());
+
+    // The engine is taken out while it runs, so recognitions in parallel load their own.
+    let engine = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        match store.engine.take() {
+            Some((loaded_key, engine)) if loaded_key == key => Some(engine),
+            _ => None,
+        }
+    };
+
+    let (language, data_path) = key.clone();
+
+    let (engine, text) = tauri::async_runtime::spawn_blocking(move || {
+        let engine = match engine {
+            Some(engine) => engine,
+            None => engine::Engine::load(&language, data_path.as_deref())?,
+        };
+
+        engine.recognize(&image)
+    })
+    .await
+    .map_err(|error| error.to_string())??;
+
+    app_handle.state::<State>().0.lock().unwrap().engine = Some((key, engine));
+
+    Ok(clean_text(&text))
+}
+
+#[cfg(not(feature = "ocr"))]
+async fn recognize_image(
+    _app_handle: &AppHandle<Wry>,
+    _settings: &Settings,
+    _image: RgbaImage,
+) -> Result<String, String> {
+    Err("Lander was built without the `ocr` feature".to_string())
+}
+
+/// Text of the focused window, for applications that expose none through the accessibility
+/// APIs. The window is captured on its own, so the panel in front of it does not have to hide.
+pub async fn recognize_focused_window(app_handle: &AppHandle<Wry>) -> Option<String> {
+    // Capturing is not free, and nothing could be recognized in the image anyway.
+    if cfg!(not(feature = "ocr")) {
+        return None;
+    }
+
+    let settings = get_settings(app_handle);
+
+    if !settings.focused_text_fallback {
+        return None;
+    }
+
+    let image = capture::capture_image(Target::Window, None)
+        .await
+        .ok()
+        .flatten()?;
+
+    recognize_image(app_handle, &settings, image)
+        .await
+        .ok()
+        .filter(|text| !text.is_empty())
+}
+
+/// Captures the screen, the focused window or a region without the panel and returns the text
+/// in it. Resolves to `null` when the user cancels the region selection.
+#[tauri::command]
+pub async fn recognize_text(
+    app_handle: AppHandle<Wry>,
+    target: Target,
+    rect: Option<Rect>,
+) -> Result<Option<String>, String> {
+    let settings = get_settings(&app_handle);
+
+    let image = match capture::capture_image_without_panel(&app_handle, target, rect).await? {
+        Some(image) => image,
+        None => return Ok(None),
+    };
+
+    recognize_image(&app_handle, &settings, image)
+        .await
+        .map(Some)
+}
+
+#[tauri::command]
+pub fn get_ocr_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_ocr_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("ocr".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
@@ -0,0 +1,64 @@
+use image::{imageops, DynamicImage, GrayImage, RgbaImage};
+use std::path::Path;
+use tesseract::Tesseract;
+
+/// Captures narrower than this are likely at a backing scale of 1, where text is too small for
+/// Tesseract, so they are upscaled first.
+const UPSCALE_BELOW_WIDTH: u32 = 2000;
+
+/// Resolution Tesseract assumes for screen text after upscaling; without one it guesses 70 DPI.
+const SOURCE_RESOLUTION: i32 = 144;
+
+/// Tesseract with the trained data of one language loaded, which takes long enough to keep it
+/// around between recognitions.
+pub struct Engine(Tesseract);
+
+impl Engine {
+    pub fn load(language: &str, data_path: Option<&Path>) -> Result<Self, String> {
+        let data_path = data_path.map(|path| path.to_string_lossy().into_owned());
+
+        Tesseract::new(data_path.as_deref(), Some(language))
+            .map(Engine)
+            .map_err(|error| {
+                format!(
+                    "the Tesseract data for `{}` could not be loaded: {}",
+                    language, error
+                )
+            })
+    }
+
+    /// Tesseract reads grayscale best, and at a size where letters are at least 20 pixels tall.
+    fn prepare(image: &RgbaImage) -> GrayImage {
+        let image = DynamicImage::ImageRgba8(image.clone()).to_luma8();
+        let (width, height) = image.dimensions();
+
+        if width < UPSCALE_BELOW_WIDTH {
+            imageops::resize(
+                &image,
+                width * 2,
+                height * 2,
+                imageops::FilterType::CatmullRom,
+            )
+        } else {
+            image
+        }
+    }
+
+    /// Consumes the engine, as `tesseract` does, and hands it back with the recognized text.
+    pub fn recognize(self, image: &RgbaImage) -> Result<(Self, String), String> {
+        let image = Self::prepare(image);
+        let (width, height) = image.dimensions();
+
+        let mut tesseract = self
+            .0
+            .set_frame(image.as_raw(), width as i32, height as i32, 1, width as i32)
+            .map_err(|error| error.to_string())?
+            .set_source_resolution(SOURCE_RESOLUTION)
+            .recognize()
+            .map_err(|error| error.to_string())?;
+
+        let text = tesseract.get_text().map_err(|error| error.to_string())?;
+
+        Ok((Engine(tesseract), text))
+    }
+}
@@ -1,5 +1,13 @@
-use std::sync::Once;
-use tauri::{AppHandle, Window, Wry};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Once,
+    },
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, Window, Wry};
+
+pub mod geometry;
 
 #[cfg(target_os = "macos")]
 mod macos;
@@ -15,6 +23,11 @@ pub use panel::State;
 
 static INIT: Once = Once::new();
 
+/// Time without further moves after which a drag is considered finished.
+const MOVE_SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+static MOVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
 #[tauri::command]
 pub fn init_panel(app_handle: AppHandle<Wry>, window: Window<Wry>) {
     INIT.call_once(|| {
@@ -36,3 +49,51 @@ pub fn hide_panel(app_handle: AppHandle<Wry>) {
 pub fn toggle_panel(app_handle: AppHandle<Wry>) {
     panel::toggle_panel(app_handle);
 }
+
+pub fn position_panel(app_handle: AppHandle<Wry>) {
+    panel::position_panel(app_handle);
+}
+
+pub fn remember_panel_position(app_handle: AppHandle<Wry>) {
+    let is_visible = app_handle
+        .get_window("main")
+        .and_then(|window| window.is_visible().ok())
+        .unwrap_or(false);
+
+    // Only moves made while the panel is on screen come from the user dragging it.
+    if !is_visible {
+        return;
+    }
+
+    // A drag moves the panel many times; only where it ends up is saved.
+    let generation = MOVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(MOVE_SETTLE_DELAY).await;
+
+        if MOVE_GENERATION.load(Ordering::SeqCst) == generation {
+            let main_thread_app_handle = app_handle.clone();
+
+            app_handle
+                .run_on_main_thread(move || panel::remember_panel_position(main_thread_app_handle))
+                .unwrap_or_default();
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_panel_settings(app_handle: AppHandle<Wry>) -> geometry::Settings {
+    geometry::get_settings(&app_handle)
+}
+
+#[tauri::command]
+pub fn set_panel_settings(app_handle: AppHandle<Wry>, settings: geometry::Settings) {
+    geometry::set_settings(&app_handle, settings);
+    panel::position_panel(app_handle);
+}
+
+#[tauri::command]
+pub fn reset_panel_positions(app_handle: AppHandle<Wry>) {
+    geometry::reset_positions(&app_handle);
+    panel::position_panel(app_handle);
+}
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+/// Which monitor the panel opens on.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Screen {
+    Cursor,
+    FocusedWindow,
+    Primary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Panel width as a fraction of the monitor width.
+    pub width: f64,
+    /// Panel height as a fraction of the monitor height.
+    pub height: f64,
+    /// Upper bound for the panel width in physical pixels.
+    pub max_width: f64,
+    /// Upper bound for the panel height in physical pixels.
+    pub max_height: f64,
+    /// Vertical position of the panel center as a fraction of the monitor height, from the top.
+    pub vertical_offset: f64,
+    pub screen: Screen,
+    pub remember_position: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: 0.6,
+            height: 0.5,
+            max_width: 1800.0,
+            max_height: 1000.0,
+            vertical_offset: 0.4,
+            screen: Screen::Cursor,
+            remember_position: false,
+        }
+    }
+}
+
+/// Rectangle in physical pixels with a top-left origin.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Panel origin relative to its monitor, stored as fractions so it survives resolution and
+/// scale factor changes.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Default)]
+pub struct Store {
+    settings: Option<Settings>,
+    positions: HashMap<String, Position>,
+    last_frame: Option<Rect>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+fn clamp(value: f64, min: f64, max: f64) -> f64 {
+    value.min(max).max(min)
+}
+
+pub fn panel_frame(settings: &Settings, monitor: &Rect, remembered: Option<&Position>) -> Rect {
+    let width = clamp(settings.width, 0.1, 1.0) * monitor.width;
+    let height = clamp(settings.height, 0.1, 1.0) * monitor.height;
+    let width = width.min(settings.max_width).round();
+    let height = height.min(settings.max_height).round();
+
+    let (x, y) = match remembered {
+        Some(position) if settings.remember_position => (
+            monitor.x + position.x * monitor.width,
+            monitor.y + position.y * monitor.height,
+        ),
+        _ => (
+            monitor.x + (monitor.width - width) / 2.0,
+            monitor.y + monitor.height * clamp(settings.vertical_offset, 0.0, 1.0) - height / 2.0,
+        ),
+    };
+
+    Rect {
+        x: clamp(x, monitor.x, monitor.x + monitor.width - width).round(),
+        y: clamp(y, monitor.y, monitor.y + monitor.height - height).round(),
+        width,
+        height,
+    }
+}
+
+pub fn relative_position(monitor: &Rect, frame: &Rect) -> Position {
+    Position {
+        x: (frame.x - monitor.x) / monitor.width,
+        y: (frame.y - monitor.y) / monitor.height,
+    }
+}
+
+fn load(app_handle: &AppHandle<Wry>) {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    if store.settings.is_some() {
+        return;
+    }
+
+    let (settings, positions) = with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            let settings = store
+                .get("panel")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default();
+
+            let positions = store
+                .get("panel_positions")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default();
+
+            Ok((settings, positions))
+        },
+    )
+    .unwrap_or_default();
+
+    store.settings = Some(settings);
+    store.positions = positions;
+}
+
+fn save(app_handle: &AppHandle<Wry>, key: &str, value: serde_json::Value) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert(key.to_string(), value)?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    load(app_handle);
+
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .settings
+        .clone()
+        .unwrap_or_default()
+}
+
+pub fn set_settings(app_handle: &AppHandle<Wry>, settings: Settings) {
+    load(app_handle);
+
+    save(app_handle, "panel", serde_json::json!(settings));
+    app_handle.state::<State>().0.lock().unwrap().settings = Some(settings);
+}
+
+/// Computes the panel frame for `monitor` and records it, so the move it triggers is not
+/// mistaken for the user dragging the panel.
+pub fn frame_for_monitor(app_handle: &AppHandle<Wry>, monitor_key: &str, monitor: &Rect) -> Rect {
+    let settings = get_settings(app_handle);
+
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    let frame = panel_frame(&settings, monitor, store.positions.get(monitor_key));
+    store.last_frame = Some(frame);
+
+    frame
+}
+
+pub fn remember_position(
+    app_handle: &AppHandle<Wry>,
+    monitor_key: &str,
+    monitor: &Rect,
+    frame: &Rect,
+) {
+    let settings = get_settings(app_handle);
+
+    if !settings.remember_position {
+        return;
+    }
+
+    let positions = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        if let Some(last_frame) = store.last_frame {
+            if (last_frame.x - frame.x).abs() < 1.0 && (last_frame.y - frame.y).abs() < 1.0 {
+                return;
+            }
+        }
+
+        store.last_frame = Some(*frame);
+        store
+            .positions
+            .insert(monitor_key.to_string(), relative_position(monitor, frame));
+
+        store.positions.clone()
+    };
+
+    save(app_handle, "panel_positions", serde_json::json!(positions));
+}
+
+pub fn reset_positions(app_handle: &AppHandle<Wry>) {
+    load(app_handle);
+
+    app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .positions
+        .clear();
+
+    save(app_handle, "panel_positions", serde_json::json!({}));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn remembering() -> Settings {
+        Settings {
+            remember_position: true,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn panel_is_centered_at_the_vertical_offset() {
+        let frame = panel_frame(&Settings::default(), &rect(0.0, 0.0, 2000.0, 1200.0), None);
+
+        assert_eq!(frame, rect(400.0, 180.0, 1200.0, 600.0));
+
+        // A monitor left of and above the primary one has negative coordinates.
+        let frame = panel_frame(
+            &Settings::default(),
+            &rect(-2000.0, -300.0, 2000.0, 1200.0),
+            None,
+        );
+
+        assert_eq!(frame, rect(-1600.0, -120.0, 1200.0, 600.0));
+    }
+
+    #[test]
+    fn panel_size_is_bounded() {
+        let frame = panel_frame(&Settings::default(), &rect(0.0, 0.0, 4000.0, 2400.0), None);
+
+        assert_eq!((frame.width, frame.height), (1800.0, 1000.0));
+
+        let settings = Settings {
+            width: 5.0,
+            height: 0.0,
+            ..Settings::default()
+        };
+        let frame = panel_frame(&settings, &rect(0.0, 0.0, 1000.0, 800.0), None);
+
+        assert_eq!((frame.width, frame.height), (1000.0, 80.0));
+    }
+
+    #[test]
+    fn panel_stays_on_the_monitor() {
+        let monitor = rect(100.0, 50.0, 1000.0, 800.0);
+
+        let settings = Settings {
+            vertical_offset: 0.0,
+            ..Settings::default()
+        };
+        assert_eq!(panel_frame(&settings, &monitor, None).y, 50.0);
+
+        let settings = Settings {
+            vertical_offset: 1.0,
+            ..Settings::default()
+        };
+        assert_eq!(panel_frame(&settings, &monitor, None).y, 450.0);
+
+        let frame = panel_frame(
+            &remembering(),
+            &monitor,
+            Some(&Position { x: -0.5, y: 2.0 }),
+        );
+        assert_eq!(frame, rect(100.0, 450.0, 600.0, 400.0));
+    }
+
+    #[test]
+    fn remembered_position_is_only_used_when_enabled() {
+        let monitor = rect(0.0, 0.0, 2000.0, 1200.0);
+        let position = Position { x: 0.1, y: 0.2 };
+
+        assert_eq!(
+            panel_frame(&Settings::default(), &monitor, Some(&position)),
+            panel_frame(&Settings::default(), &monitor, None)
+        );
+        assert_eq!(
+            panel_frame(&remembering(), &monitor, Some(&position)),
+            rect(200.0, 240.0, 1200.0, 600.0)
+        );
+    }
+
+    #[test]
+    fn remembered_position_fits_a_smaller_monitor() {
+        let large = rect(0.0, 0.0, 3000.0, 2000.0);
+        let small = rect(3000.0, 0.0, 1280.0, 800.0);
+
+        // Dragged near the bottom right corner of the large monitor.
+        let frame = rect(2500.0, 1500.0, 400.0, 400.0);
+        let position = relative_position(&large, &frame);
+        let frame = panel_frame(&remembering(), &small, Some(&position));
+
+        assert_eq!(frame, rect(3512.0, 400.0, 768.0, 400.0));
+        assert!(frame.x + frame.width <= small.x + small.width);
+        assert!(frame.y + frame.height <= small.y + small.height);
+    }
+
+    #[test]
+    fn relative_position_round_trips() {
+        let monitor = rect(-1440.0, 200.0, 1440.0, 900.0);
+        let frame = rect(-1300.0, 350.0, 864.0, 450.0);
+
+        let position = relative_position(&monitor, &frame);
+
+        assert!((position.x - 140.0 / 1440.0).abs() < 1e-9);
+        assert!((position.y - 150.0 / 900.0).abs() < 1e-9);
+        assert_eq!(
+            panel_frame(&remembering(), &monitor, Some(&position)),
+            frame
+        );
+    }
+}
@@ -5,8 +5,6 @@ use tauri::{AppHandle, Manager, Window, Wry};
 mod monitor;
 mod ns_panel;
 
-use monitor::position_panel;
-
 #[derive(Default)]
 pub struct Store {
     panel: Option<ShareId<ns_panel::NSPanel>>,
@@ -28,10 +26,19 @@ pub fn get_panel(app_handle: AppHandle<Wry>) -> ShareId<ns_panel::NSPanel> {
 
 pub fn init_panel(app_handle: AppHandle<Wry>, window: Window<Wry>) {
     app_handle.state::<State>().0.lock().unwrap().panel = Some(ns_panel::create_panel(&window));
+    monitor::register_screen_change_listener(app_handle);
+}
+
+pub fn position_panel(app_handle: AppHandle<Wry>) {
+    monitor::position_panel(&app_handle, &app_handle.get_window("main").unwrap());
+}
+
+pub fn remember_panel_position(app_handle: AppHandle<Wry>) {
+    monitor::remember_panel_position(&app_handle, &app_handle.get_window("main").unwrap());
 }
 
 pub fn show_panel(app_handle: AppHandle<Wry>) {
-    position_panel(&app_handle.clone().get_window("main").unwrap());
+    position_panel(app_handle.clone());
     get_panel(app_handle).show();
 }
 
@@ -1,13 +1,15 @@
+use block::ConcreteBlock;
 use cocoa::{
     appkit::{CGFloat, NSWindow},
     base::{id, nil, BOOL, NO, YES},
-    foundation::{NSPoint, NSRect},
+    foundation::{NSPoint, NSRect, NSSize},
 };
 use objc::{class, msg_send, sel, sel_impl};
-use tauri::{PhysicalPosition, PhysicalSize, Window, Wry};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, Window, Wry};
+
+use crate::panel::geometry::{self, Rect, Screen};
 
 struct Monitor {
-    #[allow(dead_code)]
     pub name: Option<String>,
     pub size: PhysicalSize<u32>,
     pub position: PhysicalPosition<i32>,
@@ -40,65 +42,204 @@ extern "C" {
     pub fn NSMouseInRect(aPoint: NSPoint, aRect: NSRect, flipped: BOOL) -> BOOL;
 }
 
-fn get_monitor_with_cursor() -> Option<Monitor> {
+fn get_monitor_from_screen(screen: id) -> Monitor {
+    let frame: NSRect = unsafe { msg_send![screen, frame] };
+    let name: id = unsafe { msg_send![screen, localizedName] };
+    let screen_name = nsstring_to_string!(name);
+    let scale_factor: CGFloat = unsafe { msg_send![screen, backingScaleFactor] };
+    let scale_factor: f64 = scale_factor;
+
+    Monitor {
+        name: screen_name,
+        position: PhysicalPosition {
+            x: (frame.origin.x * scale_factor) as i32,
+            y: (frame.origin.y * scale_factor) as i32,
+        },
+        size: PhysicalSize {
+            width: (frame.size.width * scale_factor) as u32,
+            height: (frame.size.height * scale_factor) as u32,
+        },
+        scale_factor,
+    }
+}
+
+fn get_monitor_with_point(point: NSPoint) -> Option<Monitor> {
     objc::rc::autoreleasepool(|| {
-        let mouse_location: NSPoint = unsafe { msg_send![class!(NSEvent), mouseLocation] };
         let screens: id = unsafe { msg_send![class!(NSScreen), screens] };
         let screens_iter: id = unsafe { msg_send![screens, objectEnumerator] };
         let mut next_screen: id;
 
-        let frame_with_cursor: Option<NSRect> = loop {
+        loop {
             next_screen = unsafe { msg_send![screens_iter, nextObject] };
             if next_screen == nil {
                 break None;
             }
 
             let frame: NSRect = unsafe { msg_send![next_screen, frame] };
-            let is_mouse_in_screen_frame: BOOL =
-                unsafe { NSMouseInRect(mouse_location, frame, NO) };
-            if is_mouse_in_screen_frame == YES {
-                break Some(frame);
+            let is_point_in_screen_frame: BOOL = unsafe { NSMouseInRect(point, frame, NO) };
+            if is_point_in_screen_frame == YES {
+                break Some(get_monitor_from_screen(next_screen));
             }
-        };
+        }
+    })
+}
+
+fn get_primary_screen_height() -> f64 {
+    let screens: id = unsafe { msg_send![class!(NSScreen), screens] };
+    let primary_screen: id = unsafe { msg_send![screens, objectAtIndex: 0] };
+    let frame: NSRect = unsafe { msg_send![primary_screen, frame] };
+
+    frame.size.height
+}
+
+fn get_monitor_with_cursor() -> Option<Monitor> {
+    let mouse_location: NSPoint = unsafe { msg_send![class!(NSEvent), mouseLocation] };
 
-        if let Some(frame) = frame_with_cursor {
-            let name: id = unsafe { msg_send![next_screen, localizedName] };
-            let screen_name = nsstring_to_string!(name);
-            let scale_factor: CGFloat = unsafe { msg_send![next_screen, backingScaleFactor] };
-            let scale_factor: f64 = scale_factor;
-
-            return Some(Monitor {
-                name: screen_name,
-                position: PhysicalPosition {
-                    x: (frame.origin.x * scale_factor) as i32,
-                    y: (frame.origin.y * scale_factor) as i32,
-                },
-                size: PhysicalSize {
-                    width: (frame.size.width * scale_factor) as u32,
-                    height: (frame.size.height * scale_factor) as u32,
-                },
-                scale_factor,
-            });
+    get_monitor_with_point(mouse_location)
+}
+
+fn get_monitor_with_focused_window() -> Option<Monitor> {
+    let pid = crate::util::macos::get_frontmost_application_pid()?;
+    let window = crate::util::macos::get_focused_window(pid)?;
+    let frame = crate::util::macos::get_window_frame(&window)?;
+
+    // Accessibility frames have a top-left origin while screen frames are flipped.
+    let center = NSPoint {
+        x: frame.origin.x + frame.size.width * 0.5,
+        y: get_primary_screen_height() - (frame.origin.y + frame.size.height * 0.5),
+    };
+
+    get_monitor_with_point(center)
+}
+
+fn get_primary_monitor() -> Option<Monitor> {
+    objc::rc::autoreleasepool(|| {
+        let screens: id = unsafe { msg_send![class!(NSScreen), screens] };
+        let count: usize = unsafe { msg_send![screens, count] };
+
+        if count == 0 {
+            return None;
         }
 
-        None
+        let primary_screen: id = unsafe { msg_send![screens, objectAtIndex: 0] };
+
+        Some(get_monitor_from_screen(primary_screen))
     })
 }
 
-pub fn position_panel(window: &Window<Wry>) {
-    if let Some(monitor) = get_monitor_with_cursor() {
-        let display_size = monitor.size.to_logical::<f64>(monitor.scale_factor);
-        let display_pos = monitor.position.to_logical::<f64>(monitor.scale_factor);
+fn get_monitor(screen: Screen) -> Option<Monitor> {
+    match screen {
+        Screen::Cursor => get_monitor_with_cursor(),
+        Screen::FocusedWindow => get_monitor_with_focused_window().or_else(get_monitor_with_cursor),
+        Screen::Primary => get_primary_monitor(),
+    }
+}
 
-        let handle: id = window.ns_window().unwrap() as _;
-        let win_frame: NSRect = unsafe { handle.frame() };
-        let rect = NSRect {
+impl Monitor {
+    fn key(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            format!(
+                "{},{},{}x{}",
+                self.position.x, self.position.y, self.size.width, self.size.height
+            )
+        })
+    }
+
+    /// Monitor bounds in physical pixels with a top-left origin.
+    fn rect(&self) -> Rect {
+        let primary_screen_height = get_primary_screen_height() * self.scale_factor;
+
+        Rect {
+            x: self.position.x as f64,
+            y: primary_screen_height - self.position.y as f64 - self.size.height as f64,
+            width: self.size.width as f64,
+            height: self.size.height as f64,
+        }
+    }
+
+    fn to_ns_rect(&self, rect: &Rect) -> NSRect {
+        let primary_screen_height = get_primary_screen_height();
+        let width = rect.width / self.scale_factor;
+        let height = rect.height / self.scale_factor;
+
+        NSRect {
             origin: NSPoint {
-                x: (display_pos.x + (display_size.width * 0.5)) - (win_frame.size.width * 0.5),
-                y: (display_pos.y + (display_size.height * 0.6)) - (win_frame.size.height * 0.5),
+                x: rect.x / self.scale_factor,
+                y: primary_screen_height - rect.y / self.scale_factor - height,
             },
-            size: win_frame.size,
-        };
+            size: NSSize { width, height },
+        }
+    }
+
+    fn from_ns_rect(&self, rect: &NSRect) -> Rect {
+        let primary_screen_height = get_primary_screen_height();
+
+        Rect {
+            x: rect.origin.x * self.scale_factor,
+            y: (primary_screen_height - rect.origin.y - rect.size.height) * self.scale_factor,
+            width: rect.size.width * self.scale_factor,
+            height: rect.size.height * self.scale_factor,
+        }
+    }
+}
+
+pub fn position_panel(app_handle: &AppHandle<Wry>, window: &Window<Wry>) {
+    let settings = geometry::get_settings(app_handle);
+
+    if let Some(monitor) = get_monitor(settings.screen) {
+        let frame = geometry::frame_for_monitor(app_handle, &monitor.key(), &monitor.rect());
+
+        let handle: id = window.ns_window().unwrap() as _;
+        let rect = monitor.to_ns_rect(&frame);
         let _: () = unsafe { msg_send![handle, setFrame: rect display: YES] };
     }
 }
+
+pub fn remember_panel_position(app_handle: &AppHandle<Wry>, window: &Window<Wry>) {
+    let handle: id = window.ns_window().unwrap() as _;
+    let win_frame: NSRect = unsafe { handle.frame() };
+
+    let center = NSPoint {
+        x: win_frame.origin.x + win_frame.size.width * 0.5,
+        y: win_frame.origin.y + win_frame.size.height * 0.5,
+    };
+
+    if let Some(monitor) = get_monitor_with_point(center) {
+        geometry::remember_position(
+            app_handle,
+            &monitor.key(),
+            &monitor.rect(),
+            &monitor.from_ns_rect(&win_frame),
+        );
+    }
+}
+
+/// Repositions the panel when displays are connected, disconnected or change resolution.
+pub fn register_screen_change_listener(app_handle: AppHandle<Wry>) {
+    unsafe {
+        let notification_center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+
+        let did_change_screen_parameters_notification: id = msg_send![
+            class!(NSString),
+            stringWithUTF8String: b"NSApplicationDidChangeScreenParametersNotification\0".as_ptr() as *const _
+        ];
+
+        let block = ConcreteBlock::new(move |_notification: id| {
+            if let Some(window) = app_handle.get_window("main") {
+                if window.is_visible().unwrap_or(false) {
+                    position_panel(&app_handle, &window);
+                }
+            }
+        });
+
+        let block = block.copy();
+
+        let _: () = msg_send![
+            notification_center,
+            addObserverForName: did_change_screen_parameters_notification
+            object: nil
+            queue: nil
+            usingBlock: block
+        ];
+    }
+}
@@ -1,5 +1,14 @@
 use std::sync::Mutex;
-use tauri::{AppHandle, Manager, Window, Wry};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Window, Wry};
+use windows_sys::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
+    UI::{
+        Shell::{DefSubclassProc, SetWindowSubclass},
+        WindowsAndMessaging::{GetCursorPos, GetForegroundWindow, GetWindowRect, WM_DISPLAYCHANGE},
+    },
+};
+
+use crate::panel::geometry::{self, Rect, Screen};
 
 #[derive(Default)]
 pub struct Store {
@@ -13,12 +22,189 @@ pub fn get_panel(app_handle: AppHandle<Wry>) -> Window {
     app_handle.get_window("main").unwrap()
 }
 
+const DISPLAY_CHANGE_SUBCLASS_ID: usize = 1;
+
 pub fn init_panel(app_handle: AppHandle<Wry>, _window: Window<Wry>) {
-    app_handle.state::<State>().0.lock().unwrap().panel =
-        Some(app_handle.get_window("main").unwrap());
+    let panel = app_handle.get_window("main").unwrap();
+
+    register_display_change_listener(app_handle.clone(), &panel);
+    app_handle.state::<State>().0.lock().unwrap().panel = Some(panel);
+}
+
+unsafe extern "system" fn display_change_subclass_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    _subclass_id: usize,
+    reference_data: usize,
+) -> LRESULT {
+    if message == WM_DISPLAYCHANGE {
+        let app_handle = &*(reference_data as *const AppHandle<Wry>);
+
+        if get_panel(app_handle.clone()).is_visible().unwrap_or(false) {
+            position_panel(app_handle.clone());
+        }
+    }
+
+    DefSubclassProc(hwnd, message, wparam, lparam)
+}
+
+/// Repositions the panel when displays are connected, disconnected or change resolution.
+fn register_display_change_listener(app_handle: AppHandle<Wry>, panel: &Window<Wry>) {
+    let hwnd = match panel.hwnd() {
+        Ok(hwnd) => hwnd.0 as HWND,
+        Err(_) => return,
+    };
+
+    // The subclass lives as long as the panel, which is never destroyed.
+    let reference_data = Box::into_raw(Box::new(app_handle)) as usize;
+
+    unsafe {
+        SetWindowSubclass(
+            hwnd,
+            Some(display_change_subclass_proc),
+            DISPLAY_CHANGE_SUBCLASS_ID,
+            reference_data,
+        );
+    }
+}
+
+fn get_monitor_key(monitor: &Monitor) -> String {
+    monitor.name().cloned().unwrap_or_else(|| {
+        format!(
+            "{},{},{}x{}",
+            monitor.position().x,
+            monitor.position().y,
+            monitor.size().width,
+            monitor.size().height
+        )
+    })
+}
+
+fn get_monitor_rect(monitor: &Monitor) -> Rect {
+    Rect {
+        x: monitor.position().x as f64,
+        y: monitor.position().y as f64,
+        width: monitor.size().width as f64,
+        height: monitor.size().height as f64,
+    }
+}
+
+fn get_monitor_with_point(window: &Window<Wry>, x: f64, y: f64) -> Option<Monitor> {
+    window
+        .available_monitors()
+        .ok()?
+        .into_iter()
+        .find(|monitor| get_monitor_rect(monitor).contains(x, y))
+}
+
+fn get_monitor_with_cursor(window: &Window<Wry>) -> Option<Monitor> {
+    let mut point = POINT { x: 0, y: 0 };
+
+    if unsafe { GetCursorPos(&mut point) } == 0 {
+        return None;
+    }
+
+    get_monitor_with_point(window, point.x as f64, point.y as f64)
+}
+
+fn get_monitor_with_focused_window(window: &Window<Wry>) -> Option<Monitor> {
+    let foreground_window = unsafe { GetForegroundWindow() };
+
+    if foreground_window == 0 {
+        return None;
+    }
+
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+
+    if unsafe { GetWindowRect(foreground_window, &mut rect) } == 0 {
+        return None;
+    }
+
+    get_monitor_with_point(
+        window,
+        (rect.left + rect.right) as f64 / 2.0,
+        (rect.top + rect.bottom) as f64 / 2.0,
+    )
+}
+
+fn get_monitor(window: &Window<Wry>, screen: Screen) -> Option<Monitor> {
+    let monitor = match screen {
+        Screen::Cursor => get_monitor_with_cursor(window),
+        Screen::FocusedWindow => {
+            get_monitor_with_focused_window(window).or_else(|| get_monitor_with_cursor(window))
+        }
+        Screen::Primary => window.primary_monitor().ok().flatten(),
+    };
+
+    monitor.or_else(|| window.current_monitor().ok().flatten())
+}
+
+pub fn position_panel(app_handle: AppHandle<Wry>) {
+    let panel = get_panel(app_handle.clone());
+    let settings = geometry::get_settings(&app_handle);
+
+    if let Some(monitor) = get_monitor(&panel, settings.screen) {
+        let frame = geometry::frame_for_monitor(
+            &app_handle,
+            &get_monitor_key(&monitor),
+            &get_monitor_rect(&monitor),
+        );
+
+        panel
+            .set_size(PhysicalSize {
+                width: frame.width as u32,
+                height: frame.height as u32,
+            })
+            .unwrap_or_default();
+
+        panel
+            .set_position(PhysicalPosition {
+                x: frame.x as i32,
+                y: frame.y as i32,
+            })
+            .unwrap_or_default();
+    }
+}
+
+pub fn remember_panel_position(app_handle: AppHandle<Wry>) {
+    let panel = get_panel(app_handle.clone());
+
+    let (position, size) = match (panel.outer_position(), panel.outer_size()) {
+        (Ok(position), Ok(size)) => (position, size),
+        _ => return,
+    };
+
+    let frame = Rect {
+        x: position.x as f64,
+        y: position.y as f64,
+        width: size.width as f64,
+        height: size.height as f64,
+    };
+
+    if let Some(monitor) = get_monitor_with_point(
+        &panel,
+        frame.x + frame.width / 2.0,
+        frame.y + frame.height / 2.0,
+    ) {
+        geometry::remember_position(
+            &app_handle,
+            &get_monitor_key(&monitor),
+            &get_monitor_rect(&monitor),
+            &frame,
+        );
+    }
 }
 
 pub fn show_panel(app_handle: AppHandle<Wry>) {
+    position_panel(app_handle.clone());
+
     let panel = get_panel(app_handle.clone());
 
     panel.show().unwrap();
@@ -35,6 +221,10 @@ pub fn toggle_panel(app_handle: AppHandle<Wry>) {
     if panel.is_visible().unwrap() {
         hide_panel(app_handle);
     } else {
-        show_panel(app_handle);
+        tauri::async_runtime::spawn(async move {
+            crate::command::application::windows::capture_focused_application(app_handle.clone())
+                .await;
+            show_panel(app_handle);
+        });
     }
 }
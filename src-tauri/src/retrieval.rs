@@ -0,0 +1,479 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::{database, document, usage};
+
+mod embedding;
+#[cfg(feature = "local-embeddings")]
+mod local;
+
+/// Characters per indexed chunk, roughly 400 tokens.
+const CHUNK_LENGTH: usize = 1600;
+
+/// Chunks re-embedded per round when the embedding model changes.
+const REINDEX_BATCH_SIZE: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// An OpenAI-compatible embeddings endpoint.
+    Remote,
+    /// An ONNX sentence-transformer run on the CPU.
+    Local,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// A local file; the source id is its path.
+    Document,
+    /// A chat thread; the source id is the thread id.
+    Thread,
+}
+
+impl SourceKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SourceKind::Document => "document",
+            SourceKind::Thread => "thread",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub enabled: bool,
+    pub backend: Backend,
+    pub remote_url: String,
+    pub remote_model: String,
+    /// Name of the stored secret sent as a bearer token, if any.
+    pub remote_api_key_secret: String,
+    /// Directory with `model.onnx` and `vocab.txt`.
+    pub local_model_dir: Option<PathBuf>,
+    pub top_k: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: Backend::Remote,
+            remote_url: "https://api.openai.com/v1/embeddings".to_string(),
+            remote_model: "text-embedding-ada-002".to_string(),
+            remote_api_key_secret: "openai_api_key".to_string(),
+            local_model_dir: None,
+            top_k: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThreadMessage {
+    author: String,
+    content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hit {
+    source_kind: String,
+    source_id: String,
+    title: String,
+    chunk_index: usize,
+    text: String,
+    score: f32,
+}
+
+#[derive(Default)]
+pub struct Store {
+    /// The loaded local model with its `embedding::model_id`, which changes when the model
+    /// file is replaced.
+    #[cfg(feature = "local-embeddings")]
+    local_model: Option<(String, std::sync::Arc<local::Model>)>,
+}
+
+#[derive(Default)]
+#[cfg_attr(not(feature = "local-embeddings"), allow(dead_code))]
+pub struct State(pub Mutex<Store>);
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("retrieval")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn replace_chunks(
+    connection: &mut Connection,
+    kind: SourceKind,
+    id: &str,
+    title: &str,
+    model: &str,
+    chunks: &[(document::Chunk, Vec<f32>)],
+) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+
+    transaction.execute(
+        "DELETE FROM retrieval_chunks WHERE source_kind = ?1 AND source_id = ?2",
+        params![kind.as_str(), id],
+    )?;
+
+    for (chunk, vector) in chunks {
+        transaction.execute(
+            "INSERT INTO retrieval_chunks
+             (source_kind, source_id, title, chunk_index, text, model, vector, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                kind.as_str(),
+                id,
+                title,
+                chunk.index,
+                chunk.text,
+                model,
+                embedding::encode(vector),
+                usage::now()
+            ],
+        )?;
+    }
+
+    transaction.commit()
+}
+
+fn query_vectors(connection: &Connection, model: &str) -> rusqlite::Result<Vec<(Hit, Vec<f32>)>> {
+    let mut statement = connection.prepare(
+        "SELECT source_kind, source_id, title, chunk_index, text, vector FROM retrieval_chunks
+         WHERE model = ?1",
+    )?;
+
+    let rows = statement
+        .query_map(params![model], |row| {
+            let vector: Vec<u8> = row.get(5)?;
+
+            Ok((
+                Hit {
+                    source_kind: row.get(0)?,
+                    source_id: row.get(1)?,
+                    title: row.get(2)?,
+                    chunk_index: row.get(3)?,
+                    text: row.get(4)?,
+                    score: 0.0,
+                },
+                embedding::decode(&vector),
+            ))
+        })?
+        .collect();
+
+    rows
+}
+
+fn query_stale_chunks(
+    connection: &Connection,
+    model: &str,
+    limit: usize,
+) -> rusqlite::Result<Vec<(i64, String)>> {
+    let mut statement = connection
+        .prepare("SELECT id, text FROM retrieval_chunks WHERE model != ?1 ORDER BY id LIMIT ?2")?;
+
+    let rows = statement
+        .query_map(params![model, limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect();
+
+    rows
+}
+
+/// Scores the chunks stored under `model` against `query_vector` and keeps the `top_k` best.
+fn rank_chunks(
+    connection: &Connection,
+    model: &str,
+    query_vector: &[f32],
+    top_k: usize,
+) -> rusqlite::Result<Vec<Hit>> {
+    let mut hits = query_vectors(connection, model)?
+        .into_iter()
+        .map(|(mut hit, vector)| {
+            hit.score = embedding::cosine_similarity(query_vector, &vector);
+            hit
+        })
+        .collect::<Vec<_>>();
+
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    hits.truncate(top_k);
+
+    Ok(hits)
+}
+
+/// Chunks and embeds `text`, replacing anything previously indexed for the same source.
+pub async fn index_source(
+    app_handle: &AppHandle<Wry>,
+    kind: SourceKind,
+    id: &str,
+    title: &str,
+    text: &str,
+) -> Result<usize, String> {
+    let settings = get_settings(app_handle);
+
+    if !settings.enabled {
+        return Ok(0);
+    }
+
+    let chunks = document::chunk_text(text, CHUNK_LENGTH);
+    let texts = chunks
+        .iter()
+        .map(|chunk| chunk.text.clone())
+        .collect::<Vec<_>>();
+
+    let vectors = embedding::embed(app_handle, &settings, &texts).await?;
+    let model = embedding::model_id(&settings);
+    let chunks = chunks.into_iter().zip(vectors).collect::<Vec<_>>();
+
+    database::with_connection(app_handle, |connection| {
+        replace_chunks(connection, kind, id, title, &model, &chunks)
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(chunks.len())
+}
+
+pub fn remove_source(app_handle: &AppHandle<Wry>, kind: SourceKind, id: &str) {
+    database::with_connection(app_handle, |connection| {
+        connection.execute(
+            "DELETE FROM retrieval_chunks WHERE source_kind = ?1 AND source_id = ?2",
+            params![kind.as_str(), id],
+        )
+    })
+    .unwrap_or_default();
+}
+
+/// Returns the `top_k` stored chunks most similar to `query` under the current embedding model.
+pub async fn search(
+    app_handle: &AppHandle<Wry>,
+    query: &str,
+    top_k: Option<usize>,
+) -> Result<Vec<Hit>, String> {
+    let settings = get_settings(app_handle);
+
+    if !settings.enabled || query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let query_vector = embedding::embed(app_handle, &settings, &[query.to_string()])
+        .await?
+        .pop()
+        .unwrap_or_default();
+
+    let model = embedding::model_id(&settings);
+    let top_k = top_k.unwrap_or(settings.top_k);
+
+    database::with_connection(app_handle, |connection| {
+        rank_chunks(connection, &model, &query_vector, top_k)
+    })
+    .map_err(|error| error.to_string())
+}
+
+/// Re-embeds chunks stored under a different model, e.g. after switching backends.
+async fn reindex(app_handle: AppHandle<Wry>) -> Result<(), String> {
+    let settings = get_settings(&app_handle);
+
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let model = embedding::model_id(&settings);
+
+    loop {
+        let rows = database::with_connection(&app_handle, |connection| {
+            query_stale_chunks(connection, &model, REINDEX_BATCH_SIZE)
+        })
+        .map_err(|error| error.to_string())?;
+
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let texts = rows
+            .iter()
+            .map(|(_, text)| text.clone())
+            .collect::<Vec<_>>();
+        let vectors = embedding::embed(&app_handle, &settings, &texts).await?;
+
+        database::with_connection(&app_handle, |connection| {
+            let transaction = connection.transaction()?;
+
+            for ((id, _), vector) in rows.iter().zip(&vectors) {
+                transaction.execute(
+                    "UPDATE retrieval_chunks SET model = ?1, vector = ?2 WHERE id = ?3",
+                    params![model, embedding::encode(vector), id],
+                )?;
+            }
+
+            transaction.commit()
+        })
+        .map_err(|error| error.to_string())?;
+    }
+}
+
+pub fn set_settings(app_handle: &AppHandle<Wry>, settings: Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("retrieval".to_string(), serde_json::json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        reindex(app_handle).await.unwrap_or_default();
+    });
+}
+
+#[tauri::command]
+pub fn get_retrieval_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_retrieval_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    set_settings(&app_handle, settings);
+}
+
+#[tauri::command]
+pub async fn index_document(app_handle: AppHandle<Wry>, path: String) -> Result<usize, String> {
+    let extract_path = PathBuf::from(&path);
+    let text = tauri::async_runtime::spawn_blocking(move || {
+        let format = document::Format::from_path(&extract_path)
+            .ok_or_else(|| "unsupported file type".to_string())?;
+
+        document::extract_text(&extract_path, format)
+    })
+    .await
+    .map_err(|error| error.to_string())??;
+
+    let title = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+
+    index_source(&app_handle, SourceKind::Document, &path, &title, &text).await
+}
+
+#[tauri::command]
+pub async fn index_thread(
+    app_handle: AppHandle<Wry>,
+    id: String,
+    title: String,
+    messages: Vec<ThreadMessage>,
+) -> Result<usize, String> {
+    let text = messages
+        .iter()
+        .map(|message| format!("{}: {}", message.author, message.content.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    index_source(&app_handle, SourceKind::Thread, &id, &title, &text).await
+}
+
+#[tauri::command]
+pub fn remove_from_index(app_handle: AppHandle<Wry>, source_kind: SourceKind, source_id: String) {
+    remove_source(&app_handle, source_kind, &source_id);
+}
+
+#[tauri::command]
+pub async fn search_index(
+    app_handle: AppHandle<Wry>,
+    query: String,
+    top_k: Option<usize>,
+) -> Result<String, String> {
+    let hits = search(&app_handle, &query, top_k).await?;
+    Ok(serde_json::to_string(&hits).expect("error serializing json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds text as its counts of `a`, `b` and `c`, standing in for a model.
+    fn embed(text: &str) -> Vec<f32> {
+        ['a', 'b', 'c']
+            .iter()
+            .map(|letter| text.matches(*letter).count() as f32)
+            .collect()
+    }
+
+    fn index(connection: &mut Connection, id: &str, model: &str, texts: &[&str]) {
+        let chunks = texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| {
+                (
+                    document::Chunk {
+                        index,
+                        text: text.to_string(),
+                    },
+                    embed(text),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        replace_chunks(connection, SourceKind::Document, id, id, model, &chunks).unwrap();
+    }
+
+    fn ranked(connection: &Connection, model: &str, query: &str, top_k: usize) -> Vec<String> {
+        rank_chunks(connection, model, &embed(query), top_k)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.text)
+            .collect()
+    }
+
+    #[test]
+    fn search_returns_the_most_similar_chunks_first() {
+        let mut connection = database::open_in_memory();
+
+        index(
+            &mut connection,
+            "letters.txt",
+            "stub",
+            &["aaa", "abc", "bbb"],
+        );
+        index(&mut connection, "more.txt", "stub", &["aab", "ccc"]);
+        index(&mut connection, "other.txt", "other", &["aaa"]);
+
+        assert_eq!(
+            ranked(&connection, "stub", "a", 3),
+            vec!["aaa", "aab", "abc"]
+        );
+        assert_eq!(ranked(&connection, "stub", "cc", 1), vec!["ccc"]);
+        assert_eq!(ranked(&connection, "other", "b", 5), vec!["aaa"]);
+        assert!(ranked(&connection, "missing", "a", 5).is_empty());
+    }
+
+    #[test]
+    fn indexing_a_source_again_replaces_its_chunks() {
+        let mut connection = database::open_in_memory();
+
+        index(&mut connection, "letters.txt", "stub", &["aaa", "bbb"]);
+        index(&mut connection, "letters.txt", "stub", &["ccc"]);
+
+        assert_eq!(ranked(&connection, "stub", "a", 5), vec!["ccc"]);
+    }
+}
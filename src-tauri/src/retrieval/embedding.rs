@@ -0,0 +1,256 @@
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::Deserialize;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Wry};
+
+use super::{Backend, Settings};
+
+/// Inputs sent per request to the remote provider.
+const REMOTE_BATCH_SIZE: usize = 64;
+
+#[derive(Deserialize)]
+struct RemoteEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct RemoteResponse {
+    data: Vec<RemoteEmbedding>,
+}
+
+/// Identifies the vectors produced by the configured backend. Vectors from different models are
+/// not comparable, so stored chunks are tagged with it and re-embedded when it changes. A local
+/// model is also identified by when `model.onnx` was last modified, so replacing it in the same
+/// directory counts as a new model.
+pub fn model_id(settings: &Settings) -> String {
+    match settings.backend {
+        Backend::Remote => format!("remote:{}", settings.remote_model),
+        Backend::Local => {
+            let model_dir = match &settings.local_model_dir {
+                Some(model_dir) => model_dir,
+                None => return "local:".to_string(),
+            };
+
+            let name = model_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let modified = std::fs::metadata(model_dir.join("model.onnx"))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+
+            format!("local:{}@{}", name, modified)
+        }
+    }
+}
+
+/// Calls an OpenAI-compatible `/embeddings` endpoint, authenticating with the stored secret.
+async fn embed_remote(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let client = Client::new();
+    let mut vectors = Vec::with_capacity(texts.len());
+
+    for batch in texts.chunks(REMOTE_BATCH_SIZE) {
+        let body = serde_json::json!({
+            "model": settings.remote_model,
+            "input": batch,
+        });
+
+        let mut request = client
+            .post(&settings.remote_url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+
+        if !settings.remote_api_key_secret.is_empty() {
+            let api_key = crate::secrets::get_secret(
+                app_handle,
+                &settings.remote_api_key_secret,
+                &settings.remote_url,
+            )
+            .map_err(|error| error.to_string())?;
+
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await.map_err(|error| error.to_string())?;
+        let status = response.status();
+        let text = response.text().await.map_err(|error| error.to_string())?;
+
+        if !status.is_success() {
+            return Err(format!(
+                "embedding request failed with {}: {}",
+                status, text
+            ));
+        }
+
+        let mut response: RemoteResponse =
+            serde_json::from_str(&text).map_err(|error| error.to_string())?;
+
+        if response.data.len() != batch.len() {
+            return Err("embedding response does not match the request".to_string());
+        }
+
+        response.data.sort_by_key(|embedding| embedding.index);
+        vectors.extend(
+            response
+                .data
+                .into_iter()
+                .map(|embedding| embedding.embedding),
+        );
+    }
+
+    Ok(vectors)
+}
+
+#[cfg(feature = "local-embeddings")]
+async fn embed_local(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    use tauri::Manager;
+
+    let model_dir = settings
+        .local_model_dir
+        .clone()
+        .ok_or_else(|| "no local embedding model is configured".to_string())?;
+
+    let model_id = model_id(settings);
+
+    let loaded_model = app_handle
+        .state::<super::State>()
+        .0
+        .lock()
+        .unwrap()
+        .local_model
+        .as_ref()
+        .filter(|(loaded_model_id, _)| *loaded_model_id == model_id)
+        .map(|(_, model)| model.clone());
+
+    // Loading the ONNX model takes a while, so it happens outside the lock.
+    let model = match loaded_model {
+        Some(model) => model,
+        None => {
+            let model =
+                tauri::async_runtime::spawn_blocking(move || super::local::Model::load(&model_dir))
+                    .await
+                    .map_err(|error| error.to_string())??;
+
+            let model = std::sync::Arc::new(model);
+            app_handle
+                .state::<super::State>()
+                .0
+                .lock()
+                .unwrap()
+                .local_model = Some((model_id, model.clone()));
+            model
+        }
+    };
+
+    let texts = texts.to_vec();
+
+    tauri::async_runtime::spawn_blocking(move || model.embed(&texts))
+        .await
+        .map_err(|error| error.to_string())?
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+async fn embed_local(
+    _app_handle: &AppHandle<Wry>,
+    _settings: &Settings,
+    _texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    Err("Lander was built without the `local-embeddings` feature".to_string())
+}
+
+pub async fn embed(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    if texts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    match settings.backend {
+        Backend::Remote => embed_remote(app_handle, settings, texts).await,
+        Backend::Local => embed_local(app_handle, settings, texts).await,
+    }
+}
+
+pub fn encode(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+pub fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot = a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>();
+    let norm_a = a.iter().map(|value| value * value).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|value| value * value).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_survive_encoding() {
+        let vector = vec![0.0, -1.5, 3.25, f32::MAX, f32::MIN_POSITIVE, -0.0];
+        let bytes = encode(&vector);
+
+        assert_eq!(bytes.len(), vector.len() * 4);
+        assert_eq!(decode(&bytes), vector);
+        assert_eq!(&bytes[4..8], &(-1.5f32).to_le_bytes());
+    }
+
+    #[test]
+    fn decoding_ignores_trailing_bytes() {
+        let mut bytes = encode(&[1.0, 2.0]);
+        bytes.push(7);
+
+        assert_eq!(decode(&bytes), vec![1.0, 2.0]);
+        assert!(decode(&[]).is_empty());
+    }
+
+    #[test]
+    fn cosine_similarity_compares_directions() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 0.0], &[-3.0, 0.0]) + 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 5.0]).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 1.0], &[1.0, 0.0]) - 0.5f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_similarity_of_mismatched_or_zero_vectors_is_zero() {
+        assert_eq!(cosine_similarity(&[1.0, 2.0], &[1.0, 2.0, 3.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+    }
+}
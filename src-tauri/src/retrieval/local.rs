@@ -0,0 +1,209 @@
+use std::{collections::HashMap, fs, path::Path};
+use tract_onnx::prelude::*;
+
+/// Sentence-transformer models are trained on short passages; longer inputs are truncated.
+const MAX_TOKENS: usize = 256;
+
+/// Words longer than this are mapped to `[UNK]` instead of being split into pieces.
+const MAX_WORD_LENGTH: usize = 100;
+
+type Plan = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
+
+/// BERT-style encoder (e.g. all-MiniLM-L6-v2) exported to ONNX, run on the CPU and mean-pooled
+/// into one normalized vector per input.
+pub struct Model {
+    plan: Plan,
+    vocabulary: HashMap<String, i64>,
+    unknown_id: i64,
+    classification_id: i64,
+    separator_id: i64,
+}
+
+fn is_punctuation(character: char) -> bool {
+    character.is_ascii_punctuation() || (!character.is_alphanumeric() && !character.is_whitespace())
+}
+
+/// Lowercases and splits on whitespace and punctuation, as BERT's uncased basic tokenizer does.
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+
+    for character in text.chars().flat_map(char::to_lowercase) {
+        if character.is_whitespace() || character.is_control() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        } else if is_punctuation(character) {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+
+            words.push(character.to_string());
+        } else {
+            word.push(character);
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+impl Model {
+    /// Loads `model.onnx` and `vocab.txt` from `model_dir`.
+    pub fn load(model_dir: &Path) -> Result<Self, String> {
+        let vocabulary = fs::read_to_string(model_dir.join("vocab.txt"))
+            .map_err(|error| format!("failed to read vocab.txt: {}", error))?
+            .lines()
+            .enumerate()
+            .map(|(index, token)| (token.to_string(), index as i64))
+            .collect::<HashMap<_, _>>();
+
+        let get_id = |token: &str| {
+            vocabulary
+                .get(token)
+                .copied()
+                .ok_or_else(|| format!("vocab.txt is missing `{}`", token))
+        };
+
+        let unknown_id = get_id("[UNK]")?;
+        let classification_id = get_id("[CLS]")?;
+        let separator_id = get_id("[SEP]")?;
+
+        let plan = tract_onnx::onnx()
+            .model_for_path(model_dir.join("model.onnx"))
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|error| format!("failed to load model.onnx: {}", error))?;
+
+        Ok(Self {
+            plan,
+            vocabulary,
+            unknown_id,
+            classification_id,
+            separator_id,
+        })
+    }
+
+    /// Greedy longest-match-first WordPiece.
+    fn push_word_pieces(&self, word: &str, ids: &mut Vec<i64>) {
+        let characters = word.chars().collect::<Vec<_>>();
+
+        if characters.len() > MAX_WORD_LENGTH {
+            ids.push(self.unknown_id);
+            return;
+        }
+
+        let mut pieces = vec![];
+        let mut start = 0;
+
+        while start < characters.len() {
+            let piece = (start + 1..=characters.len()).rev().find_map(|end| {
+                let piece = characters[start..end].iter().collect::<String>();
+                let piece = if start > 0 {
+                    format!("##{}", piece)
+                } else {
+                    piece
+                };
+
+                self.vocabulary.get(&piece).map(|id| (*id, end))
+            });
+
+            match piece {
+                Some((id, end)) => {
+                    pieces.push(id);
+                    start = end;
+                }
+                None => {
+                    ids.push(self.unknown_id);
+                    return;
+                }
+            }
+        }
+
+        ids.extend(pieces);
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<i64> {
+        let mut ids = vec![self.classification_id];
+
+        for word in split_words(text) {
+            self.push_word_pieces(&word, &mut ids);
+        }
+
+        ids.truncate(MAX_TOKENS - 1);
+        ids.push(self.separator_id);
+
+        ids
+    }
+
+    fn embed_one(&self, text: &str) -> TractResult<Vec<f32>> {
+        let ids = self.tokenize(text);
+        let length = ids.len();
+
+        let input_ids = tract_ndarray::Array2::from_shape_vec((1, length), ids)?;
+        let attention_mask = tract_ndarray::Array2::<i64>::ones((1, length));
+        let token_type_ids = tract_ndarray::Array2::<i64>::zeros((1, length));
+
+        let inputs: TVec<TValue> = tvec!(
+            input_ids.into_tensor().into(),
+            attention_mask.into_tensor().into(),
+            token_type_ids.into_tensor().into()
+        )
+        .into_iter()
+        .take(self.plan.model().inputs.len())
+        .collect();
+
+        let outputs = self.plan.run(inputs)?;
+
+        // Token embeddings are [1, length, dimensions]; every token is attended to, so mean
+        // pooling is a plain average.
+        let token_embeddings = outputs[0].to_array_view::<f32>()?;
+        let dimensions = token_embeddings.shape()[2];
+
+        let mut vector = vec![0.0; dimensions];
+
+        for token in 0..length {
+            for (dimension, value) in vector.iter_mut().enumerate() {
+                *value += token_embeddings[[0, token, dimension]] / length as f32;
+            }
+        }
+
+        let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|value| *value /= norm);
+        }
+
+        Ok(vector)
+    }
+
+    pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        texts
+            .iter()
+            .map(|text| self.embed_one(text).map_err(|error| error.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_lowercased_and_split_at_punctuation() {
+        assert_eq!(
+            split_words("Hello, World! It's  e.g.\tfine"),
+            vec!["hello", ",", "world", "!", "it", "'", "s", "e", ".", "g", ".", "fine"]
+        );
+    }
+
+    #[test]
+    fn symbols_are_words_of_their_own() {
+        assert_eq!(split_words("50% €5"), vec!["50", "%", "€", "5"]);
+        assert_eq!(split_words("Größe naïve"), vec!["größe", "naïve"]);
+        assert!(split_words(" \n\u{0}").is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::{cmp::Ordering, collections::HashMap};
+use tauri::{AppHandle, Wry};
+
+use crate::command::application::{self, Application};
+
+mod frecency;
+pub mod fuzzy;
+
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ApplicationMatch {
+    application: Application,
+    score: f64,
+    /// The name, alias or keyword the query matched against.
+    matched_text: String,
+    highlights: Vec<(usize, usize)>,
+}
+
+/// Boost added on top of the match score. Logarithmic so that frequently launched apps win ties
+/// between similar matches without burying a better textual match.
+fn frecency_boost(frecency: f64) -> f64 {
+    40.0 * (1.0 + frecency).ln()
+}
+
+fn match_application(application: &Application, query: &str) -> Option<(String, fuzzy::Match)> {
+    application
+        .search_terms()
+        .into_iter()
+        .filter_map(|term| fuzzy::fuzzy_match(term, query).map(|m| (term.to_string(), m)))
+        .max_by(|(_, a), (_, b)| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal))
+}
+
+pub fn rank_applications(
+    applications: Vec<Application>,
+    query: &str,
+    frecencies: &HashMap<String, f64>,
+    limit: usize,
+) -> Vec<ApplicationMatch> {
+    let mut matches = applications
+        .into_iter()
+        .filter_map(|application| {
+            let boost = frecency_boost(*frecencies.get(application.id()).unwrap_or(&0.0));
+
+            if query.trim().is_empty() {
+                return Some(ApplicationMatch {
+                    matched_text: application.name().to_string(),
+                    score: boost,
+                    highlights: vec![],
+                    application,
+                });
+            }
+
+            let (matched_text, fuzzy_match) = match_application(&application, query)?;
+
+            Some(ApplicationMatch {
+                matched_text,
+                score: fuzzy_match.score + boost,
+                highlights: fuzzy_match.highlights,
+                application,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                a.application
+                    .name()
+                    .to_lowercase()
+                    .cmp(&b.application.name().to_lowercase())
+            })
+    });
+
+    matches.truncate(limit);
+
+    matches
+}
+
+#[tauri::command]
+pub async fn query_applications(
+    app_handle: AppHandle<Wry>,
+    query: String,
+    limit: Option<usize>,
+) -> String {
+    let applications = application::get_installed_applications(app_handle.clone()).await;
+    let frecencies = frecency::get_frecencies(&app_handle);
+
+    let matches = rank_applications(
+        applications,
+        &query,
+        &frecencies,
+        limit.unwrap_or(DEFAULT_LIMIT),
+    );
+
+    serde_json::to_string(&matches).expect("error serializing json")
+}
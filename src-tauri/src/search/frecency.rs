@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use tauri::{AppHandle, Wry};
+
+use crate::usage::{self, LaunchHistory};
+
+/// Number of launch timestamps considered per application to estimate recency.
+const MAX_RECENT_LAUNCHES: usize = 10;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn recency_weight(age: u64) -> f64 {
+    match age / DAY {
+        0..=3 => 100.0,
+        4..=13 => 70.0,
+        14..=30 => 50.0,
+        31..=89 => 30.0,
+        _ => 10.0,
+    }
+}
+
+/// Frecency in the style of Firefox's URL bar: the total launch count scaled by the average
+/// recency weight of the most recent launches.
+pub fn frecency(launches: &LaunchHistory, now: u64) -> f64 {
+    if launches.recent.is_empty() {
+        return 0.0;
+    }
+
+    let total_weight = launches
+        .recent
+        .iter()
+        .map(|&launched_at| recency_weight(now.saturating_sub(launched_at)))
+        .sum::<f64>();
+
+    launches.count as f64 * total_weight / launches.recent.len() as f64
+}
+
+pub fn get_frecencies(app_handle: &AppHandle<Wry>) -> HashMap<String, f64> {
+    let now = usage::now();
+
+    usage::get_launch_history(app_handle, MAX_RECENT_LAUNCHES)
+        .iter()
+        .map(|(id, launches)| (id.clone(), frecency(launches, now)))
+        .collect()
+}
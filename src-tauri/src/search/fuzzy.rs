@@ -0,0 +1,379 @@
+use std::cmp;
+
+/// Result of matching a query against a single candidate string. `highlights` are half-open
+/// character ranges into the candidate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub score: f64,
+    pub highlights: Vec<(usize, usize)>,
+}
+
+const EXACT_SCORE: f64 = 1000.0;
+const PREFIX_SCORE: f64 = 900.0;
+const ACRONYM_SCORE: f64 = 800.0;
+const WORD_PREFIX_SCORE: f64 = 700.0;
+const SUBSTRING_SCORE: f64 = 600.0;
+const SUBSEQUENCE_SCORE: f64 = 400.0;
+const TYPO_SCORE: f64 = 250.0;
+
+fn normalize(text: &str) -> Vec<char> {
+    text.chars().flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// Character indices where a word starts, treating case changes (`VirtualBox`), digits and
+/// punctuation as word boundaries.
+fn word_starts(original: &[char]) -> Vec<usize> {
+    let mut starts = vec![];
+
+    for (index, c) in original.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            continue;
+        }
+
+        let is_start = match index.checked_sub(1).map(|previous| original[previous]) {
+            None => true,
+            Some(previous) => {
+                !previous.is_alphanumeric()
+                    || (previous.is_lowercase() && c.is_uppercase())
+                    || (previous.is_alphabetic() && c.is_numeric())
+            }
+        };
+
+        if is_start {
+            starts.push(index);
+        }
+    }
+
+    starts
+}
+
+fn to_ranges(indices: &[usize]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+
+    for &index in indices {
+        match ranges.last_mut() {
+            Some((_, end)) if *end == index => *end += 1,
+            _ => ranges.push((index, index + 1)),
+        }
+    }
+
+    ranges
+}
+
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    if needle.len() > haystack.len() {
+        return None;
+    }
+
+    (0..=haystack.len() - needle.len())
+        .find(|&start| &haystack[start..start + needle.len()] == needle)
+}
+
+fn match_acronym(candidate: &[char], starts: &[usize], query: &[char]) -> Option<Vec<usize>> {
+    if query.len() < 2 || query.len() > starts.len() {
+        return None;
+    }
+
+    let mut indices = vec![];
+    let mut start_index = 0;
+
+    for c in query {
+        let position = starts[start_index..]
+            .iter()
+            .position(|&start| candidate[start] == *c)?;
+
+        indices.push(starts[start_index + position]);
+        start_index += position + 1;
+    }
+
+    Some(indices)
+}
+
+/// Greedy subsequence match that prefers word starts, scored by how compact and aligned to
+/// word boundaries the matched characters are.
+fn match_subsequence(
+    candidate: &[char],
+    starts: &[usize],
+    query: &[char],
+) -> Option<(f64, Vec<usize>)> {
+    let mut indices = vec![];
+    let mut position = 0;
+
+    for c in query {
+        let next_word_start = starts
+            .iter()
+            .find(|&&start| start >= position && candidate[start] == *c)
+            .copied();
+        let next_char = (position..candidate.len()).find(|&index| candidate[index] == *c)?;
+
+        // Jump ahead to a word start only when the next literal match isn't adjacent to the
+        // previous one, so "code" in "Xcode" still matches contiguously.
+        let index = match (next_word_start, indices.last()) {
+            (Some(start), Some(&last)) if next_char != last + 1 => start,
+            (Some(start), None) => start,
+            _ => next_char,
+        };
+
+        indices.push(index);
+        position = index + 1;
+    }
+
+    let consecutive = indices
+        .windows(2)
+        .filter(|pair| pair[1] == pair[0] + 1)
+        .count();
+    let at_word_starts = indices
+        .iter()
+        .filter(|index| starts.contains(index))
+        .count();
+    let span = indices.last().unwrap() - indices.first().unwrap() + 1;
+
+    let score = SUBSEQUENCE_SCORE + 20.0 * consecutive as f64 + 30.0 * at_word_starts as f64
+        - 5.0 * (span - query.len()) as f64
+        - indices[0] as f64;
+
+    Some((score.min(SUBSTRING_SCORE - 1.0), indices))
+}
+
+/// Optimal string alignment distance, i.e. Levenshtein distance that also counts adjacent
+/// transpositions ("calcualtor" → "calculator") as a single edit.
+pub fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            distances[i][j] = cmp::min(
+                cmp::min(distances[i - 1][j] + 1, distances[i][j - 1] + 1),
+                distances[i - 1][j - 1] + cost,
+            );
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distances[i][j] = cmp::min(distances[i][j], distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+fn max_typos(query_length: usize) -> usize {
+    match query_length {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// Compares the query against every word and against the start of the candidate, allowing a
+/// number of typos that grows with the query length.
+fn match_typo(candidate: &[char], starts: &[usize], query: &[char]) -> Option<(f64, Vec<usize>)> {
+    let allowed = max_typos(query.len());
+
+    if allowed == 0 {
+        return None;
+    }
+
+    let mut best: Option<(usize, usize, usize)> = None;
+
+    for &start in starts {
+        let remaining = candidate.len() - start;
+
+        for length in
+            query.len().saturating_sub(allowed)..=cmp::min(query.len() + allowed, remaining)
+        {
+            if length == 0 {
+                continue;
+            }
+
+            let distance = edit_distance(query, &candidate[start..start + length]);
+
+            if distance <= allowed
+                && best.map_or(true, |(best_distance, _, _)| distance < best_distance)
+            {
+                best = Some((distance, start, length));
+            }
+        }
+    }
+
+    best.map(|(distance, start, length)| {
+        (
+            TYPO_SCORE - 50.0 * distance as f64 - start as f64,
+            (start..start + length).collect(),
+        )
+    })
+}
+
+pub fn fuzzy_match(candidate: &str, query: &str) -> Option<Match> {
+    let original = candidate.chars().collect::<Vec<_>>();
+    let candidate = normalize(candidate);
+    let query = normalize(query.trim());
+
+    // Lowercasing can change the number of characters (e.g. "İ"), in which case highlights
+    // would no longer line up with the original string.
+    if query.is_empty() || candidate.len() != original.len() {
+        return None;
+    }
+
+    let starts = word_starts(&original);
+    let length_penalty = (candidate.len() as f64 - query.len() as f64).max(0.0);
+
+    if candidate == query {
+        return Some(Match {
+            score: EXACT_SCORE,
+            highlights: vec![(0, candidate.len())],
+        });
+    }
+
+    if candidate.starts_with(&query) {
+        return Some(Match {
+            score: PREFIX_SCORE - length_penalty,
+            highlights: vec![(0, query.len())],
+        });
+    }
+
+    if let Some(indices) = match_acronym(&candidate, &starts, &query) {
+        return Some(Match {
+            score: ACRONYM_SCORE - length_penalty,
+            highlights: to_ranges(&indices),
+        });
+    }
+
+    for &start in &starts {
+        if candidate[start..].starts_with(&query) {
+            return Some(Match {
+                score: WORD_PREFIX_SCORE - start as f64 - length_penalty * 0.5,
+                highlights: vec![(start, start + query.len())],
+            });
+        }
+    }
+
+    if let Some(start) = find(&candidate, &query) {
+        return Some(Match {
+            score: SUBSTRING_SCORE - start as f64 - length_penalty * 0.5,
+            highlights: vec![(start, start + query.len())],
+        });
+    }
+
+    if let Some((score, indices)) = match_subsequence(&candidate, &starts, &query) {
+        return Some(Match {
+            score,
+            highlights: to_ranges(&indices),
+        });
+    }
+
+    match_typo(&candidate, &starts, &query).map(|(score, indices)| Match {
+        score,
+        highlights: to_ranges(&indices),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlights(candidate: &str, query: &str) -> Option<Vec<(usize, usize)>> {
+        fuzzy_match(candidate, query).map(|fuzzy_match| fuzzy_match.highlights)
+    }
+
+    #[test]
+    fn matches_acronyms() {
+        let cases = [
+            ("Visual Studio Code", "vsc", vec![(0, 1), (7, 8), (14, 15)]),
+            ("VirtualBox", "vb", vec![(0, 1), (7, 8)]),
+            ("Google Chrome", "gc", vec![(0, 1), (7, 8)]),
+            ("IntelliJ IDEA", "iji", vec![(0, 1), (7, 8), (9, 10)]),
+        ];
+
+        for (candidate, query, expected) in cases {
+            assert_eq!(highlights(candidate, query), Some(expected), "{}", query);
+            assert!(fuzzy_match(candidate, query).unwrap().score > SUBSTRING_SCORE);
+        }
+    }
+
+    #[test]
+    fn matches_typos() {
+        let cases = [
+            ("Calculator", "calcualtor", Some(vec![(0, 10)])),
+            ("Photoshop", "fotoshop", Some(vec![(0, 9)])),
+            ("Spotify", "spotfiy", Some(vec![(0, 7)])),
+            // A missing letter is still a subsequence.
+            (
+                "Adobe Illustrator",
+                "ilustrator",
+                Some(vec![(6, 8), (9, 17)]),
+            ),
+            // Short queries are too ambiguous for typos.
+            ("Mail", "mla", None),
+            ("Notes", "xyzzy", None),
+        ];
+
+        for (candidate, query, expected) in cases {
+            assert_eq!(highlights(candidate, query), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn matches_word_starts() {
+        let cases = [
+            ("Microsoft Word", "word", vec![(10, 14)]),
+            ("Adobe Photoshop 2024", "2024", vec![(16, 20)]),
+            ("VirtualBox", "box", vec![(7, 10)]),
+            ("System Settings", "set", vec![(7, 10)]),
+        ];
+
+        for (candidate, query, expected) in cases {
+            assert_eq!(highlights(candidate, query), Some(expected), "{}", query);
+        }
+    }
+
+    #[test]
+    fn ranks_better_matches_higher() {
+        let cases = [
+            ("code", "Code", "Xcode"),
+            ("term", "Terminal", "iTerm"),
+            ("chr", "Chrome", "Google Chrome"),
+            ("word", "Microsoft Word", "Password Manager"),
+            ("vsc", "Visual Studio Code", "Vine Server Control Panel"),
+            ("calc", "Calculator", "Calendar Classic"),
+        ];
+
+        for (query, better, worse) in cases {
+            let better_score = fuzzy_match(better, query).unwrap().score;
+            let worse_score = fuzzy_match(worse, query).map_or(0.0, |m| m.score);
+
+            assert!(
+                better_score > worse_score,
+                "{}: {} ({}) <= {} ({})",
+                query,
+                better,
+                better_score,
+                worse,
+                worse_score
+            );
+        }
+    }
+
+    #[test]
+    fn counts_transpositions_as_one_edit() {
+        let distance = |a: &str, b: &str| {
+            edit_distance(
+                &a.chars().collect::<Vec<_>>(),
+                &b.chars().collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(distance("calculator", "calcualtor"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,349 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, Manager, State as TauriState, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+mod file;
+
+const SERVICE: &str = "com.lander.Lander";
+
+static PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{secret:([A-Za-z0-9_.-]+)\}\}").unwrap());
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound(String),
+    HostNotAllowed { name: String, url: String },
+    Backend(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(name) => write!(f, "secret `{}` does not exist", name),
+            Error::HostNotAllowed { name, url } => {
+                write!(f, "secret `{}` may not be sent to {}", name, url)
+            }
+            Error::Backend(message) => write!(f, "secret storage error: {}", message),
+        }
+    }
+}
+
+impl From<keyring::Error> for Error {
+    fn from(error: keyring::Error) -> Self {
+        Error::Backend(error.to_string())
+    }
+}
+
+/// A stored secret and the hosts it may be sent to.
+#[derive(Serialize, Deserialize)]
+struct Secret {
+    value: String,
+    /// Host names, or `*.example.com` for any subdomain. Secrets stored before hosts were
+    /// recorded have none and are never sent anywhere.
+    #[serde(default)]
+    hosts: Vec<String>,
+}
+
+impl Secret {
+    fn decode(data: String) -> Self {
+        serde_json::from_str(&data).unwrap_or(Secret {
+            value: data,
+            hosts: vec![],
+        })
+    }
+
+    fn encode(&self) -> String {
+        serde_json::to_string(self).expect("error serializing json")
+    }
+
+    fn allows(&self, url: &str) -> bool {
+        let host = match Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+        {
+            Some(host) => host,
+            None => return false,
+        };
+
+        self.hosts
+            .iter()
+            .any(|allowed| match allowed.strip_prefix("*.") {
+                Some(domain) => host.ends_with(&format!(".{}", domain)),
+                None => host == *allowed,
+            })
+    }
+}
+
+enum Backend {
+    Keychain,
+    File(file::SecretFile),
+}
+
+#[derive(Default)]
+pub struct Store {
+    backend: Option<Backend>,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+fn is_keychain_available() -> bool {
+    if std::env::var("LANDER_SECRETS_BACKEND").as_deref() == Ok("file") {
+        return false;
+    }
+
+    // Secret Service may be missing entirely on headless Linux machines, in which case every
+    // keyring call fails with a platform error rather than `NoEntry`.
+    match keyring::Entry::new(SERVICE, "__lander_probe__").and_then(|entry| entry.get_password()) {
+        Ok(_) | Err(keyring::Error::NoEntry) => true,
+        Err(_) => false,
+    }
+}
+
+fn with_backend<T>(
+    app_handle: &AppHandle<Wry>,
+    f: impl FnOnce(&Backend) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+
+    if store.backend.is_none() {
+        store.backend = Some(if is_keychain_available() {
+            Backend::Keychain
+        } else {
+            let app_data_dir = app_handle
+                .path_resolver()
+                .app_data_dir()
+                .ok_or_else(|| Error::Backend("missing app data directory".to_string()))?;
+
+            Backend::File(file::SecretFile::open(&app_data_dir)?)
+        });
+    }
+
+    f(store.backend.as_ref().unwrap())
+}
+
+fn read_secret(app_handle: &AppHandle<Wry>, name: &str) -> Result<Secret, Error> {
+    let data = with_backend(app_handle, |backend| match backend {
+        Backend::Keychain => match keyring::Entry::new(SERVICE, name)?.get_password() {
+            Ok(data) => Ok(data),
+            Err(keyring::Error::NoEntry) => Err(Error::NotFound(name.to_string())),
+            Err(error) => Err(error.into()),
+        },
+        Backend::File(file) => file
+            .get(name)?
+            .ok_or_else(|| Error::NotFound(name.to_string())),
+    })?;
+
+    Ok(Secret::decode(data))
+}
+
+/// Returns the secret if it may be sent to the host of `url`.
+pub fn get_secret(app_handle: &AppHandle<Wry>, name: &str, url: &str) -> Result<String, Error> {
+    let secret = read_secret(app_handle, name)?;
+
+    if !secret.allows(url) {
+        return Err(Error::HostNotAllowed {
+            name: name.to_string(),
+            url: url.to_string(),
+        });
+    }
+
+    Ok(secret.value)
+}
+
+fn update_secret_names(
+    app_handle: &AppHandle<Wry>,
+    stores: TauriState<StoreCollection<Wry>>,
+    name: &str,
+    is_stored: bool,
+) {
+    with_store(
+        app_handle.clone(),
+        stores,
+        PathBuf::from("settings.json"),
+        |store| {
+            let mut names = match store.get("secrets") {
+                Some(value) => {
+                    serde_json::from_value::<Vec<String>>(value.clone()).unwrap_or_default()
+                }
+                None => vec![],
+            };
+
+            names.retain(|existing_name| existing_name != name);
+
+            if is_stored {
+                names.push(name.to_string());
+                names.sort();
+            }
+
+            store.insert("secrets".to_string(), serde_json::json!(names))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+fn replace_placeholders(
+    value: &str,
+    get_secret: impl Fn(&str) -> Result<String, Error>,
+) -> Result<String, Error> {
+    let mut error = None;
+
+    let resolved = PLACEHOLDER.replace_all(value, |captures: &Captures| {
+        match get_secret(&captures[1]) {
+            Ok(secret) => secret,
+            Err(err) => {
+                error.get_or_insert(err);
+                String::new()
+            }
+        }
+    });
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(resolved.into_owned()),
+    }
+}
+
+/// Replaces every `{{secret:<name>}}` placeholder in `value`, a header of a request to `url`,
+/// with the stored secret, so requests built by the webview can reference credentials without
+/// ever receiving them. Fails for secrets that may not be sent to the host of `url`.
+pub fn resolve_placeholders(
+    app_handle: &AppHandle<Wry>,
+    value: &str,
+    url: &str,
+) -> Result<String, Error> {
+    replace_placeholders(value, |name| get_secret(app_handle, name, url))
+}
+
+/// Like `resolve_placeholders`, for the environment of a local process. Hosts do not apply, since
+/// the process could run anything with the user's rights anyway.
+pub fn resolve_process_placeholders(
+    app_handle: &AppHandle<Wry>,
+    value: &str,
+) -> Result<String, Error> {
+    replace_placeholders(value, |name| {
+        read_secret(app_handle, name).map(|secret| secret.value)
+    })
+}
+
+#[tauri::command]
+pub fn set_secret(
+    app_handle: AppHandle<Wry>,
+    stores: TauriState<StoreCollection<Wry>>,
+    name: String,
+    value: String,
+    hosts: Vec<String>,
+) -> Result<(), String> {
+    let hosts = hosts
+        .iter()
+        .map(|host| host.trim().to_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect::<Vec<_>>();
+
+    if hosts.is_empty() {
+        return Err("a secret needs at least one host it may be sent to".to_string());
+    }
+
+    let data = Secret { value, hosts }.encode();
+
+    with_backend(&app_handle, |backend| match backend {
+        Backend::Keychain => Ok(keyring::Entry::new(SERVICE, &name)?.set_password(&data)?),
+        Backend::File(file) => file.set(&name, &data),
+    })
+    .map_err(|error| error.to_string())?;
+
+    update_secret_names(&app_handle, stores, &name, true);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_secret(
+    app_handle: AppHandle<Wry>,
+    stores: TauriState<StoreCollection<Wry>>,
+    name: String,
+) -> Result<(), String> {
+    with_backend(&app_handle, |backend| match backend {
+        Backend::Keychain => match keyring::Entry::new(SERVICE, &name)?.delete_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(error) => Err(error.into()),
+        },
+        Backend::File(file) => file.delete(&name),
+    })
+    .map_err(|error| error.to_string())?;
+
+    update_secret_names(&app_handle, stores, &name, false);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn has_secret(app_handle: AppHandle<Wry>, name: String) -> Result<bool, String> {
+    match read_secret(&app_handle, &name) {
+        Ok(_) => Ok(true),
+        Err(Error::NotFound(_)) => Ok(false),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(hosts: &[&str]) -> Secret {
+        Secret {
+            value: "sk-test".to_string(),
+            hosts: hosts.iter().map(|host| host.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn allows_only_bound_hosts() {
+        let secret = secret(&["api.openai.com", "*.example.com"]);
+
+        assert!(secret.allows("https://api.openai.com/v1/chat/completions"));
+        assert!(secret.allows("https://API.OpenAI.com:443/v1"));
+        assert!(secret.allows("https://eu.api.example.com/v1"));
+        assert!(!secret.allows("https://example.com/v1"));
+        assert!(!secret.allows("https://api.openai.com.evil.test/v1"));
+        assert!(!secret.allows("https://evil.test/?api.openai.com"));
+        assert!(!secret.allows("https://api.openai.com@evil.test/"));
+        assert!(!secret.allows("not a url"));
+    }
+
+    #[test]
+    fn legacy_secrets_have_no_hosts() {
+        let legacy = Secret::decode("sk-test".to_string());
+
+        assert_eq!(legacy.value, "sk-test");
+        assert!(!legacy.allows("https://api.openai.com/v1"));
+
+        let stored = Secret::decode(secret(&["api.openai.com"]).encode());
+
+        assert_eq!(stored.value, "sk-test");
+        assert!(stored.allows("https://api.openai.com/v1"));
+    }
+
+    #[test]
+    fn replaces_placeholders() {
+        let get_secret = |name: &str| match name {
+            "openai" => Ok("sk-test".to_string()),
+            name => Err(Error::NotFound(name.to_string())),
+        };
+
+        assert_eq!(
+            replace_placeholders("Bearer {{secret:openai}}", get_secret).unwrap(),
+            "Bearer sk-test"
+        );
+        assert!(matches!(
+            replace_placeholders("Bearer {{secret:missing}}", get_secret),
+            Err(Error::NotFound(name)) if name == "missing"
+        ));
+    }
+}
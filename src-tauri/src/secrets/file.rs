@@ -0,0 +1,183 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use super::Error;
+
+const NONCE_LENGTH: usize = 12;
+
+/// Encrypted secret storage for machines without an OS keychain, e.g. headless Linux test runners.
+/// The key is read from `LANDER_SECRETS_KEY` (base64) when set, otherwise from a key file that is
+/// only readable by the current user.
+pub struct SecretFile {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+}
+
+fn backend_error(error: impl ToString) -> Error {
+    Error::Backend(error.to_string())
+}
+
+fn load_key(dir: &Path) -> Result<Key, Error> {
+    if let Ok(encoded_key) = std::env::var("LANDER_SECRETS_KEY") {
+        let key = STANDARD.decode(encoded_key).map_err(backend_error)?;
+
+        if key.len() != 32 {
+            return Err(Error::Backend(
+                "LANDER_SECRETS_KEY must be 32 bytes".to_string(),
+            ));
+        }
+
+        return Ok(*Key::from_slice(&key));
+    }
+
+    let key_path = dir.join("secrets.key");
+
+    if let Ok(key) = fs::read(&key_path) {
+        if key.len() == 32 {
+            return Ok(*Key::from_slice(&key));
+        }
+    }
+
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    fs::write(&key_path, key.as_slice()).map_err(backend_error)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600)).map_err(backend_error)?;
+    }
+
+    Ok(key)
+}
+
+impl SecretFile {
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(dir).map_err(backend_error)?;
+
+        let key = load_key(dir)?;
+
+        Ok(Self {
+            path: dir.join("secrets.enc.json"),
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    fn read(&self) -> Result<HashMap<String, String>, Error> {
+        match fs::read_to_string(&self.path) {
+            Ok(data) => serde_json::from_str(&data).map_err(backend_error),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(backend_error(error)),
+        }
+    }
+
+    fn write(&self, secrets: &HashMap<String, String>) -> Result<(), Error> {
+        let data = serde_json::to_string(secrets).map_err(backend_error)?;
+        fs::write(&self.path, data).map_err(backend_error)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>, Error> {
+        let secrets = self.read()?;
+
+        let encoded = match secrets.get(name) {
+            Some(encoded) => encoded,
+            None => return Ok(None),
+        };
+
+        let data = STANDARD.decode(encoded).map_err(backend_error)?;
+
+        if data.len() < NONCE_LENGTH {
+            return Err(Error::Backend(format!("secret `{}` is corrupted", name)));
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::Backend(format!("failed to decrypt secret `{}`", name)))?;
+
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(backend_error)
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<(), Error> {
+        let mut secrets = self.read()?;
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|_| Error::Backend(format!("failed to encrypt secret `{}`", name)))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+
+        secrets.insert(name.to_string(), STANDARD.encode(data));
+
+        self.write(&secrets)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), Error> {
+        let mut secrets = self.read()?;
+
+        if secrets.remove(name).is_some() {
+            self.write(&secrets)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_through_the_file() {
+        let dir = std::env::temp_dir().join(format!("lander-secrets-{}", std::process::id()));
+
+        let file = SecretFile::open(&dir).unwrap();
+        file.set("openai", "sk-test").unwrap();
+        file.set("anthropic", "sk-ant-test").unwrap();
+
+        let stored = fs::read_to_string(dir.join("secrets.enc.json")).unwrap();
+        assert!(!stored.contains("sk-test"));
+
+        // A second instance reads the key file written by the first.
+        let file = SecretFile::open(&dir).unwrap();
+        assert_eq!(file.get("openai").unwrap().as_deref(), Some("sk-test"));
+        assert_eq!(
+            file.get("anthropic").unwrap().as_deref(),
+            Some("sk-ant-test")
+        );
+
+        file.delete("openai").unwrap();
+        assert_eq!(file.get("openai").unwrap(), None);
+        assert_eq!(file.get("missing").unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_secret_files_are_errors() {
+        let dir = std::env::temp_dir().join(format!("lander-secrets-dir-{}", std::process::id()));
+
+        let file = SecretFile::open(&dir).unwrap();
+        fs::create_dir_all(dir.join("secrets.enc.json")).unwrap();
+
+        assert!(matches!(file.get("openai"), Err(Error::Backend(_))));
+        assert!(file.set("openai", "sk-test").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
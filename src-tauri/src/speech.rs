@@ -0,0 +1,728 @@
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, GlobalShortcutManager, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+mod synthesis;
+mod system;
+
+/// How often the speaking thread checks for new sentences and finished playback.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Sentences longer than this are cut at a space, so speech does not wait for them to complete.
+const MAX_SENTENCE_LENGTH: usize = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// The speech engine of the operating system: `say` on macOS, `System.Speech` on Windows and
+    /// `espeak-ng` on Linux.
+    System,
+    /// An OpenAI-compatible `/audio/speech` endpoint.
+    Remote,
+    /// A local program that reads text on its standard input and writes WAV to its standard
+    /// output, such as Piper.
+    Command,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Read responses aloud as they stream in.
+    pub read_responses: bool,
+    /// Global hotkey that reads the selected text of the focused application aloud, or stops
+    /// speaking when pressed while speaking.
+    pub hotkey: String,
+    pub backend: Backend,
+    /// Multiple of the normal speaking rate.
+    pub rate: f32,
+    /// Voice of the system engine or the remote provider; their default voice when empty.
+    pub voice: String,
+    pub remote_url: String,
+    pub remote_model: String,
+    /// Name of the stored secret sent as a bearer token, if any.
+    pub remote_api_key_secret: String,
+    pub command: Option<String>,
+    pub command_arguments: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            read_responses: false,
+            hotkey: String::new(),
+            backend: Backend::System,
+            rate: 1.0,
+            voice: String::new(),
+            remote_url: "https://api.openai.com/v1/audio/speech".to_string(),
+            remote_model: "tts-1".to_string(),
+            remote_api_key_secret: "openai_api_key".to_string(),
+            command: None,
+            command_arguments: vec![],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Store {
+    /// Streamed text that does not form a complete sentence yet.
+    buffer: String,
+    is_in_code_block: bool,
+    queue: VecDeque<String>,
+    is_paused: bool,
+    is_speaking: bool,
+    /// Incremented on stop, so audio synthesized for stopped text is dropped.
+    generation: u64,
+    /// System engine speaking `current`, killed to stop or pause it.
+    engine: Option<system::Engine>,
+    current: Option<String>,
+    sink: Option<Arc<Sink>>,
+    hotkey: Option<String>,
+}
+
+impl Store {
+    /// Adds streamed text to the buffer and queues its complete sentences, skipping code blocks.
+    /// With `is_final`, the incomplete rest is queued too.
+    fn push_text(&mut self, text: &str, is_final: bool) {
+        self.buffer.push_str(text);
+
+        let mut sentences = take_sentences(&mut self.buffer);
+
+        if is_final {
+            sentences.push(self.buffer.trim().to_string());
+            self.buffer.clear();
+        }
+
+        for sentence in sentences {
+            // Code is not worth listening to.
+            if sentence.starts_with("```") {
+                self.is_in_code_block = !self.is_in_code_block;
+                continue;
+            }
+
+            if self.is_in_code_block {
+                continue;
+            }
+
+            let sentence = clean_markdown(&sentence);
+
+            if !sentence.trim().is_empty() {
+                self.queue.push_back(sentence);
+            }
+        }
+
+        if is_final {
+            self.is_in_code_block = false;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("speech")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn save_settings(app_handle: &AppHandle<Wry>, settings: &Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("speech".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+fn is_sentence_end(character: char) -> bool {
+    matches!(character, '.' | '!' | '?' | '…' | '。' | '！' | '？')
+}
+
+/// Splits complete sentences off the start of `buffer`, leaving the incomplete rest in it. Latin
+/// punctuation only ends a sentence once whitespace follows, since the next chunk may continue a
+/// number or an abbreviation. Line breaks always end one.
+fn take_sentences(buffer: &mut String) -> Vec<String> {
+    let mut sentences = vec![];
+
+    loop {
+        let characters = buffer.char_indices().collect::<Vec<_>>();
+        let mut end = None;
+
+        for (index, (offset, character)) in characters.iter().enumerate() {
+            let is_cjk = matches!(character, '。' | '！' | '？');
+            let is_followed_by_space = characters
+                .get(index + 1)
+                .map_or(false, |(_, next)| next.is_whitespace());
+
+            if *character == '\n'
+                || (is_sentence_end(*character) && (is_cjk || is_followed_by_space))
+            {
+                end = Some(offset + character.len_utf8());
+                break;
+            }
+
+            if index >= MAX_SENTENCE_LENGTH && character.is_whitespace() {
+                end = Some(*offset);
+                break;
+            }
+        }
+
+        let end = match end {
+            Some(end) => end,
+            None => break,
+        };
+
+        let sentence = buffer[..end].trim().to_string();
+        buffer.replace_range(..end, "");
+
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+    }
+
+    sentences
+}
+
+/// Drops Markdown syntax that engines would read out: heading and list markers, emphasis, code
+/// ticks and link targets.
+fn clean_markdown(sentence: &str) -> String {
+    let mut text = sentence
+        .trim_start_matches(|character: char| {
+            matches!(character, '#' | '>' | '-' | '*' | '+') || character.is_whitespace()
+        })
+        .to_string();
+
+    while let Some(start) = text.find("](") {
+        match text[start..].find(')') {
+            Some(end) => text.replace_range(start + 1..start + end + 1, ""),
+            None => break,
+        }
+    }
+
+    text.replace(['*', '`', '[', ']'], "")
+}
+
+fn emit_state(app_handle: &AppHandle<Wry>) {
+    let (is_speaking, is_paused) = {
+        let state = app_handle.state::<State>();
+        let store = state.0.lock().unwrap();
+        (store.is_speaking, store.is_paused)
+    };
+
+    app_handle
+        .emit_all(
+            "speech_state",
+            json!({ "is_speaking": is_speaking, "is_paused": is_paused }),
+        )
+        .unwrap_or_default();
+}
+
+/// Speaks a sentence with the system engine and returns once it is spoken, stopped or paused.
+fn speak_with_system(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    sentence: &str,
+    generation: u64,
+) -> Result<(), String> {
+    let engine = app_handle.state::<State>().0.lock().unwrap().engine.take();
+    let engine = system::Engine::speak(engine, sentence, settings)?;
+
+    {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        // `stop` and `pause` find no engine to kill while it starts, so they are checked for here.
+        if store.generation != generation {
+            engine.kill();
+            return Ok(());
+        }
+
+        if store.is_paused {
+            engine.kill();
+            store.queue.push_front(sentence.to_string());
+            return Ok(());
+        }
+
+        store.engine = Some(engine);
+        store.current = Some(sentence.to_string());
+    }
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        let is_done = match store.engine.as_mut() {
+            Some(engine) => engine.is_done(),
+            // Taken by `stop` or `pause`.
+            None => return Ok(()),
+        };
+
+        // The engine is kept for the next sentence.
+        if is_done {
+            store.current = None;
+            return Ok(());
+        }
+    }
+}
+
+/// Synthesizes a sentence and queues it on the sink. One sentence is kept queued behind the one
+/// playing, so synthesis overlaps playback.
+fn play_synthesized(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    sentence: &str,
+    generation: u64,
+    output: &mut Option<(OutputStream, OutputStreamHandle)>,
+) -> Result<(), String> {
+    loop {
+        let state = app_handle.state::<State>();
+        let store = state.0.lock().unwrap();
+
+        if store.generation != generation {
+            return Ok(());
+        }
+
+        if store.sink.as_ref().map_or(true, |sink| sink.len() < 2) {
+            break;
+        }
+
+        drop(store);
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    let audio = match settings.backend {
+        Backend::Remote => tauri::async_runtime::block_on(synthesis::synthesize_remote(
+            app_handle, settings, sentence,
+        ))?,
+        _ => synthesis::synthesize_with_command(settings, sentence)?,
+    };
+
+    let source = Decoder::new(Cursor::new(audio)).map_err(|error| error.to_string())?;
+
+    if output.is_none() {
+        *output = Some(OutputStream::try_default().map_err(|error| error.to_string())?);
+    }
+
+    let sink = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        if store.generation != generation {
+            return Ok(());
+        }
+
+        match &store.sink {
+            Some(sink) => sink.clone(),
+            None => {
+                let handle = &output.as_ref().unwrap().1;
+                let sink = Arc::new(Sink::try_new(handle).map_err(|error| error.to_string())?);
+
+                if store.is_paused {
+                    sink.pause();
+                }
+
+                store.sink = Some(sink.clone());
+                sink
+            }
+        }
+    };
+
+    // The remote provider applies the rate itself; programs like Piper speak at one rate.
+    if settings.backend == Backend::Command {
+        sink.set_speed(settings.rate);
+    }
+
+    sink.append(source);
+
+    Ok(())
+}
+
+/// Speaks queued sentences one after another until the queue runs out and playback ends.
+fn run_speaker(app_handle: AppHandle<Wry>) {
+    // Opened on first use, as the system engine plays its audio itself.
+    let mut output = None;
+
+    loop {
+        let next = {
+            let state = app_handle.state::<State>();
+            let mut store = state.0.lock().unwrap();
+
+            let is_playing = store.sink.as_ref().map_or(false, |sink| !sink.empty());
+            let sentence = if store.is_paused {
+                None
+            } else {
+                store.queue.pop_front()
+            };
+
+            match sentence {
+                Some(sentence) => Some((sentence, store.generation)),
+                None if store.is_paused || is_playing => None,
+                None => {
+                    store.is_speaking = false;
+                    // The sink cannot outlive the output stream of this thread.
+                    store.sink = None;
+                    drop(store);
+
+                    emit_state(&app_handle);
+                    return;
+                }
+            }
+        };
+
+        let (sentence, generation) = match next {
+            Some(next) => next,
+            None => {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+        };
+
+        let settings = get_settings(&app_handle);
+
+        app_handle
+            .emit_all("speech_sentence", json!({ "text": sentence }))
+            .unwrap_or_default();
+
+        let result = match settings.backend {
+            Backend::System => speak_with_system(&app_handle, &settings, &sentence, generation),
+            _ => play_synthesized(&app_handle, &settings, &sentence, generation, &mut output),
+        };
+
+        if let Err(error) = result {
+            app_handle
+                .emit_all("speech_error", json!({ "error": error }))
+                .unwrap_or_default();
+            stop(&app_handle);
+        }
+    }
+}
+
+/// Queues streamed text. Complete sentences are spoken as soon as they arrive; the rest waits
+/// for more text, or is spoken right away when `is_final`.
+pub fn speak(app_handle: &AppHandle<Wry>, text: &str, is_final: bool) {
+    let should_start = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.push_text(text, is_final);
+
+        let should_start = !store.is_speaking && !store.queue.is_empty();
+
+        if should_start {
+            store.is_speaking = true;
+        }
+
+        should_start
+    };
+
+    if should_start {
+        let speaker_app_handle = app_handle.clone();
+        thread::spawn(move || run_speaker(speaker_app_handle));
+
+        emit_state(app_handle);
+    }
+}
+
+pub fn stop(app_handle: &AppHandle<Wry>) {
+    {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.generation += 1;
+        store.buffer.clear();
+        store.is_in_code_block = false;
+        store.queue.clear();
+        store.is_paused = false;
+        store.current = None;
+
+        if let Some(engine) = store.engine.take() {
+            engine.kill();
+        }
+
+        if let Some(sink) = &store.sink {
+            sink.stop();
+        }
+    }
+
+    emit_state(app_handle);
+}
+
+/// Pauses speech. The system engine cannot pause, so its sentence is stopped and spoken again
+/// from the start on resume.
+pub fn pause(app_handle: &AppHandle<Wry>) {
+    {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.is_paused = true;
+
+        if let Some(engine) = store.engine.take() {
+            engine.kill();
+        }
+
+        if let Some(sentence) = store.current.take() {
+            store.queue.push_front(sentence);
+        }
+
+        if let Some(sink) = &store.sink {
+            sink.pause();
+        }
+    }
+
+    emit_state(app_handle);
+}
+
+pub fn resume(app_handle: &AppHandle<Wry>) {
+    {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.is_paused = false;
+
+        if let Some(sink) = &store.sink {
+            sink.play();
+        }
+    }
+
+    emit_state(app_handle);
+}
+
+/// The selection of the focused application. On Windows, the one it had when the panel was last
+/// shown is all that is kept, so the live one is copied; elsewhere copying is the fallback for
+/// applications that do not expose it to the accessibility APIs.
+fn get_selected_text(app_handle: &AppHandle<Wry>) -> Option<String> {
+    let accessible_text = if cfg!(target_os = "windows") {
+        None
+    } else {
+        crate::command::application::get_focused_application(app_handle.clone())
+            .and_then(|application| application.selected_text().map(str::to_string))
+            .filter(|text| !text.trim().is_empty())
+    };
+
+    accessible_text.or_else(crate::command::application::copy_selected_text)
+}
+
+fn read_selection(app_handle: &AppHandle<Wry>) {
+    let is_speaking = app_handle.state::<State>().0.lock().unwrap().is_speaking;
+
+    if is_speaking {
+        stop(app_handle);
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+
+    // Copying waits for the application, which the hotkey's thread should not.
+    thread::spawn(move || {
+        if let Some(text) = get_selected_text(&app_handle) {
+            speak(&app_handle, &text, true);
+        }
+    });
+}
+
+fn register_hotkey(app_handle: &AppHandle<Wry>, hotkey: &str) {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+    let mut shortcut_manager = app_handle.global_shortcut_manager();
+
+    if let Some(hotkey) = store.hotkey.take() {
+        shortcut_manager.unregister(&hotkey).unwrap_or_default();
+    }
+
+    if hotkey.trim().is_empty() {
+        return;
+    }
+
+    let handler_app_handle = app_handle.clone();
+
+    if shortcut_manager
+        .register(hotkey, move || read_selection(&handler_app_handle))
+        .is_ok()
+    {
+        store.hotkey = Some(hotkey.to_string());
+    }
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    let settings = get_settings(&app_handle);
+    register_hotkey(&app_handle, &settings.hotkey);
+}
+
+/// Speaks text as it streams in; pass `is_final` with the last chunk so the incomplete sentence
+/// at the end is spoken too.
+#[tauri::command]
+pub fn speak_text(app_handle: AppHandle<Wry>, text: String, is_final: bool) {
+    speak(&app_handle, &text, is_final);
+}
+
+#[tauri::command]
+pub fn stop_speaking(app_handle: AppHandle<Wry>) {
+    stop(&app_handle);
+}
+
+#[tauri::command]
+pub fn pause_speaking(app_handle: AppHandle<Wry>) {
+    pause(&app_handle);
+}
+
+#[tauri::command]
+pub fn resume_speaking(app_handle: AppHandle<Wry>) {
+    resume(&app_handle);
+}
+
+/// Changes the speaking rate from the next sentence on; 1 is the normal rate.
+#[tauri::command]
+pub fn set_speech_rate(app_handle: AppHandle<Wry>, rate: f32) {
+    let mut settings = get_settings(&app_handle);
+    settings.rate = rate.clamp(0.25, 4.0);
+
+    save_settings(&app_handle, &settings);
+}
+
+#[tauri::command]
+pub fn get_speech_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_speech_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    save_settings(&app_handle, &settings);
+    register_hotkey(&app_handle, &settings.hotkey);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(text: &str) -> (Vec<String>, String) {
+        let mut buffer = text.to_string();
+        let sentences = take_sentences(&mut buffer);
+
+        (sentences, buffer)
+    }
+
+    #[test]
+    fn sentences_end_at_punctuation_followed_by_space() {
+        assert_eq!(
+            sentences("First one. Second one! Third"),
+            (
+                vec!["First one.".to_string(), "Second one!".to_string()],
+                " Third".to_string()
+            )
+        );
+        assert_eq!(
+            sentences("A list:\n- item\n"),
+            (
+                vec!["A list:".to_string(), "- item".to_string()],
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn decimals_and_abbreviations_wait_for_a_space() {
+        assert_eq!(
+            sentences("It costs 3.5"),
+            (vec![], "It costs 3.5".to_string())
+        );
+        assert_eq!(sentences("Use e.g"), (vec![], "Use e.g".to_string()));
+        assert_eq!(
+            sentences("Pi is 3.14159 or so"),
+            (vec![], "Pi is 3.14159 or so".to_string())
+        );
+
+        let mut buffer = "It costs 3.".to_string();
+        assert!(take_sentences(&mut buffer).is_empty());
+
+        buffer.push_str("50 now. Then");
+        assert_eq!(take_sentences(&mut buffer), vec!["It costs 3.50 now."]);
+        assert_eq!(buffer, " Then");
+    }
+
+    #[test]
+    fn cjk_punctuation_ends_sentences_without_a_space() {
+        assert_eq!(
+            sentences("你好。今天天气很好！明天呢？还有"),
+            (
+                vec![
+                    "你好。".to_string(),
+                    "今天天气很好！".to_string(),
+                    "明天呢？".to_string()
+                ],
+                "还有".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn long_sentences_are_cut_at_a_space() {
+        let text = "word ".repeat(100);
+        let (sentences, buffer) = sentences(&text);
+
+        assert_eq!(sentences.len(), 1);
+        assert!(sentences[0].chars().count() >= MAX_SENTENCE_LENGTH);
+        assert!(sentences[0].chars().count() <= MAX_SENTENCE_LENGTH + "word".len());
+        assert!(sentences[0].ends_with("word"));
+        assert_eq!(
+            buffer.split_whitespace().count() + sentences[0].split_whitespace().count(),
+            100
+        );
+
+        // Without a space there is nowhere to cut, so it waits.
+        let text = "a".repeat(MAX_SENTENCE_LENGTH * 2);
+        assert_eq!(take_sentences(&mut text.clone()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn markdown_syntax_is_dropped() {
+        assert_eq!(clean_markdown("## Install it"), "Install it");
+        assert_eq!(clean_markdown("- **Bold** and *italic*"), "Bold and italic");
+        assert_eq!(clean_markdown("> Run `cargo build`."), "Run cargo build.");
+        assert_eq!(
+            clean_markdown("Read [the guide](https://example.com/guide) first."),
+            "Read the guide first."
+        );
+        assert_eq!(clean_markdown("An [unclosed](link"), "An unclosed(link");
+    }
+
+    #[test]
+    fn code_blocks_are_skipped() {
+        let mut store = Store::default();
+
+        store.push_text("Run this:\n```rust\nlet x = 1.", false);
+        store.push_text("0;\n```\nThen **check** [the log](log.txt).\n", false);
+        store.push_text("```\nunclosed code", true);
+        store.push_text("Done", true);
+
+        assert_eq!(
+            store.queue.into_iter().collect::<Vec<_>>(),
+            vec!["Run this:", "Then check the log.", "Done"]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use reqwest::{header::CONTENT_TYPE, Client};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+use tauri::{AppHandle, Wry};
+
+use super::Settings;
+
+/// Voice of the remote provider when none is configured.
+const DEFAULT_REMOTE_VOICE: &str = "alloy";
+
+/// Calls an OpenAI-compatible `/audio/speech` endpoint, authenticating with the stored secret,
+/// and returns the spoken text as a WAV file.
+pub async fn synthesize_remote(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    text: &str,
+) -> Result<Vec<u8>, String> {
+    let voice = if settings.voice.is_empty() {
+        DEFAULT_REMOTE_VOICE
+    } else {
+        &settings.voice
+    };
+
+    let body = serde_json::json!({
+        "model": settings.remote_model,
+        "input": text,
+        "voice": voice,
+        "speed": settings.rate.clamp(0.25, 4.0),
+        "response_format": "wav",
+    });
+
+    let mut request = Client::new()
+        .post(&settings.remote_url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+
+    if !settings.remote_api_key_secret.is_empty() {
+        let api_key = crate::secrets::get_secret(
+            app_handle,
+            &settings.remote_api_key_secret,
+            &settings.remote_url,
+        )
+        .map_err(|error| error.to_string())?;
+
+        request = request.bearer_auth(api_key);
+    }
+
+    let response = request.send().await.map_err(|error| error.to_string())?;
+    let status = response.status();
+
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("speech request failed with {}: {}", status, text));
+    }
+
+    response
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|error| error.to_string())
+}
+
+/// Runs the configured program, such as Piper, with the text on its standard input and reads a
+/// WAV file from its standard output.
+pub fn synthesize_with_command(settings: &Settings, text: &str) -> Result<Vec<u8>, String> {
+    let program = settings
+        .command
+        .as_deref()
+        .filter(|program| !program.trim().is_empty())
+        .ok_or_else(|| "no speech command is configured".to_string())?;
+
+    let mut child = Command::new(program)
+        .args(&settings.command_arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|error| format!("`{}` could not be started: {}", program, error))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|error| error.to_string())?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|error| error.to_string())?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!("`{}` produced no audio", program));
+    }
+
+    Ok(output.stdout)
+}
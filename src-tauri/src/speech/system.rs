@@ -0,0 +1,209 @@
+use std::process::{Child, Command, Stdio};
+
+use super::Settings;
+
+/// Words per minute the speech engines of macOS and `espeak-ng` use by default.
+#[cfg(not(target_os = "windows"))]
+const DEFAULT_WORDS_PER_MINUTE: f32 = 175.0;
+
+/// `say` reads the text and exits once it is spoken; killing it stops the speech.
+#[cfg(target_os = "macos")]
+fn command(text: &str, settings: &Settings) -> Command {
+    let mut command = Command::new("/usr/bin/say");
+
+    command.arg("-r").arg(
+        (DEFAULT_WORDS_PER_MINUTE * settings.rate)
+            .round()
+            .to_string(),
+    );
+
+    if !settings.voice.is_empty() {
+        command.args(["-v", &settings.voice]);
+    }
+
+    command.arg("--").arg(text);
+    command
+}
+
+#[cfg(target_os = "linux")]
+fn command(text: &str, settings: &Settings) -> Command {
+    let mut command = Command::new("espeak-ng");
+
+    command.arg("-s").arg(
+        (DEFAULT_WORDS_PER_MINUTE * settings.rate)
+            .round()
+            .to_string(),
+    );
+
+    if !settings.voice.is_empty() {
+        command.args(["-v", &settings.voice]);
+    }
+
+    command.arg("--").arg(text);
+    command
+}
+
+/// `System.Speech` through PowerShell, reading one sentence per line from its standard input
+/// and answering each with a line once it is spoken. Sentences are base64-encoded UTF-8, so
+/// neither line breaks nor the console's code page get in the way.
+#[cfg(target_os = "windows")]
+const SYNTHESIZER_SCRIPT: &str = "\
+    Add-Type -AssemblyName System.Speech; \
+    $synthesizer = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+    if ($env:LANDER_SPEECH_VOICE) { $synthesizer.SelectVoice($env:LANDER_SPEECH_VOICE) }; \
+    while ($null -ne ($line = [Console]::In.ReadLine())) { \
+        $rate, $text = $line.Split(' ', 2); \
+        $synthesizer.Rate = [int]$rate; \
+        $synthesizer.Speak([Text.Encoding]::UTF8.GetString([Convert]::FromBase64String($text))); \
+        [Console]::Out.WriteLine('spoken'); \
+        [Console]::Out.Flush() \
+    }";
+
+/// The speech engine of the operating system. `say` and `espeak-ng` start quickly enough to run
+/// once per sentence, but PowerShell takes about a second, so on Windows one process speaks
+/// every sentence until it is killed or the voice changes.
+pub struct Engine {
+    child: Child,
+    #[cfg(target_os = "windows")]
+    stdin: std::process::ChildStdin,
+    /// Receives a message for every sentence spoken.
+    #[cfg(target_os = "windows")]
+    spoken: std::sync::mpsc::Receiver<()>,
+    #[cfg(target_os = "windows")]
+    voice: String,
+}
+
+impl Engine {
+    #[cfg(not(target_os = "windows"))]
+    fn start(sentence: &str, settings: &Settings) -> std::io::Result<Self> {
+        let child = command(sentence, settings)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        Ok(Self { child })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn start(settings: &Settings) -> std::io::Result<Self> {
+        use std::{
+            io::{BufRead, BufReader},
+            os::windows::process::CommandExt,
+            sync::mpsc,
+            thread,
+        };
+
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+        let mut child = Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                SYNTHESIZER_SCRIPT,
+            ])
+            .env("LANDER_SPEECH_VOICE", &settings.voice)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        let stdout = child.stdout.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        let (spoken_tx, spoken) = mpsc::channel();
+
+        thread::spawn(move || {
+            for _ in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if spoken_tx.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            spoken,
+            voice: settings.voice.clone(),
+        })
+    }
+
+    /// Starts speaking `sentence`, with `engine` if it can speak another one.
+    #[cfg(not(target_os = "windows"))]
+    pub fn speak(
+        engine: Option<Self>,
+        sentence: &str,
+        settings: &Settings,
+    ) -> Result<Self, String> {
+        if let Some(engine) = engine {
+            engine.kill();
+        }
+
+        Self::start(sentence, settings)
+            .map_err(|error| format!("the speech engine could not be started: {}", error))
+    }
+
+    /// Starts speaking `sentence`, with `engine` if it can speak another one.
+    #[cfg(target_os = "windows")]
+    pub fn speak(
+        engine: Option<Self>,
+        sentence: &str,
+        settings: &Settings,
+    ) -> Result<Self, String> {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use std::io::Write;
+
+        let engine = engine.and_then(|mut engine| {
+            if engine.voice == settings.voice && matches!(engine.child.try_wait(), Ok(None)) {
+                Some(engine)
+            } else {
+                engine.kill();
+                None
+            }
+        });
+
+        let mut engine = match engine {
+            Some(engine) => engine,
+            None => Self::start(settings)
+                .map_err(|error| format!("the speech engine could not be started: {}", error))?,
+        };
+
+        // `Rate` goes from -10 to 10, where 10 is about three times the normal rate.
+        let rate = ((settings.rate.max(0.1).log(3.0)) * 10.0)
+            .round()
+            .clamp(-10.0, 10.0);
+
+        // Sentences spoken before this one may still be acknowledged.
+        while engine.spoken.try_recv().is_ok() {}
+
+        writeln!(engine.stdin, "{} {}", rate, STANDARD.encode(sentence))
+            .and_then(|_| engine.stdin.flush())
+            .map_err(|error| format!("the speech engine stopped: {}", error))?;
+
+        Ok(engine)
+    }
+
+    /// Whether the sentence is spoken, or the engine exited.
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_done(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Whether the sentence is spoken, or the engine exited.
+    #[cfg(target_os = "windows")]
+    pub fn is_done(&mut self) -> bool {
+        use std::sync::mpsc::TryRecvError;
+
+        match self.spoken.try_recv() {
+            Ok(()) | Err(TryRecvError::Disconnected) => true,
+            Err(TryRecvError::Empty) => !matches!(self.child.try_wait(), Ok(None)),
+        }
+    }
+
+    /// Stops speaking right away.
+    pub fn kill(mut self) {
+        self.child.kill().unwrap_or_default();
+        self.child.wait().map(|_| ()).unwrap_or_default();
+    }
+}
@@ -9,12 +9,39 @@ use tauri::{AppHandle, Manager, Wry};
 
 #[derive(Default)]
 pub struct Store {
-    is_canceled: bool,
+    pub is_canceled: bool,
 }
 
 #[derive(Default)]
 pub struct State(pub Mutex<Store>);
 
+/// Header values may reference secrets as `{{secret:<name>}}`, which are only ever resolved here
+/// so the webview never sees the credentials, and only for hosts the secret is bound to.
+fn build_headers(
+    app_handle: &AppHandle<Wry>,
+    url: &str,
+    headers: &str,
+) -> Result<HeaderMap, String> {
+    let headers: HashMap<String, Value> =
+        serde_json::from_str(headers).map_err(|error| error.to_string())?;
+
+    let mut header_map = HeaderMap::new();
+
+    for (key, value) in headers {
+        if let Value::String(value) = value {
+            let value = crate::secrets::resolve_placeholders(app_handle, &value, url)
+                .map_err(|error| error.to_string())?;
+
+            header_map.insert(
+                HeaderName::from_bytes(key.as_bytes()).map_err(|error| error.to_string())?,
+                HeaderValue::from_str(&value).map_err(|error| error.to_string())?,
+            );
+        }
+    }
+
+    Ok(header_map)
+}
+
 #[tauri::command]
 pub async fn stream(
     app_handle: AppHandle<Wry>,
@@ -27,28 +54,20 @@ pub async fn stream(
     let window = app_handle.get_window("main").unwrap();
 
     let client = Client::new();
-    let mut request = client.post(url);
+    let mut request = client.post(&url);
 
     if let Some(body) = body {
         request = request.body(body);
     }
 
     if let Some(headers) = headers {
-        let headers_json: Value = serde_json::from_str(&headers).unwrap();
-
-        let mut header_map = HeaderMap::new();
-        if let Value::Object(map) = headers_json {
-            for (key, value) in map {
-                if let Value::String(s) = value {
-                    header_map.insert(
-                        HeaderName::from_bytes(key.as_bytes()).unwrap(),
-                        HeaderValue::from_str(&s).unwrap(),
-                    );
-                }
+        match build_headers(&app_handle, &url, &headers) {
+            Ok(header_map) => request = request.headers(header_map),
+            Err(_) => {
+                window.emit("stream", "[LANDER_STREAM_ERROR]").unwrap();
+                return None;
             }
         }
-
-        request = request.headers(header_map);
     }
 
     let response = match request.send().await {
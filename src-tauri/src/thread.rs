@@ -0,0 +1,594 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Wry};
+
+use crate::{database, retrieval, usage};
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMessage {
+    pub id: String,
+    pub author: String,
+    pub content: String,
+    /// Seconds since the Unix epoch.
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thread {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub thread_type: String,
+    pub messages: Vec<ThreadMessage>,
+    /// The command the thread was started from, stored as the webview serialized it.
+    #[serde(default)]
+    pub command: Option<serde_json::Value>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl Thread {
+    /// Title shown for the thread in search results and retrieval hits.
+    pub fn title(&self) -> String {
+        if let Some(title) = self
+            .command
+            .as_ref()
+            .and_then(|command| command["title"].as_str())
+        {
+            return title.to_string();
+        }
+
+        self.messages
+            .iter()
+            .find(|message| !message.content.trim().is_empty())
+            .map(|message| message.content.trim().chars().take(80).collect())
+            .unwrap_or_default()
+    }
+
+    fn text(&self) -> String {
+        self.messages
+            .iter()
+            .filter(|message| !message.content.trim().is_empty())
+            .map(|message| format!("{}: {}", message.author, message.content.trim()))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    thread_id: String,
+    message_id: String,
+    author: String,
+    /// Matching excerpt with matches wrapped in `[` and `]`.
+    snippet: String,
+    updated_at: u64,
+}
+
+fn insert_thread(connection: &Connection, thread: &Thread) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO threads (id, type, command, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (id) DO UPDATE SET
+            type = excluded.type,
+            command = excluded.command,
+            updated_at = excluded.updated_at",
+        params![
+            thread.id,
+            thread.thread_type,
+            thread.command.as_ref().map(|command| command.to_string()),
+            thread.created_at,
+            thread.updated_at
+        ],
+    )?;
+
+    connection.execute(
+        "DELETE FROM thread_messages WHERE thread_id = ?1",
+        params![thread.id],
+    )?;
+
+    for (position, message) in thread.messages.iter().enumerate() {
+        connection.execute(
+            "INSERT INTO thread_messages
+             (id, thread_id, position, author, content, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                message.id,
+                thread.id,
+                position,
+                message.author,
+                message.content,
+                message.created_at,
+                message.updated_at
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn query_messages(
+    connection: &Connection,
+    thread_id: Option<&str>,
+) -> rusqlite::Result<HashMap<String, Vec<ThreadMessage>>> {
+    let mut statement = connection.prepare(
+        "SELECT thread_id, id, author, content, created_at, updated_at FROM thread_messages
+         WHERE ?1 IS NULL OR thread_id = ?1
+         ORDER BY thread_id, position",
+    )?;
+
+    let mut messages = HashMap::<String, Vec<ThreadMessage>>::new();
+
+    let rows = statement.query_map(params![thread_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            ThreadMessage {
+                id: row.get(1)?,
+                author: row.get(2)?,
+                content: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            },
+        ))
+    })?;
+
+    for row in rows {
+        let (thread_id, message) = row?;
+        messages.entry(thread_id).or_default().push(message);
+    }
+
+    Ok(messages)
+}
+
+fn query_threads(
+    connection: &Connection,
+    id: Option<&str>,
+    limit: Option<usize>,
+    offset: usize,
+) -> rusqlite::Result<Vec<Thread>> {
+    let mut statement = connection.prepare(
+        "SELECT id, type, command, created_at, updated_at FROM threads
+         WHERE ?1 IS NULL OR id = ?1
+         ORDER BY updated_at DESC, rowid DESC
+         LIMIT ?2 OFFSET ?3",
+    )?;
+
+    let mut threads = statement
+        .query_map(
+            params![id, limit.map(|limit| limit as i64).unwrap_or(-1), offset],
+            |row| {
+                let command: Option<String> = row.get(2)?;
+
+                Ok(Thread {
+                    id: row.get(0)?,
+                    thread_type: row.get(1)?,
+                    messages: vec![],
+                    command: command.and_then(|command| serde_json::from_str(&command).ok()),
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut messages = query_messages(connection, id)?;
+
+    for thread in threads.iter_mut() {
+        thread.messages = messages.remove(&thread.id).unwrap_or_default();
+    }
+
+    Ok(threads)
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix, so user input can never
+/// be parsed as query syntax.
+fn to_match_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn query_search(
+    connection: &Connection,
+    query: &str,
+    limit: usize,
+) -> rusqlite::Result<Vec<SearchHit>> {
+    let mut statement = connection.prepare(
+        "SELECT m.thread_id, m.id, m.author,
+                snippet(thread_messages_fts, 0, '[', ']', '…', 16), t.updated_at
+         FROM thread_messages_fts
+         JOIN thread_messages m ON m.rowid = thread_messages_fts.rowid
+         JOIN threads t ON t.id = m.thread_id
+         WHERE thread_messages_fts MATCH ?1
+         ORDER BY bm25(thread_messages_fts), t.updated_at DESC
+         LIMIT ?2",
+    )?;
+
+    let hits = statement
+        .query_map(params![to_match_query(query), limit], |row| {
+            Ok(SearchHit {
+                thread_id: row.get(0)?,
+                message_id: row.get(1)?,
+                author: row.get(2)?,
+                snippet: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?
+        .collect();
+
+    hits
+}
+
+fn index(app_handle: &AppHandle<Wry>, thread: &Thread) {
+    let app_handle = app_handle.clone();
+    let (id, title, text) = (thread.id.clone(), thread.title(), thread.text());
+
+    tauri::async_runtime::spawn(async move {
+        retrieval::index_source(
+            &app_handle,
+            retrieval::SourceKind::Thread,
+            &id,
+            &title,
+            &text,
+        )
+        .await
+        .unwrap_or_default();
+    });
+}
+
+#[tauri::command]
+pub fn upsert_thread(app_handle: AppHandle<Wry>, mut thread: Thread) -> Result<(), String> {
+    let now = usage::now();
+
+    if thread.created_at == 0 {
+        thread.created_at = now;
+    }
+
+    thread.updated_at = now;
+
+    database::with_connection(&app_handle, |connection| {
+        let transaction = connection.transaction()?;
+        insert_thread(&transaction, &thread)?;
+        transaction.commit()
+    })
+    .map_err(|error| error.to_string())?;
+
+    index(&app_handle, &thread);
+
+    Ok(())
+}
+
+/// One-time import of threads kept by the webview before they moved to the database. Threads
+/// that already exist are left untouched; returns how many were imported.
+#[tauri::command]
+pub fn import_threads(app_handle: AppHandle<Wry>, threads: Vec<Thread>) -> Result<usize, String> {
+    let imported = database::with_connection(&app_handle, |connection| {
+        let transaction = connection.transaction()?;
+        let mut imported = vec![];
+
+        for thread in threads {
+            let exists = transaction
+                .query_row(
+                    "SELECT 1 FROM threads WHERE id = ?1",
+                    params![thread.id],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some();
+
+            if !exists {
+                insert_thread(&transaction, &thread)?;
+                imported.push(thread);
+            }
+        }
+
+        transaction.commit()?;
+
+        Ok(imported)
+    })
+    .map_err(|error| error.to_string())?;
+
+    for thread in &imported {
+        index(&app_handle, thread);
+    }
+
+    Ok(imported.len())
+}
+
+#[tauri::command]
+pub fn get_threads(
+    app_handle: AppHandle<Wry>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<String, String> {
+    let threads = database::with_connection(&app_handle, |connection| {
+        query_threads(connection, None, limit, offset.unwrap_or(0))
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&threads).expect("error serializing json"))
+}
+
+pub fn get(app_handle: &AppHandle<Wry>, id: &str) -> Result<Option<Thread>, String> {
+    let mut threads = database::with_connection(app_handle, |connection| {
+        query_threads(connection, Some(id), Some(1), 0)
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(threads.pop())
+}
+
+#[tauri::command]
+pub fn get_thread(app_handle: AppHandle<Wry>, id: String) -> Result<String, String> {
+    let thread = get(&app_handle, &id)?;
+    Ok(serde_json::to_string(&thread).expect("error serializing json"))
+}
+
+#[tauri::command]
+pub fn delete_thread(app_handle: AppHandle<Wry>, id: String) -> Result<(), String> {
+    database::with_connection(&app_handle, |connection| {
+        connection.execute("DELETE FROM threads WHERE id = ?1", params![id])
+    })
+    .map_err(|error| error.to_string())?;
+
+    retrieval::remove_source(&app_handle, retrieval::SourceKind::Thread, &id);
+
+    Ok(())
+}
+
+/// Deletes every thread, and through the cascade their messages, returning the deleted ids.
+fn delete_threads(connection: &Connection) -> rusqlite::Result<Vec<String>> {
+    let ids = connection
+        .prepare("SELECT id FROM threads")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    connection.execute("DELETE FROM threads", [])?;
+
+    Ok(ids)
+}
+
+#[tauri::command]
+pub fn delete_all_threads(app_handle: AppHandle<Wry>) -> Result<(), String> {
+    let ids = database::with_connection(&app_handle, |connection| delete_threads(connection))
+        .map_err(|error| error.to_string())?;
+
+    for id in ids {
+        retrieval::remove_source(&app_handle, retrieval::SourceKind::Thread, &id);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn search_threads(
+    app_handle: AppHandle<Wry>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<String, String> {
+    if query.trim().is_empty() {
+        return Ok("[]".to_string());
+    }
+
+    let hits = database::with_connection(&app_handle, |connection| {
+        query_search(connection, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&hits).expect("error serializing json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, author: &str, content: &str) -> ThreadMessage {
+        ThreadMessage {
+            id: id.to_string(),
+            author: author.to_string(),
+            content: content.to_string(),
+            created_at: 1,
+            updated_at: 1,
+        }
+    }
+
+    fn thread(id: &str, messages: Vec<ThreadMessage>) -> Thread {
+        Thread {
+            id: id.to_string(),
+            thread_type: "Chat".to_string(),
+            messages,
+            command: None,
+            created_at: 10,
+            updated_at: 10,
+        }
+    }
+
+    fn search(connection: &Connection, query: &str) -> Vec<String> {
+        query_search(connection, query, 10)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.message_id)
+            .collect()
+    }
+
+    /// Fails when the full-text index no longer matches the messages it indexes.
+    fn check_index(connection: &Connection) {
+        connection
+            .execute(
+                "INSERT INTO thread_messages_fts (thread_messages_fts) VALUES ('integrity-check')",
+                [],
+            )
+            .unwrap();
+    }
+
+    fn count(connection: &Connection, table: &str) -> i64 {
+        connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn upsert_replaces_messages() {
+        let connection = database::open_in_memory();
+
+        insert_thread(
+            &connection,
+            &thread(
+                "t1",
+                vec![
+                    message("m1", "User", "How do I install rust?"),
+                    message("m2", "AI", "Use rustup."),
+                ],
+            ),
+        )
+        .unwrap();
+
+        let mut updated = thread(
+            "t1",
+            vec![
+                message("m1", "User", "How do I install cargo?"),
+                message("m3", "AI", "It comes with rustup."),
+            ],
+        );
+        updated.created_at = 20;
+        updated.updated_at = 30;
+        updated.command = Some(serde_json::json!({ "title": "Install" }));
+
+        insert_thread(&connection, &updated).unwrap();
+
+        let threads = query_threads(&connection, None, None, 0).unwrap();
+
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].created_at, 10);
+        assert_eq!(threads[0].updated_at, 30);
+        assert_eq!(threads[0].title(), "Install");
+        assert_eq!(
+            threads[0]
+                .messages
+                .iter()
+                .map(|message| (message.id.as_str(), message.content.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("m1", "How do I install cargo?"),
+                ("m3", "It comes with rustup.")
+            ]
+        );
+        assert_eq!(count(&connection, "thread_messages"), 2);
+    }
+
+    #[test]
+    fn search_index_follows_updates_and_deletes() {
+        let connection = database::open_in_memory();
+
+        insert_thread(
+            &connection,
+            &thread(
+                "t1",
+                vec![
+                    message("m1", "User", "Which compiler flags matter?"),
+                    message("m2", "AI", "Start with the optimization level."),
+                ],
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(search(&connection, "compil"), vec!["m1"]);
+
+        insert_thread(
+            &connection,
+            &thread(
+                "t1",
+                vec![message("m1", "User", "Which linker flags matter?")],
+            ),
+        )
+        .unwrap();
+
+        assert!(search(&connection, "compiler").is_empty());
+        assert!(search(&connection, "optimization").is_empty());
+        assert_eq!(search(&connection, "linker"), vec!["m1"]);
+        check_index(&connection);
+
+        connection
+            .execute(
+                "UPDATE thread_messages SET content = 'Which debugger works?' WHERE id = 'm1'",
+                [],
+            )
+            .unwrap();
+
+        assert!(search(&connection, "linker").is_empty());
+        assert_eq!(search(&connection, "debugger"), vec!["m1"]);
+        check_index(&connection);
+
+        connection
+            .execute("DELETE FROM thread_messages WHERE id = 'm1'", [])
+            .unwrap();
+
+        assert!(search(&connection, "debugger").is_empty());
+        check_index(&connection);
+    }
+
+    #[test]
+    fn match_queries_quote_every_term() {
+        assert_eq!(to_match_query("rust  compiler"), r#""rust"* "compiler"*"#);
+        assert_eq!(
+            to_match_query(r#"say "hi" OR NEAR(x)"#),
+            r#""say"* """hi"""* "OR"* "NEAR(x)"*"#
+        );
+        assert_eq!(to_match_query("   "), "");
+
+        let connection = database::open_in_memory();
+
+        insert_thread(
+            &connection,
+            &thread("t1", vec![message("m1", "User", r#"He said "hi" OR bye"#)]),
+        )
+        .unwrap();
+
+        assert_eq!(search(&connection, r#""hi"#), vec!["m1"]);
+        assert_eq!(search(&connection, "hi OR"), vec!["m1"]);
+        assert!(search(&connection, "hi AND nothing").is_empty());
+        assert!(query_search(&connection, "NEAR(hi bye) *", 10).is_ok());
+    }
+
+    #[test]
+    fn deleting_all_threads_cascades_to_messages() {
+        let connection = database::open_in_memory();
+
+        insert_thread(
+            &connection,
+            &thread("t1", vec![message("m1", "User", "First thread")]),
+        )
+        .unwrap();
+        insert_thread(
+            &connection,
+            &thread(
+                "t2",
+                vec![
+                    message("m2", "User", "Second thread"),
+                    message("m3", "AI", "Reply"),
+                ],
+            ),
+        )
+        .unwrap();
+
+        let mut ids = delete_threads(&connection).unwrap();
+        ids.sort();
+
+        assert_eq!(ids, vec!["t1", "t2"]);
+        assert_eq!(count(&connection, "threads"), 0);
+        assert_eq!(count(&connection, "thread_messages"), 0);
+        assert!(search(&connection, "thread").is_empty());
+        check_index(&connection);
+    }
+}
@@ -0,0 +1,526 @@
+use futures::{channel::oneshot, future::BoxFuture, Future, FutureExt};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+
+use crate::cortex;
+
+mod calculator;
+mod provider;
+mod schema;
+pub mod shell;
+
+pub use provider::Provider;
+
+const DEFAULT_MAX_STEPS: usize = 8;
+
+/// How long a call waits for the user before it counts as declined.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Runs whenever the model calls it.
+    Allow,
+    /// Runs once the user approves the call in the webview.
+    Ask,
+    /// Never offered to the model.
+    Deny,
+}
+
+pub type Handler =
+    Arc<dyn Fn(AppHandle<Wry>, Value) -> BoxFuture<'static, Result<String, String>> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Tool {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the arguments object.
+    pub parameters: Value,
+    /// Permission used until the user picks one in the settings.
+    pub default_permission: Permission,
+    /// The handler requests approval itself on every call, e.g. to record declined calls, so the
+    /// registry never asks and `allow` is treated as `ask`.
+    pub asks_for_approval: bool,
+    pub handler: Handler,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolInfo {
+    name: String,
+    description: String,
+    parameters: Value,
+    permission: Permission,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    #[serde(flatten)]
+    call: ToolCall,
+    output: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Run {
+    /// The final assistant reply.
+    text: String,
+    steps: Vec<Step>,
+    /// The request messages extended with every tool round, for the next turn of the thread.
+    messages: Value,
+    response: Value,
+}
+
+/// Chat completion endpoint cortex sends chats to with the tools attached.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatEndpoint {
+    pub provider: Provider,
+    pub url: String,
+    pub model: String,
+    /// Name of the stored secret sent as the API key.
+    pub api_key_secret: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Model rounds with tool calls before the model is asked for a final answer.
+    pub max_steps: usize,
+    pub permissions: HashMap<String, Permission>,
+    /// Without an endpoint chats go to the Lander API, which does not call tools.
+    pub chat: Option<ChatEndpoint>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_steps: DEFAULT_MAX_STEPS,
+            permissions: HashMap::new(),
+            chat: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Store {
+    tools: BTreeMap<String, Tool>,
+    approvals: HashMap<u64, oneshot::Sender<bool>>,
+    next_approval_id: u64,
+}
+
+#[derive(Default)]
+pub struct State(pub Mutex<Store>);
+
+/// Wraps an async function as a tool handler.
+pub fn handler<F, R>(f: F) -> Handler
+where
+    F: Fn(AppHandle<Wry>, Value) -> R + Send + Sync + 'static,
+    R: Future<Output = Result<String, String>> + Send + 'static,
+{
+    Arc::new(move |app_handle, arguments| f(app_handle, arguments).boxed())
+}
+
+pub fn register(app_handle: &AppHandle<Wry>, tool: Tool) {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+    store.tools.insert(tool.name.clone(), tool);
+}
+
+pub fn unregister(app_handle: &AppHandle<Wry>, name: &str) {
+    let state = app_handle.state::<State>();
+    let mut store = state.0.lock().unwrap();
+    store.tools.remove(name);
+}
+
+pub fn is_registered(app_handle: &AppHandle<Wry>, name: &str) -> bool {
+    let state = app_handle.state::<State>();
+    let store = state.0.lock().unwrap();
+    store.tools.contains_key(name)
+}
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("tools")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn set_settings(app_handle: &AppHandle<Wry>, settings: &Settings) {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("tools".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+}
+
+fn permission(settings: &Settings, tool: &Tool) -> Permission {
+    let permission = settings
+        .permissions
+        .get(&tool.name)
+        .copied()
+        .unwrap_or(tool.default_permission);
+
+    match permission {
+        Permission::Allow if tool.asks_for_approval => Permission::Ask,
+        permission => permission,
+    }
+}
+
+fn get_tool(app_handle: &AppHandle<Wry>, name: &str) -> Option<Tool> {
+    let state = app_handle.state::<State>();
+    let store = state.0.lock().unwrap();
+    store.tools.get(name).cloned()
+}
+
+/// Registered tools that may be offered to the model, optionally limited to `names`.
+fn get_enabled_tools(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    names: Option<&[String]>,
+) -> Vec<Tool> {
+    let state = app_handle.state::<State>();
+    let store = state.0.lock().unwrap();
+
+    store
+        .tools
+        .values()
+        .filter(|tool| permission(settings, tool) != Permission::Deny)
+        .filter(|tool| {
+            names
+                .map(|names| names.contains(&tool.name))
+                .unwrap_or(true)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Asks the webview to approve a call and waits for `resolve_tool_approval`. Without an answer
+/// within `APPROVAL_TIMEOUT` the call is declined and a `tool_approval_expired` event withdraws
+/// the request.
+pub async fn request_approval(app_handle: &AppHandle<Wry>, name: &str, arguments: &Value) -> bool {
+    let (sender, receiver) = oneshot::channel();
+
+    let id = {
+        let state = app_handle.state::<State>();
+        let mut store = state.0.lock().unwrap();
+
+        store.next_approval_id += 1;
+        let id = store.next_approval_id;
+        store.approvals.insert(id, sender);
+
+        id
+    };
+
+    app_handle
+        .emit_all(
+            "tool_approval_request",
+            json!({ "id": id, "name": name, "arguments": arguments }),
+        )
+        .unwrap_or_default();
+
+    match tokio::time::timeout(APPROVAL_TIMEOUT, receiver).await {
+        Ok(approved) => approved.unwrap_or(false),
+        Err(_) => {
+            app_handle
+                .state::<State>()
+                .0
+                .lock()
+                .unwrap()
+                .approvals
+                .remove(&id);
+
+            app_handle
+                .emit_all("tool_approval_expired", json!({ "id": id }))
+                .unwrap_or_default();
+
+            false
+        }
+    }
+}
+
+/// Checks the permission and arguments of a call before running it. Errors are returned to the
+/// model as the tool result, so they should explain what to do differently.
+pub async fn execute(
+    app_handle: &AppHandle<Wry>,
+    settings: &Settings,
+    call: &ToolCall,
+) -> Result<String, String> {
+    let tool =
+        get_tool(app_handle, &call.name).ok_or_else(|| format!("unknown tool `{}`", call.name))?;
+
+    let permission = permission(settings, &tool);
+
+    if permission == Permission::Deny {
+        return Err(format!("the user has not allowed `{}`", tool.name));
+    }
+
+    schema::validate(&tool.parameters, &call.arguments)
+        .map_err(|error| format!("invalid arguments: {}", error))?;
+
+    if permission == Permission::Ask
+        && !tool.asks_for_approval
+        && !request_approval(app_handle, &call.name, &call.arguments).await
+    {
+        return Err(format!("the user declined to run `{}`", tool.name));
+    }
+
+    (tool.handler)(app_handle.clone(), call.arguments.clone()).await
+}
+
+fn build_headers(
+    app_handle: &AppHandle<Wry>,
+    url: &str,
+    headers: HashMap<String, String>,
+) -> Result<HeaderMap, String> {
+    let mut header_map = HeaderMap::new();
+
+    for (name, value) in headers {
+        let value = crate::secrets::resolve_placeholders(app_handle, &value, url)
+            .map_err(|error| error.to_string())?;
+
+        header_map.insert(
+            HeaderName::from_bytes(name.as_bytes()).map_err(|error| error.to_string())?,
+            HeaderValue::from_str(&value).map_err(|error| error.to_string())?,
+        );
+    }
+
+    header_map.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    Ok(header_map)
+}
+
+fn setup_builtin_tools(app_handle: &AppHandle<Wry>) {
+    register(
+        app_handle,
+        Tool {
+            name: "calculator".to_string(),
+            description: "Evaluates a mathematical expression, e.g. `sqrt(2) * (3 + 4)^2`."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "expression": { "type": "string", "minLength": 1 },
+                },
+                "required": ["expression"],
+                "additionalProperties": false,
+            }),
+            default_permission: Permission::Allow,
+            asks_for_approval: false,
+            handler: handler(|_, arguments| async move {
+                let expression = arguments["expression"].as_str().unwrap_or_default();
+                calculator::evaluate(expression).map(calculator::format)
+            }),
+        },
+    );
+
+    register(
+        app_handle,
+        Tool {
+            name: "web_search".to_string(),
+            description: "Searches the web and returns the featured snippet. Useful for current \
+                events and facts that may have changed."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "minLength": 1 },
+                    "browser": { "type": "string", "enum": ["chrome", "firefox"] },
+                },
+                "required": ["query"],
+                "additionalProperties": false,
+            }),
+            default_permission: Permission::Allow,
+            asks_for_approval: false,
+            handler: handler(|app_handle, arguments| async move {
+                let query = arguments["query"].as_str().unwrap_or_default().to_string();
+                let browser = arguments["browser"]
+                    .as_str()
+                    .unwrap_or("chrome")
+                    .to_string();
+
+                // The search panics when the browser or its driver is missing.
+                tauri::async_runtime::spawn(cortex::google_search(app_handle, browser, query))
+                    .await
+                    .map_err(|_| "the search failed".to_string())?
+            }),
+        },
+    );
+
+    register(app_handle, shell::tool());
+}
+
+pub fn setup(app_handle: AppHandle<Wry>) {
+    setup_builtin_tools(&app_handle);
+}
+
+#[tauri::command]
+pub fn get_tools(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    let state = app_handle.state::<State>();
+    let store = state.0.lock().unwrap();
+
+    let tools = store
+        .tools
+        .values()
+        .map(|tool| ToolInfo {
+            name: tool.name.clone(),
+            description: tool.description.clone(),
+            parameters: tool.parameters.clone(),
+            permission: permission(&settings, tool),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&tools).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn get_tool_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_tool_settings(app_handle: AppHandle<Wry>, settings: Settings) {
+    set_settings(&app_handle, &settings);
+}
+
+#[tauri::command]
+pub fn resolve_tool_approval(app_handle: AppHandle<Wry>, id: u64, approved: bool) {
+    let sender = app_handle
+        .state::<State>()
+        .0
+        .lock()
+        .unwrap()
+        .approvals
+        .remove(&id);
+
+    if let Some(sender) = sender {
+        sender.send(approved).unwrap_or_default();
+    }
+}
+
+/// Sends `request` to a chat completion endpoint with the registered tools attached, runs the
+/// tool calls of every response and feeds their results back until the model answers without
+/// calling a tool. After `max_steps` rounds tool calls are disabled so the model has to answer.
+///
+/// Header values may reference stored secrets bound to the host of `url` as `{{secret:<name>}}`.
+/// Every executed call is emitted as a `tool_step` event while the run continues.
+#[tauri::command]
+pub async fn run_tools(
+    app_handle: AppHandle<Wry>,
+    provider: Provider,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    mut request: Value,
+    tools: Option<Vec<String>>,
+) -> Result<String, String> {
+    let settings = get_settings(&app_handle);
+    let enabled_tools = get_enabled_tools(&app_handle, &settings, tools.as_deref());
+    let headers = build_headers(&app_handle, &url, headers.unwrap_or_default())?;
+
+    if !request["messages"].is_array() {
+        return Err("the request has no messages".to_string());
+    }
+
+    if let Value::Object(request) = &mut request {
+        request.insert("stream".to_string(), Value::Bool(false));
+    }
+
+    let client = Client::new();
+    let mut steps = vec![];
+    let mut round = 0;
+
+    loop {
+        let allows_calls = round < settings.max_steps;
+        provider.set_tools(&mut request, &enabled_tools, allows_calls);
+
+        let response = client
+            .post(&url)
+            .headers(headers.clone())
+            .body(request.to_string())
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+
+        let status = response.status();
+        let text = response.text().await.map_err(|error| error.to_string())?;
+
+        if !status.is_success() {
+            // Only the provider's error message goes back to the webview, since a body can echo
+            // the request with its resolved headers.
+            let message = serde_json::from_str::<Value>(&text)
+                .ok()
+                .and_then(|body| body["error"]["message"].as_str().map(str::to_string));
+
+            return Err(match message {
+                Some(message) => format!("request failed with {}: {}", status, message),
+                None => format!("request failed with {}", status),
+            });
+        }
+
+        let response: Value = serde_json::from_str(&text).map_err(|error| error.to_string())?;
+        let calls = provider.tool_calls(&response);
+
+        if calls.is_empty() || !allows_calls {
+            let run = Run {
+                text: provider.text(&response),
+                steps,
+                messages: request["messages"].take(),
+                response,
+            };
+
+            return Ok(serde_json::to_string(&run).expect("error serializing json"));
+        }
+
+        let mut results = vec![];
+
+        for call in calls {
+            let result = execute(&app_handle, &settings, &call).await;
+
+            let step = Step {
+                call: call.clone(),
+                output: result.as_ref().ok().cloned(),
+                error: result.as_ref().err().cloned(),
+            };
+
+            app_handle.emit_all("tool_step", &step).unwrap_or_default();
+
+            steps.push(step);
+            results.push((call, result));
+        }
+
+        provider.push_results(&mut request, &response, &results);
+        round += 1;
+    }
+}
@@ -0,0 +1,277 @@
+use std::{iter::Peekable, str::Chars};
+
+/// Evaluates an arithmetic expression with `+ - * / % ^`, parentheses, the constants `pi` and
+/// `e`, and common functions such as `sqrt(x)` or `log(x)`.
+pub fn evaluate(expression: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        characters: expression.chars().peekable(),
+    };
+
+    let value = parser.expression()?;
+    parser.skip_whitespace();
+
+    match parser.characters.next() {
+        Some(character) => Err(format!("unexpected `{}`", character)),
+        None if value.is_finite() => Ok(value),
+        None => Err("the result is not a finite number".to_string()),
+    }
+}
+
+/// Formats a result without a trailing `.0` and without float noise such as `0.30000000000000004`.
+pub fn format(value: f64) -> String {
+    // Large values have no fractional digits to round, and scaling them up could overflow.
+    let rounded = if value.abs() < 1e15 {
+        (value * 1e12).round() / 1e12
+    } else {
+        value
+    };
+
+    if rounded.fract() == 0.0 && rounded.abs() < 1e15 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+struct Parser<'a> {
+    characters: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .characters
+            .peek()
+            .map(|character| character.is_whitespace())
+            .unwrap_or(false)
+        {
+            self.characters.next();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.characters.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(character) if character == expected => {
+                self.characters.next();
+                Ok(())
+            }
+            Some(character) => Err(format!("expected `{}`, found `{}`", expected, character)),
+            None => Err(format!("expected `{}`", expected)),
+        }
+    }
+
+    /// expression = term (("+" | "-") term)*
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.characters.next();
+                    value += self.term()?;
+                }
+                Some('-') => {
+                    self.characters.next();
+                    value -= self.term()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    /// term = unary (("*" | "/" | "%") unary)*
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+
+        loop {
+            match self.peek() {
+                Some('*') | Some('×') => {
+                    self.characters.next();
+                    value *= self.unary()?;
+                }
+                Some('/') | Some('÷') => {
+                    self.characters.next();
+                    value /= self.unary()?;
+                }
+                Some('%') => {
+                    self.characters.next();
+                    value %= self.unary()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    /// unary = ("-" | "+") unary | power
+    fn unary(&mut self) -> Result<f64, String> {
+        match self.peek() {
+            Some('-') => {
+                self.characters.next();
+                Ok(-self.unary()?)
+            }
+            Some('+') => {
+                self.characters.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// power = atom ("^" unary)?, right-associative so `2^3^2` is `2^9`.
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.atom()?;
+
+        if self.peek() == Some('^') {
+            self.characters.next();
+            return Ok(base.powf(self.unary()?));
+        }
+
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64, String> {
+        match self.peek() {
+            Some('(') => {
+                self.characters.next();
+                let value = self.expression()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(character) if character.is_ascii_digit() || character == '.' => self.number(),
+            Some(character) if character.is_alphabetic() => self.identifier(),
+            Some(character) => Err(format!("unexpected `{}`", character)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let mut number = String::new();
+
+        while let Some(&character) = self.characters.peek() {
+            if character.is_ascii_digit() || character == '.' {
+                number.push(character);
+            } else if character == '_' || character == ',' {
+                // Digit separators, as in `1,000,000`.
+            } else if (character == 'e' || character == 'E') && !number.contains('e') {
+                number.push('e');
+                self.characters.next();
+
+                if let Some(&sign) = self.characters.peek() {
+                    if sign == '-' || sign == '+' {
+                        number.push(sign);
+                        self.characters.next();
+                    }
+                }
+
+                continue;
+            } else {
+                break;
+            }
+
+            self.characters.next();
+        }
+
+        number
+            .parse()
+            .map_err(|_| format!("invalid number `{}`", number))
+    }
+
+    fn identifier(&mut self) -> Result<f64, String> {
+        let mut name = String::new();
+
+        while let Some(&character) = self.characters.peek() {
+            if !character.is_alphanumeric() {
+                break;
+            }
+
+            name.push(character);
+            self.characters.next();
+        }
+
+        let name = name.to_lowercase();
+
+        match name.as_str() {
+            "pi" | "π" => return Ok(std::f64::consts::PI),
+            "e" => return Ok(std::f64::consts::E),
+            _ => {}
+        }
+
+        self.expect('(')?;
+        let argument = self.expression()?;
+        self.expect(')')?;
+
+        let value = match name.as_str() {
+            "sqrt" => argument.sqrt(),
+            "cbrt" => argument.cbrt(),
+            "abs" => argument.abs(),
+            "exp" => argument.exp(),
+            "ln" => argument.ln(),
+            "log" | "log10" => argument.log10(),
+            "log2" => argument.log2(),
+            "sin" => argument.sin(),
+            "cos" => argument.cos(),
+            "tan" => argument.tan(),
+            "asin" => argument.asin(),
+            "acos" => argument.acos(),
+            "atan" => argument.atan(),
+            "round" => argument.round(),
+            "floor" => argument.floor(),
+            "ceil" => argument.ceil(),
+            _ => return Err(format!("unknown function `{}`", name)),
+        };
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_follows_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(evaluate("-2^2"), Ok(-4.0));
+        assert_eq!(evaluate("2^3^2"), Ok(512.0));
+        assert_eq!(evaluate("2^-1"), Ok(0.5));
+        assert_eq!(evaluate("10 % 4 * 2"), Ok(4.0));
+    }
+
+    #[test]
+    fn evaluate_reads_separators_functions_and_constants() {
+        assert_eq!(evaluate("1,000 + 1"), Ok(1001.0));
+        assert_eq!(evaluate("1_000_000 / 1e3"), Ok(1000.0));
+        assert_eq!(evaluate("sqrt(16) * 2"), Ok(8.0));
+        assert_eq!(evaluate("PI"), Ok(std::f64::consts::PI));
+    }
+
+    #[test]
+    fn evaluate_rejects_invalid_expressions() {
+        assert!(evaluate("2 *").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("foo(1)").is_err());
+        assert!(evaluate("1 / 0").is_err());
+        assert_eq!(evaluate("1 + 2)"), Err("unexpected `)`".to_string()));
+    }
+
+    #[test]
+    fn format_drops_float_noise() {
+        assert_eq!(format(0.1 + 0.2), "0.3");
+        assert_eq!(format(2.0), "2");
+        assert_eq!(format(-4.0), "-4");
+        assert_eq!(format(1.5), "1.5");
+    }
+
+    #[test]
+    fn format_keeps_large_values() {
+        assert_eq!(format(1e15), "1000000000000000");
+        assert_eq!(format(1e300), format!("{}", 1e300));
+        assert_eq!(format(-f64::MAX), format!("{}", -f64::MAX));
+    }
+}
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{Tool, ToolCall};
+
+/// Tool-calling wire format of the chat completion endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    /// `/v1/chat/completions` with `tools` and `tool_calls`.
+    OpenAi,
+    /// `/v1/messages` with `tools`, `tool_use` and `tool_result` blocks.
+    Anthropic,
+}
+
+impl Provider {
+    pub fn tool_definitions(&self, tools: &[Tool]) -> Value {
+        let definitions = tools
+            .iter()
+            .map(|tool| match self {
+                Provider::OpenAi => json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": tool.parameters,
+                    },
+                }),
+                Provider::Anthropic => json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "input_schema": tool.parameters,
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        Value::Array(definitions)
+    }
+
+    /// Sets the tools offered in `request`. Without `allows_calls` the definitions stay, since
+    /// earlier tool turns refer to them, but the model is told to answer directly.
+    pub fn set_tools(&self, request: &mut Value, tools: &[Tool], allows_calls: bool) {
+        if let Value::Object(request) = request {
+            if tools.is_empty() {
+                request.remove("tools");
+                request.remove("tool_choice");
+                return;
+            }
+
+            request.insert("tools".to_string(), self.tool_definitions(tools));
+
+            let tool_choice = match (self, allows_calls) {
+                (Provider::OpenAi, true) => json!("auto"),
+                (Provider::OpenAi, false) => json!("none"),
+                (Provider::Anthropic, true) => json!({ "type": "auto" }),
+                (Provider::Anthropic, false) => json!({ "type": "none" }),
+            };
+
+            request.insert("tool_choice".to_string(), tool_choice);
+        }
+    }
+
+    /// Tool calls requested by a non-streamed response.
+    pub fn tool_calls(&self, response: &Value) -> Vec<ToolCall> {
+        match self {
+            Provider::OpenAi => response["choices"][0]["message"]["tool_calls"]
+                .as_array()
+                .map(|calls| {
+                    calls
+                        .iter()
+                        .map(|call| {
+                            let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+
+                            ToolCall {
+                                id: call["id"].as_str().unwrap_or_default().to_string(),
+                                name: call["function"]["name"]
+                                    .as_str()
+                                    .unwrap_or_default()
+                                    .to_string(),
+                                // Malformed arguments are kept as a string and rejected by
+                                // validation, which tells the model what went wrong.
+                                arguments: serde_json::from_str(arguments)
+                                    .unwrap_or_else(|_| Value::String(arguments.to_string())),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Provider::Anthropic => response["content"]
+                .as_array()
+                .map(|blocks| {
+                    blocks
+                        .iter()
+                        .filter(|block| block["type"] == "tool_use")
+                        .map(|block| ToolCall {
+                            id: block["id"].as_str().unwrap_or_default().to_string(),
+                            name: block["name"].as_str().unwrap_or_default().to_string(),
+                            arguments: block["input"].clone(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Text of the assistant reply in a non-streamed response.
+    pub fn text(&self, response: &Value) -> String {
+        match self {
+            Provider::OpenAi => response["choices"][0]["message"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            Provider::Anthropic => response["content"]
+                .as_array()
+                .map(|blocks| {
+                    blocks
+                        .iter()
+                        .filter(|block| block["type"] == "text")
+                        .filter_map(|block| block["text"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("")
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Appends the assistant turn that requested the tool calls, followed by their results, to the
+    /// messages of `request` so the next round continues the conversation.
+    pub fn push_results(
+        &self,
+        request: &mut Value,
+        response: &Value,
+        results: &[(ToolCall, Result<String, String>)],
+    ) {
+        let mut turns = match self {
+            Provider::OpenAi => {
+                let mut turns = vec![response["choices"][0]["message"].clone()];
+
+                turns.extend(results.iter().map(|(call, result)| {
+                    json!({
+                        "role": "tool",
+                        "tool_call_id": call.id,
+                        "content": match result {
+                            Ok(output) => output.clone(),
+                            Err(error) => format!("Error: {}", error),
+                        },
+                    })
+                }));
+
+                turns
+            }
+            Provider::Anthropic => {
+                let results = results
+                    .iter()
+                    .map(|(call, result)| match result {
+                        Ok(output) => json!({
+                            "type": "tool_result",
+                            "tool_use_id": call.id,
+                            "content": output,
+                        }),
+                        Err(error) => json!({
+                            "type": "tool_result",
+                            "tool_use_id": call.id,
+                            "content": error,
+                            "is_error": true,
+                        }),
+                    })
+                    .collect::<Vec<_>>();
+
+                vec![
+                    json!({ "role": "assistant", "content": response["content"] }),
+                    json!({ "role": "user", "content": results }),
+                ]
+            }
+        };
+
+        if let Some(messages) = request["messages"].as_array_mut() {
+            messages.append(&mut turns);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::{handler, Permission};
+
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: "Evaluates an expression".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": { "expression": { "type": "string" } },
+            }),
+            default_permission: Permission::Allow,
+            asks_for_approval: false,
+            handler: handler(|_, _| async { Ok(String::new()) }),
+        }
+    }
+
+    fn results(calls: &[ToolCall]) -> Vec<(ToolCall, Result<String, String>)> {
+        vec![
+            (calls[0].clone(), Ok("4".to_string())),
+            (calls[1].clone(), Err("unknown function `foo`".to_string())),
+        ]
+    }
+
+    #[test]
+    fn open_ai_round_trip() {
+        let provider = Provider::OpenAi;
+        let mut request = json!({
+            "model": "gpt-4o",
+            "messages": [{ "role": "user", "content": "What is 2 + 2?" }],
+        });
+
+        provider.set_tools(&mut request, &[tool("calculator")], true);
+
+        assert_eq!(request["tool_choice"], "auto");
+        assert_eq!(request["tools"][0]["type"], "function");
+        assert_eq!(request["tools"][0]["function"]["name"], "calculator");
+        assert_eq!(
+            request["tools"][0]["function"]["parameters"]["properties"]["expression"]["type"],
+            "string"
+        );
+
+        let response = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [
+                        {
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {
+                                "name": "calculator",
+                                "arguments": "{\"expression\":\"2 + 2\"}",
+                            },
+                        },
+                        {
+                            "id": "call_2",
+                            "type": "function",
+                            "function": { "name": "calculator", "arguments": "{not json" },
+                        },
+                    ],
+                },
+            }],
+        });
+
+        let calls = provider.tool_calls(&response);
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].name, "calculator");
+        assert_eq!(calls[0].arguments, json!({ "expression": "2 + 2" }));
+        assert_eq!(calls[1].arguments, json!("{not json"));
+        assert_eq!(provider.text(&response), "");
+
+        provider.push_results(&mut request, &response, &results(&calls));
+
+        let messages = request["messages"].as_array().unwrap();
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1], response["choices"][0]["message"]);
+        assert_eq!(
+            messages[2],
+            json!({ "role": "tool", "tool_call_id": "call_1", "content": "4" })
+        );
+        assert_eq!(
+            messages[3],
+            json!({
+                "role": "tool",
+                "tool_call_id": "call_2",
+                "content": "Error: unknown function `foo`",
+            })
+        );
+
+        provider.set_tools(&mut request, &[tool("calculator")], false);
+
+        assert_eq!(request["tool_choice"], "none");
+        assert!(request["tools"].is_array());
+
+        let answer = json!({
+            "choices": [{ "message": { "role": "assistant", "content": "2 + 2 is 4." } }],
+        });
+
+        assert!(provider.tool_calls(&answer).is_empty());
+        assert_eq!(provider.text(&answer), "2 + 2 is 4.");
+    }
+
+    #[test]
+    fn anthropic_round_trip() {
+        let provider = Provider::Anthropic;
+        let mut request = json!({
+            "model": "claude",
+            "max_tokens": 1024,
+            "messages": [{ "role": "user", "content": "What is 2 + 2?" }],
+        });
+
+        provider.set_tools(&mut request, &[tool("calculator")], true);
+
+        assert_eq!(request["tool_choice"], json!({ "type": "auto" }));
+        assert_eq!(request["tools"][0]["name"], "calculator");
+        assert_eq!(
+            request["tools"][0]["input_schema"]["properties"]["expression"]["type"],
+            "string"
+        );
+
+        let response = json!({
+            "role": "assistant",
+            "content": [
+                { "type": "text", "text": "Let me calculate that." },
+                {
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "calculator",
+                    "input": { "expression": "2 + 2" },
+                },
+                {
+                    "type": "tool_use",
+                    "id": "toolu_2",
+                    "name": "calculator",
+                    "input": { "expression": "foo(1)" },
+                },
+            ],
+        });
+
+        let calls = provider.tool_calls(&response);
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "toolu_1");
+        assert_eq!(calls[0].arguments, json!({ "expression": "2 + 2" }));
+        assert_eq!(provider.text(&response), "Let me calculate that.");
+
+        provider.push_results(&mut request, &response, &results(&calls));
+
+        let messages = request["messages"].as_array().unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1],
+            json!({ "role": "assistant", "content": response["content"] })
+        );
+        assert_eq!(
+            messages[2],
+            json!({
+                "role": "user",
+                "content": [
+                    { "type": "tool_result", "tool_use_id": "toolu_1", "content": "4" },
+                    {
+                        "type": "tool_result",
+                        "tool_use_id": "toolu_2",
+                        "content": "unknown function `foo`",
+                        "is_error": true,
+                    },
+                ],
+            })
+        );
+
+        provider.set_tools(&mut request, &[tool("calculator")], false);
+
+        assert_eq!(request["tool_choice"], json!({ "type": "none" }));
+    }
+
+    #[test]
+    fn set_tools_without_tools_removes_them() {
+        let mut request = json!({ "messages": [], "tools": [], "tool_choice": "auto" });
+
+        Provider::OpenAi.set_tools(&mut request, &[], true);
+
+        assert_eq!(request, json!({ "messages": [] }));
+    }
+}
@@ -0,0 +1,319 @@
+use regex::Regex;
+use serde_json::Value;
+
+/// Checks `value` against the subset of JSON Schema tools declare their arguments with: `type`,
+/// `enum`, `properties`, `required`, `additionalProperties`, `items` and the usual length and
+/// range bounds. Returns the first violation, prefixed with the path of the offending value.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), String> {
+    validate_at(schema, value, "$")
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value
+            .as_f64()
+            .map(|number| number.fract() == 0.0)
+            .unwrap_or(false),
+        expected => type_name(value) == expected,
+    }
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        // `true` and a missing schema accept anything, `false` accepts nothing.
+        Value::Bool(false) => return Err(format!("{}: no value is allowed here", path)),
+        _ => return Ok(()),
+    };
+
+    match schema.get("type") {
+        Some(Value::String(expected)) if !is_type(value, expected) => {
+            return Err(format!(
+                "{}: expected {}, got {}",
+                path,
+                expected,
+                type_name(value)
+            ));
+        }
+        Some(Value::Array(expected))
+            if !expected
+                .iter()
+                .filter_map(|expected| expected.as_str())
+                .any(|expected| is_type(value, expected)) =>
+        {
+            let expected = expected
+                .iter()
+                .filter_map(|expected| expected.as_str())
+                .collect::<Vec<_>>()
+                .join(" or ");
+
+            return Err(format!(
+                "{}: expected {}, got {}",
+                path,
+                expected,
+                type_name(value)
+            ));
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed = allowed
+                .iter()
+                .map(|allowed| allowed.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            return Err(format!("{}: expected one of {}", path, allowed));
+        }
+    }
+
+    match value {
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+
+            if let Some(minimum) = schema.get("minimum").and_then(|minimum| minimum.as_f64()) {
+                if number < minimum {
+                    return Err(format!("{}: must be at least {}", path, minimum));
+                }
+            }
+
+            if let Some(maximum) = schema.get("maximum").and_then(|maximum| maximum.as_f64()) {
+                if number > maximum {
+                    return Err(format!("{}: must be at most {}", path, maximum));
+                }
+            }
+        }
+        Value::String(string) => {
+            let length = string.chars().count() as u64;
+
+            if let Some(min_length) = schema.get("minLength").and_then(|min| min.as_u64()) {
+                if length < min_length {
+                    return Err(format!(
+                        "{}: must be at least {} characters long",
+                        path, min_length
+                    ));
+                }
+            }
+
+            if let Some(max_length) = schema.get("maxLength").and_then(|max| max.as_u64()) {
+                if length > max_length {
+                    return Err(format!(
+                        "{}: must be at most {} characters long",
+                        path, max_length
+                    ));
+                }
+            }
+
+            if let Some(pattern) = schema.get("pattern").and_then(|pattern| pattern.as_str()) {
+                let regex = Regex::new(pattern).map_err(|error| {
+                    format!("{}: invalid pattern `{}`: {}", path, pattern, error)
+                })?;
+
+                if !regex.is_match(string) {
+                    return Err(format!("{}: must match `{}`", path, pattern));
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min_items) = schema.get("minItems").and_then(|min| min.as_u64()) {
+                if (items.len() as u64) < min_items {
+                    return Err(format!("{}: must have at least {} items", path, min_items));
+                }
+            }
+
+            if let Some(max_items) = schema.get("maxItems").and_then(|max| max.as_u64()) {
+                if items.len() as u64 > max_items {
+                    return Err(format!("{}: must have at most {} items", path, max_items));
+                }
+            }
+
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}[{}]", path, index))?;
+                }
+            }
+        }
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(|name| name.as_str()) {
+                    if !object.contains_key(name) {
+                        return Err(format!("{}: missing required property `{}`", path, name));
+                    }
+                }
+            }
+
+            let properties = schema
+                .get("properties")
+                .and_then(|properties| properties.as_object());
+
+            for (name, property) in object {
+                let property_path = format!("{}.{}", path, name);
+
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property_schema) => {
+                        validate_at(property_schema, property, &property_path)?
+                    }
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(format!("{}: unknown property `{}`", path, name));
+                        }
+                        Some(additional_schema) => {
+                            validate_at(additional_schema, property, &property_path)?
+                        }
+                        None => {}
+                    },
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "minLength": 1, "pattern": "^[a-z ]+$" },
+                "limit": { "type": "integer", "minimum": 1, "maximum": 10 },
+                "browser": { "type": "string", "enum": ["chrome", "firefox"] },
+                "filters": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "field": { "type": ["string", "null"] } },
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["query"],
+            "additionalProperties": false,
+        })
+    }
+
+    #[test]
+    fn accepts_valid_arguments() {
+        let arguments = json!({
+            "query": "weather",
+            "limit": 3,
+            "browser": "firefox",
+            "filters": [{ "field": "title" }, { "field": null }],
+        });
+
+        assert_eq!(validate(&schema(), &arguments), Ok(()));
+    }
+
+    #[test]
+    fn rejects_wrong_types() {
+        assert_eq!(
+            validate(&schema(), &json!("weather")),
+            Err("$: expected object, got string".to_string())
+        );
+        assert_eq!(
+            validate(&schema(), &json!({ "query": "weather", "limit": 1.5 })),
+            Err("$.limit: expected integer, got number".to_string())
+        );
+        assert_eq!(
+            validate(
+                &schema(),
+                &json!({ "query": "weather", "filters": [{ "field": 1 }] })
+            ),
+            Err("$.filters[0].field: expected string or null, got number".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_values_outside_enum() {
+        assert_eq!(
+            validate(
+                &schema(),
+                &json!({ "query": "weather", "browser": "safari" })
+            ),
+            Err(r#"$.browser: expected one of "chrome", "firefox""#.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_missing_required_properties() {
+        assert_eq!(
+            validate(&schema(), &json!({ "limit": 3 })),
+            Err("$: missing required property `query`".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_additional_properties() {
+        assert_eq!(
+            validate(&schema(), &json!({ "query": "weather", "page": 2 })),
+            Err("$: unknown property `page`".to_string())
+        );
+        assert_eq!(
+            validate(
+                &schema(),
+                &json!({ "query": "weather", "filters": [{}, { "value": 1 }] })
+            ),
+            Err("$.filters[1]: unknown property `value`".to_string())
+        );
+
+        let schema = json!({ "type": "object", "additionalProperties": { "type": "number" } });
+
+        assert_eq!(validate(&schema, &json!({ "a": 1 })), Ok(()));
+        assert_eq!(
+            validate(&schema, &json!({ "a": "1" })),
+            Err("$.a: expected number, got string".to_string())
+        );
+    }
+
+    #[test]
+    fn checks_bounds_and_patterns() {
+        assert_eq!(
+            validate(&schema(), &json!({ "query": "" })),
+            Err("$.query: must be at least 1 characters long".to_string())
+        );
+        assert_eq!(
+            validate(&schema(), &json!({ "query": "weather", "limit": 11 })),
+            Err("$.limit: must be at most 10".to_string())
+        );
+        assert_eq!(
+            validate(&schema(), &json!({ "query": "Weather" })),
+            Err("$.query: must match `^[a-z ]+$`".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let schema = json!({ "type": "string", "pattern": "(" });
+
+        assert!(validate(&schema, &json!("anything"))
+            .unwrap_err()
+            .starts_with("$: invalid pattern `(`"));
+    }
+
+    #[test]
+    fn boolean_schemas() {
+        assert_eq!(validate(&json!(true), &json!([1, "a"])), Ok(()));
+        assert_eq!(
+            validate(&json!(false), &json!(1)),
+            Err("$: no value is allowed here".to_string())
+        );
+    }
+}
@@ -0,0 +1,692 @@
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+use tokio::sync::mpsc;
+
+use super::{handler, Permission, Tool};
+use crate::{database, usage};
+
+mod process;
+
+pub const NAME: &str = "run_command";
+
+const DEFAULT_LOG_LIMIT: usize = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Commands may only start in these directories or below them. On macOS they are also the
+    /// only places commands can write to, besides the temporary directory; elsewhere nothing
+    /// stops a command from changing directory once it runs.
+    pub allowed_directories: Vec<PathBuf>,
+    /// Regular expressions of commands that are rejected without asking.
+    pub blocked_patterns: Vec<String>,
+    /// Seconds a command may run when the model does not ask for a timeout.
+    pub default_timeout: u64,
+    pub max_timeout: u64,
+    /// Characters of output returned to the model and kept in the log.
+    pub max_output_length: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            allowed_directories: dirs::home_dir().into_iter().collect(),
+            blocked_patterns: vec![
+                r"\bsudo\b".to_string(),
+                r"\bsu\b".to_string(),
+                r"\bmkfs".to_string(),
+                r"\b(shutdown|reboot|halt)\b".to_string(),
+                r"\brm\s+(-\w+\s+)*(/|~)\s*($|;|&|\|)".to_string(),
+            ],
+            default_timeout: 30,
+            max_timeout: 600,
+            max_output_length: 20_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Blocked by the settings before the user was asked.
+    Rejected,
+    Declined,
+    Running,
+    Exited,
+    TimedOut,
+    /// The shell could not be started.
+    Failed,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Rejected => "rejected",
+            Status::Declined => "declined",
+            Status::Running => "running",
+            Status::Exited => "exited",
+            Status::TimedOut => "timed_out",
+            Status::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogEntry {
+    id: i64,
+    command: String,
+    cwd: String,
+    status: String,
+    exit_code: Option<i32>,
+    output: String,
+    started_at: u64,
+    duration_ms: Option<u64>,
+}
+
+pub fn get_settings(app_handle: &AppHandle<Wry>) -> Settings {
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            Ok(store
+                .get("run_command")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_default())
+        },
+    )
+    .unwrap_or_default()
+}
+
+fn insert_log(
+    connection: &Connection,
+    command: &str,
+    cwd: &str,
+    status: Status,
+    output: &str,
+) -> rusqlite::Result<i64> {
+    connection.execute(
+        "INSERT INTO command_log (command, cwd, status, output, started_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![command, cwd, status.as_str(), output, usage::now()],
+    )?;
+
+    Ok(connection.last_insert_rowid())
+}
+
+fn log(app_handle: &AppHandle<Wry>, command: &str, cwd: &str, status: Status, output: &str) -> i64 {
+    database::with_connection(app_handle, |connection| {
+        insert_log(connection, command, cwd, status, output)
+    })
+    .unwrap_or_default()
+}
+
+fn finish_log(
+    app_handle: &AppHandle<Wry>,
+    id: i64,
+    status: Status,
+    exit_code: Option<i32>,
+    output: &str,
+    duration: Duration,
+) {
+    database::with_connection(app_handle, |connection| {
+        connection.execute(
+            "UPDATE command_log SET status = ?1, exit_code = ?2, output = ?3, duration_ms = ?4
+             WHERE id = ?5",
+            params![
+                status.as_str(),
+                exit_code,
+                output,
+                duration.as_millis() as u64,
+                id
+            ],
+        )
+    })
+    .unwrap_or_default();
+}
+
+fn query_log(
+    connection: &Connection,
+    limit: usize,
+    offset: usize,
+) -> rusqlite::Result<Vec<LogEntry>> {
+    let mut statement = connection.prepare(
+        "SELECT id, command, cwd, status, exit_code, output, started_at, duration_ms
+         FROM command_log ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+    )?;
+
+    let entries = statement
+        .query_map(params![limit, offset], |row| {
+            Ok(LogEntry {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                cwd: row.get(2)?,
+                status: row.get(3)?,
+                exit_code: row.get(4)?,
+                output: row.get(5)?,
+                started_at: row.get(6)?,
+                duration_ms: row.get(7)?,
+            })
+        })?
+        .collect();
+
+    entries
+}
+
+/// Resolves the requested working directory, relative to the first allowed directory, and checks
+/// that it lies inside an allowed one. Symlinks are resolved first so they cannot escape.
+fn resolve_cwd(settings: &Settings, cwd: Option<&str>) -> Result<PathBuf, String> {
+    let base = settings
+        .allowed_directories
+        .first()
+        .ok_or_else(|| "no working directory is allowed".to_string())?;
+
+    let cwd = match cwd {
+        Some(cwd) if !cwd.trim().is_empty() => base.join(cwd.trim()),
+        _ => base.clone(),
+    };
+
+    let cwd = fs::canonicalize(&cwd)
+        .map_err(|_| format!("`{}` is not an existing directory", cwd.display()))?;
+
+    let is_allowed = settings
+        .allowed_directories
+        .iter()
+        .filter_map(|directory| fs::canonicalize(directory).ok())
+        .any(|directory| cwd.starts_with(directory));
+
+    if !is_allowed || !cwd.is_dir() {
+        return Err(format!(
+            "`{}` is outside of the directories commands may run in",
+            cwd.display()
+        ));
+    }
+
+    Ok(cwd)
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|error| format!("invalid blocked pattern `{}`: {}", pattern, error))
+}
+
+/// Returns the first blocked pattern `command` matches. An invalid pattern, e.g. from a settings
+/// file edited by hand, is an error rather than skipped so it cannot silently unblock commands.
+fn blocked_pattern<'a>(settings: &'a Settings, command: &str) -> Result<Option<&'a str>, String> {
+    for pattern in &settings.blocked_patterns {
+        if compile_pattern(pattern)?.is_match(command) {
+            return Ok(Some(pattern));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Appends a line to the output kept for the model and the log, up to `max_length` characters.
+fn push_output(output: &mut String, is_truncated: &mut bool, line: &str, max_length: usize) {
+    if *is_truncated {
+        return;
+    }
+
+    if output.chars().count() + line.chars().count() + 1 > max_length {
+        *is_truncated = true;
+        output.push_str("[output truncated]\n");
+        return;
+    }
+
+    output.push_str(line);
+    output.push('\n');
+}
+
+struct Outcome {
+    status: Status,
+    exit_code: Option<i32>,
+    output: String,
+}
+
+/// Collects the output of a running command until it exits, or kills it once `deadline` passes.
+/// `on_line` gets the stream and text of every line as it arrives.
+async fn wait_for_exit(
+    mut process: process::Process,
+    mut rx: mpsc::UnboundedReceiver<process::Output>,
+    deadline: tokio::time::Instant,
+    max_output_length: usize,
+    mut on_line: impl FnMut(&str, &str),
+) -> Outcome {
+    let mut output = String::new();
+    let mut is_truncated = false;
+    let mut exit_code = None;
+    let mut status = Status::Exited;
+
+    loop {
+        let (stream, line) = match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(process::Output::Stdout(line))) => ("stdout", line),
+            Ok(Some(process::Output::Stderr(line))) => ("stderr", line),
+            Ok(Some(process::Output::Exited(code))) => {
+                exit_code = code;
+                break;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                process.kill();
+                status = Status::TimedOut;
+                break;
+            }
+        };
+
+        push_output(&mut output, &mut is_truncated, &line, max_output_length);
+        on_line(stream, &line);
+    }
+
+    Outcome {
+        status,
+        exit_code,
+        output,
+    }
+}
+
+/// Runs a command proposed by the model once the user approves it in the panel. Output lines
+/// are emitted as `command_output` events while the command runs, and every proposal is recorded
+/// in the command log whatever its outcome.
+async fn run(app_handle: AppHandle<Wry>, arguments: Value) -> Result<String, String> {
+    let settings = get_settings(&app_handle);
+    let command = arguments["command"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    let cwd = match resolve_cwd(&settings, arguments["cwd"].as_str()) {
+        Ok(cwd) => cwd,
+        Err(error) => {
+            let cwd = arguments["cwd"].as_str().unwrap_or_default();
+            log(&app_handle, &command, cwd, Status::Rejected, &error);
+            return Err(error);
+        }
+    };
+
+    let cwd_text = cwd.display().to_string();
+
+    let rejection = match blocked_pattern(&settings, &command) {
+        Ok(Some(pattern)) => Some(format!(
+            "the command matches the blocked pattern `{}`",
+            pattern
+        )),
+        Ok(None) => None,
+        Err(error) => Some(error),
+    };
+
+    if let Some(error) = rejection {
+        log(&app_handle, &command, &cwd_text, Status::Rejected, &error);
+        return Err(error);
+    }
+
+    let timeout = arguments["timeout"]
+        .as_u64()
+        .unwrap_or(settings.default_timeout)
+        .min(settings.max_timeout);
+
+    let is_approved = super::request_approval(
+        &app_handle,
+        NAME,
+        &json!({ "command": command, "cwd": cwd_text, "timeout": timeout }),
+    )
+    .await;
+
+    if !is_approved {
+        log(&app_handle, &command, &cwd_text, Status::Declined, "");
+        return Err("the user declined to run the command".to_string());
+    }
+
+    let id = log(&app_handle, &command, &cwd_text, Status::Running, "");
+    let started_at = Instant::now();
+
+    let (process, rx) = match process::spawn(&command, &cwd, &settings.allowed_directories) {
+        Ok(spawned) => spawned,
+        Err(error) => {
+            let error = error.to_string();
+            finish_log(
+                &app_handle,
+                id,
+                Status::Failed,
+                None,
+                &error,
+                started_at.elapsed(),
+            );
+            return Err(error);
+        }
+    };
+
+    app_handle
+        .emit_all(
+            "command_started",
+            json!({ "id": id, "command": command, "cwd": cwd_text }),
+        )
+        .unwrap_or_default();
+
+    let deadline = tokio::time::Instant::from_std(started_at + Duration::from_secs(timeout));
+
+    let Outcome {
+        status,
+        exit_code,
+        output,
+    } = wait_for_exit(
+        process,
+        rx,
+        deadline,
+        settings.max_output_length,
+        |stream, line| {
+            app_handle
+                .emit_all(
+                    "command_output",
+                    json!({ "id": id, "stream": stream, "line": line }),
+                )
+                .unwrap_or_default();
+        },
+    )
+    .await;
+
+    finish_log(
+        &app_handle,
+        id,
+        status,
+        exit_code,
+        &output,
+        started_at.elapsed(),
+    );
+
+    app_handle
+        .emit_all(
+            "command_finished",
+            json!({ "id": id, "status": status, "exit_code": exit_code }),
+        )
+        .unwrap_or_default();
+
+    match (status, exit_code) {
+        (Status::TimedOut, _) => Err(format!(
+            "the command timed out after {} seconds and was stopped\n\n{}",
+            timeout, output
+        )),
+        (_, Some(exit_code)) => Ok(format!("exit code {}\n\n{}", exit_code, output)),
+        _ => Ok(format!("terminated by a signal\n\n{}", output)),
+    }
+}
+
+pub fn tool() -> Tool {
+    Tool {
+        name: NAME.to_string(),
+        description: format!(
+            "Runs a shell command on the user's computer with `{}` and returns its exit code and \
+             output. The user has to approve every command. Prefer read-only commands, and never \
+             run interactive programs since there is no input.",
+            if cfg!(target_os = "windows") {
+                "cmd /C"
+            } else {
+                "sh -c"
+            }
+        ),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string", "minLength": 1 },
+                "cwd": {
+                    "type": "string",
+                    "description": "Working directory, relative to the user's home directory by default",
+                },
+                "timeout": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Seconds after which the command is stopped",
+                },
+            },
+            "required": ["command"],
+            "additionalProperties": false,
+        }),
+        default_permission: Permission::Ask,
+        asks_for_approval: true,
+        handler: handler(run),
+    }
+}
+
+#[tauri::command]
+pub fn get_run_command_settings(app_handle: AppHandle<Wry>) -> String {
+    let settings = get_settings(&app_handle);
+    serde_json::to_string(&settings).expect("error serializing json")
+}
+
+#[tauri::command]
+pub fn set_run_command_settings(
+    app_handle: AppHandle<Wry>,
+    settings: Settings,
+) -> Result<(), String> {
+    for pattern in &settings.blocked_patterns {
+        compile_pattern(pattern)?;
+    }
+
+    with_store(
+        app_handle.clone(),
+        app_handle.state::<StoreCollection<Wry>>(),
+        PathBuf::from("settings.json"),
+        |store| {
+            store.insert("run_command".to_string(), json!(settings))?;
+            store.save()
+        },
+    )
+    .unwrap_or_default();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_command_log(
+    app_handle: AppHandle<Wry>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<String, String> {
+    let entries = database::with_connection(&app_handle, |connection| {
+        query_log(
+            connection,
+            limit.unwrap_or(DEFAULT_LOG_LIMIT),
+            offset.unwrap_or(0),
+        )
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&entries).expect("error serializing json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("lander-shell-{}-{}", name, std::process::id()));
+
+        fs::create_dir_all(dir.join("allowed").join("project")).unwrap();
+        fs::create_dir_all(dir.join("outside")).unwrap();
+
+        dir
+    }
+
+    fn settings(dir: &Path) -> Settings {
+        Settings {
+            allowed_directories: vec![dir.join("allowed")],
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn resolve_cwd_stays_inside_allowed_directories() {
+        let dir = temp_dir("cwd");
+        let settings = settings(&dir);
+        let allowed = fs::canonicalize(dir.join("allowed")).unwrap();
+        let project = dir.join("allowed").join("project");
+
+        assert_eq!(resolve_cwd(&settings, None), Ok(allowed.clone()));
+        assert_eq!(resolve_cwd(&settings, Some(" ")), Ok(allowed.clone()));
+        assert_eq!(
+            resolve_cwd(&settings, Some("project")),
+            Ok(allowed.join("project"))
+        );
+        assert_eq!(
+            resolve_cwd(&settings, project.to_str()),
+            Ok(allowed.join("project"))
+        );
+        assert_eq!(
+            resolve_cwd(&settings, Some("project/..")),
+            Ok(allowed.clone())
+        );
+
+        assert!(resolve_cwd(&settings, Some("../outside")).is_err());
+        assert!(resolve_cwd(&settings, Some("project/../../outside")).is_err());
+        assert!(resolve_cwd(&settings, dir.join("outside").to_str()).is_err());
+        assert!(resolve_cwd(&settings, Some("missing")).is_err());
+
+        let no_directories = Settings {
+            allowed_directories: vec![],
+            ..Settings::default()
+        };
+
+        assert!(resolve_cwd(&no_directories, None).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_cwd_rejects_symlinks_out_of_allowed_directories() {
+        let dir = temp_dir("symlink");
+        let settings = settings(&dir);
+
+        std::os::unix::fs::symlink(dir.join("outside"), dir.join("allowed").join("link")).unwrap();
+
+        assert!(resolve_cwd(&settings, Some("link")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn blocked_patterns_match_dangerous_commands() {
+        let settings = Settings::default();
+
+        assert_eq!(
+            blocked_pattern(&settings, "sudo apt install jq"),
+            Ok(Some(r"\bsudo\b"))
+        );
+        assert!(matches!(
+            blocked_pattern(&settings, "rm -rf /"),
+            Ok(Some(_))
+        ));
+        assert!(matches!(
+            blocked_pattern(&settings, "rm -rf ~ ; ls"),
+            Ok(Some(_))
+        ));
+        assert!(matches!(
+            blocked_pattern(&settings, "shutdown -h now"),
+            Ok(Some(_))
+        ));
+
+        assert_eq!(blocked_pattern(&settings, "rm -rf ./build"), Ok(None));
+        assert_eq!(blocked_pattern(&settings, "rm -rf /tmp/build"), Ok(None));
+        assert_eq!(blocked_pattern(&settings, "echo pseudo"), Ok(None));
+    }
+
+    #[test]
+    fn invalid_blocked_patterns_are_errors() {
+        let settings = Settings {
+            blocked_patterns: vec!["(".to_string(), "ls".to_string()],
+            ..Settings::default()
+        };
+
+        assert!(blocked_pattern(&settings, "ls")
+            .unwrap_err()
+            .starts_with("invalid blocked pattern `(`"));
+        assert!(compile_pattern(r"\bsudo\b").is_ok());
+    }
+
+    #[test]
+    fn push_output_truncates_at_max_length() {
+        let mut output = String::new();
+        let mut is_truncated = false;
+
+        push_output(&mut output, &mut is_truncated, "abc", 10);
+        push_output(&mut output, &mut is_truncated, "défg", 10);
+
+        assert_eq!(output, "abc\ndéfg\n");
+        assert!(!is_truncated);
+
+        push_output(&mut output, &mut is_truncated, "hi", 10);
+        push_output(&mut output, &mut is_truncated, "h", 10);
+
+        assert_eq!(output, "abc\ndéfg\n[output truncated]\n");
+        assert!(is_truncated);
+    }
+
+    fn run_command(command: &str, cwd: &Path, timeout: Duration) -> (Outcome, Vec<String>) {
+        tauri::async_runtime::block_on(async {
+            let (process, rx) = process::spawn(command, cwd, &[cwd.to_path_buf()]).unwrap();
+            let deadline = tokio::time::Instant::now() + timeout;
+            let mut lines = vec![];
+
+            let outcome = wait_for_exit(process, rx, deadline, 1_000, |stream, line| {
+                lines.push(format!("{}: {}", stream, line.trim()))
+            })
+            .await;
+
+            (outcome, lines)
+        })
+    }
+
+    #[test]
+    fn wait_for_exit_collects_output_and_exit_code() {
+        let dir = temp_dir("exit");
+        let (outcome, lines) = run_command(
+            "echo one&& echo two 1>&2&& exit 3",
+            &dir,
+            Duration::from_secs(10),
+        );
+
+        assert_eq!(outcome.status, Status::Exited);
+        assert_eq!(outcome.exit_code, Some(3));
+        assert!(lines.contains(&"stdout: one".to_string()));
+        assert!(lines.contains(&"stderr: two".to_string()));
+        assert_eq!(outcome.output.lines().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn timed_out_commands_are_killed_with_their_children() {
+        let dir = temp_dir("timeout");
+
+        // The background child writes the marker after a second unless it is killed with its
+        // parent.
+        let command = if cfg!(target_os = "windows") {
+            r#"start /b cmd /c "ping -n 2 127.0.0.1 >NUL & echo alive> marker" & ping -n 30 127.0.0.1 >NUL"#
+        } else {
+            "(sleep 1; echo alive > marker) & sleep 30"
+        };
+
+        let started_at = Instant::now();
+        let (outcome, _) = run_command(command, &dir, Duration::from_millis(300));
+
+        assert_eq!(outcome.status, Status::TimedOut);
+        assert_eq!(outcome.exit_code, None);
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+
+        std::thread::sleep(Duration::from_secs(2));
+
+        assert!(!dir.join("marker").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    sync::{mpsc, oneshot},
+};
+
+/// How long output still in the pipes is read after the command exits. Processes it left running
+/// in the background may keep the pipes open indefinitely.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug)]
+pub enum Output {
+    Stdout(String),
+    Stderr(String),
+    /// The last event, with the exit code, which is `None` when the command was killed by a
+    /// signal.
+    Exited(Option<i32>),
+}
+
+/// A running command. It gets its own process group, or job object on Windows, so stopping it
+/// also stops everything it started.
+pub struct Process {
+    kill_tx: Option<oneshot::Sender<()>>,
+    #[cfg(unix)]
+    process_group: Option<i32>,
+    #[cfg(target_os = "windows")]
+    job: Option<windows::Job>,
+}
+
+/// Files commands may write to on macOS besides the allowed directories.
+#[cfg(target_os = "macos")]
+const SANDBOX_WRITABLE_FILES: [&str; 4] =
+    ["/dev/null", "/dev/zero", "/dev/tty", "/dev/dtracehelper"];
+
+/// `sandbox-exec` profile that lets commands read and run anything but only write below
+/// `directories`, which have to be canonical since the sandbox sees resolved paths.
+#[cfg(target_os = "macos")]
+fn sandbox_profile(directories: &[PathBuf]) -> String {
+    let quote = |path: &str| format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""));
+
+    let mut profile = String::from(
+        "(version 1)\n(allow default)\n(deny file-write*)\n(allow file-write*\n    (subpath \"/dev/fd\")",
+    );
+
+    for file in SANDBOX_WRITABLE_FILES {
+        profile.push_str(&format!("\n    (literal {})", quote(file)));
+    }
+
+    for directory in directories {
+        profile.push_str(&format!(
+            "\n    (subpath {})",
+            quote(&directory.to_string_lossy())
+        ));
+    }
+
+    profile.push_str(")\n");
+    profile
+}
+
+#[cfg(target_os = "macos")]
+fn shell(command: &str, allowed_directories: &[PathBuf]) -> std::process::Command {
+    let mut directories = allowed_directories
+        .iter()
+        .chain(std::iter::once(&std::env::temp_dir()))
+        .filter_map(|directory| std::fs::canonicalize(directory).ok())
+        .collect::<Vec<_>>();
+
+    directories.dedup();
+
+    let mut shell = std::process::Command::new("/usr/bin/sandbox-exec");
+    shell.args([
+        "-p",
+        &sandbox_profile(&directories),
+        "/bin/sh",
+        "-c",
+        command,
+    ]);
+    shell
+}
+
+#[cfg(target_os = "linux")]
+fn shell(command: &str, _allowed_directories: &[PathBuf]) -> std::process::Command {
+    let mut shell = std::process::Command::new("/bin/sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+/// `cmd` does its own parsing of the command line, so the command is passed verbatim. `/S` makes
+/// it strip exactly the outer quotes and keep the ones inside.
+#[cfg(target_os = "windows")]
+fn shell(command: &str, _allowed_directories: &[PathBuf]) -> std::process::Command {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let mut shell = std::process::Command::new("cmd");
+
+    shell
+        .args(["/D", "/S", "/C"])
+        .raw_arg(format!("\"{}\"", command))
+        .creation_flags(CREATE_NO_WINDOW);
+
+    shell
+}
+
+fn forward_lines(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    tx: mpsc::UnboundedSender<Output>,
+    output: fn(String) -> Output,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&line)
+                        .trim_end_matches(['\r', '\n'])
+                        .to_string();
+
+                    if tx.send(output(text)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Starts `command` in `cwd` with the platform shell. Output lines arrive on the receiver, followed
+/// by the exit code once the command exits.
+pub fn spawn(
+    command: &str,
+    cwd: &Path,
+    allowed_directories: &[PathBuf],
+) -> io::Result<(Process, mpsc::UnboundedReceiver<Output>)> {
+    let mut shell = shell(command, allowed_directories);
+
+    shell
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+
+    let mut child = tokio::process::Command::from(shell)
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (kill_tx, kill_rx) = oneshot::channel();
+    let mut readers = vec![];
+
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward_lines(stdout, tx.clone(), Output::Stdout));
+    }
+
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward_lines(stderr, tx.clone(), Output::Stderr));
+    }
+
+    let process = Process {
+        kill_tx: Some(kill_tx),
+        #[cfg(unix)]
+        process_group: child.id().map(|id| id as i32),
+        #[cfg(target_os = "windows")]
+        job: child.raw_handle().and_then(windows::Job::with_process),
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let status = tokio::select! {
+            status = child.wait() => status,
+            _ = kill_rx => {
+                child.start_kill().unwrap_or_default();
+                child.wait().await
+            }
+        };
+
+        tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, futures::future::join_all(readers))
+            .await
+            .unwrap_or_default();
+
+        tx.send(Output::Exited(status.ok().and_then(|status| status.code())))
+            .unwrap_or_default();
+    });
+
+    Ok((process, rx))
+}
+
+impl Process {
+    /// Stops the command and everything it started.
+    pub fn kill(&mut self) {
+        #[cfg(unix)]
+        if let Some(process_group) = self.process_group {
+            unsafe {
+                libc::kill(-process_group, libc::SIGKILL);
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        if let Some(job) = &self.job {
+            job.terminate();
+        }
+
+        if let Some(kill_tx) = self.kill_tx.take() {
+            kill_tx.send(()).unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::{os::windows::io::RawHandle, ptr};
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject},
+    };
+
+    pub struct Job(HANDLE);
+
+    impl Job {
+        /// Creates a job holding `process`, which its child processes then join.
+        pub fn with_process(process: RawHandle) -> Option<Self> {
+            let job = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+
+            if job == 0 {
+                return None;
+            }
+
+            let job = Job(job);
+
+            if unsafe { AssignProcessToJobObject(job.0, process as HANDLE) } == 0 {
+                return None;
+            }
+
+            Some(job)
+        }
+
+        pub fn terminate(&self) {
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sandbox_profile_allows_writes_below_the_directories() {
+        let profile = sandbox_profile(&[
+            PathBuf::from("/Users/lander/Projects"),
+            PathBuf::from("/Users/lander/\"quoted\""),
+        ]);
+
+        assert!(profile.contains("(deny file-write*)"));
+        assert!(profile.contains("(subpath \"/Users/lander/Projects\")"));
+        assert!(profile.contains("(subpath \"/Users/lander/\\\"quoted\\\"\")"));
+        assert!(profile.contains("(literal \"/dev/null\")"));
+    }
+}
@@ -0,0 +1,412 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Wry};
+
+use crate::database;
+
+const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// An application opened from the launcher; `target` is the application id.
+    Launch,
+    /// A launcher command; `target` is the command id.
+    Command,
+    /// An AI prompt run; `target` is the prompt or command id.
+    Prompt,
+    /// Text inserted into or replacing the selection of another application; `target` is the
+    /// insertion mode.
+    Insertion,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Launch => "launch",
+            Kind::Command => "command",
+            Kind::Prompt => "prompt",
+            Kind::Insertion => "insertion",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageEvent {
+    kind: String,
+    target: String,
+    source_application: Option<String>,
+    created_at: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageCount {
+    kind: String,
+    target: String,
+    count: u64,
+    last_used_at: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ApplicationStats {
+    id: String,
+    launch_count: u64,
+    first_launched_at: Option<u64>,
+    last_launched_at: Option<u64>,
+    /// Usage that happened while the application was focused, keyed by kind.
+    source_counts: HashMap<String, u64>,
+}
+
+/// Launch count and most recent launch timestamps of an application, newest first.
+#[derive(Debug, Default, Clone)]
+pub struct LaunchHistory {
+    pub count: u64,
+    pub recent: Vec<u64>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn insert_event(
+    connection: &Connection,
+    kind: Kind,
+    target: &str,
+    source_application: Option<&str>,
+    created_at: u64,
+) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO usage_events (kind, target, source_application, created_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![kind.as_str(), target, source_application, created_at],
+    )?;
+
+    Ok(())
+}
+
+fn query_recent(
+    connection: &Connection,
+    kind: Option<Kind>,
+    limit: usize,
+) -> rusqlite::Result<Vec<UsageEvent>> {
+    let mut statement = connection.prepare(
+        "SELECT kind, target, source_application, created_at FROM usage_events
+         WHERE ?1 IS NULL OR kind = ?1
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?2",
+    )?;
+
+    let events = statement
+        .query_map(params![kind.map(|kind| kind.as_str()), limit], |row| {
+            Ok(UsageEvent {
+                kind: row.get(0)?,
+                target: row.get(1)?,
+                source_application: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?
+        .collect();
+
+    events
+}
+
+fn query_most_used(
+    connection: &Connection,
+    kind: Option<Kind>,
+    since: Option<u64>,
+    limit: usize,
+) -> rusqlite::Result<Vec<UsageCount>> {
+    let mut statement = connection.prepare(
+        "SELECT kind, target, COUNT(*) AS count, MAX(created_at) FROM usage_events
+         WHERE (?1 IS NULL OR kind = ?1) AND created_at >= ?2
+         GROUP BY kind, target
+         ORDER BY count DESC, MAX(created_at) DESC
+         LIMIT ?3",
+    )?;
+
+    let counts = statement
+        .query_map(
+            params![kind.map(|kind| kind.as_str()), since.unwrap_or(0), limit],
+            |row| {
+                Ok(UsageCount {
+                    kind: row.get(0)?,
+                    target: row.get(1)?,
+                    count: row.get(2)?,
+                    last_used_at: row.get(3)?,
+                })
+            },
+        )?
+        .collect();
+
+    counts
+}
+
+fn query_application_stats(
+    connection: &Connection,
+    id: &str,
+) -> rusqlite::Result<ApplicationStats> {
+    let (launch_count, first_launched_at, last_launched_at) = connection.query_row(
+        "SELECT COUNT(*), MIN(created_at), MAX(created_at) FROM usage_events
+         WHERE kind = 'launch' AND target = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let mut statement = connection.prepare(
+        "SELECT kind, COUNT(*) FROM usage_events WHERE source_application = ?1 GROUP BY kind",
+    )?;
+
+    let source_counts = statement
+        .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<HashMap<String, u64>>>()?;
+
+    Ok(ApplicationStats {
+        id: id.to_string(),
+        launch_count,
+        first_launched_at,
+        last_launched_at,
+        source_counts,
+    })
+}
+
+fn query_launch_history(
+    connection: &Connection,
+    max_recent: usize,
+) -> rusqlite::Result<HashMap<String, LaunchHistory>> {
+    let mut history: HashMap<String, LaunchHistory> = HashMap::new();
+
+    let mut statement = connection.prepare(
+        "SELECT target, COUNT(*) FROM usage_events WHERE kind = 'launch' GROUP BY target",
+    )?;
+
+    for row in statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+        let (target, count) = row?;
+        history.entry(target).or_default().count = count;
+    }
+
+    let mut statement = connection.prepare(
+        "SELECT target, created_at FROM (
+            SELECT target, created_at,
+                ROW_NUMBER() OVER (PARTITION BY target ORDER BY created_at DESC) AS position
+            FROM usage_events WHERE kind = 'launch'
+         )
+         WHERE position <= ?1
+         ORDER BY target, created_at DESC",
+    )?;
+
+    for row in statement.query_map(params![max_recent], |row| {
+        Ok((row.get::<_, String>(0)?, row.get(1)?))
+    })? {
+        let (target, created_at) = row?;
+        history.entry(target).or_default().recent.push(created_at);
+    }
+
+    Ok(history)
+}
+
+pub fn record(
+    app_handle: &AppHandle<Wry>,
+    kind: Kind,
+    target: &str,
+    source_application: Option<&str>,
+) {
+    database::with_connection(app_handle, |connection| {
+        insert_event(connection, kind, target, source_application, now())
+    })
+    .unwrap_or_default();
+}
+
+pub fn get_launch_history(
+    app_handle: &AppHandle<Wry>,
+    max_recent: usize,
+) -> HashMap<String, LaunchHistory> {
+    database::with_connection(app_handle, |connection| {
+        query_launch_history(connection, max_recent)
+    })
+    .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn record_usage(
+    app_handle: AppHandle<Wry>,
+    kind: Kind,
+    target: String,
+    source_application: Option<String>,
+) {
+    let source_application =
+        source_application.or_else(|| crate::command::get_focused_application_id(&app_handle));
+
+    record(&app_handle, kind, &target, source_application.as_deref());
+}
+
+#[tauri::command]
+pub fn get_recent_usage(
+    app_handle: AppHandle<Wry>,
+    kind: Option<Kind>,
+    limit: Option<usize>,
+) -> Result<String, String> {
+    let events = database::with_connection(&app_handle, |connection| {
+        query_recent(connection, kind, limit.unwrap_or(DEFAULT_LIMIT))
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&events).expect("error serializing json"))
+}
+
+#[tauri::command]
+pub fn get_most_used(
+    app_handle: AppHandle<Wry>,
+    kind: Option<Kind>,
+    since: Option<u64>,
+    limit: Option<usize>,
+) -> Result<String, String> {
+    let counts = database::with_connection(&app_handle, |connection| {
+        query_most_used(connection, kind, since, limit.unwrap_or(DEFAULT_LIMIT))
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&counts).expect("error serializing json"))
+}
+
+#[tauri::command]
+pub fn get_application_usage(app_handle: AppHandle<Wry>, id: String) -> Result<String, String> {
+    let stats = database::with_connection(&app_handle, |connection| {
+        query_application_stats(connection, &id)
+    })
+    .map_err(|error| error.to_string())?;
+
+    Ok(serde_json::to_string(&stats).expect("error serializing json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection_with_events(events: &[(Kind, &str, Option<&str>, u64)]) -> Connection {
+        let connection = database::open_in_memory();
+
+        for (kind, target, source_application, created_at) in events {
+            insert_event(&connection, *kind, target, *source_application, *created_at).unwrap();
+        }
+
+        connection
+    }
+
+    #[test]
+    fn recent_usage_is_newest_first() {
+        let connection = connection_with_events(&[
+            (Kind::Launch, "safari", None, 10),
+            (Kind::Command, "clipboard", Some("safari"), 20),
+            (Kind::Launch, "mail", None, 20),
+            (Kind::Launch, "notes", None, 5),
+        ]);
+
+        let targets = |kind, limit| {
+            query_recent(&connection, kind, limit)
+                .unwrap()
+                .into_iter()
+                .map(|event| event.target)
+                .collect::<Vec<_>>()
+        };
+
+        // Ties on the timestamp go to the later insertion.
+        assert_eq!(targets(None, 3), vec!["mail", "clipboard", "safari"]);
+        assert_eq!(
+            targets(Some(Kind::Launch), 10),
+            vec!["mail", "safari", "notes"]
+        );
+    }
+
+    #[test]
+    fn most_used_counts_within_the_window() {
+        let connection = connection_with_events(&[
+            (Kind::Launch, "safari", None, 1),
+            (Kind::Launch, "safari", None, 2),
+            (Kind::Launch, "safari", None, 3),
+            (Kind::Launch, "mail", None, 30),
+            (Kind::Launch, "mail", None, 40),
+            (Kind::Launch, "notes", None, 50),
+            (Kind::Launch, "notes", None, 35),
+            (Kind::Prompt, "summarize", None, 60),
+        ]);
+
+        let counts = |kind, since| {
+            query_most_used(&connection, kind, since, 10)
+                .unwrap()
+                .into_iter()
+                .map(|count| (count.target, count.count, count.last_used_at))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            counts(Some(Kind::Launch), None),
+            vec![
+                ("safari".to_string(), 3, 3),
+                ("notes".to_string(), 2, 50),
+                ("mail".to_string(), 2, 40),
+            ]
+        );
+        assert_eq!(
+            counts(None, Some(36)),
+            vec![
+                ("summarize".to_string(), 1, 60),
+                ("notes".to_string(), 1, 50),
+                ("mail".to_string(), 1, 40),
+            ]
+        );
+    }
+
+    #[test]
+    fn application_stats_include_usage_while_focused() {
+        let connection = connection_with_events(&[
+            (Kind::Launch, "safari", Some("finder"), 10),
+            (Kind::Launch, "safari", None, 30),
+            (Kind::Prompt, "summarize", Some("safari"), 40),
+            (Kind::Prompt, "translate", Some("safari"), 50),
+            (Kind::Insertion, "replace", Some("safari"), 50),
+        ]);
+
+        let stats = query_application_stats(&connection, "safari").unwrap();
+
+        assert_eq!(stats.launch_count, 2);
+        assert_eq!(stats.first_launched_at, Some(10));
+        assert_eq!(stats.last_launched_at, Some(30));
+        assert_eq!(
+            stats.source_counts,
+            HashMap::from([("prompt".to_string(), 2), ("insertion".to_string(), 1)])
+        );
+
+        let stats = query_application_stats(&connection, "mail").unwrap();
+
+        assert_eq!(stats.launch_count, 0);
+        assert_eq!(stats.first_launched_at, None);
+        assert!(stats.source_counts.is_empty());
+    }
+
+    #[test]
+    fn launch_history_keeps_the_most_recent_launches_of_each_application() {
+        let connection = connection_with_events(&[
+            (Kind::Launch, "safari", None, 10),
+            (Kind::Launch, "safari", None, 40),
+            (Kind::Launch, "safari", None, 20),
+            (Kind::Launch, "safari", None, 30),
+            (Kind::Launch, "mail", None, 15),
+            (Kind::Command, "safari", None, 50),
+        ]);
+
+        let history = query_launch_history(&connection, 2).unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history["safari"].count, 4);
+        assert_eq!(history["safari"].recent, vec![40, 30]);
+        assert_eq!(history["mail"].count, 1);
+        assert_eq!(history["mail"].recent, vec![15]);
+    }
+}
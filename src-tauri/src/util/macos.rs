@@ -1,6 +1,18 @@
+use accessibility::{AXAttribute, AXUIElement};
+use accessibility_sys::{
+    kAXFocusedWindowAttribute, kAXPositionAttribute, kAXSizeAttribute, kAXValueTypeCGPoint,
+    kAXValueTypeCGSize, AXValueGetValue, AXValueRef,
+};
 use cocoa::appkit::{NSApplication, NSApplicationActivationPolicy};
 use cocoa::base::{nil, NO};
-use objc::{msg_send, sel, sel_impl};
+use core_foundation::{base::TCFType, string::CFString};
+use core_graphics::geometry::{CGPoint, CGRect, CGSize};
+use objc::{
+    msg_send,
+    runtime::{Class, Object},
+    sel, sel_impl,
+};
+use std::ffi::c_void;
 
 #[cfg(target_os = "macos")]
 pub fn set_activation_policy(policy: NSApplicationActivationPolicy) {
@@ -10,3 +22,58 @@ pub fn set_activation_policy(policy: NSApplicationActivationPolicy) {
         app.activateIgnoringOtherApps_(NO);
     }
 }
+
+pub fn get_frontmost_application_pid() -> Option<i32> {
+    unsafe {
+        let workspace_class = Class::get("NSWorkspace").unwrap();
+        let shared_workspace: *mut Object = msg_send![workspace_class, sharedWorkspace];
+        let frontmost_application: *mut Object = msg_send![shared_workspace, frontmostApplication];
+
+        if frontmost_application.is_null() {
+            None
+        } else {
+            Some(msg_send![frontmost_application, processIdentifier])
+        }
+    }
+}
+
+pub fn get_focused_window(pid: i32) -> Option<AXUIElement> {
+    AXUIElement::application(pid)
+        .attribute(&AXAttribute::new(&CFString::new(kAXFocusedWindowAttribute)))
+        .ok()?
+        .downcast::<AXUIElement>()
+}
+
+/// Returns the frame of an accessibility window in global coordinates with a top-left origin.
+pub fn get_window_frame(window: &AXUIElement) -> Option<CGRect> {
+    let position = window
+        .attribute(&AXAttribute::new(&CFString::new(kAXPositionAttribute)))
+        .ok()?;
+    let size = window
+        .attribute(&AXAttribute::new(&CFString::new(kAXSizeAttribute)))
+        .ok()?;
+
+    let mut origin = CGPoint::new(0.0, 0.0);
+    let mut extent = CGSize::new(0.0, 0.0);
+
+    let has_origin = unsafe {
+        AXValueGetValue(
+            position.as_CFTypeRef() as AXValueRef,
+            kAXValueTypeCGPoint,
+            &mut origin as *mut CGPoint as *mut c_void,
+        )
+    };
+    let has_extent = unsafe {
+        AXValueGetValue(
+            size.as_CFTypeRef() as AXValueRef,
+            kAXValueTypeCGSize,
+            &mut extent as *mut CGSize as *mut c_void,
+        )
+    };
+
+    if has_origin && has_extent {
+        Some(CGRect::new(&origin, &extent))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,101 @@
+//! Drives the MCP client against `bin/mcp-example.rs` over stdio.
+
+#[allow(dead_code)]
+#[path = "../src/mcp/client.rs"]
+mod client;
+
+use client::{Client, Endpoint, REQUEST_TIMEOUT};
+use serde_json::json;
+use std::collections::HashMap;
+
+async fn connect() -> Client {
+    Client::connect(Endpoint::Stdio {
+        command: env!("CARGO_BIN_EXE_mcp-example").to_string(),
+        args: vec![],
+        env: HashMap::new(),
+        cwd: None,
+    })
+    .await
+    .unwrap()
+}
+
+#[test]
+fn initializes() {
+    tauri::async_runtime::block_on(async {
+        let client = connect().await;
+
+        assert_eq!(client.server["serverInfo"]["name"], "lander-example");
+        assert!(client.has_capability("tools"));
+        assert!(client.has_capability("resources"));
+        assert!(client.is_alive());
+    });
+}
+
+#[test]
+fn lists_and_calls_tools() {
+    tauri::async_runtime::block_on(async {
+        let client = connect().await;
+
+        let tools = client.list("tools/list", "tools").await.unwrap();
+        let names = tools
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["echo", "add", "current_time"]);
+
+        let result = client
+            .request(
+                "tools/call",
+                json!({ "name": "add", "arguments": { "a": 2, "b": 3.5 } }),
+                REQUEST_TIMEOUT,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result["content"][0]["text"], "5.5");
+        assert_eq!(result["isError"], false);
+    });
+}
+
+#[test]
+fn returns_remote_errors() {
+    tauri::async_runtime::block_on(async {
+        let client = connect().await;
+
+        let error = client
+            .request(
+                "tools/call",
+                json!({ "name": "missing", "arguments": {} }),
+                REQUEST_TIMEOUT,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, client::Error::Remote { code: -32602, .. }));
+    });
+}
+
+#[test]
+fn reads_resources() {
+    tauri::async_runtime::block_on(async {
+        let client = connect().await;
+
+        let resources = client.list("resources/list", "resources").await.unwrap();
+        assert_eq!(resources[0]["uri"], "example://readme");
+
+        let result = client
+            .request(
+                "resources/read",
+                json!({ "uri": "example://readme" }),
+                REQUEST_TIMEOUT,
+            )
+            .await
+            .unwrap();
+
+        assert!(result["contents"][0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("This is the example MCP server"));
+    });
+}
@@ -0,0 +1,159 @@
+//! Drives the `applications` sidecar client against `bin/applications-mock.rs`.
+
+#[allow(dead_code)]
+#[path = "../src/command/application/sidecar.rs"]
+mod sidecar;
+
+use serde_json::{json, Value};
+use sidecar::{Client, Error, Program};
+use std::{path::PathBuf, time::Duration};
+
+fn client() -> Client {
+    Client::new(Program::Path(PathBuf::from(env!(
+        "CARGO_BIN_EXE_applications-mock"
+    ))))
+}
+
+#[test]
+fn returns_results() {
+    tauri::async_runtime::block_on(async {
+        let client = client();
+        let applications = client
+            .request::<Vec<Value>>("list", json!({}))
+            .await
+            .unwrap();
+
+        assert_eq!(applications.len(), 2);
+        assert_eq!(applications[0]["name"], "Calculator");
+
+        let result = client
+            .request::<Value>("open", json!({ "target": "notepad" }))
+            .await
+            .unwrap();
+
+        assert_eq!(result, Value::Null);
+    });
+}
+
+#[test]
+fn returns_remote_errors() {
+    tauri::async_runtime::block_on(async {
+        let client = client();
+
+        let error = client
+            .request::<Value>("open", json!({}))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, Error::Remote { code: -32602, .. }),
+            "{}",
+            error
+        );
+
+        let error = client
+            .request::<Value>("unknown", json!({}))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, Error::Remote { code: -32601, .. }),
+            "{}",
+            error
+        );
+    });
+}
+
+#[test]
+fn rejects_results_of_the_wrong_shape() {
+    tauri::async_runtime::block_on(async {
+        let error = client()
+            .request::<Vec<Value>>("focused_application", json!({}))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Protocol(_)), "{}", error);
+    });
+}
+
+#[test]
+fn respawns_after_a_crash() {
+    tauri::async_runtime::block_on(async {
+        let client = client();
+
+        let error = client
+            .request::<Value>("crash", json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Crashed), "{}", error);
+
+        let applications = client
+            .request::<Vec<Value>>("list", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(applications.len(), 2);
+    });
+}
+
+#[test]
+fn restarts_a_sidecar_that_timed_out() {
+    tauri::async_runtime::block_on(async {
+        let client = client();
+
+        let error = client
+            .request_with_timeout::<Value>(
+                "sleep",
+                json!({ "milliseconds": 30_000 }),
+                Duration::from_millis(200),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Timeout), "{}", error);
+
+        // The hung sidecar would only answer once it wakes up.
+        let applications = client
+            .request_with_timeout::<Vec<Value>>("list", json!({}), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(applications.len(), 2);
+    });
+}
+
+#[test]
+fn answers_requests_while_a_slow_one_runs() {
+    tauri::async_runtime::block_on(async {
+        let client = client();
+
+        let slow_request = client.request::<Value>("sleep", json!({ "milliseconds": 2_000 }));
+        let focused_application = async {
+            // Sent once the slow request is in flight.
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            client
+                .request_with_timeout::<Value>(
+                    "focused_application",
+                    json!({}),
+                    Duration::from_millis(1_000),
+                )
+                .await
+        };
+
+        let (slow_result, focused_application) = futures::join!(slow_request, focused_application);
+
+        assert_eq!(focused_application.unwrap()["name"], "Notepad");
+        assert_eq!(slow_result.unwrap(), Value::Null);
+    });
+}
+
+#[test]
+fn fails_to_spawn_missing_programs() {
+    tauri::async_runtime::block_on(async {
+        let client = Client::new(Program::Path(PathBuf::from("/nonexistent/applications")));
+        let error = client
+            .request::<Value>("list", json!({}))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Spawn(_)), "{}", error);
+    });
+}
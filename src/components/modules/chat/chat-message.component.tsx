@@ -51,6 +51,21 @@ const STextAvatar = styled("div")`
   background: ${(props) => props.theme?.colors.gray3};
 `;
 
+const SImagesWrapper = styled("div")`
+  display: grid;
+  grid-auto-flow: column;
+  justify-content: start;
+  gap: 8px;
+  margin-top: 8px;
+`;
+
+const SImage = styled("img")`
+  max-height: 120px;
+  max-width: 240px;
+  border-radius: 4px;
+  border: ${(props) => `0.5px solid ${props.theme?.colors.gray3}`};
+`;
+
 const SChevron = styled(Icon)<{ rotation: "up" | "down" }>`
   margin-left: -8px;
   transform: ${(props) => `rotate(${props.rotation === "down" ? 0 : -180}deg)`};
@@ -199,6 +214,14 @@ export const ChatMessage: Component<Props> = (props) => {
             </Show>
           </Show>
 
+          <Show when={props.message.images?.length}>
+            <SImagesWrapper>
+              <For each={props.message.images}>
+                {(image) => <SImage src={image} draggable={false} />}
+              </For>
+            </SImagesWrapper>
+          </Show>
+
           <Show
             when={content()}
             fallback={
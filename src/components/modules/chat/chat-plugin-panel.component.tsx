@@ -1,7 +1,15 @@
-import { Component, createMemo, For, on, Show } from "solid-js";
+import {
+  Component,
+  createMemo,
+  createResource,
+  For,
+  on,
+  Show,
+} from "solid-js";
 import { styled } from "solid-styled-components";
 import { Button, Checkbox, Text } from "~/components/atoms";
-import { CalculatorPlugin, Plugin, SearchPlugin } from "~/cortex";
+import { Plugin } from "~/cortex";
+import { InvokeService } from "~/services";
 import { commandStore } from "~/store";
 import { chatStore } from "~/store/chat.store";
 import { getBrowserDrivers } from "~/util/browser";
@@ -58,24 +66,43 @@ interface Props {
   visible: boolean;
 }
 
-const searchPlugin = new SearchPlugin();
-const calculatorPlugin = new CalculatorPlugin();
-
-const plugins = [searchPlugin, calculatorPlugin];
+/** Tools that need a browser driver to run. */
+const BROWSER_TOOLS = ["web_search"];
+
+const getPlugins = async () => {
+  const tools = await InvokeService.shared.getTools().catch(() => []);
+  const { chat } = await InvokeService.shared
+    .getToolSettings()
+    .catch(() => ({ chat: undefined }));
+
+  return {
+    plugins: tools
+      .filter((tool) => tool.permission !== "deny")
+      .map((tool): Plugin => ({
+        name: tool.name,
+        description: tool.description,
+      })),
+    hasEndpoint: !!chat,
+  };
+};
 
 export const ChatPluginPanel: Component<Props> = (props) => {
   const { setSelectedPlugins, setIsPluginsPanelVisible } = chatStore;
   const { commandSections } = commandStore;
 
+  const [plugins] = createResource(getPlugins);
+
   const handleTogglePlugin = (plugin: Plugin, value: boolean) => {
     setSelectedPlugins((prev) => {
+      const next = new Set(
+        Array.from(prev).filter(({ name }) => name !== plugin.name)
+      );
+
       if (value) {
-        prev.add(plugin);
-      } else {
-        prev.delete(plugin);
+        next.add(plugin);
       }
 
-      return new Set(prev);
+      return next;
     });
   };
 
@@ -85,6 +112,9 @@ export const ChatPluginPanel: Component<Props> = (props) => {
     })
   );
 
+  const isAvailable = (plugin: Plugin) =>
+    !BROWSER_TOOLS.includes(plugin.name) || isBrowser();
+
   return (
     <SWrapper>
       <SContentWrapper visible={props.visible}>
@@ -99,52 +129,42 @@ export const ChatPluginPanel: Component<Props> = (props) => {
           </Button>
         </SHeader>
 
+        <Show when={plugins() && !plugins()?.hasEndpoint}>
+          <Text.Caption color="gray" mt="16px">
+            Plugins are used once a chat endpoint that supports tool calls is
+            set up
+          </Text.Caption>
+        </Show>
+
         <SPluginsWrapper>
-          <SPluginTileWrapper>
-            <Checkbox
-              shortcutKey="0"
-              onChange={(event) => {
-                handleTogglePlugin(searchPlugin, event.currentTarget.checked);
-              }}
-              disabled={!isBrowser()}
-            />
-
-            <div>
-              <Text.Callout color={isBrowser() ? "text" : "gray"}>
-                {searchPlugin.name}
-              </Text.Callout>
-              <Text.Callout color={isBrowser() ? "gray" : "gray2"} mt="2px">
-                {searchPlugin.description}
-              </Text.Callout>
-
-              <Text.Caption color={isBrowser() ? "gray" : "gray2"} mt="4px">
-                <Show when={!isBrowser()}>
-                  You need to install Mozilla Firefox or Google Chrome to enable
-                  web browsing
-                </Show>
-                <Show when={isBrowser()}>
-                  Lander uses system browsers in a secure sandbox to search and
-                  browse the web
-                </Show>
-              </Text.Caption>
-            </div>
-          </SPluginTileWrapper>
-
-          <For each={plugins.filter((plugin) => plugin.name !== "Search")}>
+          <For each={plugins()?.plugins}>
             {(plugin, index) => (
               <SPluginTileWrapper>
                 <Checkbox
-                  shortcutKey={String(index() + 1)}
+                  shortcutKey={String(index())}
                   onChange={(event) => {
                     handleTogglePlugin(plugin, event.currentTarget.checked);
                   }}
+                  disabled={!isAvailable(plugin)}
                 />
 
                 <div>
-                  <Text.Callout>{plugin.name}</Text.Callout>
-                  <Text.Callout color="gray" mt="2px">
+                  <Text.Callout color={isAvailable(plugin) ? "text" : "gray"}>
+                    {plugin.name}
+                  </Text.Callout>
+                  <Text.Callout
+                    color={isAvailable(plugin) ? "gray" : "gray2"}
+                    mt="2px"
+                  >
                     {plugin.description}
                   </Text.Callout>
+
+                  <Show when={!isAvailable(plugin)}>
+                    <Text.Caption color="gray2" mt="4px">
+                      You need to install Mozilla Firefox or Google Chrome to
+                      enable web browsing
+                    </Text.Caption>
+                  </Show>
                 </div>
               </SPluginTileWrapper>
             )}
@@ -0,0 +1,206 @@
+import { listen } from "@tauri-apps/api/event";
+import { Component, createSignal, For, onCleanup, Show } from "solid-js";
+import { styled } from "solid-styled-components";
+import { Button, Text } from "~/components/atoms";
+import { InvokeService } from "~/services";
+import { CommandStatus, ToolApprovalRequest } from "~/services/invoke.service";
+
+const SWrapper = styled("div")`
+  display: grid;
+  gap: 12px;
+  margin: 16px;
+`;
+
+const STileWrapper = styled("div")`
+  display: grid;
+  gap: 8px;
+  padding: 12px;
+  border-radius: 8px;
+  background: ${(props) => props.theme?.colors.gray5};
+  border: 0.5px solid ${(props) => props.theme?.colors.gray3};
+`;
+
+const SCode = styled("pre")`
+  margin: 0;
+  max-height: 200px;
+  overflow: auto;
+  white-space: pre-wrap;
+  word-break: break-all;
+  font-size: 12px;
+  color: ${(props) => props.theme?.colors.text};
+`;
+
+const SLine = styled("div")<{ isError: boolean }>`
+  color: ${(props) =>
+    props.isError ? props.theme?.colors.gray : props.theme?.colors.text};
+`;
+
+const SButtonsWrapper = styled("div")`
+  display: grid;
+  grid-auto-flow: column;
+  justify-content: end;
+  gap: 8px;
+`;
+
+interface CommandRun {
+  id: number;
+  command: string;
+  cwd: string;
+  lines: Array<{ stream: "stdout" | "stderr"; line: string }>;
+  status: CommandStatus;
+  exitCode?: number;
+}
+
+/** Lines of a running command kept on screen; the full output is in the log. */
+const MAX_VISIBLE_LINES = 200;
+
+const statusText = (run: CommandRun) => {
+  switch (run.status) {
+    case "running":
+      return "Running…";
+    case "timed_out":
+      return "Timed out";
+    default:
+      return run.exitCode !== undefined
+        ? `Exited with code ${run.exitCode}`
+        : "Stopped";
+  }
+};
+
+/**
+ * Tool calls waiting for the user's approval and the output of approved shell
+ * commands while they run.
+ */
+export const ChatToolApproval: Component = () => {
+  const [requests, setRequests] = createSignal<ToolApprovalRequest[]>([]);
+  const [runs, setRuns] = createSignal<CommandRun[]>([]);
+
+  const updateRun = (id: number, update: (run: CommandRun) => CommandRun) => {
+    setRuns((prev) => prev.map((run) => (run.id === id ? update(run) : run)));
+  };
+
+  const handleResolve = async (
+    request: ToolApprovalRequest,
+    approved: boolean
+  ) => {
+    setRequests((prev) => prev.filter(({ id }) => id !== request.id));
+    await InvokeService.shared.resolveToolApproval(request.id, approved);
+  };
+
+  const unsubscribes = Promise.all([
+    listen("tool_approval_request", (event) => {
+      const request = event.payload as ToolApprovalRequest;
+      setRequests((prev) => [...prev, request]);
+    }),
+    listen("tool_approval_expired", (event) => {
+      const { id } = event.payload as { id: number };
+      setRequests((prev) => prev.filter((request) => request.id !== id));
+    }),
+    listen("command_started", (event) => {
+      const payload = event.payload as {
+        id: number;
+        command: string;
+        cwd: string;
+      };
+
+      setRuns((prev) => [
+        ...prev.slice(-2),
+        { ...payload, lines: [], status: "running" },
+      ]);
+    }),
+    listen("command_output", (event) => {
+      const { id, stream, line } = event.payload as {
+        id: number;
+        stream: "stdout" | "stderr";
+        line: string;
+      };
+
+      updateRun(id, (run) => ({
+        ...run,
+        lines: [...run.lines, { stream, line }].slice(-MAX_VISIBLE_LINES),
+      }));
+    }),
+    listen("command_finished", (event) => {
+      const { id, status, exit_code } = event.payload as {
+        id: number;
+        status: CommandStatus;
+        exit_code: number | null;
+      };
+
+      updateRun(id, (run) => ({
+        ...run,
+        status,
+        exitCode: exit_code ?? undefined,
+      }));
+    }),
+  ]);
+
+  onCleanup(async () => {
+    (await unsubscribes).forEach((unsubscribe) => unsubscribe());
+  });
+
+  return (
+    <SWrapper>
+      <For each={runs()}>
+        {(run) => (
+          <STileWrapper>
+            <Text.Callout fontWeight="medium">$ {run.command}</Text.Callout>
+            <Text.Caption color="gray">{run.cwd}</Text.Caption>
+
+            <Show when={run.lines.length}>
+              <SCode>
+                <For each={run.lines}>
+                  {({ stream, line }) => (
+                    <SLine isError={stream === "stderr"}>{line}</SLine>
+                  )}
+                </For>
+              </SCode>
+            </Show>
+
+            <Text.Caption color="gray">{statusText(run)}</Text.Caption>
+          </STileWrapper>
+        )}
+      </For>
+
+      <For each={requests()}>
+        {(request) => {
+          const args = request.arguments as Record<string, unknown>;
+
+          return (
+            <STileWrapper>
+              <Show
+                when={request.name === "run_command"}
+                fallback={
+                  <>
+                    <Text.Callout fontWeight="medium">
+                      Allow the assistant to use {request.name}?
+                    </Text.Callout>
+                    <SCode>{JSON.stringify(request.arguments, null, 2)}</SCode>
+                  </>
+                }
+              >
+                <Text.Callout fontWeight="medium">
+                  Allow the assistant to run this command?
+                </Text.Callout>
+                <SCode>$ {String(args.command)}</SCode>
+                <Text.Caption color="gray">
+                  In {String(args.cwd)}, stopped after{" "}
+                  {String(args.timeout)} seconds
+                </Text.Caption>
+              </Show>
+
+              <SButtonsWrapper>
+                <Button onClick={() => handleResolve(request, false)}>
+                  Deny
+                </Button>
+                <Button onClick={() => handleResolve(request, true)}>
+                  Approve
+                </Button>
+              </SButtonsWrapper>
+            </STileWrapper>
+          );
+        }}
+      </For>
+    </SWrapper>
+  );
+};
@@ -22,6 +22,7 @@ import { retryLander } from "~/util";
 import { chatCommand } from "~/util/ai-commands";
 import { ChatCommand } from "./chat-command.component";
 import { ChatMessage } from "./chat-message.component";
+import { ChatToolApproval } from "./chat-tool-approval.component";
 
 const SWrapper = styled("div")`
   height: calc(100% - 102px);
@@ -313,6 +314,8 @@ export const ChatView: Component = () => {
       <For each={messages()}>
         {(message) => <ChatMessage message={message} onRetry={handleRetry} />}
       </For>
+
+      <ChatToolApproval />
     </SWrapper>
   );
 };
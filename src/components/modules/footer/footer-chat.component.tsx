@@ -1,8 +1,19 @@
+import { listen } from "@tauri-apps/api/event";
 import Fuse from "fuse.js";
-import { batch, Component, createEffect, on, onCleanup, Show } from "solid-js";
+import {
+  batch,
+  Component,
+  createEffect,
+  createSignal,
+  For,
+  on,
+  onCleanup,
+  Show,
+} from "solid-js";
 import { styled } from "solid-styled-components";
 import { Button, Icon } from "~/components/atoms";
 import { useArchive, useUser } from "~/queries";
+import { InvokeService } from "~/services/invoke.service";
 import { NetworkService } from "~/services/network.service";
 import { router, View } from "~/store";
 import { chatStore } from "~/store/chat.store";
@@ -69,6 +80,15 @@ const SButtonIcon = styled(Icon)`
   }
 `;
 
+const SAttachment = styled("img")`
+  height: 24px;
+  max-width: 48px;
+  object-fit: cover;
+  border-radius: 4px;
+  border: ${(props) => `0.5px solid ${props.theme?.colors.gray2}`};
+  cursor: pointer;
+`;
+
 const drafts: { chat: string | undefined } = {
   chat: undefined,
 };
@@ -78,6 +98,8 @@ export const FooterChat: Component = () => {
   const {
     thread,
     contextualText,
+    attachedImages,
+    setAttachedImages,
     isArchiveVisible,
     setArchiveSearchResults,
     setHighlightedArchiveTile,
@@ -167,6 +189,73 @@ export const FooterChat: Component = () => {
     }
   };
 
+  const [isDictating, setIsDictating] = createSignal(false);
+
+  /** What was typed before dictation started, kept in front of the transcript. */
+  let dictationPrefix = "";
+
+  const setDictatedText = (transcript: string) => {
+    if (!ref) {
+      return;
+    }
+
+    const text = [dictationPrefix, transcript].filter(Boolean).join(" ");
+
+    if (!text) {
+      ref.replaceChildren();
+      return;
+    }
+
+    const el = document.createElement("div");
+    el.innerText = text;
+    ref.replaceChildren(el);
+
+    moveCursorToEnd();
+  };
+
+  const handleToggleDictation = async () => {
+    if (!isDictating()) {
+      dictationPrefix = ref?.innerText.trim() ?? "";
+      setIsDictating(true);
+
+      await InvokeService.shared.startDictation("panel").catch((error) => {
+        setIsDictating(false);
+        console.error(error);
+      });
+
+      return;
+    }
+
+    setIsDictating(false);
+
+    const transcript = await InvokeService.shared
+      .stopDictation()
+      .catch((error) => {
+        console.error(error);
+        return null;
+      });
+
+    if (transcript) {
+      setDictatedText(transcript);
+    }
+  };
+
+  const unlistenPartial = listen("dictation_partial", (event) => {
+    const { text } = event.payload as { text: string };
+
+    if (isDictating()) {
+      setDictatedText(text);
+    }
+  });
+
+  onCleanup(async () => {
+    (await unlistenPartial)();
+
+    if (isDictating()) {
+      await InvokeService.shared.cancelDictation();
+    }
+  });
+
   createEffect(() => {
     if (!ref) {
       return;
@@ -273,6 +362,10 @@ export const FooterChat: Component = () => {
             ? "Search archive"
             : contextualText()
             ? `Ask from ${contextualText()?.provider}`
+            : isDictating()
+            ? "Listening…"
+            : attachedImages().length
+            ? "Ask about the screenshot"
             : "Ask me anything"
         }
         ref={ref}
@@ -283,6 +376,19 @@ export const FooterChat: Component = () => {
 
       <Show when={!isArchiveVisible()}>
         <SChatButtonsWrapper>
+          <For each={attachedImages()}>
+            {(image) => (
+              <SAttachment
+                src={image}
+                title="Remove screenshot"
+                draggable={false}
+                onClick={() =>
+                  setAttachedImages((prev) => prev.filter((i) => i !== image))
+                }
+              />
+            )}
+          </For>
+
           <Show when={user.data?.subscription}>
             <Button
               py="2px"
@@ -294,6 +400,13 @@ export const FooterChat: Component = () => {
             </Button>
           </Show>
 
+          <SButtonIcon
+            onClick={handleToggleDictation}
+            name="microphone"
+            size="18px"
+            stroke={isDictating() ? "orange" : undefined}
+          />
+
           <Show when={isStreaming()}>
             <SButtonIcon onClick={handleCancel} name="cross" size="18px" />
           </Show>
@@ -1,7 +1,6 @@
-export * from "./json-parser";
 export * from "./memory";
 export * from "./message";
 export * from "./model";
 export * from "./plugin";
-export * from "./plugins";
 export * from "./prompt";
+export * from "./tools";
@@ -2,13 +2,16 @@ import { ThreadMessage, ThreadMessageAuthor } from "~/models";
 
 interface MessageType {
   content: string;
+  images?: string[];
 }
 
 export class BaseMessage implements MessageType {
   content: string;
+  images?: string[];
 
-  constructor(content: string) {
+  constructor(content: string, images?: string[]) {
     this.content = content;
+    this.images = images;
   }
 }
 
@@ -41,7 +44,7 @@ export class Messages extends Array<Message> {
             ? new SystemMessage(messages.content)
             : messages.author === ThreadMessageAuthor.AI
             ? new AIMessage(messages.content)
-            : new UserMessage(messages.content);
+            : new UserMessage(messages.content, messages.images);
 
         this.push(message);
       } else {
@@ -65,6 +68,7 @@ export class Messages extends Array<Message> {
               ? ThreadMessageAuthor.AI
               : ThreadMessageAuthor.User,
           content: message.content,
+          images: message.images,
         })
     );
   }
@@ -1,12 +1,17 @@
+import { listen } from "@tauri-apps/api/event";
 import { ThreadType } from "~/models";
+import {
+  InvokeService,
+  ToolChatEndpoint,
+  ToolStep,
+} from "~/services/invoke.service";
 import { NetworkRequest, NetworkService } from "~/services/network.service";
-import { JSONParser } from "./json-parser";
 import { Memory } from "./memory";
-import { AIMessage } from "./message";
 import { Plugin } from "./plugin";
-import { FinalPromptResponse, PluginPromptResponse, Prompt } from "./prompt";
+import { Prompt } from "./prompt";
+import { toToolHeaders, toToolRequest } from "./tools";
 
-type ListenerEvent = "response" | "plugin" | "end";
+type ListenerEvent = "response" | "tool" | "end";
 
 type Listener =
   | {
@@ -14,8 +19,8 @@ type Listener =
       callback: (data: string) => void;
     }
   | {
-      event: "plugin";
-      callback: (plugin: Plugin, input: string) => void;
+      event: "tool";
+      callback: (step: ToolStep) => void;
     }
   | {
       event: "end";
@@ -25,25 +30,20 @@ type Listener =
 export abstract class Model {
   memory = new Memory();
 
-  plugins = new Set<Plugin>();
-
-  jsonParser = new JSONParser<
-    Partial<PluginPromptResponse | FinalPromptResponse>
-  >();
-
-  listeners = new Array<Listener>();
-
-  iterations = 0;
+  /** Retrieved excerpts added to the prompt of every call. */
+  context = new Array<string>();
 
-  maxIterations = -1;
+  /** Data URLs of screenshots attached to the next call only. */
+  images = new Array<string>();
 
-  private response = "";
+  /** Tools the model may call; calls only happen with a chat endpoint set. */
+  plugins = new Set<Plugin>();
 
-  private streamedResponse = "";
+  listeners = new Array<Listener>();
 
   on(event: "response", callback: (data: string) => void): void;
 
-  on(event: "plugin", callback: (plugin: Plugin, input: string) => void): void;
+  on(event: "tool", callback: (step: ToolStep) => void): void;
 
   on(event: "end", callback: () => void): void;
 
@@ -51,138 +51,52 @@ export abstract class Model {
     this.listeners.push({ event, callback } as Listener);
   }
 
-  init() {
-    this.iterations += 1;
-
-    if (this.iterations > this.maxIterations) {
-      this.listeners.forEach((listener) => {
-        if (listener.event === "response") {
-          listener.callback("[LANDER_STREAM_ERROR]");
-        }
-      });
-      return;
-    }
-
-    this.jsonParser.on("data", (data) => {
-      if ("response" in data && data.response?.replace) {
-        const message = data.response.replace(this.streamedResponse, "");
-        this.listeners.forEach((listener) => {
-          if (listener.event === "response") {
-            listener.callback(message);
-            this.streamedResponse = data.response!;
-          }
-        });
-      }
-    });
-
-    this.jsonParser.on("end", async (data) => {
-      if ("plugin" in data && data.input && data.plugin) {
-        const plugin = Array.from(this.plugins).find(
-          (plugin) => plugin.name === data.plugin
-        );
-
-        if (plugin) {
-          this.listeners.forEach((listener) => {
-            if (listener.event === "plugin") {
-              listener.callback(plugin, data.input!);
-            }
-          });
-
-          let pluginResponse = "";
-
-          try {
-            pluginResponse = await plugin.call(data.input);
-          } catch {
-            pluginResponse =
-              "No response from plugin, please try again with a different input.";
-          }
-
-          this.memory.messages.add(new AIMessage(JSON.stringify(data)));
-
-          let formattedResponse = `Response from the plugin:\n\n${pluginResponse}\n\n`;
-
-          if (this.iterations + 1 >= this.maxIterations) {
-            formattedResponse +=
-              "You have reached your maximum iterations so you must output your final answer (option 1).";
-          } else {
-            formattedResponse +=
-              "You can either use an additional plugin (option 2) or output your final answer (option 1).";
-          }
-
-          this.call(formattedResponse);
-        } else {
-          this.listeners.forEach((listener) => {
-            if (listener.event === "end") {
-              listener.callback();
-            }
-          });
-        }
+  onStreamMessage(data: string) {
+    this.listeners.forEach((listener) => {
+      if (listener.event === "response") {
+        listener.callback(data);
       }
     });
   }
 
-  onStreamMessage(data: string) {
-    this.response += data;
-
-    if (!this.plugins.size) {
-      this.listeners.forEach((listener) => {
-        if (listener.event === "response") {
-          listener.callback(data);
-        }
-      });
-    } else {
-      if (
-        this.response.trim().replace(/\s+/g, "").startsWith('{"plugin"') ||
-        /^\{("?)(p(l(u(g(i(n)?)?)?)?)?)?$/.test(
-          this.response.trim().replace(/\s+/g, "")
-        ) ||
-        this.response.trim().replace(/\s+/g, "").startsWith('{"response"') ||
-        /^\{("?)(r(e(s(p(o(n(s(e)?)?)?)?)?)?)?)?$/.test(
-          this.response.trim().replace(/\s+/g, "")
-        )
-      ) {
-        this.jsonParser.push(data);
-      } else {
-        const message = this.response.replace(this.streamedResponse, "");
-        this.listeners.forEach((listener) => {
-          if (listener.event === "response") {
-            listener.callback(message);
-            this.streamedResponse = this.response;
-          }
-        });
+  onToolStep(step: ToolStep) {
+    this.listeners.forEach((listener) => {
+      if (listener.event === "tool") {
+        listener.callback(step);
       }
-    }
+    });
   }
 
   onStreamEnd() {
-    this.jsonParser.close();
-    this.jsonParser.unsubscribe();
-    this.response = "";
-    this.streamedResponse = "";
-
-    if (!this.plugins.size) {
-      this.listeners.forEach((listener) => {
-        if (listener.event === "end") {
-          listener.callback();
-        }
-      });
-    }
+    this.listeners.forEach((listener) => {
+      if (listener.event === "end") {
+        listener.callback();
+      }
+    });
   }
 
   abstract call(message: string): void;
 }
 
 export class OpenAIChatModel extends Model {
-  maxIterations = 4;
-
   async call(message: string) {
     const prompt = new Prompt({
       input: message,
-      plugins: this.plugins,
+      images: this.images.length ? this.images : undefined,
       memory: this.memory,
+      context: this.context,
     });
 
-    this.init();
+    this.images = [];
+
+    const { chat: endpoint } = await InvokeService.shared
+      .getToolSettings()
+      .catch(() => ({ chat: undefined }));
+
+    if (endpoint) {
+      await this.callWithTools(endpoint, prompt);
+      return;
+    }
 
     await NetworkService.shared.stream(
       new NetworkRequest("/chat", "POST", {
@@ -194,4 +108,37 @@ export class OpenAIChatModel extends Model {
       () => this.onStreamEnd()
     );
   }
+
+  /**
+   * Sends the chat through `run_tools`, which calls the selected tools until
+   * the model answers. The answer arrives whole rather than streamed.
+   */
+  private async callWithTools(endpoint: ToolChatEndpoint, prompt: Prompt) {
+    NetworkService.isStreaming = true;
+
+    const unsubscribe = await listen("tool_step", (event) => {
+      this.onToolStep(event.payload as ToolStep);
+    });
+
+    const run = await InvokeService.shared
+      .runTools({
+        provider: endpoint.provider,
+        url: endpoint.url,
+        headers: toToolHeaders(endpoint),
+        request: toToolRequest(endpoint, prompt.threadMessages),
+        tools: Array.from(this.plugins).map((plugin) => plugin.name),
+      })
+      .catch(() => undefined);
+
+    unsubscribe();
+    NetworkService.isStreaming = false;
+
+    if (!run) {
+      this.onStreamMessage("[LANDER_STREAM_ERROR]");
+      return;
+    }
+
+    this.onStreamMessage(run.text);
+    this.onStreamEnd();
+  }
 }
@@ -1,8 +1,7 @@
+/** A tool the model may call, enabled per chat. */
 export interface Plugin {
   name: string;
   description: string;
-  modelDescription: string;
-  call: (input: string) => Promise<string> | string;
 }
 
 export class Plugins extends Set<Plugin> {}
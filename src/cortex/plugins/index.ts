@@ -1,2 +0,0 @@
-export * from "./calculator.plugin";
-export * from "./search.plugin";
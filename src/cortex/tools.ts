@@ -0,0 +1,102 @@
+import { ThreadMessage, ThreadMessageAuthor } from "~/models";
+import { ToolChatEndpoint } from "~/services/invoke.service";
+
+const ANTHROPIC_VERSION = "2023-06-01";
+
+const ANTHROPIC_MAX_TOKENS = 4096;
+
+const secret = (name: string) => `{{secret:${name}}}`;
+
+/** Splits a `data:<media type>;base64,<data>` URL. */
+const parseDataUrl = (url: string) => {
+  const match = /^data:([^;,]+);base64,(.*)$/s.exec(url);
+  return match ? { mediaType: match[1], data: match[2] } : undefined;
+};
+
+const openAIMessage = (message: ThreadMessage) => {
+  const role =
+    message.author === ThreadMessageAuthor.System
+      ? "system"
+      : message.author === ThreadMessageAuthor.AI
+      ? "assistant"
+      : "user";
+
+  if (!message.images?.length) {
+    return { role, content: message.content };
+  }
+
+  return {
+    role,
+    content: [
+      { type: "text", text: message.content },
+      ...message.images.map((url) => ({
+        type: "image_url",
+        image_url: { url },
+      })),
+    ],
+  };
+};
+
+const anthropicMessage = (message: ThreadMessage) => {
+  const role = message.author === ThreadMessageAuthor.AI ? "assistant" : "user";
+
+  const images = (message.images || [])
+    .map(parseDataUrl)
+    .filter((image): image is NonNullable<typeof image> => !!image);
+
+  if (!images.length) {
+    return { role, content: message.content };
+  }
+
+  return {
+    role,
+    content: [
+      ...images.map(({ mediaType, data }) => ({
+        type: "image",
+        source: { type: "base64", media_type: mediaType, data },
+      })),
+      { type: "text", text: message.content },
+    ],
+  };
+};
+
+/** Chat completion request for the endpoint, without the tools `run_tools` adds. */
+export const toToolRequest = (
+  endpoint: ToolChatEndpoint,
+  messages: ThreadMessage[]
+): Record<string, unknown> => {
+  if (endpoint.provider === "anthropic") {
+    const system = messages
+      .filter((message) => message.author === ThreadMessageAuthor.System)
+      .map((message) => message.content.trim())
+      .join("\n\n");
+
+    return {
+      model: endpoint.model,
+      max_tokens: ANTHROPIC_MAX_TOKENS,
+      ...(system ? { system } : {}),
+      messages: messages
+        .filter((message) => message.author !== ThreadMessageAuthor.System)
+        .map(anthropicMessage),
+    };
+  }
+
+  return {
+    model: endpoint.model,
+    messages: messages.map(openAIMessage),
+  };
+};
+
+/** Headers authenticating with the endpoint's stored API key. */
+export const toToolHeaders = (
+  endpoint: ToolChatEndpoint
+): Record<string, string> => {
+  if (endpoint.provider === "anthropic") {
+    return {
+      "x-api-key": secret(endpoint.apiKeySecret),
+      "anthropic-version": ANTHROPIC_VERSION,
+    };
+  }
+
+  return { Authorization: `Bearer ${secret(endpoint.apiKeySecret)}` };
+};
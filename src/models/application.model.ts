@@ -3,13 +3,33 @@ import { __macos__, __windows__ } from "~/constants";
 import { InvokeService } from "~/services";
 import { Command, CommandType } from "./command.model";
 
+/** Where the user is in the focused application. */
+export interface ApplicationContext {
+  windowTitle?: string;
+  /** Offset of the caret in `focusedText`, in characters. */
+  caretPosition?: number;
+  textBeforeCaret?: string;
+  textAfterCaret?: string;
+  /** Address of the page in the focused browser window. */
+  url?: string;
+  /** Path of the file open in the focused window. */
+  documentPath?: string;
+}
+
 export interface ApplicationData {
   id: string;
   name: string;
   icon: string;
   path: string;
+  version?: string;
+  executable?: string;
+  localizedNames?: string[];
+  category?: string;
+  aliases?: string[];
+  keywords?: string[];
   selectedText?: string;
   focusedText?: string;
+  context?: ApplicationContext;
 }
 
 export class Application implements ApplicationData {
@@ -17,18 +37,58 @@ export class Application implements ApplicationData {
   name: string;
   icon: string;
   path: string;
+  version?: string;
+  executable?: string;
+  localizedNames: string[];
+  category?: string;
+  aliases: string[];
+  keywords: string[];
   selectedText?: string;
 
   @Exclude()
   focusedText?: string;
 
+  @Exclude()
+  context?: ApplicationContext;
+
   constructor(data: ApplicationData) {
     this.id = data.id;
     this.name = data.name;
     this.icon = data.icon;
     this.path = data.path;
+    this.version = data.version;
+    this.executable = data.executable;
+    this.localizedNames = data.localizedNames ?? [];
+    this.category = data.category;
+    this.aliases = data.aliases ?? [];
+    this.keywords = data.keywords ?? [];
     this.selectedText = data.selectedText;
     this.focusedText = data.focusedText;
+    this.context = data.context;
+  }
+
+  /** Describes the context for a prompt, or `undefined` when there is none. */
+  describeContext() {
+    const context = this.context;
+
+    if (!context) {
+      return undefined;
+    }
+
+    const lines = [
+      context.windowTitle && `Window: ${context.windowTitle}`,
+      context.url && `URL: ${context.url}`,
+      context.documentPath && `Document: ${context.documentPath}`,
+      context.caretPosition !== undefined &&
+        context.caretPosition !== null &&
+        `Text around the cursor:\n${context.textBeforeCaret ?? ""}<cursor>${
+          context.textAfterCaret ?? ""
+        }`,
+    ].filter(Boolean);
+
+    return lines.length
+      ? `The user is in ${this.name}.\n${lines.join("\n")}`
+      : undefined;
   }
 
   toCommand(type?: CommandType) {
@@ -1,5 +1,5 @@
 import { Exclude } from "class-transformer";
-import { AnalyticsService } from "~/services";
+import { AnalyticsService, InvokeService } from "~/services";
 import { AnalyticsEventType } from "./analytics-event.model";
 import { Application } from "./application.model";
 
@@ -59,6 +59,13 @@ export class Command {
   onClick() {
     this.onClickMethod();
 
+    if (this.type !== CommandType.Application) {
+      InvokeService.shared.recordUsage(
+        this.type === CommandType.AI ? "prompt" : "command",
+        this.id.replace("suggestion-", "")
+      );
+    }
+
     AnalyticsService.shared.addEvent({
       type: AnalyticsEventType.Command,
       event: {
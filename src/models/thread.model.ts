@@ -32,14 +32,20 @@ export class ThreadMessage {
   @Exclude()
   plugins?: ThreadMessagePlugin[];
 
+  /** Data URLs of attached screenshots, sent to the model but not stored. */
+  @Expose({ groups: ["stream"] })
+  images?: string[];
+
   constructor(opts: {
     id?: string;
     author: ThreadMessageAuthor;
     content: string;
+    images?: string[];
   }) {
     this.id = opts.id || this.id;
     this.author = opts.author;
     this.content = opts.content;
+    this.images = opts.images;
   }
 }
 
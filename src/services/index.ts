@@ -1,4 +1,3 @@
 export * from "./analytics.service";
-export * from "./event.service";
 export * from "./invoke.service";
 export * from "./settings.service";
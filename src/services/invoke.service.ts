@@ -8,6 +8,391 @@ export enum BrowserDriver {
   Firefox = "firefox",
 }
 
+export interface PanelSettings {
+  width: number;
+  height: number;
+  maxWidth: number;
+  maxHeight: number;
+  verticalOffset: number;
+  screen: "cursor" | "focused_window" | "primary";
+  rememberPosition: boolean;
+}
+
+export interface ApplicationMatch {
+  application: Application;
+  score: number;
+  matchedText: string;
+  highlights: Array<[number, number]>;
+}
+
+export interface RunningWindow {
+  id: string;
+  pid: number;
+  title: string;
+  isMinimized: boolean;
+  isFocused: boolean;
+  monitor?: string;
+  frame?: { x: number; y: number; width: number; height: number };
+}
+
+export interface RunningApplication {
+  id: string;
+  name: string;
+  pid: number;
+  path?: string;
+  isActive: boolean;
+  isHidden: boolean;
+  windows: RunningWindow[];
+}
+
+export type WindowAction =
+  | "maximize"
+  | "left_half"
+  | "right_half"
+  | "top_half"
+  | "bottom_half"
+  | "top_left"
+  | "top_right"
+  | "bottom_left"
+  | "bottom_right"
+  | "left_third"
+  | "center_third"
+  | "right_third"
+  | "center"
+  | "next_display"
+  | "restore";
+
+export interface LaunchOptions {
+  arguments?: string[];
+  files?: string[];
+  newInstance?: boolean;
+  hidden?: boolean;
+  background?: boolean;
+}
+
+export interface FileSearchSettings {
+  roots: string[];
+  exclude: string[];
+  includeHidden: boolean;
+  respectIgnoreFiles: boolean;
+  maxDepth: number;
+  maxEntries: number;
+  maxContentSize: number;
+}
+
+export interface FileMatch {
+  path: string;
+  name: string;
+  isDir: boolean;
+  size: number;
+  modified: number;
+  score: number;
+  highlights: Array<[number, number]>;
+  contentMatches: Array<{ line: number; text: string }>;
+}
+
+export type DocumentFormat =
+  | "plain_text"
+  | "markdown"
+  | "code"
+  | "pdf"
+  | "docx";
+
+export interface ContextDocument {
+  path: string;
+  name: string;
+  format?: DocumentFormat;
+  chunks: Array<{ index: number; text: string }>;
+  totalLength: number;
+  isTruncated: boolean;
+  error?: string;
+}
+
+export interface RetrievalSettings {
+  enabled: boolean;
+  backend: "remote" | "local";
+  remoteUrl: string;
+  remoteModel: string;
+  remoteApiKeySecret: string;
+  localModelDir?: string;
+  topK: number;
+}
+
+export type RetrievalSourceKind = "document" | "thread";
+
+export interface RetrievalHit {
+  sourceKind: RetrievalSourceKind;
+  sourceId: string;
+  title: string;
+  chunkIndex: number;
+  text: string;
+  score: number;
+}
+
+export interface ThreadRecord {
+  id: string;
+  type: string;
+  messages: Array<{
+    id: string;
+    author: string;
+    content: string;
+    createdAt: number;
+    updatedAt: number;
+  }>;
+  command?: Record<string, unknown>;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface ThreadSearchHit {
+  threadId: string;
+  messageId: string;
+  author: string;
+  snippet: string;
+  updatedAt: number;
+}
+
+export type ExportFormat = "markdown" | "html" | "json" | "pdf";
+
+export type CaptureTarget = "screen" | "window" | "region";
+
+export interface CaptureSettings {
+  /** Longest side in pixels that captures are downscaled to. */
+  maxDimension: number;
+  format: "png" | "jpeg";
+  jpegQuality: number;
+}
+
+export interface Capture {
+  mediaType: string;
+  /** Base64 of the encoded image. */
+  data: string;
+  width: number;
+  height: number;
+}
+
+export interface OcrSettings {
+  /** Recognize the focused window when it exposes no accessibility text. */
+  focusedTextFallback: boolean;
+  /** Tesseract language codes, joined with `+` for several. */
+  language: string;
+  dataPath?: string;
+}
+
+export type DictationTarget = "panel" | "application";
+
+export interface DictationSettings {
+  /**
+   * Push-to-talk hotkey that dictates into the focused application while it
+   * is held.
+   */
+  hotkey: string;
+  backend: "remote" | "local";
+  remoteUrl: string;
+  remoteModel: string;
+  remoteApiKeySecret: string;
+  /** `ggml` model file for whisper.cpp. */
+  localModelPath?: string;
+  /** ISO 639-1 code; detected from the audio when unset. */
+  language?: string;
+  /**
+   * Milliseconds between partial transcripts; 0 turns them off. When unset,
+   * only the local backend makes them.
+   */
+  partialIntervalMs?: number;
+}
+
+export interface InferenceSettings {
+  /** Answer chats with the selected local model instead of the server. */
+  enabled: boolean;
+  /** `models` in the app data directory when unset. */
+  modelsDirectory?: string;
+  /** File name of the selected GGUF model. */
+  model?: string;
+  promptFormat: "chat_ml" | "llama2" | "zephyr";
+  contextLength: number;
+  maxTokens: number;
+  temperature: number;
+  /** Seconds the model stays loaded after its last reply; 0 keeps it. */
+  unloadAfterSeconds: number;
+}
+
+export interface LocalModel {
+  name: string;
+  size: number;
+  isSelected: boolean;
+  isLoaded: boolean;
+}
+
+export interface SpeechSettings {
+  /** Read responses aloud as they stream in. */
+  readResponses: boolean;
+  /** Global hotkey that reads the selected text of the focused application. */
+  hotkey: string;
+  backend: "system" | "remote" | "command";
+  /** Multiple of the normal speaking rate. */
+  rate: number;
+  /** Default voice of the engine when empty. */
+  voice: string;
+  remoteUrl: string;
+  remoteModel: string;
+  remoteApiKeySecret: string;
+  /** Program that reads text on stdin and writes WAV to stdout. */
+  command?: string;
+  commandArguments: string[];
+}
+
+export type ToolProvider = "open_ai" | "anthropic";
+
+export type ToolPermission = "allow" | "ask" | "deny";
+
+export interface ToolInfo {
+  name: string;
+  description: string;
+  /** JSON schema of the arguments. */
+  parameters: Record<string, unknown>;
+  permission: ToolPermission;
+}
+
+export interface ToolChatEndpoint {
+  provider: ToolProvider;
+  url: string;
+  model: string;
+  /** Name of the stored secret sent as the API key. */
+  apiKeySecret: string;
+}
+
+export interface ToolSettings {
+  maxSteps: number;
+  /** Keyed by tool name. */
+  permissions: Record<string, ToolPermission>;
+  /** Chats go to the Lander API, without tools, until an endpoint is set. */
+  chat?: ToolChatEndpoint;
+}
+
+export interface ToolStep {
+  id: string;
+  name: string;
+  arguments: unknown;
+  output?: string;
+  error?: string;
+}
+
+export interface ToolRun {
+  text: string;
+  steps: ToolStep[];
+  /** Request messages including every tool round, in the provider's format. */
+  messages: unknown[];
+  response: unknown;
+}
+
+export interface ToolApprovalRequest {
+  id: number;
+  name: string;
+  arguments: unknown;
+}
+
+export interface RunCommandSettings {
+  /** Commands may only start in these directories or below them. */
+  allowedDirectories: string[];
+  /** Regular expressions of commands that are rejected without asking. */
+  blockedPatterns: string[];
+  /** In seconds. */
+  defaultTimeout: number;
+  maxTimeout: number;
+  maxOutputLength: number;
+}
+
+export type CommandStatus =
+  | "rejected"
+  | "declined"
+  | "running"
+  | "exited"
+  | "timed_out"
+  | "failed";
+
+export interface CommandLogEntry {
+  id: number;
+  command: string;
+  cwd: string;
+  status: CommandStatus;
+  exitCode?: number;
+  output: string;
+  startedAt: number;
+  durationMs?: number;
+}
+
+export type McpTransport =
+  | {
+      type: "stdio";
+      command: string;
+      args?: string[];
+      /** Values may reference stored secrets as `{{secret:<name>}}`. */
+      env?: Record<string, string>;
+      cwd?: string;
+    }
+  | {
+      type: "sse" | "http";
+      url: string;
+      /**
+       * Values may reference stored secrets bound to the host of `url` as
+       * `{{secret:<name>}}`.
+       */
+      headers?: Record<string, string>;
+    };
+
+export interface McpServerConfig {
+  name: string;
+  enabled: boolean;
+  transport: McpTransport;
+}
+
+export interface McpSettings {
+  servers: McpServerConfig[];
+}
+
+export interface McpServer {
+  name: string;
+  status: "connecting" | "connected" | "failed" | "disabled";
+  error?: string;
+  tools: Array<{ name: string; description?: string; inputSchema?: unknown }>;
+  resources: Array<{
+    uri: string;
+    name: string;
+    description?: string;
+    mimeType?: string;
+  }>;
+  prompts: Array<{
+    name: string;
+    description?: string;
+    arguments?: Array<{ name: string; required?: boolean }>;
+  }>;
+}
+
+export type UsageKind = "launch" | "command" | "prompt" | "insertion";
+
+export interface UsageEvent {
+  kind: UsageKind;
+  target: string;
+  sourceApplication?: string;
+  createdAt: number;
+}
+
+export interface UsageCount {
+  kind: UsageKind;
+  target: string;
+  count: number;
+  lastUsedAt: number;
+}
+
+export interface ApplicationUsage {
+  id: string;
+  launchCount: number;
+  firstLaunchedAt?: number;
+  lastLaunchedAt?: number;
+  sourceCounts: Partial<Record<UsageKind, number>>;
+}
+
 export class InvokeService {
   static shared = new InvokeService();
 
@@ -35,6 +420,31 @@ export class InvokeService {
     await invoke("hide_panel");
   }
 
+  async getPanelSettings() {
+    const settings = await invoke("get_panel_settings");
+    return convertKeysFromSnakeCaseToCamelCase<PanelSettings>(
+      settings as Record<string, unknown>
+    );
+  }
+
+  async setPanelSettings(settings: PanelSettings) {
+    await invoke("set_panel_settings", {
+      settings: {
+        width: settings.width,
+        height: settings.height,
+        max_width: settings.maxWidth,
+        max_height: settings.maxHeight,
+        vertical_offset: settings.verticalOffset,
+        screen: settings.screen,
+        remember_position: settings.rememberPosition,
+      },
+    });
+  }
+
+  async resetPanelPositions() {
+    await invoke("reset_panel_positions");
+  }
+
   async openSettingsWindow(view?: SettingsView) {
     await invoke("open_settings_window", { view });
   }
@@ -62,6 +472,67 @@ export class InvokeService {
     await invoke("cancel_stream");
   }
 
+  /**
+   * Answers a chat with the local model, emitting the reply on the same
+   * `stream` event as `stream`.
+   */
+  async streamLocal(body: string) {
+    const response = (await invoke("stream_local", { body })) as string;
+
+    return response;
+  }
+
+  async listLocalModels() {
+    const response = (await invoke("list_local_models")) as string;
+    return this.parse(response) as LocalModel[];
+  }
+
+  /**
+   * Downloads a GGUF model, emitting `local_model_download` progress events.
+   * Resolves to the model's name, or `null` when canceled.
+   */
+  async downloadLocalModel(url: string, name?: string) {
+    return (await invoke("download_local_model", { url, name })) as
+      | string
+      | null;
+  }
+
+  async cancelLocalModelDownload(name: string) {
+    await invoke("cancel_local_model_download", { name });
+  }
+
+  async deleteLocalModel(name: string) {
+    await invoke("delete_local_model", { name });
+  }
+
+  async loadLocalModel() {
+    await invoke("load_local_model");
+  }
+
+  async unloadLocalModel() {
+    await invoke("unload_local_model");
+  }
+
+  async getInferenceSettings() {
+    const response = (await invoke("get_inference_settings")) as string;
+    return this.parse(response) as InferenceSettings;
+  }
+
+  async setInferenceSettings(settings: InferenceSettings) {
+    await invoke("set_inference_settings", {
+      settings: {
+        enabled: settings.enabled,
+        models_directory: settings.modelsDirectory,
+        model: settings.model,
+        prompt_format: settings.promptFormat,
+        context_length: settings.contextLength,
+        max_tokens: settings.maxTokens,
+        temperature: settings.temperature,
+        unload_after_seconds: settings.unloadAfterSeconds,
+      },
+    });
+  }
+
   async getInstalledApplications() {
     const response = (await invoke("get_installed_applications")) as string;
     const applications = this.parse(response) as Array<ApplicationData>;
@@ -69,6 +540,22 @@ export class InvokeService {
     return applications.map((application) => new Application(application));
   }
 
+  async queryApplications(query: string, limit?: number) {
+    const response = (await invoke("query_applications", {
+      query,
+      limit,
+    })) as string;
+
+    const matches = this.parse(response) as Array<
+      Omit<ApplicationMatch, "application"> & { application: ApplicationData }
+    >;
+
+    return matches.map((match) => ({
+      ...match,
+      application: new Application(match.application),
+    })) as ApplicationMatch[];
+  }
+
   async getFocusedApplication() {
     const response = (await invoke("get_focused_application")) as string;
 
@@ -87,6 +574,102 @@ export class InvokeService {
     await invoke("launch_application", { id });
   }
 
+  async launchApplicationWithOptions(id: string, options: LaunchOptions) {
+    await invoke("launch_application_with_options", {
+      id,
+      options: {
+        arguments: options.arguments ?? [],
+        files: options.files ?? [],
+        new_instance: options.newInstance ?? false,
+        hidden: options.hidden ?? false,
+        background: options.background ?? false,
+      },
+    });
+  }
+
+  async getRunningApplications() {
+    const response = (await invoke("get_running_applications")) as string;
+    return this.parse(response) as RunningApplication[];
+  }
+
+  async activateWindow(pid: number, windowId?: string) {
+    await invoke("activate_window", { pid, windowId });
+  }
+
+  async hideApplication(pid: number) {
+    await invoke("hide_application", { pid });
+  }
+
+  async quitApplication(pid: number) {
+    await invoke("quit_application", { pid });
+  }
+
+  async forceQuitApplication(pid: number) {
+    await invoke("force_quit_application", { pid });
+  }
+
+  async manageWindow(action: WindowAction) {
+    await invoke("manage_window", { action });
+  }
+
+  async getWindowHotkeys() {
+    const response = (await invoke("get_window_hotkeys")) as string;
+    return JSON.parse(response) as Partial<Record<WindowAction, string>>;
+  }
+
+  async setWindowHotkeys(hotkeys: Partial<Record<WindowAction, string>>) {
+    await invoke("set_window_hotkeys", { hotkeys });
+  }
+
+  async setApplicationMetadata(
+    id: string,
+    aliases: string[],
+    keywords: string[]
+  ) {
+    await invoke("set_application_metadata", { id, aliases, keywords });
+  }
+
+  async searchFiles(query: string, limit?: number, content?: boolean) {
+    const response = (await invoke("search_files", {
+      query,
+      limit,
+      content,
+    })) as string;
+
+    return this.parse(response) as FileMatch[];
+  }
+
+  async getFileSearchSettings() {
+    const response = (await invoke("get_file_search_settings")) as string;
+    return this.parse(response) as FileSearchSettings;
+  }
+
+  async setFileSearchSettings(settings: FileSearchSettings) {
+    await invoke("set_file_search_settings", {
+      settings: {
+        roots: settings.roots,
+        exclude: settings.exclude,
+        include_hidden: settings.includeHidden,
+        respect_ignore_files: settings.respectIgnoreFiles,
+        max_depth: settings.maxDepth,
+        max_entries: settings.maxEntries,
+        max_content_size: settings.maxContentSize,
+      },
+    });
+  }
+
+  async openFile(path: string) {
+    await invoke("open_file", { path });
+  }
+
+  async revealFile(path: string) {
+    await invoke("reveal_file", { path });
+  }
+
+  async copyFilePath(path: string) {
+    await invoke("copy_file_path", { path });
+  }
+
   async copyText(text: string) {
     await invoke("copy_text_to_clipboard", { text });
   }
@@ -104,6 +687,467 @@ export class InvokeService {
     await invoke("replace_text", { text });
   }
 
+  /**
+   * Stores a secret that is only sent to the given hosts, e.g.
+   * `api.openai.com` or `*.example.com` for any subdomain.
+   */
+  async setSecret(name: string, value: string, hosts: string[]) {
+    await invoke("set_secret", { name, value, hosts });
+  }
+
+  async deleteSecret(name: string) {
+    await invoke("delete_secret", { name });
+  }
+
+  async hasSecret(name: string) {
+    const result = await invoke("has_secret", { name });
+    return result as boolean;
+  }
+
+  async recordUsage(kind: UsageKind, target: string) {
+    await invoke("record_usage", { kind, target });
+  }
+
+  async getRecentUsage(kind?: UsageKind, limit?: number) {
+    const response = (await invoke("get_recent_usage", {
+      kind,
+      limit,
+    })) as string;
+
+    return this.parse(response) as UsageEvent[];
+  }
+
+  async getMostUsed(kind?: UsageKind, since?: number, limit?: number) {
+    const response = (await invoke("get_most_used", {
+      kind,
+      since,
+      limit,
+    })) as string;
+
+    return this.parse(response) as UsageCount[];
+  }
+
+  async getApplicationUsage(id: string) {
+    const response = (await invoke("get_application_usage", { id })) as string;
+    return this.parse(response) as ApplicationUsage;
+  }
+
+  async readDocumentsForContext(
+    paths: string[],
+    maxTokens?: number,
+    chunkTokens?: number
+  ) {
+    const response = (await invoke("read_documents_for_context", {
+      paths,
+      maxTokens,
+      chunkTokens,
+    })) as string;
+
+    return this.parse(response) as ContextDocument[];
+  }
+
+  async getRetrievalSettings() {
+    const response = (await invoke("get_retrieval_settings")) as string;
+    return this.parse(response) as RetrievalSettings;
+  }
+
+  async setRetrievalSettings(settings: RetrievalSettings) {
+    await invoke("set_retrieval_settings", {
+      settings: {
+        enabled: settings.enabled,
+        backend: settings.backend,
+        remote_url: settings.remoteUrl,
+        remote_model: settings.remoteModel,
+        remote_api_key_secret: settings.remoteApiKeySecret,
+        local_model_dir: settings.localModelDir,
+        top_k: settings.topK,
+      },
+    });
+  }
+
+  async indexDocument(path: string) {
+    const count = await invoke("index_document", { path });
+    return count as number;
+  }
+
+  async indexThread(
+    id: string,
+    title: string,
+    messages: Array<{ author: string; content: string }>
+  ) {
+    const count = await invoke("index_thread", { id, title, messages });
+    return count as number;
+  }
+
+  async removeFromIndex(sourceKind: RetrievalSourceKind, sourceId: string) {
+    await invoke("remove_from_index", { sourceKind, sourceId });
+  }
+
+  async searchIndex(query: string, topK?: number) {
+    const response = (await invoke("search_index", { query, topK })) as string;
+    return this.parse(response) as RetrievalHit[];
+  }
+
+  async upsertThread(thread: ThreadRecord) {
+    await invoke("upsert_thread", { thread: this.toThreadDTO(thread) });
+  }
+
+  async importThreads(threads: ThreadRecord[]) {
+    const count = await invoke("import_threads", {
+      threads: threads.map((thread) => this.toThreadDTO(thread)),
+    });
+
+    return count as number;
+  }
+
+  async getThreads(limit?: number, offset?: number) {
+    const response = (await invoke("get_threads", { limit, offset })) as string;
+    return this.parse(response) as ThreadRecord[];
+  }
+
+  async getThread(id: string) {
+    const response = (await invoke("get_thread", { id })) as string;
+
+    if (!response || response === "null") {
+      return undefined;
+    }
+
+    return this.parse(response) as ThreadRecord;
+  }
+
+  async deleteThread(id: string) {
+    await invoke("delete_thread", { id });
+  }
+
+  async deleteAllThreads() {
+    await invoke("delete_all_threads");
+  }
+
+  async searchThreads(query: string, limit?: number) {
+    const response = (await invoke("search_threads", {
+      query,
+      limit,
+    })) as string;
+
+    return this.parse(response) as ThreadSearchHit[];
+  }
+
+  /**
+   * Resolves to the written path, or `null` when no path was given and the save dialog was
+   * canceled.
+   */
+  async exportThread(id: string, format: ExportFormat, path?: string) {
+    return (await invoke("export_thread", { id, format, path })) as
+      | string
+      | null;
+  }
+
+  private toThreadDTO(thread: ThreadRecord) {
+    return {
+      id: thread.id,
+      type: thread.type,
+      messages: thread.messages.map((message) => ({
+        id: message.id,
+        author: message.author,
+        content: message.content,
+        created_at: message.createdAt,
+        updated_at: message.updatedAt,
+      })),
+      command: thread.command,
+      created_at: thread.createdAt,
+      updated_at: thread.updatedAt,
+    };
+  }
+
+  /**
+   * Captures the screen without the panel. A region without `rect` is selected
+   * by the user, and `null` is returned when they cancel. `rect` is in points
+   * on macOS and physical pixels elsewhere.
+   */
+  async captureScreenshot(
+    target: CaptureTarget,
+    rect?: { x: number; y: number; width: number; height: number }
+  ) {
+    const response = (await invoke("capture_screenshot", {
+      target,
+      rect,
+    })) as string | null;
+
+    return response ? (this.parse(response) as Capture) : null;
+  }
+
+  async getCaptureSettings() {
+    const response = (await invoke("get_capture_settings")) as string;
+    return this.parse(response) as CaptureSettings;
+  }
+
+  async setCaptureSettings(settings: CaptureSettings) {
+    await invoke("set_capture_settings", {
+      settings: {
+        max_dimension: settings.maxDimension,
+        format: settings.format,
+        jpeg_quality: settings.jpegQuality,
+      },
+    });
+  }
+
+  /**
+   * Captures like `captureScreenshot` and returns the text recognized in the
+   * capture, or `null` when the user cancels the region selection.
+   */
+  async recognizeText(
+    target: CaptureTarget,
+    rect?: { x: number; y: number; width: number; height: number }
+  ) {
+    return (await invoke("recognize_text", { target, rect })) as string | null;
+  }
+
+  async getOcrSettings() {
+    const response = (await invoke("get_ocr_settings")) as string;
+    return this.parse(response) as OcrSettings;
+  }
+
+  async setOcrSettings(settings: OcrSettings) {
+    await invoke("set_ocr_settings", {
+      settings: {
+        focused_text_fallback: settings.focusedTextFallback,
+        language: settings.language,
+        data_path: settings.dataPath,
+      },
+    });
+  }
+
+  /**
+   * Starts recording from the microphone. Partial transcripts arrive as
+   * `dictation_partial` events until `stopDictation` is called.
+   */
+  async startDictation(target: DictationTarget) {
+    return (await invoke("start_dictation", { target })) as number;
+  }
+
+  /** Resolves to the final transcript, or `null` when nothing was recorded. */
+  async stopDictation() {
+    return (await invoke("stop_dictation")) as string | null;
+  }
+
+  async cancelDictation() {
+    await invoke("cancel_dictation");
+  }
+
+  async transcribeAudioFile(path: string) {
+    return (await invoke("transcribe_audio_file", { path })) as string;
+  }
+
+  async getDictationSettings() {
+    const response = (await invoke("get_dictation_settings")) as string;
+    return this.parse(response) as DictationSettings;
+  }
+
+  async setDictationSettings(settings: DictationSettings) {
+    await invoke("set_dictation_settings", {
+      settings: {
+        hotkey: settings.hotkey,
+        backend: settings.backend,
+        remote_url: settings.remoteUrl,
+        remote_model: settings.remoteModel,
+        remote_api_key_secret: settings.remoteApiKeySecret,
+        local_model_path: settings.localModelPath,
+        language: settings.language,
+        partial_interval_ms: settings.partialIntervalMs,
+      },
+    });
+  }
+
+  /**
+   * Queues streamed text to be spoken a sentence at a time. `isFinal` speaks
+   * whatever is left of an unfinished sentence.
+   */
+  async speakText(text: string, isFinal: boolean) {
+    await invoke("speak_text", { text, isFinal });
+  }
+
+  async stopSpeaking() {
+    await invoke("stop_speaking");
+  }
+
+  async pauseSpeaking() {
+    await invoke("pause_speaking");
+  }
+
+  async resumeSpeaking() {
+    await invoke("resume_speaking");
+  }
+
+  async setSpeechRate(rate: number) {
+    await invoke("set_speech_rate", { rate });
+  }
+
+  async getSpeechSettings() {
+    const response = (await invoke("get_speech_settings")) as string;
+    return this.parse(response) as SpeechSettings;
+  }
+
+  async setSpeechSettings(settings: SpeechSettings) {
+    await invoke("set_speech_settings", {
+      settings: {
+        read_responses: settings.readResponses,
+        hotkey: settings.hotkey,
+        backend: settings.backend,
+        rate: settings.rate,
+        voice: settings.voice,
+        remote_url: settings.remoteUrl,
+        remote_model: settings.remoteModel,
+        remote_api_key_secret: settings.remoteApiKeySecret,
+        command: settings.command,
+        command_arguments: settings.commandArguments,
+      },
+    });
+  }
+
+  // Tool names, schemas and provider payloads are returned as is, since converting their keys to
+  // camel case would change them.
+
+  async getTools() {
+    const response = (await invoke("get_tools")) as string;
+    return JSON.parse(response) as ToolInfo[];
+  }
+
+  async getToolSettings() {
+    const response = (await invoke("get_tool_settings")) as string;
+    const settings = JSON.parse(response);
+
+    return {
+      maxSteps: settings.max_steps,
+      permissions: settings.permissions,
+      chat: settings.chat
+        ? {
+            provider: settings.chat.provider,
+            url: settings.chat.url,
+            model: settings.chat.model,
+            apiKeySecret: settings.chat.api_key_secret,
+          }
+        : undefined,
+    } as ToolSettings;
+  }
+
+  async setToolSettings(settings: ToolSettings) {
+    await invoke("set_tool_settings", {
+      settings: {
+        max_steps: settings.maxSteps,
+        permissions: settings.permissions,
+        chat: settings.chat
+          ? {
+              provider: settings.chat.provider,
+              url: settings.chat.url,
+              model: settings.chat.model,
+              api_key_secret: settings.chat.apiKeySecret,
+            }
+          : null,
+      },
+    });
+  }
+
+  async resolveToolApproval(id: number, approved: boolean) {
+    await invoke("resolve_tool_approval", { id, approved });
+  }
+
+  /**
+   * Runs a chat completion request with the registered tools until the model answers. Header
+   * values may reference stored secrets bound to the host of `url` as `{{secret:<name>}}`.
+   */
+  async runTools(options: {
+    provider: ToolProvider;
+    url: string;
+    headers?: Record<string, string>;
+    request: Record<string, unknown>;
+    tools?: string[];
+  }) {
+    const response = (await invoke("run_tools", options)) as string;
+    return JSON.parse(response) as ToolRun;
+  }
+
+  async getRunCommandSettings() {
+    const response = (await invoke("get_run_command_settings")) as string;
+    return this.parse(response) as RunCommandSettings;
+  }
+
+  async setRunCommandSettings(settings: RunCommandSettings) {
+    await invoke("set_run_command_settings", {
+      settings: {
+        allowed_directories: settings.allowedDirectories,
+        blocked_patterns: settings.blockedPatterns,
+        default_timeout: settings.defaultTimeout,
+        max_timeout: settings.maxTimeout,
+        max_output_length: settings.maxOutputLength,
+      },
+    });
+  }
+
+  async getCommandLog(options?: { limit?: number; offset?: number }) {
+    const response = (await invoke("get_command_log", options)) as string;
+    return this.parse(response) as CommandLogEntry[];
+  }
+
+  async getMcpSettings() {
+    const response = (await invoke("get_mcp_settings")) as string;
+    return JSON.parse(response) as McpSettings;
+  }
+
+  /** Saves the server list and resolves once every server has reconnected. */
+  async setMcpSettings(settings: McpSettings) {
+    await invoke("set_mcp_settings", { settings });
+  }
+
+  async getMcpServers() {
+    const response = (await invoke("get_mcp_servers")) as string;
+    return JSON.parse(response) as McpServer[];
+  }
+
+  async reconnectMcpServer(name: string) {
+    await invoke("reconnect_mcp_server", { name });
+  }
+
+  async callMcpTool(server: string, name: string, args: unknown) {
+    return (await invoke("call_mcp_tool", {
+      server,
+      name,
+      arguments: args,
+    })) as string;
+  }
+
+  async readMcpResource(server: string, uri: string) {
+    const response = (await invoke("read_mcp_resource", {
+      server,
+      uri,
+    })) as string;
+
+    return JSON.parse(response) as {
+      contents: Array<{
+        uri: string;
+        mimeType?: string;
+        text?: string;
+        blob?: string;
+      }>;
+    };
+  }
+
+  async getMcpPrompt(
+    server: string,
+    name: string,
+    args?: Record<string, string>
+  ) {
+    const response = (await invoke("get_mcp_prompt", {
+      server,
+      name,
+      arguments: args,
+    })) as string;
+
+    return JSON.parse(response) as {
+      description?: string;
+      messages: Array<{ role: "user" | "assistant"; content: unknown }>;
+    };
+  }
+
   async fetchUser() {
     await invoke("fetch_user");
   }
@@ -163,18 +163,30 @@ export class NetworkService {
       }
     });
 
-    const response = await InvokeService.shared.stream({
-      url: networkURL.url.href,
-      method: networkURL.method as "GET" | "POST",
-      ...(networkURL.dto ? { body: JSON.stringify(networkURL.dto) } : {}),
-      ...(accessToken
-        ? {
-            headers: JSON.stringify({
-              Authorization: `Bearer ${accessToken}`,
-            }),
-          }
-        : {}),
-    });
+    const { enabled: isLocal } = await InvokeService.shared
+      .getInferenceSettings()
+      .catch(() => ({ enabled: false }));
+
+    // Chats go to the local model when it is enabled, so they work offline.
+    const response =
+      isLocal && networkURL.url.pathname.endsWith("/chat")
+        ? await InvokeService.shared.streamLocal(
+            JSON.stringify(networkURL.dto || {})
+          )
+        : await InvokeService.shared.stream({
+            url: networkURL.url.href,
+            method: networkURL.method as "GET" | "POST",
+            ...(networkURL.dto
+              ? { body: JSON.stringify(networkURL.dto) }
+              : {}),
+            ...(accessToken
+              ? {
+                  headers: JSON.stringify({
+                    Authorization: `Bearer ${accessToken}`,
+                  }),
+                }
+              : {}),
+          });
 
     this.listeners.forEach(({ type, callback }) => {
       if (type === "stream") {
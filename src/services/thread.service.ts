@@ -1,44 +1,154 @@
 import { instanceToPlain } from "class-transformer";
-import { Thread, ThreadMessage } from "~/models";
+import { Command, Thread, ThreadMessage } from "~/models";
 import { LanderDatabase } from "~/util/lander.database";
+import { ExportFormat, InvokeService, ThreadRecord } from "./invoke.service";
+
+const MIGRATED_KEY = "threads_migrated";
+
+const toSeconds = (date?: Date | string) =>
+  date ? Math.floor(new Date(date).getTime() / 1000) : 0;
+
+const fromSeconds = (seconds: number) =>
+  seconds ? new Date(seconds * 1000) : new Date();
+
+interface StoredThread {
+  id: string;
+  type: string;
+  messages: Array<{
+    id: string;
+    author: string;
+    content: string;
+    created_at?: Date | string;
+    updated_at?: Date | string;
+  }>;
+  command?: Record<string, unknown>;
+  created_at?: Date | string;
+  updated_at?: Date | string;
+}
 
 export class ThreadService {
   static shared = new ThreadService();
 
   private db = LanderDatabase.shared.thread;
 
+  private migration?: Promise<void>;
+
   async getAll() {
-    const data = await this.db.orderBy("updated_at").reverse().toArray();
+    await this.migrate();
 
-    return data.map((thread) => {
-      thread.messages = thread.messages.map(
-        (message) => new ThreadMessage(message)
-      );
-      return new Thread(thread);
-    });
+    const threads = await InvokeService.shared.getThreads();
+
+    return threads.map((data) => this.toThread(data));
+  }
+
+  async get(id: string) {
+    await this.migrate();
+
+    const data = await InvokeService.shared.getThread(id);
+
+    return data ? this.toThread(data) : undefined;
+  }
+
+  async search(query: string, limit?: number) {
+    await this.migrate();
+
+    return InvokeService.shared.searchThreads(query, limit);
   }
 
   async upsertThread(data: Thread) {
+    await this.migrate();
+
     const thread = {
-      ...(instanceToPlain(data, { groups: ["local"] }) as Thread),
+      ...(instanceToPlain(data, { groups: ["local"] }) as StoredThread),
       created_at: data.createdAt,
       updated_at: new Date(),
     };
 
-    const existingThread = await this.db.get({ id: data.id });
+    await InvokeService.shared.upsertThread(this.toRecord(thread));
+  }
 
-    if (existingThread) {
-      await this.db.update(data, thread);
-    } else {
-      await this.db.add(thread);
-    }
+  async export(id: string, format: ExportFormat, path?: string) {
+    await this.migrate();
+    return InvokeService.shared.exportThread(id, format, path);
   }
 
   async remove(id: string) {
-    await this.db.delete(id);
+    await this.migrate();
+    await InvokeService.shared.deleteThread(id);
   }
 
   async removeAll() {
-    await this.db.clear();
+    await this.migrate();
+    await InvokeService.shared.deleteAllThreads();
+  }
+
+  /**
+   * Threads used to be kept in IndexedDB only; they are copied to the backend once and the old
+   * table is left in place as a backup.
+   */
+  private migrate() {
+    if (localStorage.getItem(MIGRATED_KEY)) {
+      return Promise.resolve();
+    }
+
+    this.migration ??= (async () => {
+      const threads = (await this.db.toArray()) as unknown as StoredThread[];
+
+      if (threads.length) {
+        await InvokeService.shared.importThreads(
+          threads.map((thread) => this.toRecord(thread))
+        );
+      }
+
+      localStorage.setItem(MIGRATED_KEY, "true");
+    })().catch((error) => {
+      // Cleared so the next call tries again; callers must not go on without the threads.
+      this.migration = undefined;
+      throw error;
+    });
+
+    return this.migration;
+  }
+
+  private toRecord(thread: StoredThread): ThreadRecord {
+    return {
+      id: thread.id,
+      type: thread.type,
+      messages: thread.messages.map((message) => ({
+        id: message.id,
+        author: message.author,
+        content: message.content,
+        createdAt: toSeconds(message.created_at),
+        updatedAt: toSeconds(message.updated_at),
+      })),
+      command: thread.command,
+      createdAt: toSeconds(thread.created_at),
+      updatedAt: toSeconds(thread.updated_at),
+    };
+  }
+
+  private toThread(data: ThreadRecord) {
+    const thread = new Thread({
+      id: data.id,
+      type: data.type as Thread["type"],
+      command: data.command as Command | undefined,
+      messages: data.messages.map((data) => {
+        const message = new ThreadMessage({
+          id: data.id,
+          author: data.author as ThreadMessage["author"],
+          content: data.content,
+        });
+
+        message.createdAt = fromSeconds(data.createdAt);
+        message.updatedAt = fromSeconds(data.updatedAt);
+
+        return message;
+      }),
+    });
+
+    thread.createdAt = fromSeconds(data.createdAt);
+    thread.updatedAt = fromSeconds(data.updatedAt);
+
+    return thread;
   }
 }
@@ -13,6 +13,8 @@ export const chatStore = createRoot(() => {
   const [highlightedMessage, setHighlightedMessage] =
     createSignal<ThreadMessage>();
   const [contextualText, setContextualText] = createSignal<ContextualText>();
+  /** Data URLs of screenshots attached to the next prompt. */
+  const [attachedImages, setAttachedImages] = createSignal<string[]>([]);
   const [chatCount, setChatCount] = createSignal(
     Number(localStorage.getItem("chat_count") || 0)
   );
@@ -48,6 +50,8 @@ export const chatStore = createRoot(() => {
     setHighlightedMessage,
     contextualText,
     setContextualText,
+    attachedImages,
+    setAttachedImages,
     chatCount,
     setChatCount,
     chatCountTTL,
@@ -7,6 +7,7 @@ import {
   Thread,
   ThreadType,
 } from "~/models";
+import { CaptureTarget, InvokeService } from "~/services/invoke.service";
 import { commandStore, router, View } from "~/store";
 import { chatStore } from "~/store/chat.store";
 import { askLander } from "./ask-lander";
@@ -60,6 +61,44 @@ export const chatCommand = new Command({
   },
 });
 
+const getCaptureCommand = (title: string, target: CaptureTarget) => {
+  return new Command({
+    id: `ai-capture-${target}`,
+    type: CommandType.AI,
+    title,
+    icon,
+    suggestable: false,
+    async onClick() {
+      const capture = await InvokeService.shared
+        .captureScreenshot(target)
+        .catch((error) => {
+          console.error(error);
+          return null;
+        });
+
+      if (!capture) {
+        return;
+      }
+
+      const { setAttachedImages } = chatStore;
+
+      setAttachedImages((prev) => [
+        ...prev,
+        `data:${capture.mediaType};base64,${capture.data}`,
+      ]);
+
+      chatCommand.onClick();
+    },
+  });
+};
+
+/** Attach a screenshot to the next prompt of a new chat. */
+export const captureCommands = [
+  getCaptureCommand("Ask about the screen", "screen"),
+  getCaptureCommand("Ask about the focused window", "window"),
+  getCaptureCommand("Ask about a screen region", "region"),
+];
+
 export const getCustomCommand = (focusedApplication: Application) => {
   const prompt = focusedApplication.focusedText?.match(/\/lander(.*)/)?.[1];
 
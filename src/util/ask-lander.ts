@@ -1,12 +1,12 @@
-import { instanceToPlain } from "class-transformer";
 import { batch } from "solid-js";
-import { OpenAIChatModel, Plugin } from "~/cortex";
+import { OpenAIChatModel } from "~/cortex";
 import {
   Thread,
   ThreadMessage,
   ThreadMessageAuthor,
   ThreadMessagePlugin,
 } from "~/models";
+import { InvokeService } from "~/services/invoke.service";
 import { NetworkService } from "~/services/network.service";
 import { ThreadService } from "~/services/thread.service";
 import { chatStore } from "~/store/chat.store";
@@ -25,6 +25,8 @@ export const askLander = async (input: string | undefined, thread: Thread) => {
     setThread,
     contextualText,
     setContextualText,
+    attachedImages,
+    setAttachedImages,
     setHighlightedMessage,
     selectedPlugins,
   } = chatStore;
@@ -38,12 +40,15 @@ export const askLander = async (input: string | undefined, thread: Thread) => {
   const message =
     context && input ? `${context}\n\n${input}` : context || input;
 
+  const images = message ? attachedImages() : [];
+
   if (message) {
     thread.messages.push(
       new ThreadMessage({
         id: crypto.randomUUID(),
         author: ThreadMessageAuthor.User,
         content: message,
+        images: images.length ? images : undefined,
       })
     );
   }
@@ -61,6 +66,9 @@ export const askLander = async (input: string | undefined, thread: Thread) => {
     setContextualText((prev) =>
       prev ? { ...prev, additionalText: input } : undefined
     );
+    if (images.length) {
+      setAttachedImages([]);
+    }
     setThread(new Thread(thread));
   });
 
@@ -75,24 +83,42 @@ export const askLander = async (input: string | undefined, thread: Thread) => {
   );
 
   chat.plugins = selectedPlugins();
+  chat.images = images;
 
   const messagePlugins = new Array<ThreadMessagePlugin>();
 
+  const { readResponses } = await InvokeService.shared
+    .getSpeechSettings()
+    .catch(() => ({ readResponses: false }));
+
+  if (readResponses) {
+    await InvokeService.shared.stopSpeaking();
+  }
+
   chat.on("response", (data) => {
     thread.messages[messageIndex].content = `${responseMessage.content}${data}`;
     setThread(new Thread(thread));
+
+    if (readResponses && data !== "[LANDER_STREAM_ERROR]") {
+      InvokeService.shared.speakText(data, false);
+    }
   });
 
-  chat.on("plugin", (plugin, input) => {
+  chat.on("tool", (step) => {
     messagePlugins.push({
-      ...(instanceToPlain(plugin) as Plugin),
-      input,
+      name: step.name,
+      description: "",
+      input: JSON.stringify(step.arguments),
     });
     thread.messages[messageIndex].plugins = [...messagePlugins];
     setThread(new Thread(thread));
   });
 
   chat.on("end", () => {
+    if (readResponses) {
+      InvokeService.shared.speakText("", true);
+    }
+
     const next = chatStore.thread();
 
     if (next) {
@@ -100,5 +126,21 @@ export const askLander = async (input: string | undefined, thread: Thread) => {
     }
   });
 
-  chat.call(message || thread.messages[thread.messages.length - 2].content);
+  const query = message || thread.messages[thread.messages.length - 2].content;
+
+  const hits = await InvokeService.shared.searchIndex(query).catch(() => []);
+
+  chat.context = hits
+    .filter((hit) => hit.sourceKind !== "thread" || hit.sourceId !== thread.id)
+    .map((hit) => `From "${hit.title}":\n${hit.text}`);
+
+  const applicationContext = !thread.messages.slice(0, -2).length
+    ? thread.command?.application?.describeContext?.()
+    : undefined;
+
+  if (applicationContext) {
+    chat.context.unshift(applicationContext);
+  }
+
+  chat.call(query);
 };
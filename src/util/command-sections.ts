@@ -2,7 +2,12 @@ import dayjs from "dayjs";
 import { Application, Command, CommandSection, CommandType } from "~/models";
 import { AnalyticsService, InvokeService } from "~/services";
 import { networkStore } from "~/store/network.store";
-import { aiCommands, chatCommand, getCustomCommand } from "./ai-commands";
+import {
+  aiCommands,
+  captureCommands,
+  chatCommand,
+  getCustomCommand,
+} from "./ai-commands";
 import { landerCommands } from "./lander-commands";
 
 // TODO: Add better types
@@ -24,7 +29,7 @@ export const getCommandSection = async (
         commands.push(customCommand);
       }
 
-      commands.push(chatCommand);
+      commands.push(chatCommand, ...captureCommands);
 
       if (focusedApplication.selectedText) {
         commands.push(...aiCommands);
@@ -49,7 +54,7 @@ export const getCommandSection = async (
     return new CommandSection({
       type: CommandType.AI,
       title: "AI",
-      commands: [chatCommand],
+      commands: [chatCommand, ...captureCommands],
     });
   }
 
@@ -1,14 +1,9 @@
 import { listen } from "@tauri-apps/api/event";
 import { batch, onMount } from "solid-js";
 import { calculator } from "~/calculator";
-import { CommandType, User } from "~/models";
+import { Application, ApplicationData, CommandType, User } from "~/models";
 import { useUser } from "~/queries";
-import {
-  EventKey,
-  EventService,
-  InvokeService,
-  SettingsService,
-} from "~/services";
+import { InvokeService, SettingsService } from "~/services";
 import { HTTPError, NetworkService } from "~/services/network.service";
 import { commandStore, mouseStore } from "~/store";
 import { chatStore } from "~/store/chat.store";
@@ -18,18 +13,17 @@ import {
   getCommandSections,
   getSuggestionsCommandSection,
 } from "./command-sections";
+import { convertKeysFromSnakeCaseToCamelCase } from "./convert-keys-from-snake-case-to-camel-case";
 
 let lastCurrencyRefresh: Date | undefined = undefined;
 
-const installedApplicationsEvent = new EventService(
-  EventKey.InstalledApplications,
-  async (applications) => {
-    commandStore.setCommandSection(
-      CommandType.Application,
-      await getCommandSection(CommandType.Application, { applications })
-    );
-  }
-);
+let installedApplications: Application[] = [];
+
+interface InstalledApplicationsDiff {
+  added: ApplicationData[];
+  updated: ApplicationData[];
+  removed: string[];
+}
 
 export const useLaunch = () => {
   const { setIsMouseActive } = mouseStore;
@@ -85,8 +79,21 @@ export const useLaunch = () => {
     return true;
   };
 
+  // Chats are answered without the server when local inference is enabled.
+  const isChatAvailable = async () => {
+    if (await authenticate()) {
+      return true;
+    }
+
+    const { enabled } = await InvokeService.shared
+      .getInferenceSettings()
+      .catch(() => ({ enabled: false }));
+
+    return enabled;
+  };
+
   onMount(async () => {
-    setIsOffline(!(await authenticate()));
+    setIsOffline(!(await isChatAvailable()));
 
     await calculator.refreshCurrencyData();
 
@@ -147,6 +154,47 @@ export const useLaunch = () => {
       user.refetch();
     });
 
+    InvokeService.shared
+      .getInstalledApplications()
+      .then(async (applications) => {
+        installedApplications = applications;
+
+        setCommandSection(
+          CommandType.Application,
+          await getCommandSection(CommandType.Application, { applications })
+        );
+      });
+
+    listen("installed_applications_changed", async (event) => {
+      const diff =
+        convertKeysFromSnakeCaseToCamelCase<InstalledApplicationsDiff>(
+          event.payload as Record<string, unknown>
+        );
+
+      const changedIDs = new Set([
+        ...diff.removed,
+        ...diff.updated.map((application) => application.id),
+      ]);
+
+      installedApplications = [
+        ...installedApplications.filter(
+          (application) => !changedIDs.has(application.id)
+        ),
+        ...[...diff.added, ...diff.updated].map(
+          (data) => new Application(data)
+        ),
+      ].sort((a, b) =>
+        a.name.toLowerCase().localeCompare(b.name.toLowerCase())
+      );
+
+      setCommandSection(
+        CommandType.Application,
+        await getCommandSection(CommandType.Application, {
+          applications: installedApplications,
+        })
+      );
+    });
+
     window.addEventListener("mousemove", (event) => {
       if (
         event.screenX !== lastMousePosition?.x ||
@@ -165,8 +213,6 @@ export const useLaunch = () => {
     });
 
     window.addEventListener("focus", async () => {
-      installedApplicationsEvent.emit();
-
       const focusedApplication =
         await InvokeService.shared.getFocusedApplication();
 
@@ -194,7 +240,7 @@ export const useLaunch = () => {
       });
 
       setTimeout(async () => {
-        setIsOffline(!(await authenticate()));
+        setIsOffline(!(await isChatAvailable()));
       });
 
       setTimeout(async () => {
@@ -209,11 +255,11 @@ export const useLaunch = () => {
     });
 
     window.addEventListener("online", async () => {
-      setIsOffline(!(await authenticate()));
+      setIsOffline(!(await isChatAvailable()));
     });
 
-    window.addEventListener("offline", () => {
-      setIsOffline(true);
+    window.addEventListener("offline", async () => {
+      setIsOffline(!(await isChatAvailable()));
     });
   });
 };